@@ -7,7 +7,9 @@ use uuid::Uuid;
 
 use cosmic_gardener_backend::game::celestial_bodies::{CelestialBody, CelestialType};
 use cosmic_gardener_backend::game::physics::PhysicsEngine;
-use cosmic_gardener_backend::game::physics_simd::SimdPhysicsEngine;
+use cosmic_gardener_backend::game::physics_simd::{ForceKernel, SimdPhysicsEngine};
+use cosmic_gardener_backend::game::physics_store::PhysicsStore;
+use cosmic_gardener_backend::game::spatial_index::SpatialIndex;
 use cosmic_gardener_backend::game::resources::fixed;
 use cosmic_gardener_backend::services::metrics::{MetricsService, MetricsConfig};
 
@@ -87,6 +89,87 @@ fn bench_simd_physics(c: &mut Criterion) {
     group.finish();
 }
 
+/// 天体経由の更新と配列上の更新の比較
+///
+/// `update_optimized`は毎回天体から配列へ読み込んで書き戻す。`update_store`はシミュレーションが
+/// 所有する配列をその場で進めるだけで、天体の複製もティックごとの確保も行わない。
+fn bench_store_physics(c: &mut Criterion) {
+    let mut group = c.benchmark_group("store_physics");
+    
+    let metrics_config = MetricsConfig::default();
+    let metrics_service = Arc::new(MetricsService::new(metrics_config).unwrap());
+    
+    for body_count in [100, 500, 1000, 5000].iter() {
+        let mut bodies = generate_test_bodies(*body_count);
+        let mut store = PhysicsStore::from_bodies(&bodies);
+        let mut spatial_index = SpatialIndex::new();
+        spatial_index.sync(&bodies);
+        let mut simd_engine = SimdPhysicsEngine::new(metrics_service.clone());
+        
+        group.throughput(Throughput::Elements(*body_count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("body_round_trip", body_count),
+            body_count,
+            |b, _| {
+                b.iter(|| {
+                    let _ = simd_engine.update_optimized(black_box(&mut bodies), black_box(0.016));
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("in_place", body_count),
+            body_count,
+            |b, _| {
+                b.iter(|| {
+                    let _ = simd_engine.update_store(black_box(&mut store), &spatial_index, black_box(0.016));
+                })
+            },
+        );
+    }
+    
+    group.finish();
+}
+
+/// スカラー版とレーン並列版の力の計算カーネルの比較
+fn bench_force_kernels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("force_kernels");
+    
+    let metrics_config = MetricsConfig::default();
+    let metrics_service = Arc::new(MetricsService::new(metrics_config).unwrap());
+    
+    for body_count in [100, 500, 1000].iter() {
+        let bodies = generate_test_bodies(*body_count);
+        let mut spatial_index = SpatialIndex::new();
+        spatial_index.sync(&bodies);
+        
+        group.throughput(Throughput::Elements(*body_count as u64));
+        let kernels = [
+            ("scalar", ForceKernel::Scalar),
+            ("simd", ForceKernel::Simd),
+            ("simd_avx", ForceKernel::SimdAvx),
+            ("simd_avx2_fma", ForceKernel::SimdAvx2Fma),
+        ];
+        // 実行中のCPUで使えない版は計測しない
+        for (name, kernel) in kernels.into_iter().filter(|(_, kernel)| kernel.is_supported()) {
+            let mut store = PhysicsStore::from_bodies(&bodies);
+            let mut simd_engine = SimdPhysicsEngine::new(metrics_service.clone());
+            simd_engine.force_kernel = kernel;
+            
+            group.bench_with_input(
+                BenchmarkId::new(name, body_count),
+                body_count,
+                |b, _| {
+                    b.iter(|| {
+                        let _ = simd_engine.update_store(black_box(&mut store), &spatial_index, black_box(0.016));
+                    })
+                },
+            );
+        }
+    }
+    
+    group.finish();
+}
+
 /// 物理演算アルゴリズム比較
 fn bench_physics_algorithms(c: &mut Criterion) {
     let mut group = c.benchmark_group("physics_algorithms");
@@ -237,6 +320,10 @@ fn bench_accuracy_vs_performance(c: &mut Criterion) {
             gravitational_constant: 6.67430e-11,
             softening_factor: *softening,
             max_velocity: 0.1 * 299792458.0,
+            theta: *theta,
+            integrator: cosmic_gardener_backend::game::integrator::IntegratorKind::default(),
+            timestep: cosmic_gardener_backend::game::timestep::TimestepSettings::default(),
+            kernel: cosmic_gardener_backend::game::physics_simd::KernelSelection::default(),
         };
         simd_engine.configure(config);
         
@@ -285,6 +372,8 @@ criterion_group!(
     benches,
     bench_traditional_physics,
     bench_simd_physics,
+    bench_store_physics,
+    bench_force_kernels,
     bench_physics_algorithms,
     bench_large_scale_physics,
     bench_memory_efficiency,
@@ -0,0 +1,55 @@
+-- Migration: Prestige System
+-- Version: 003
+-- Description: Persist prestige state and history on the server
+
+-- プレステージ状態テーブル（サーバー側の正本）
+CREATE TABLE IF NOT EXISTS player_prestige (
+    player_id UUID PRIMARY KEY,
+    prestige_count INTEGER NOT NULL DEFAULT 0,
+    total_points BIGINT NOT NULL DEFAULT 0,
+    production_multiplier DOUBLE PRECISION NOT NULL DEFAULT 1.0,
+    last_prestige_at TIMESTAMPTZ,
+    
+    -- 更新時間
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    -- 制約
+    CHECK (prestige_count >= 0),
+    CHECK (total_points >= 0),
+    CHECK (production_multiplier >= 1.0),
+    
+    -- 外部キー
+    FOREIGN KEY (player_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- プレステージ履歴テーブル
+CREATE TABLE IF NOT EXISTS prestige_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    player_id UUID NOT NULL,
+    prestige_number INTEGER NOT NULL,
+    points_awarded BIGINT NOT NULL,
+    total_points BIGINT NOT NULL,
+    production_multiplier DOUBLE PRECISION NOT NULL,
+    
+    -- リセット時点の記録
+    lifetime_totals JSONB NOT NULL,
+    resources_at_reset JSONB NOT NULL,
+    bodies_at_reset INTEGER NOT NULL DEFAULT 0,
+    server_tick BIGINT NOT NULL,
+    performed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    
+    -- 制約
+    UNIQUE(player_id, prestige_number),
+    CHECK (points_awarded > 0),
+    
+    -- 外部キー
+    FOREIGN KEY (player_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- インデックス作成
+CREATE INDEX IF NOT EXISTS idx_prestige_history_player ON prestige_history(player_id, prestige_number DESC);
+CREATE INDEX IF NOT EXISTS idx_player_prestige_points ON player_prestige(total_points DESC);
+
+-- 権限設定
+GRANT SELECT, INSERT, UPDATE, DELETE ON player_prestige TO cosmic_gardener_app;
+GRANT SELECT, INSERT ON prestige_history TO cosmic_gardener_app;
@@ -10,6 +10,11 @@ use crate::services::websocket::compression::CompressionConfig;
 use crate::services::secrets::{SecretsConfig, SecretsProvider, load_production_config, load_development_config};
 use crate::game::physics_simd::SimdPhysicsConfig;
 use crate::game::concurrent_game_loop::GameLoopConfig;
+use crate::game::offline::OfflineProgressConfig;
+use crate::game::upgrade_catalog::UpgradeCatalogConfig;
+use crate::game::prestige::PrestigeConfig;
+use crate::game::research::ResearchConfig;
+use crate::game::achievements::AchievementConfig;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
@@ -38,6 +43,11 @@ pub struct Config {
     pub compression: CompressionConfig,
     pub physics: SimdPhysicsConfig,
     pub game_loop: GameLoopConfig,
+    pub offline_progress: OfflineProgressConfig,
+    pub upgrades: UpgradeCatalogConfig,
+    pub prestige: PrestigeConfig,
+    pub research: ResearchConfig,
+    pub achievements: AchievementConfig,
     pub secrets: SecretsConfig,
 }
 
@@ -89,6 +99,11 @@ impl Config {
             compression: CompressionConfig::default(),
             physics: SimdPhysicsConfig::default(),
             game_loop: GameLoopConfig::default(),
+            offline_progress: OfflineProgressConfig::default(),
+            upgrades: UpgradeCatalogConfig::default(),
+            prestige: PrestigeConfig::default(),
+            research: ResearchConfig::default(),
+            achievements: AchievementConfig::default(),
             secrets: SecretsConfig::default(),
         })
     }
@@ -171,6 +186,28 @@ impl Config {
             }
         }
         
+        // アップグレードカタログ設定
+        if let Ok(path) = env::var("UPGRADE_CATALOG_PATH") {
+            self.upgrades.path = path;
+        }
+        
+        // 研究ツリー設定
+        if let Ok(path) = env::var("RESEARCH_TREE_PATH") {
+            self.research.tree_path = path;
+        }
+        
+        // 実績カタログ設定
+        if let Ok(path) = env::var("ACHIEVEMENT_CATALOG_PATH") {
+            self.achievements.catalog_path = path;
+        }
+        
+        // オフライン進行設定
+        if let Ok(max_hours) = env::var("OFFLINE_MAX_HOURS") {
+            if let Ok(val) = max_hours.parse() {
+                self.offline_progress.max_offline_hours = val;
+            }
+        }
+        
         // データベースプール設定
         if let Ok(pool_size) = env::var("DB_POOL_MAX_SIZE") {
             if let Ok(val) = pool_size.parse() {
@@ -253,6 +290,21 @@ impl Config {
             return Err(ConfigValidationError::InvalidMaxBodies);
         }
         
+        // オフライン進行設定の検証
+        if self.offline_progress.validate().is_err() {
+            return Err(ConfigValidationError::InvalidOfflineProgress);
+        }
+        
+        // プレステージ設定の検証
+        if self.prestige.validate().is_err() {
+            return Err(ConfigValidationError::InvalidPrestige);
+        }
+        
+        // 研究設定の検証
+        if self.research.max_queue_length == 0 {
+            return Err(ConfigValidationError::InvalidResearchQueue);
+        }
+        
         Ok(())
     }
 
@@ -281,6 +333,11 @@ impl Config {
                 compression: CompressionConfig::default(),
                 physics: SimdPhysicsConfig::default(),
                 game_loop: GameLoopConfig::default(),
+                offline_progress: OfflineProgressConfig::default(),
+                upgrades: UpgradeCatalogConfig::default(),
+                prestige: PrestigeConfig::default(),
+                research: ResearchConfig::default(),
+                achievements: AchievementConfig::default(),
                 secrets: SecretsConfig::default(),
             }
         });
@@ -330,6 +387,11 @@ impl Config {
             compression: CompressionConfig::default(),
             physics: SimdPhysicsConfig::default(),
             game_loop: GameLoopConfig::default(),
+            offline_progress: OfflineProgressConfig::default(),
+            upgrades: UpgradeCatalogConfig::default(),
+            prestige: PrestigeConfig::default(),
+            research: ResearchConfig::default(),
+            achievements: AchievementConfig::default(),
             secrets: SecretsConfig::default(),
         }
     }
@@ -361,6 +423,15 @@ pub enum ConfigValidationError {
     
     #[error("Invalid maximum celestial bodies")]
     InvalidMaxBodies,
+    
+    #[error("Invalid offline progress configuration")]
+    InvalidOfflineProgress,
+    
+    #[error("Invalid prestige configuration")]
+    InvalidPrestige,
+    
+    #[error("Invalid research queue length")]
+    InvalidResearchQueue,
 }
 
 #[cfg(test)]
@@ -404,6 +475,16 @@ mod tests {
         assert!(matches!(result.unwrap_err(), ConfigValidationError::InvalidPhysicsThreshold));
     }
     
+    #[test]
+    fn test_config_validation_invalid_offline_progress() {
+        let mut config = Config::test();
+        config.offline_progress.efficiency_curve[0].efficiency = 1.5;
+        
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), ConfigValidationError::InvalidOfflineProgress));
+    }
+    
     #[test]
     fn test_config_from_env_override() {
         let mut config = Config::test();
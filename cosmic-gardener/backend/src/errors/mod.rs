@@ -65,6 +65,9 @@ pub enum GameError {
     #[error("Maximum number of bodies reached")]
     BodyLimitReached,
     
+    #[error("Maximum number of {body_type} bodies reached ({limit})")]
+    BodyTypeLimitReached { body_type: String, limit: usize },
+    
     #[error("Position is out of bounds")]
     OutOfBounds,
     
@@ -230,6 +233,15 @@ impl ResponseError for GameError {
                 "BODY_LIMIT_REACHED",
                 "Maximum number of bodies reached",
             ),
+            GameError::BodyTypeLimitReached { body_type, limit } => {
+                return HttpResponse::BadRequest().json(ApiError {
+                    code: "BODY_TYPE_LIMIT_REACHED".to_string(),
+                    message: self.to_string(),
+                    timestamp: chrono::Utc::now(),
+                    request_id: None,
+                    details: Some(serde_json::json!({ "body_type": body_type, "limit": limit })),
+                });
+            }
             GameError::OutOfBounds => (
                 actix_web::http::StatusCode::BAD_REQUEST,
                 "OUT_OF_BOUNDS",
@@ -0,0 +1,239 @@
+//! ブラックホールの降着
+//!
+//! シュヴァルツシルト半径を越えた天体の吸収と、周囲のガスや塵（彗星・小惑星・巨大ガス惑星）からの
+//! 質量の剥ぎ取りを行う。実際に降着した質量から`accretion_rate`を求め、ダークマター生成に使う。
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::game::celestial_bodies::{BodyId, CelestialBody, CelestialType, PlanetType, Vec3Fixed};
+use crate::game::determinism::sorted_body_ids;
+use crate::game::resources::{fixed, Fixed};
+
+/// 降着のパラメータ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AccretionSettings {
+    /// 降着の影響範囲（シュヴァルツシルト半径に対する倍率）
+    pub influence_radius_factor: f64,
+    /// 事象の地平面における1秒あたりの剥ぎ取り率（距離の2乗に反比例して弱まる）
+    pub stripping_rate: f64,
+    /// これより軽くなった天体は丸ごと吸収する
+    pub min_remnant_mass: f64,
+    /// 降着率の平滑化の時定数（秒）
+    pub rate_time_constant: f64,
+}
+
+impl Default for AccretionSettings {
+    fn default() -> Self {
+        Self {
+            influence_radius_factor: 20.0,
+            stripping_rate: 1.0,
+            min_remnant_mass: 1.0,
+            rate_time_constant: 1.0,
+        }
+    }
+}
+
+/// ブラックホールに吸収された天体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Absorption {
+    pub black_hole_id: BodyId,
+    pub body_id: BodyId,
+}
+
+/// 全ブラックホールの降着を`delta_time`秒だけ進め、吸収された天体を返す
+pub fn process_accretion(
+    bodies: &mut HashMap<BodyId, CelestialBody>,
+    delta_time: f64,
+    settings: &AccretionSettings,
+) -> Vec<Absorption> {
+    let mut absorptions = Vec::new();
+    if delta_time <= 0.0 {
+        return absorptions;
+    }
+
+    let black_hole_ids: Vec<BodyId> = sorted_body_ids(bodies)
+        .into_iter()
+        .filter(|id| matches!(bodies[id].body_type, CelestialType::BlackHole(_)))
+        .collect();
+
+    for black_hole_id in black_hole_ids {
+        // 同じティックで他のブラックホールに吸収されている場合がある
+        let Some(black_hole) = bodies.get(&black_hole_id) else {
+            continue;
+        };
+        let CelestialType::BlackHole(data) = &black_hole.body_type else {
+            continue;
+        };
+        let horizon = fixed::to_f64(data.schwarzschild_radius);
+        let influence_radius = horizon * settings.influence_radius_factor;
+        let center = black_hole.physics.position;
+
+        let mut accreted_mass = 0.0;
+        for body_id in sorted_body_ids(bodies) {
+            if body_id == black_hole_id {
+                continue;
+            }
+            let body = &bodies[&body_id];
+            let distance = (body.physics.position - center).magnitude();
+
+            // 事象の地平面を越えた天体は丸ごと吸収する
+            let stripped: Fixed = if distance < horizon {
+                body.physics.mass
+            } else if distance < influence_radius && is_diffuse(&body.body_type) {
+                let fraction = (settings.stripping_rate * (horizon / distance).powi(2) * delta_time).min(1.0);
+                let stripped = (body.physics.mass as f64 * fraction) as Fixed;
+                if fixed::to_f64(body.physics.mass - stripped) < settings.min_remnant_mass {
+                    body.physics.mass
+                } else {
+                    stripped
+                }
+            } else {
+                continue;
+            };
+            if stripped <= 0 {
+                continue;
+            }
+
+            let velocity = body.physics.velocity;
+            let fully_absorbed = stripped >= body.physics.mass;
+            if fully_absorbed {
+                bodies.remove(&body_id);
+                absorptions.push(Absorption { black_hole_id, body_id });
+            } else if let Some(body) = bodies.get_mut(&body_id) {
+                // 剥ぎ取られたガスは天体と同じ速度で運ばれるので、残りの速度は変わらない
+                body.physics.mass -= stripped;
+            }
+
+            if let Some(black_hole) = bodies.get_mut(&black_hole_id) {
+                add_mass(black_hole, stripped, velocity);
+            }
+            accreted_mass += fixed::to_f64(stripped);
+        }
+
+        // 実際の降着量から降着率（質量/秒）を平滑化して更新する
+        if let Some(CelestialType::BlackHole(data)) = bodies.get_mut(&black_hole_id).map(|b| &mut b.body_type) {
+            let weight = 1.0 - (-delta_time / settings.rate_time_constant.max(f64::MIN_POSITIVE)).exp();
+            let current = fixed::to_f64(data.accretion_rate);
+            let measured = accreted_mass / delta_time;
+            data.accretion_rate = fixed::from_f64(current + (measured - current) * weight);
+        }
+    }
+
+    absorptions
+}
+
+/// ブラックホールに質量を加える（運動量を保存し、半径を質量に比例して広げる）
+pub fn add_mass(black_hole: &mut CelestialBody, mass: Fixed, velocity: Vec3Fixed) {
+    let old_mass = fixed::to_f64(black_hole.physics.mass);
+    let added = fixed::to_f64(mass);
+    let new_mass = old_mass + added;
+    if new_mass <= 0.0 {
+        return;
+    }
+
+    black_hole.physics.velocity = (black_hole.physics.velocity * old_mass + velocity * added) / new_mass;
+    black_hole.physics.mass = black_hole.physics.mass.saturating_add(mass);
+
+    if old_mass > 0.0 {
+        let scale = new_mass / old_mass;
+        black_hole.physics.radius = fixed::from_f64(fixed::to_f64(black_hole.physics.radius) * scale);
+        if let CelestialType::BlackHole(data) = &mut black_hole.body_type {
+            data.schwarzschild_radius = fixed::from_f64(fixed::to_f64(data.schwarzschild_radius) * scale);
+        }
+    }
+}
+
+/// 剥ぎ取られるガスや塵を持つ天体
+fn is_diffuse(body_type: &CelestialType) -> bool {
+    match body_type {
+        CelestialType::Comet | CelestialType::Asteroid => true,
+        CelestialType::Planet(planet) => matches!(planet.planet_type, PlanetType::GasGiant | PlanetType::IceGiant),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::game::celestial_bodies::BlackHoleData;
+
+    fn black_hole() -> CelestialBody {
+        let id = Uuid::new_v4();
+        CelestialBody::new(
+            id,
+            CelestialType::BlackHole(BlackHoleData {
+                schwarzschild_radius: fixed::from_f64(2.0),
+                accretion_rate: 0,
+                formation_time: Utc::now(),
+            }),
+            Vec3Fixed::zeros(),
+            fixed::from_f64(1.0e6),
+            fixed::from_f64(2.0),
+        )
+    }
+
+    fn body(body_type: CelestialType, position: Vec3Fixed, mass: f64) -> CelestialBody {
+        CelestialBody::new(Uuid::new_v4(), body_type, position, fixed::from_f64(mass), fixed::from_f64(0.1))
+    }
+
+    fn accretion_rate(body: &CelestialBody) -> f64 {
+        match &body.body_type {
+            CelestialType::BlackHole(data) => fixed::to_f64(data.accretion_rate),
+            _ => panic!("not a black hole"),
+        }
+    }
+
+    #[test]
+    fn test_body_inside_horizon_is_absorbed() {
+        let mut bodies = HashMap::new();
+        let hole = black_hole();
+        let hole_id = hole.id;
+        bodies.insert(hole_id, hole);
+        let mut moon = body(CelestialType::Moon, Vec3Fixed::new(1.5, 0.0, 0.0), 1000.0);
+        moon.physics.velocity = Vec3Fixed::new(0.0, 10.0, 0.0);
+        let moon_id = moon.id;
+        bodies.insert(moon_id, moon);
+
+        let absorptions = process_accretion(&mut bodies, 0.05, &AccretionSettings::default());
+
+        assert_eq!(absorptions, vec![Absorption { black_hole_id: hole_id, body_id: moon_id }]);
+        let hole = &bodies[&hole_id];
+        assert!((fixed::to_f64(hole.physics.mass) - 1.001e6).abs() < 1e-3);
+        assert!((hole.physics.velocity.y * 1.001e6 - 1.0e4).abs() < 1e-3);
+        assert!(fixed::to_f64(hole.physics.radius) > 2.0);
+        assert!(accretion_rate(hole) > 0.0);
+    }
+
+    #[test]
+    fn test_nearby_dust_raises_accretion_rate() {
+        let mut bodies = HashMap::new();
+        let hole = black_hole();
+        let hole_id = hole.id;
+        bodies.insert(hole_id, hole);
+        // 影響範囲外の小惑星と、範囲内だが剥ぎ取られない岩石の衛星
+        let far = body(CelestialType::Asteroid, Vec3Fixed::new(100.0, 0.0, 0.0), 1000.0);
+        let far_id = far.id;
+        bodies.insert(far_id, far);
+        bodies.insert(Uuid::new_v4(), body(CelestialType::Moon, Vec3Fixed::new(0.0, 10.0, 0.0), 1000.0));
+
+        let settings = AccretionSettings::default();
+        for _ in 0..20 {
+            process_accretion(&mut bodies, 0.05, &settings);
+        }
+        assert_eq!(accretion_rate(&bodies[&hole_id]), 0.0);
+        assert_eq!(fixed::to_f64(bodies[&far_id].physics.mass), 1000.0);
+
+        let comet = body(CelestialType::Comet, Vec3Fixed::new(0.0, 0.0, 10.0), 1000.0);
+        let comet_id = comet.id;
+        bodies.insert(comet_id, comet);
+        for _ in 0..20 {
+            process_accretion(&mut bodies, 0.05, &settings);
+        }
+
+        assert!(accretion_rate(&bodies[&hole_id]) > 0.0);
+        assert!(fixed::to_f64(bodies[&comet_id].physics.mass) < 1000.0);
+    }
+}
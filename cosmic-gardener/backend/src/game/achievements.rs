@@ -0,0 +1,552 @@
+//! 実績システム
+//!
+//! ゲームイベントを購読し、`config/achievements.toml`で宣言された条件
+//! （カウンター・しきい値・シーケンス）を評価して実績を解除する。
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::errors::{GameError, Result};
+use crate::game::celestial_bodies::{CelestialBodyManager, LifeStage};
+use crate::game::resources::{ResourceManager, ResourceType, Resources};
+
+/// 組み込みの実績カタログ
+const BUILTIN_CATALOG: &str = include_str!("../../config/achievements.toml");
+
+/// 実績ID
+pub type AchievementId = String;
+
+/// 実績判定に使うイベントの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerKind {
+    BodyCreated,
+    BodyDestroyed,
+    LifeEvolved,
+    Extinction,
+    TechAdvanced,
+    UpgradePurchased,
+    Collision,
+    BodyAbsorbed,
+    BodyLeftBounds,
+    StellarEvolved,
+    Supernova,
+    ResourceMilestone,
+    ResearchUnlocked,
+    PrestigePerformed,
+    OfflineProgress,
+    GameLoaded,
+}
+
+/// 実績判定の入力となるイベント
+///
+/// `subject`は天体タイプ名・生命段階名・技術IDなど、イベントの対象を表す。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AchievementTrigger {
+    pub kind: TriggerKind,
+    pub subject: Option<String>,
+}
+
+impl AchievementTrigger {
+    pub fn new(kind: TriggerKind) -> Self {
+        Self { kind, subject: None }
+    }
+
+    pub fn with_subject(kind: TriggerKind, subject: impl Into<String>) -> Self {
+        Self { kind, subject: Some(subject.into()) }
+    }
+}
+
+/// イベントの一致条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventFilter {
+    pub kind: TriggerKind,
+    /// 指定した場合は対象も一致する必要がある
+    #[serde(default)]
+    pub subject: Option<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, trigger: &AchievementTrigger) -> bool {
+        self.kind == trigger.kind
+            && self.subject.as_ref().map_or(true, |subject| trigger.subject.as_ref() == Some(subject))
+    }
+}
+
+/// しきい値判定の対象となるプレイヤー指標
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AchievementMetric {
+    /// 累計獲得リソース
+    LifetimeResource { resource: ResourceType },
+    /// 現在の天体数
+    BodyCount,
+    /// 生命が存在する天体数
+    LifeBearingBodies,
+    /// 研究済みの技術数
+    ResearchUnlocked,
+    /// プレステージ回数
+    PrestigeCount,
+}
+
+/// 実績の解除条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AchievementCondition {
+    /// 一致するイベントが`count`回発生
+    Counter { event: EventFilter, count: u64 },
+    /// 指標が`value`以上
+    Threshold { metric: AchievementMetric, value: f64 },
+    /// イベントを定義順に達成（途中の無関係なイベントは無視）
+    Sequence {
+        steps: Vec<EventFilter>,
+        /// 最初のステップからの制限時間
+        #[serde(default)]
+        within_ms: Option<u64>,
+    },
+}
+
+/// 実績定義
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementDefinition {
+    pub id: AchievementId,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub points: u32,
+    pub condition: AchievementCondition,
+}
+
+/// 実績カタログ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementCatalog {
+    pub achievements: Vec<AchievementDefinition>,
+}
+
+impl Default for AchievementCatalog {
+    fn default() -> Self {
+        Self::from_toml(BUILTIN_CATALOG).expect("Built-in achievement catalog must be valid")
+    }
+}
+
+impl AchievementCatalog {
+    /// TOML文字列から読み込み（検証込み）
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let catalog: Self = toml::from_str(content)
+            .map_err(|e| GameError::validation(format!("Failed to parse achievement catalog: {}", e)))?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    /// ファイルから読み込み（検証込み）
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            GameError::validation(format!("Failed to read achievement catalog {}: {}", path.display(), e))
+        })?;
+        Self::from_toml(&content)
+    }
+
+    /// カタログの検証
+    pub fn validate(&self) -> Result<()> {
+        let mut ids = HashSet::new();
+        for achievement in &self.achievements {
+            if !ids.insert(achievement.id.as_str()) {
+                return Err(GameError::validation(format!("Duplicate achievement id: {}", achievement.id)));
+            }
+
+            let valid = match &achievement.condition {
+                AchievementCondition::Counter { count, .. } => *count > 0,
+                AchievementCondition::Threshold { value, .. } => value.is_finite() && *value >= 0.0,
+                AchievementCondition::Sequence { steps, within_ms } => {
+                    !steps.is_empty() && within_ms.map_or(true, |ms| ms > 0)
+                }
+            };
+            if !valid {
+                return Err(GameError::validation(format!(
+                    "{}: invalid condition {:?}", achievement.id, achievement.condition
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&AchievementDefinition> {
+        self.achievements.iter().find(|achievement| achievement.id == id)
+    }
+}
+
+/// シーケンス条件の進行状況
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SequenceProgress {
+    pub step: usize,
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+/// プレイヤーごとの実績進行状況（スナップショットに保存される）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AchievementProgress {
+    pub unlocked: BTreeMap<AchievementId, DateTime<Utc>>,
+    pub counters: BTreeMap<AchievementId, u64>,
+    pub sequences: BTreeMap<AchievementId, SequenceProgress>,
+}
+
+/// 実績解除の通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementUnlock {
+    pub achievement_id: AchievementId,
+    pub name: String,
+    pub description: String,
+    pub points: u32,
+    pub unlocked_at: DateTime<Utc>,
+}
+
+/// クライアント表示用の実績状態
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementStatus {
+    pub id: AchievementId,
+    pub name: String,
+    pub description: String,
+    pub points: u32,
+    pub unlocked_at: Option<DateTime<Utc>>,
+    /// 進捗率（0.0-1.0）
+    pub progress: f64,
+}
+
+/// しきい値判定用のプレイヤー指標
+#[derive(Debug, Clone, Default)]
+pub struct PlayerMetrics {
+    pub lifetime_totals: Resources,
+    pub body_count: usize,
+    pub life_bearing_bodies: usize,
+    pub research_unlocked: usize,
+    pub prestige_count: u32,
+}
+
+impl PlayerMetrics {
+    /// 現在の状態から指標を収集
+    pub fn collect(
+        resource_manager: &ResourceManager,
+        celestial_manager: &CelestialBodyManager,
+        research_unlocked: usize,
+    ) -> Self {
+        let game_state = resource_manager.get_game_state();
+        let life_bearing_bodies = celestial_manager
+            .get_all_bodies()
+            .values()
+            .filter(|body| !matches!(body.lifecycle.life_stage, LifeStage::None))
+            .count();
+
+        Self {
+            lifetime_totals: game_state.lifetime_totals.clone(),
+            body_count: celestial_manager.get_body_count(),
+            life_bearing_bodies,
+            research_unlocked,
+            prestige_count: game_state.prestige.prestige_count,
+        }
+    }
+
+    pub fn value(&self, metric: &AchievementMetric) -> f64 {
+        match metric {
+            AchievementMetric::LifetimeResource { resource } => self.lifetime_totals.get(*resource) as f64,
+            AchievementMetric::BodyCount => self.body_count as f64,
+            AchievementMetric::LifeBearingBodies => self.life_bearing_bodies as f64,
+            AchievementMetric::ResearchUnlocked => self.research_unlocked as f64,
+            AchievementMetric::PrestigeCount => self.prestige_count as f64,
+        }
+    }
+}
+
+/// プレイヤーごとの実績トラッカー
+#[derive(Debug, Clone)]
+pub struct AchievementTracker {
+    catalog: Arc<AchievementCatalog>,
+    progress: AchievementProgress,
+}
+
+impl AchievementTracker {
+    pub fn new(catalog: Arc<AchievementCatalog>) -> Self {
+        Self {
+            catalog,
+            progress: AchievementProgress::default(),
+        }
+    }
+
+    pub fn catalog(&self) -> &Arc<AchievementCatalog> {
+        &self.catalog
+    }
+
+    pub fn progress(&self) -> &AchievementProgress {
+        &self.progress
+    }
+
+    /// 進行状況の復元
+    pub fn set_progress(&mut self, progress: AchievementProgress) {
+        self.progress = progress;
+    }
+
+    /// 永続化済みの解除記録を取り込む（データベースの記録を優先）
+    pub fn merge_unlocked(&mut self, unlocked: impl IntoIterator<Item = (AchievementId, DateTime<Utc>)>) {
+        for (id, unlocked_at) in unlocked {
+            self.progress.unlocked.insert(id, unlocked_at);
+        }
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.progress.unlocked.contains_key(id)
+    }
+
+    /// イベントを記録し、解除された実績を返す
+    pub fn record(&mut self, trigger: &AchievementTrigger, now: DateTime<Utc>) -> Vec<AchievementUnlock> {
+        let catalog = Arc::clone(&self.catalog);
+        let mut unlocks = Vec::new();
+
+        for achievement in &catalog.achievements {
+            if self.is_unlocked(&achievement.id) {
+                continue;
+            }
+
+            let completed = match &achievement.condition {
+                AchievementCondition::Counter { event, count } => {
+                    if !event.matches(trigger) {
+                        continue;
+                    }
+                    let counter = self.progress.counters.entry(achievement.id.clone()).or_insert(0);
+                    *counter += 1;
+                    *counter >= *count
+                }
+                AchievementCondition::Sequence { steps, within_ms } => {
+                    let sequence = self.progress.sequences.entry(achievement.id.clone()).or_default();
+                    Self::advance_sequence(sequence, steps, *within_ms, trigger, now)
+                }
+                AchievementCondition::Threshold { .. } => false,
+            };
+
+            if completed {
+                unlocks.push(self.unlock(achievement, now));
+            }
+        }
+
+        unlocks
+    }
+
+    /// しきい値条件を評価し、解除された実績を返す
+    pub fn evaluate(&mut self, metrics: &PlayerMetrics, now: DateTime<Utc>) -> Vec<AchievementUnlock> {
+        let catalog = Arc::clone(&self.catalog);
+        let mut unlocks = Vec::new();
+
+        for achievement in &catalog.achievements {
+            if let AchievementCondition::Threshold { metric, value } = &achievement.condition {
+                if !self.is_unlocked(&achievement.id) && metrics.value(metric) >= *value {
+                    unlocks.push(self.unlock(achievement, now));
+                }
+            }
+        }
+
+        unlocks
+    }
+
+    /// 全実績の状態
+    pub fn statuses(&self, metrics: &PlayerMetrics) -> Vec<AchievementStatus> {
+        self.catalog
+            .achievements
+            .iter()
+            .map(|achievement| {
+                let unlocked_at = self.progress.unlocked.get(&achievement.id).copied();
+                let progress = if unlocked_at.is_some() {
+                    1.0
+                } else {
+                    match &achievement.condition {
+                        AchievementCondition::Counter { count, .. } => {
+                            self.progress.counters.get(&achievement.id).copied().unwrap_or(0) as f64 / *count as f64
+                        }
+                        AchievementCondition::Threshold { metric, value } if *value > 0.0 => {
+                            metrics.value(metric) / value
+                        }
+                        AchievementCondition::Threshold { .. } => 0.0,
+                        AchievementCondition::Sequence { steps, .. } => {
+                            let step = self.progress.sequences.get(&achievement.id).map_or(0, |sequence| sequence.step);
+                            step as f64 / steps.len() as f64
+                        }
+                    }
+                };
+
+                AchievementStatus {
+                    id: achievement.id.clone(),
+                    name: achievement.name.clone(),
+                    description: achievement.description.clone(),
+                    points: achievement.points,
+                    unlocked_at,
+                    progress: progress.clamp(0.0, 1.0),
+                }
+            })
+            .collect()
+    }
+
+    /// シーケンスを1イベント分進める（完了した場合はtrue）
+    fn advance_sequence(
+        sequence: &mut SequenceProgress,
+        steps: &[EventFilter],
+        within_ms: Option<u64>,
+        trigger: &AchievementTrigger,
+        now: DateTime<Utc>,
+    ) -> bool {
+        // 制限時間切れの場合は最初からやり直す
+        if let (Some(limit), Some(started_at)) = (within_ms, sequence.started_at) {
+            if now.signed_duration_since(started_at).num_milliseconds() > limit as i64 {
+                *sequence = SequenceProgress::default();
+            }
+        }
+
+        if !steps[sequence.step].matches(trigger) {
+            return false;
+        }
+
+        if sequence.step == 0 {
+            sequence.started_at = Some(now);
+        }
+        sequence.step += 1;
+
+        sequence.step >= steps.len()
+    }
+
+    fn unlock(&mut self, achievement: &AchievementDefinition, now: DateTime<Utc>) -> AchievementUnlock {
+        self.progress.unlocked.insert(achievement.id.clone(), now);
+        self.progress.counters.remove(&achievement.id);
+        self.progress.sequences.remove(&achievement.id);
+
+        info!("[ACHIEVEMENTS] Achievement unlocked: {} ({} points)", achievement.id, achievement.points);
+
+        AchievementUnlock {
+            achievement_id: achievement.id.clone(),
+            name: achievement.name.clone(),
+            description: achievement.description.clone(),
+            points: achievement.points,
+            unlocked_at: now,
+        }
+    }
+}
+
+/// 実績設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementConfig {
+    pub catalog_path: String,
+}
+
+impl Default for AchievementConfig {
+    fn default() -> Self {
+        Self {
+            catalog_path: "config/achievements.toml".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn tracker() -> AchievementTracker {
+        AchievementTracker::new(Arc::new(AchievementCatalog::default()))
+    }
+
+    fn body_created(body_type: &str) -> AchievementTrigger {
+        AchievementTrigger::with_subject(TriggerKind::BodyCreated, body_type)
+    }
+
+    #[test]
+    fn test_builtin_catalog_is_valid() {
+        let catalog = AchievementCatalog::default();
+        assert!(catalog.get("first_star").is_some());
+        assert!(catalog.get("cradle_of_life").is_some());
+    }
+
+    #[test]
+    fn test_invalid_catalog_rejected() {
+        let duplicate = r#"
+            [[achievements]]
+            id = "a"
+            name = "A"
+            condition = { type = "counter", count = 1, event = { kind = "collision" } }
+
+            [[achievements]]
+            id = "a"
+            name = "A"
+            condition = { type = "counter", count = 1, event = { kind = "collision" } }
+        "#;
+        assert!(AchievementCatalog::from_toml(duplicate).is_err());
+
+        let zero_count = r#"
+            [[achievements]]
+            id = "a"
+            name = "A"
+            condition = { type = "counter", count = 0, event = { kind = "collision" } }
+        "#;
+        assert!(AchievementCatalog::from_toml(zero_count).is_err());
+    }
+
+    #[test]
+    fn test_counter_unlocks_once() {
+        let mut tracker = tracker();
+        let now = Utc::now();
+
+        // 恒星以外は数えない
+        assert!(tracker.record(&body_created("Asteroid"), now).is_empty());
+
+        let unlocks = tracker.record(&body_created("Star"), now);
+        assert!(unlocks.iter().any(|unlock| unlock.achievement_id == "first_star"));
+        assert!(tracker.is_unlocked("first_star"));
+
+        // 解除済みの実績は再度通知されない
+        let unlocks = tracker.record(&body_created("Star"), now);
+        assert!(!unlocks.iter().any(|unlock| unlock.achievement_id == "first_star"));
+    }
+
+    #[test]
+    fn test_threshold_from_metrics() {
+        let mut tracker = tracker();
+        let mut metrics = PlayerMetrics::default();
+        metrics.lifetime_totals.dark_matter = 999;
+
+        assert!(tracker.evaluate(&metrics, Utc::now()).is_empty());
+        let status = tracker.statuses(&metrics).into_iter().find(|status| status.id == "dark_matter_adept").unwrap();
+        assert!((status.progress - 0.999).abs() < 1e-9);
+
+        metrics.lifetime_totals.dark_matter = 1000;
+        let unlocks = tracker.evaluate(&metrics, Utc::now());
+        assert_eq!(unlocks.len(), 1);
+        assert_eq!(unlocks[0].achievement_id, "dark_matter_adept");
+    }
+
+    #[test]
+    fn test_sequence_order_and_time_limit() {
+        let mut tracker = tracker();
+        let start = Utc::now();
+        let life = AchievementTrigger::with_subject(TriggerKind::LifeEvolved, "Microbial");
+
+        // 順序が違う場合は進まない
+        tracker.record(&body_created("Planet"), start);
+        assert_eq!(tracker.progress().sequences["cradle_of_life"].step, 0);
+
+        tracker.record(&body_created("Star"), start);
+        tracker.record(&body_created("Planet"), start + Duration::minutes(10));
+
+        // 制限時間（1時間）を過ぎるとリセット
+        tracker.record(&life, start + Duration::hours(2));
+        assert!(!tracker.is_unlocked("cradle_of_life"));
+        assert_eq!(tracker.progress().sequences["cradle_of_life"].step, 0);
+
+        let restart = start + Duration::hours(3);
+        tracker.record(&body_created("Star"), restart);
+        tracker.record(&body_created("Planet"), restart);
+        let unlocks = tracker.record(&life, restart + Duration::minutes(30));
+        assert!(unlocks.iter().any(|unlock| unlock.achievement_id == "cradle_of_life"));
+        assert_eq!(tracker.progress().unlocked["cradle_of_life"], restart + Duration::minutes(30));
+    }
+}
@@ -6,11 +6,22 @@ use uuid::Uuid;
 use tracing::{info, warn, error, debug};
 
 use crate::errors::{GameError, Result};
-use crate::game::resources::{Resources, ProductionRates, Fixed, fixed};
+use crate::game::resources::{Resources, ResourceType, ProductionRates, BodyContribution, Fixed, fixed};
+use crate::game::determinism::{sorted_body_ids, DeterministicRng};
+use crate::game::orbital::OrbitalElements;
+use crate::game::physics::GRAVITATIONAL_CONSTANT;
+use crate::game::stellar_evolution::{self, StellarPhase, StellarTransition};
+use crate::game::habitability::{self, HabitabilityBreakdown, HabitabilitySettings, LightSource, LIFE_EMERGENCE_THRESHOLD};
+use crate::game::life::{self, ExtinctionCause, LifeTransition};
+use crate::game::production_modifiers::{self, ProductionBreakdown, ProductionModifier, ProductionModifierSettings};
+use crate::game::spatial_index::SpatialIndex;
 
 /// 天体のID
 pub type BodyId = Uuid;
 
+/// 1太陽質量の恒星の半径（実際の縮尺ではワールドに惑星系が収まらないため縮小している）
+pub const SOLAR_RADIUS: f64 = 20_000.0;
+
 /// 3D位置ベクトル（固定小数点）
 pub type Vec3Fixed = Vector3<f64>;
 
@@ -29,6 +40,52 @@ pub enum CelestialType {
     DwarfPlanet,
 }
 
+impl CelestialType {
+    /// 天体タイプ名の取得
+    pub fn name(&self) -> &'static str {
+        self.category().name()
+    }
+    
+    /// 天体の分類
+    pub fn category(&self) -> BodyCategory {
+        match self {
+            CelestialType::Star(_) => BodyCategory::Star,
+            CelestialType::Planet(_) => BodyCategory::Planet,
+            CelestialType::BlackHole(_) => BodyCategory::BlackHole,
+            CelestialType::Asteroid => BodyCategory::Asteroid,
+            CelestialType::Comet => BodyCategory::Comet,
+            CelestialType::Moon => BodyCategory::Moon,
+            CelestialType::DwarfPlanet => BodyCategory::DwarfPlanet,
+        }
+    }
+}
+
+/// 天体の分類（作成制限とコストの単位。スペクトル型や大気などの違いは区別しない）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BodyCategory {
+    Star,
+    Planet,
+    BlackHole,
+    Asteroid,
+    Comet,
+    Moon,
+    DwarfPlanet,
+}
+
+impl BodyCategory {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BodyCategory::Star => "Star",
+            BodyCategory::Planet => "Planet",
+            BodyCategory::BlackHole => "BlackHole",
+            BodyCategory::Asteroid => "Asteroid",
+            BodyCategory::Comet => "Comet",
+            BodyCategory::Moon => "Moon",
+            BodyCategory::DwarfPlanet => "DwarfPlanet",
+        }
+    }
+}
+
 /// 恒星データ
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct StarData {
@@ -37,6 +94,8 @@ pub struct StarData {
     pub luminosity: Fixed,
     pub age: u64,
     pub lifespan: u64,
+    #[serde(default)]
+    pub phase: StellarPhase,
 }
 
 /// スペクトル型
@@ -78,7 +137,8 @@ pub struct PlanetData {
     pub atmosphere: AtmosphereType,
     pub water_coverage: u8, // 0-100
     pub temperature_range: (i16, i16),
-    pub habitability: u8, // 0-100
+    /// 最後に評価した居住可能性（0-100、主星との位置関係などから毎回計算し直す）
+    pub habitability: u8,
 }
 
 /// 惑星タイプ
@@ -158,6 +218,19 @@ pub enum LifeStage {
     },
 }
 
+impl LifeStage {
+    /// 段階名の取得
+    pub fn name(&self) -> &'static str {
+        match self {
+            LifeStage::None => "None",
+            LifeStage::Microbial { .. } => "Microbial",
+            LifeStage::Plant { .. } => "Plant",
+            LifeStage::Animal { .. } => "Animal",
+            LifeStage::Intelligent { .. } => "Intelligent",
+        }
+    }
+}
+
 /// ライフサイクルデータ
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LifecycleData {
@@ -186,6 +259,9 @@ pub struct BodyResources {
     pub production_rates: ProductionRates,
     pub resource_multiplier: Fixed,
     pub efficiency: Fixed,
+    /// 周囲の天体による生産補正（リソース生成の更新ごとに評価し直す）
+    #[serde(default)]
+    pub modifiers: Vec<ProductionModifier>,
 }
 
 impl BodyResources {
@@ -194,7 +270,20 @@ impl BodyResources {
             production_rates: ProductionRates::new(),
             resource_multiplier: fixed::from_f64(1.0),
             efficiency: fixed::from_f64(1.0),
+            modifiers: Vec::new(),
+        }
+    }
+    
+    /// 生産補正と倍率・効率を適用した実効生産レート
+    pub fn effective_rates(&self) -> ProductionRates {
+        let factor = fixed::to_f64(self.resource_multiplier) * fixed::to_f64(self.efficiency);
+        let modified = production_modifiers::apply(&self.production_rates, &self.modifiers);
+        let mut rates = ProductionRates::new();
+        for resource_type in ResourceType::all() {
+            let rate = fixed::to_f64(modified.get(resource_type)) * factor;
+            rates.set(resource_type, fixed::from_f64(rate));
         }
+        rates
     }
 }
 
@@ -206,6 +295,9 @@ pub struct CelestialBody {
     pub physics: PhysicsData,
     pub lifecycle: LifecycleData,
     pub resources: BodyResources,
+    /// 周回している親天体
+    #[serde(default)]
+    pub parent_id: Option<BodyId>,
     pub created_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
 }
@@ -225,6 +317,7 @@ impl CelestialBody {
             physics: PhysicsData::new(position, mass, radius),
             lifecycle: LifecycleData::new(),
             resources: BodyResources::new(),
+            parent_id: None,
             created_at: now,
             last_updated: now,
         }
@@ -235,56 +328,40 @@ impl CelestialBody {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreationLimits {
     pub max_bodies: usize,
-    pub max_bodies_per_type: HashMap<CelestialType, usize>,
+    /// 種類別の上限（未設定の種類は総数上限のみ）
+    pub max_bodies_per_type: HashMap<BodyCategory, usize>,
     pub min_separation: Fixed,
     pub max_position: Fixed,
-    pub creation_costs: HashMap<CelestialType, Resources>,
+    /// 種類別の作成コスト（未設定の種類は無料）
+    pub creation_costs: HashMap<BodyCategory, Resources>,
 }
 
 impl Default for CreationLimits {
     fn default() -> Self {
-        let mut max_bodies_per_type = HashMap::new();
-        max_bodies_per_type.insert(CelestialType::Star(StarData {
-            spectral_type: SpectralType::G,
-            temperature: 5778,
-            luminosity: fixed::from_f64(1.0),
-            age: 0,
-            lifespan: 10_000_000,
-        }), 50);
-        max_bodies_per_type.insert(CelestialType::Planet(PlanetData {
-            planet_type: PlanetType::Rocky,
-            atmosphere: AtmosphereType::None,
-            water_coverage: 0,
-            temperature_range: (0, 0),
-            habitability: 0,
-        }), 200);
-        max_bodies_per_type.insert(CelestialType::BlackHole(BlackHoleData {
-            schwarzschild_radius: fixed::from_f64(1.0),
-            accretion_rate: fixed::from_f64(0.1),
-            formation_time: Utc::now(),
-        }), 10);
-        max_bodies_per_type.insert(CelestialType::Asteroid, 1000);
-        max_bodies_per_type.insert(CelestialType::Comet, 1000);
-        max_bodies_per_type.insert(CelestialType::Moon, 500);
-        max_bodies_per_type.insert(CelestialType::DwarfPlanet, 100);
-        
-        let mut creation_costs = HashMap::new();
-        creation_costs.insert(CelestialType::Asteroid, Resources {
-            cosmic_dust: 100,
-            ..Default::default()
-        });
-        creation_costs.insert(CelestialType::Comet, Resources {
-            cosmic_dust: 500,
-            ..Default::default()
-        });
-        creation_costs.insert(CelestialType::Moon, Resources {
-            cosmic_dust: 1000,
-            ..Default::default()
-        });
-        creation_costs.insert(CelestialType::DwarfPlanet, Resources {
-            cosmic_dust: 2500,
-            ..Default::default()
-        });
+        let max_bodies_per_type = HashMap::from([
+            (BodyCategory::Star, 50),
+            (BodyCategory::Planet, 200),
+            (BodyCategory::BlackHole, 10),
+            (BodyCategory::Asteroid, 1000),
+            (BodyCategory::Comet, 1000),
+            (BodyCategory::Moon, 500),
+            (BodyCategory::DwarfPlanet, 100),
+        ]);
+        
+        let dust = |cosmic_dust| Resources { cosmic_dust, ..Default::default() };
+        let creation_costs = HashMap::from([
+            (BodyCategory::Star, dust(10000)),
+            (BodyCategory::Planet, dust(5000)),
+            (BodyCategory::BlackHole, Resources {
+                cosmic_dust: 100000,
+                energy: 10000,
+                ..Default::default()
+            }),
+            (BodyCategory::Asteroid, dust(100)),
+            (BodyCategory::Comet, dust(500)),
+            (BodyCategory::Moon, dust(1000)),
+            (BodyCategory::DwarfPlanet, dust(2500)),
+        ]);
         
         Self {
             max_bodies: 10000,
@@ -301,6 +378,20 @@ pub struct CelestialBodyManager {
     pub bodies: HashMap<BodyId, CelestialBody>,
     pub limits: CreationLimits,
     pub tick_duration_ms: u64,
+    /// 研究による天体数上限の増加
+    max_bodies_bonus: usize,
+    /// 研究による種類別上限の増加
+    type_limit_bonuses: HashMap<BodyCategory, usize>,
+    /// 研究による作成コストの倍率
+    creation_cost_multiplier: f64,
+    /// 研究による生命進化速度の倍率
+    evolution_speed: f64,
+    /// 決定論モードの乱数（`None`の場合は天体IDをランダムに採番する）
+    rng: Option<DeterministicRng>,
+    habitability_settings: HabitabilitySettings,
+    production_settings: ProductionModifierSettings,
+    /// 物理演算と共有する空間インデックス（`bodies`を直接書き換えた場合は`sync_spatial_index`で同期する）
+    spatial_index: SpatialIndex,
 }
 
 impl CelestialBodyManager {
@@ -309,6 +400,156 @@ impl CelestialBodyManager {
             bodies: HashMap::new(),
             limits: CreationLimits::default(),
             tick_duration_ms,
+            max_bodies_bonus: 0,
+            type_limit_bonuses: HashMap::new(),
+            creation_cost_multiplier: 1.0,
+            evolution_speed: 1.0,
+            rng: None,
+            habitability_settings: HabitabilitySettings::default(),
+            production_settings: ProductionModifierSettings::default(),
+            spatial_index: SpatialIndex::new(),
+        }
+    }
+    
+    /// 天体数上限（研究ボーナス込み）
+    pub fn max_bodies(&self) -> usize {
+        self.limits.max_bodies + self.max_bodies_bonus
+    }
+    
+    /// 天体数上限ボーナスの設定
+    pub fn set_max_bodies_bonus(&mut self, bonus: usize) {
+        self.max_bodies_bonus = bonus;
+    }
+    
+    /// 種類別の天体数上限（研究ボーナス込み、`None`は総数上限のみ）
+    pub fn max_bodies_of(&self, category: BodyCategory) -> Option<usize> {
+        let limit = self.limits.max_bodies_per_type.get(&category)?;
+        Some(limit + self.type_limit_bonuses.get(&category).copied().unwrap_or(0))
+    }
+    
+    /// 種類別上限ボーナスの設定
+    pub fn set_type_limit_bonuses(&mut self, bonuses: HashMap<BodyCategory, usize>) {
+        self.type_limit_bonuses = bonuses;
+    }
+    
+    /// 種類別の天体数
+    pub fn count_bodies(&self, category: BodyCategory) -> usize {
+        self.bodies.values().filter(|body| body.body_type.category() == category).count()
+    }
+    
+    /// 作成コスト（研究による倍率込み、端数は切り上げ）
+    pub fn creation_cost(&self, category: BodyCategory) -> Resources {
+        let mut cost = self.limits.creation_costs.get(&category).cloned().unwrap_or_default();
+        for resource_type in ResourceType::all() {
+            let scaled = (cost.get(resource_type) as f64 * self.creation_cost_multiplier).ceil() as u64;
+            cost.set(resource_type, scaled);
+        }
+        cost
+    }
+    
+    /// 作成コスト倍率の設定
+    pub fn set_creation_cost_multiplier(&mut self, multiplier: f64) {
+        self.creation_cost_multiplier = multiplier;
+    }
+    
+    /// 生命進化速度の設定
+    pub fn set_evolution_speed(&mut self, speed: f64) {
+        self.evolution_speed = speed;
+    }
+    
+    /// 決定論モードの乱数の設定
+    pub fn set_rng(&mut self, rng: Option<DeterministicRng>) {
+        self.rng = rng;
+    }
+    
+    /// 決定論モードの乱数
+    pub fn rng(&self) -> Option<&DeterministicRng> {
+        self.rng.as_ref()
+    }
+    
+    /// 空間インデックス（近傍・範囲検索用）
+    pub fn spatial_index(&self) -> &SpatialIndex {
+        &self.spatial_index
+    }
+    
+    /// 空間インデックスを全天体と同期
+    pub fn sync_spatial_index(&mut self) {
+        self.spatial_index.sync(&self.bodies);
+    }
+    
+    /// 物理演算用に全天体と空間インデックスを同時に借用
+    pub fn bodies_and_index_mut(&mut self) -> (&mut HashMap<BodyId, CelestialBody>, &mut SpatialIndex) {
+        (&mut self.bodies, &mut self.spatial_index)
+    }
+    
+    /// 居住可能性評価のパラメータの設定
+    pub fn set_habitability_settings(&mut self, settings: HabitabilitySettings) {
+        self.habitability_settings = settings;
+    }
+    
+    /// 惑星の居住可能性の内訳（現在の天体配置から評価する）
+    pub fn evaluate_habitability(&self, body_id: BodyId) -> Option<HabitabilityBreakdown> {
+        let body = self.bodies.get(&body_id)?;
+        habitability::evaluate(body, &habitability::light_sources(&self.bodies), &self.habitability_settings)
+    }
+    
+    /// 全惑星の居住可能性の内訳
+    pub fn evaluate_all_habitability(&self) -> HashMap<BodyId, HabitabilityBreakdown> {
+        habitability::evaluate_all(&self.bodies, &self.habitability_settings)
+    }
+    
+    /// 生産補正のパラメータの設定
+    pub fn set_production_modifier_settings(&mut self, settings: ProductionModifierSettings) {
+        self.production_settings = settings;
+    }
+    
+    /// 天体の生産の内訳（補正は現在の天体配置から評価する）
+    pub fn production_breakdown(&self, body_id: BodyId) -> Option<ProductionBreakdown> {
+        let body = self.bodies.get(&body_id)?;
+        Some(self.breakdown_production(body, &habitability::light_sources(&self.bodies)))
+    }
+    
+    /// 全天体の生産の内訳
+    pub fn production_breakdowns(&self) -> HashMap<BodyId, ProductionBreakdown> {
+        let lights = habitability::light_sources(&self.bodies);
+        self.bodies.iter().map(|(id, body)| (*id, self.breakdown_production(body, &lights))).collect()
+    }
+    
+    /// 全天体の生産の合計（補正と倍率・効率を適用したレート）
+    pub fn total_production(&self) -> ProductionRates {
+        let mut total = ProductionRates::new();
+        for body in self.bodies.values() {
+            let rates = body.resources.effective_rates();
+            for resource_type in ResourceType::all() {
+                total.add(resource_type, rates.get(resource_type));
+            }
+        }
+        total
+    }
+    
+    /// 生産している天体ごとの寄与（ID順）
+    pub fn body_contributions(&self) -> Vec<BodyContribution> {
+        sorted_body_ids(&self.bodies)
+            .into_iter()
+            .filter_map(|id| {
+                let body = &self.bodies[&id];
+                let rates = body.resources.effective_rates();
+                ResourceType::all().into_iter().any(|resource_type| rates.get(resource_type) != 0).then(|| BodyContribution {
+                    body_id: id,
+                    category: body.body_type.category(),
+                    rates,
+                })
+            })
+            .collect()
+    }
+    
+    fn breakdown_production(&self, body: &CelestialBody, lights: &[LightSource]) -> ProductionBreakdown {
+        let modifiers = production_modifiers::evaluate(body, &self.bodies, &self.spatial_index, lights, &self.production_settings);
+        let resources = BodyResources { modifiers, ..body.resources.clone() };
+        ProductionBreakdown {
+            base_rates: resources.production_rates.clone(),
+            effective_rates: resources.effective_rates(),
+            modifiers: resources.modifiers,
         }
     }
     
@@ -323,16 +564,18 @@ impl CelestialBodyManager {
         self.validate_creation(&body_type, &position)?;
         
         // コストチェック
-        if let Some(cost) = self.limits.creation_costs.get(&body_type) {
-            if !resources.can_afford(cost) {
-                warn!("[CELESTIAL_BODIES] Insufficient resources for body creation");
-                return Err(GameError::InsufficientResources);
-            }
-            resources.spend(cost)?;
+        let cost = self.creation_cost(body_type.category());
+        if !resources.can_afford(&cost) {
+            warn!("[CELESTIAL_BODIES] Insufficient resources for {} creation", body_type.name());
+            return Err(GameError::InsufficientResources);
         }
+        resources.spend(&cost)?;
         
         // 天体の作成
-        let id = Uuid::new_v4();
+        let id = match self.rng.as_mut() {
+            Some(rng) => rng.next_uuid(),
+            None => Uuid::new_v4(),
+        };
         let (mass, radius) = self.calculate_body_properties(&body_type);
         
         let mut body = CelestialBody::new(id, body_type.clone(), position, mass, radius);
@@ -340,14 +583,70 @@ impl CelestialBodyManager {
         // 初期化
         self.initialize_body(&mut body);
         
+        self.spatial_index.insert(&body);
         self.bodies.insert(id, body);
         
         Ok(id)
     }
     
+    /// 親天体を周回する軌道上に天体を作成
+    ///
+    /// `orbit`の`central_mass`は無視され、親天体と新しい天体の質量の和で置き換えられる。
+    pub fn create_body_in_orbit(
+        &mut self,
+        body_type: CelestialType,
+        parent_id: BodyId,
+        orbit: OrbitalElements,
+        resources: &mut Resources,
+    ) -> Result<BodyId> {
+        let parent = self.bodies.get(&parent_id).ok_or_else(|| {
+            warn!("[CELESTIAL_BODIES] Parent body not found: {}", parent_id);
+            GameError::BodyNotFound
+        })?;
+        let (mass, radius) = self.calculate_body_properties(&body_type);
+
+        let orbit = OrbitalElements {
+            central_mass: fixed::to_f64(parent.physics.mass) + fixed::to_f64(mass),
+            ..orbit
+        };
+        let (relative_position, relative_velocity) = orbit.to_state_vector(GRAVITATIONAL_CONSTANT)?;
+
+        // 近点で親天体に接触する軌道は作らない
+        let min_periapsis = fixed::to_f64(parent.physics.radius + radius + self.limits.min_separation);
+        if orbit.periapsis() < min_periapsis {
+            warn!("[CELESTIAL_BODIES] Orbit periapsis too low: {} < {}", orbit.periapsis(), min_periapsis);
+            return Err(GameError::TooClose);
+        }
+
+        let position = parent.physics.position + relative_position;
+        let velocity = parent.physics.velocity + relative_velocity;
+
+        let id = self.create_body(body_type, position, resources)?;
+        if let Some(body) = self.bodies.get_mut(&id) {
+            body.physics.velocity = velocity;
+            body.parent_id = Some(parent_id);
+        }
+
+        Ok(id)
+    }
+
+    /// 親天体に対する現在の軌道要素
+    pub fn orbital_elements(&self, id: BodyId) -> Option<OrbitalElements> {
+        let body = self.bodies.get(&id)?;
+        let parent = self.bodies.get(&body.parent_id?)?;
+        OrbitalElements::from_state_vector(
+            &(body.physics.position - parent.physics.position),
+            &(body.physics.velocity - parent.physics.velocity),
+            fixed::to_f64(parent.physics.mass) + fixed::to_f64(body.physics.mass),
+            GRAVITATIONAL_CONSTANT,
+        )
+        .ok()
+    }
+    
     /// 天体の削除
     pub fn remove_body(&mut self, id: BodyId) -> Result<()> {
         if self.bodies.remove(&id).is_some() {
+            self.spatial_index.remove(id);
             Ok(())
         } else {
             warn!("[CELESTIAL_BODIES] Body not found: {}", id);
@@ -373,107 +672,79 @@ impl CelestialBodyManager {
     }
     
     /// 生命システムの更新
-    pub fn update_life_systems(&mut self, delta_time_ms: u64) {
+    ///
+    /// 生命の誕生・進化・後退・絶滅と技術の進歩を返す。
+    pub fn update_life_systems(&mut self, delta_time_ms: u64) -> Vec<LifeTransition> {
         let time_factor = delta_time_ms as f64 / self.tick_duration_ms as f64;
+        let mut transitions = Vec::new();
         
-        let body_ids: Vec<BodyId> = self.bodies.keys().copied().collect();
+        let body_ids = sorted_body_ids(&self.bodies);
+        let lights = habitability::light_sources(&self.bodies);
         
         for body_id in body_ids {
             if let Some(body) = self.bodies.get_mut(&body_id) {
                 body.lifecycle.age += delta_time_ms / self.tick_duration_ms;
                 
+                // 居住可能性は主星との位置関係が変わるので毎回評価し直す
+                let breakdown = habitability::evaluate(body, &lights, &self.habitability_settings);
+                if let (Some(breakdown), CelestialType::Planet(planet_data)) = (&breakdown, &mut body.body_type) {
+                    planet_data.habitability = breakdown.habitability;
+                }
+                
                 // 生命進化の更新
-                if let CelestialType::Planet(planet_data) = &body.body_type {
-                    if planet_data.habitability > 50 {
-                        self.update_life_evolution_for_body(body_id, time_factor);
+                if let Some(breakdown) = breakdown {
+                    if let Some(transition) = life::update_life(body, &breakdown, time_factor * self.evolution_speed) {
+                        debug!("[CELESTIAL_BODIES] Life {:?} on {}: {} -> {}",
+                            transition.kind, body_id, transition.from_stage.name(), transition.to_stage.name());
+                        transitions.push(transition);
                     }
                 }
                 
                 // リソース生成の更新
-                self.update_resource_production_for_body(body_id, time_factor);
+                self.update_resource_production_for_body(body_id, time_factor, &lights);
             }
         }
+        
+        transitions
     }
     
-    /// 生命進化の更新（単体）
-    fn update_life_evolution_for_body(&mut self, body_id: BodyId, time_factor: f64) {
-        Self::update_life_evolution(self.bodies.get_mut(&body_id), time_factor);
+    /// 天災による生命への被害（`severity`は失われる人口の割合）
+    pub fn apply_life_catastrophe(&mut self, body_id: BodyId, severity: f64, cause: ExtinctionCause) -> Vec<LifeTransition> {
+        let Some(body) = self.bodies.get_mut(&body_id) else {
+            return Vec::new();
+        };
+        let transitions = life::apply_catastrophe(body, severity, cause);
+        for transition in &transitions {
+            info!("[CELESTIAL_BODIES] Life on {} struck by {}: {} -> {}",
+                body_id, cause.name(), transition.from_stage.name(), transition.to_stage.name());
+        }
+        transitions
     }
     
-    /// 生命進化の更新
-    fn update_life_evolution(body_opt: Option<&mut CelestialBody>, time_factor: f64) {
-        if let Some(body) = body_opt {
-        body.lifecycle.evolution_timer += (time_factor * 1000.0) as u64;
-        
-        match &body.lifecycle.life_stage {
-            LifeStage::None => {
-                if body.lifecycle.evolution_timer > 1000 && body.lifecycle.population == 0 {
-                    // 微生物の発生
-                    body.lifecycle.life_stage = LifeStage::Microbial {
-                        diversity: 1,
-                        evolution_pressure: fixed::from_f64(0.1),
-                    };
-                    body.lifecycle.population = 1000;
-                    body.lifecycle.evolution_timer = 0;
-                }
-            },
-            LifeStage::Microbial { .. } => {
-                // 人口成長
-                body.lifecycle.population = (body.lifecycle.population as f64 * 1.01) as u64;
-                
-                // 植物への進化
-                if body.lifecycle.evolution_timer > 5000 && body.lifecycle.population > 100000 {
-                    body.lifecycle.life_stage = LifeStage::Plant {
-                        coverage: 10,
-                        oxygen_production: fixed::from_f64(0.1),
-                    };
-                    body.lifecycle.evolution_timer = 0;
-                }
-            },
-            LifeStage::Plant { .. } => {
-                // 人口成長
-                body.lifecycle.population = (body.lifecycle.population as f64 * 1.05) as u64;
-                
-                // 動物への進化
-                if body.lifecycle.evolution_timer > 10000 && body.lifecycle.population > 1000000 {
-                    body.lifecycle.life_stage = LifeStage::Animal {
-                        species_count: 10,
-                        food_chain_complexity: 1,
-                    };
-                    body.lifecycle.evolution_timer = 0;
-                }
-            },
-            LifeStage::Animal { .. } => {
-                // 人口成長
-                body.lifecycle.population = (body.lifecycle.population as f64 * 1.1) as u64;
-                
-                // 知的生命への進化
-                if body.lifecycle.evolution_timer > 20000 && body.lifecycle.population > 10000000 {
-                    body.lifecycle.life_stage = LifeStage::Intelligent {
-                        tech_level: 1,
-                        unity: 50,
-                        knowledge_rate: fixed::from_f64(0.5),
-                    };
-                    body.lifecycle.evolution_timer = 0;
-                }
-            },
-            LifeStage::Intelligent { .. } => {
-                // 人口成長（減速）
-                body.lifecycle.population = (body.lifecycle.population as f64 * 1.02) as u64;
-                
-                // 技術進歩
-                if body.lifecycle.evolution_timer > 30000 {
-                    // 技術レベルアップのロジック
-                    body.lifecycle.evolution_timer = 0;
-                }
-            },
-        }
+    /// 恒星進化の更新
+    ///
+    /// 進化段階が変化した恒星の遷移を返す。
+    pub fn update_stellar_evolution(&mut self, delta_time_ms: u64) -> Vec<StellarTransition> {
+        let transitions = stellar_evolution::evolve_stars(&mut self.bodies, delta_time_ms / self.tick_duration_ms);
+        for transition in &transitions {
+            // 巨星化や残骸化で半径が変わる
+            self.spatial_index.refresh(transition.body_id, &self.bodies);
+            info!("[CELESTIAL_BODIES] Star {} evolved: {} -> {}",
+                transition.body_id, transition.from_phase.name(), transition.to_phase.name());
         }
+        transitions
     }
     
     /// リソース生成の更新（単体）
-    fn update_resource_production_for_body(&mut self, body_id: BodyId, time_factor: f64) {
+    fn update_resource_production_for_body(&mut self, body_id: BodyId, time_factor: f64, lights: &[LightSource]) {
+        // 補正は他の天体の生産レートに依存しないので、更新の順序によらない
+        let modifiers = self.bodies.get(&body_id).map(|body| {
+            production_modifiers::evaluate(body, &self.bodies, &self.spatial_index, lights, &self.production_settings)
+        });
         Self::update_resource_production(self.bodies.get_mut(&body_id), time_factor);
+        if let (Some(body), Some(modifiers)) = (self.bodies.get_mut(&body_id), modifiers) {
+            body.resources.modifiers = modifiers;
+        }
     }
     
     /// リソース生成の更新
@@ -486,23 +757,28 @@ impl CelestialBodyManager {
                 body.resources.production_rates.energy_per_tick = energy_rate;
             },
             CelestialType::Planet(_) => {
-                // 惑星からの生命ベースリソース
-                match &body.lifecycle.life_stage {
-                    LifeStage::Plant { .. } => {
-                        body.resources.production_rates.organic_per_tick = fixed::from_f64(0.5);
-                        body.resources.production_rates.biomass_per_tick = fixed::from_f64(0.1);
-                    },
-                    LifeStage::Animal { .. } => {
-                        body.resources.production_rates.organic_per_tick = fixed::from_f64(0.8);
-                        body.resources.production_rates.biomass_per_tick = fixed::from_f64(0.3);
-                    },
-                    LifeStage::Intelligent { .. } => {
-                        body.resources.production_rates.organic_per_tick = fixed::from_f64(1.0);
-                        body.resources.production_rates.biomass_per_tick = fixed::from_f64(0.5);
-                        let thought_rate = fixed::from_f64((body.lifecycle.population as f64 / 1000000.0) * 0.1);
-                        body.resources.production_rates.thought_per_tick = thought_rate;
-                    },
-                    _ => {},
+                // 惑星からの生命ベースリソース（後退・絶滅した段階の生産は止まる）
+                let rates = &mut body.resources.production_rates;
+                let (organic, biomass) = match &body.lifecycle.life_stage {
+                    LifeStage::Plant { .. } => (0.5, 0.1),
+                    LifeStage::Animal { .. } => (0.8, 0.3),
+                    LifeStage::Intelligent { .. } => (1.0, 0.5),
+                    _ => (0.0, 0.0),
+                };
+                rates.organic_per_tick = fixed::from_f64(organic);
+                rates.biomass_per_tick = fixed::from_f64(biomass);
+                
+                // 知的生命の思考ポイントは団結度に、技術レベルはボーナスの解放に影響する
+                if let LifeStage::Intelligent { tech_level, unity, knowledge_rate } = &body.lifecycle.life_stage {
+                    let bonuses = life::tech_bonuses(*tech_level);
+                    let thought_rate = life::thought_rate(body.lifecycle.population, fixed::to_f64(*knowledge_rate), *unity);
+                    rates.thought_per_tick = fixed::from_f64(thought_rate);
+                    rates.energy_per_tick = fixed::from_f64(bonuses.energy_per_tick);
+                    rates.dark_per_tick = fixed::from_f64(bonuses.dark_matter_per_tick);
+                } else {
+                    rates.thought_per_tick = 0;
+                    rates.energy_per_tick = 0;
+                    rates.dark_per_tick = 0;
                 }
             },
             CelestialType::Asteroid => {
@@ -514,7 +790,7 @@ impl CelestialBodyManager {
                 body.resources.production_rates.dust_per_tick = fixed::from_f64(0.5);
             },
             CelestialType::BlackHole(black_hole_data) => {
-                // ブラックホールからダークマター生成
+                // ブラックホールからダークマター生成（物理演算で実際に降着した質量/秒に比例）
                 let dark_rate = fixed::from_f64(fixed::to_f64(black_hole_data.accretion_rate) * 0.1);
                 body.resources.production_rates.dark_per_tick = dark_rate;
             },
@@ -526,16 +802,23 @@ impl CelestialBodyManager {
     /// 作成の検証
     fn validate_creation(&self, body_type: &CelestialType, position: &Vec3Fixed) -> Result<()> {
         // 総数制限
-        if self.bodies.len() >= self.limits.max_bodies {
-            warn!("[CELESTIAL_BODIES] Body limit reached: {} >= {}", self.bodies.len(), self.limits.max_bodies);
+        if self.bodies.len() >= self.max_bodies() {
+            warn!("[CELESTIAL_BODIES] Body limit reached: {} >= {}", self.bodies.len(), self.max_bodies());
             return Err(GameError::BodyLimitReached);
         }
         
-        // 型別制限
-        let type_key = std::mem::discriminant(body_type);
-        let _current_count = self.bodies.values()
-            .filter(|b| std::mem::discriminant(&b.body_type) == type_key)
-            .count();
+        // 種類別制限
+        let category = body_type.category();
+        if let Some(limit) = self.max_bodies_of(category) {
+            let current_count = self.count_bodies(category);
+            if current_count >= limit {
+                warn!("[CELESTIAL_BODIES] {} limit reached: {} >= {}", category.name(), current_count, limit);
+                return Err(GameError::BodyTypeLimitReached {
+                    body_type: category.name().to_string(),
+                    limit,
+                });
+            }
+        }
         
         // 境界チェック
         if position.magnitude() > fixed::to_f64(self.limits.max_position) {
@@ -544,13 +827,10 @@ impl CelestialBodyManager {
         }
         
         // 最小分離距離チェック
-        for body in self.bodies.values() {
-            let distance = (position - body.physics.position).magnitude();
-            let min_distance = fixed::to_f64(body.physics.radius + self.limits.min_separation);
-            if distance < min_distance {
-                warn!("[CELESTIAL_BODIES] Bodies too close: distance {} < min_distance {}", distance, min_distance);
-                return Err(GameError::TooClose);
-            }
+        let min_separation = fixed::to_f64(self.limits.min_separation);
+        if let Some(nearby_id) = self.spatial_index.within_distance(*position, min_separation).first() {
+            warn!("[CELESTIAL_BODIES] Too close to body {}: min_separation {}", nearby_id, min_separation);
+            return Err(GameError::TooClose);
         }
         
         Ok(())
@@ -562,7 +842,7 @@ impl CelestialBodyManager {
             CelestialType::Star(star_data) => {
                 let (min_mass, max_mass) = star_data.spectral_type.mass_range();
                 let mass = fixed::from_f64(min_mass + (max_mass - min_mass) * 0.5);
-                let radius = fixed::from_f64(fixed::to_f64(mass).sqrt() * 696340.0); // 太陽半径基準
+                let radius = fixed::from_f64(fixed::to_f64(mass).sqrt() * SOLAR_RADIUS);
                 (mass, radius)
             },
             CelestialType::Planet(_) => {
@@ -600,9 +880,14 @@ impl CelestialBodyManager {
     
     /// 天体の初期化
     fn initialize_body(&self, body: &mut CelestialBody) {
+        // 恒星の寿命と光度
+        stellar_evolution::initialize_star(body);
+        
         // 初期生命条件チェック
-        if let CelestialType::Planet(planet_data) = &body.body_type {
-            if planet_data.habitability > 30 {
+        let breakdown = habitability::evaluate(body, &habitability::light_sources(&self.bodies), &self.habitability_settings);
+        if let (Some(breakdown), CelestialType::Planet(planet_data)) = (breakdown, &mut body.body_type) {
+            planet_data.habitability = breakdown.habitability;
+            if breakdown.habitability > LIFE_EMERGENCE_THRESHOLD {
                 // 生命発生の可能性
                 body.lifecycle.evolution_timer = 0;
             }
@@ -639,11 +924,35 @@ impl CelestialBodyManager {
     pub fn get_all_bodies_mut(&mut self) -> &mut HashMap<BodyId, CelestialBody> {
         &mut self.bodies
     }
+    
+    /// 全天体の削除（削除数を返す）
+    pub fn clear_bodies(&mut self) -> usize {
+        let count = self.bodies.len();
+        self.bodies.clear();
+        self.spatial_index.clear();
+        info!("[CELESTIAL_BODIES] Cleared {} bodies", count);
+        count
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::production_modifiers::ModifierSource;
+    
+    /// 1天文単位の位置に惑星を置ける主星を原点に作成
+    fn create_sun(manager: &mut CelestialBodyManager, resources: &mut Resources) -> Vec3Fixed {
+        let sun = CelestialType::Star(StarData {
+            spectral_type: SpectralType::G,
+            temperature: 5800,
+            luminosity: 0,
+            age: 0,
+            lifespan: 0,
+            phase: StellarPhase::MainSequence,
+        });
+        manager.create_body(sun, Vec3Fixed::zeros(), resources).unwrap();
+        Vec3Fixed::new(HabitabilitySettings::default().astronomical_unit, 0.0, 0.0)
+    }
     
     #[test]
     fn test_spectral_type_ranges() {
@@ -674,6 +983,143 @@ mod tests {
         assert_eq!(resources.cosmic_dust, 900); // 100消費
     }
     
+    #[test]
+    fn test_create_body_in_orbit() {
+        let mut manager = CelestialBodyManager::new(50);
+        let mut resources = Resources::new();
+        resources.cosmic_dust = 1000;
+        
+        let parent_id = manager.create_body(CelestialType::Comet, Vec3Fixed::new(100.0, 0.0, 0.0), &mut resources).unwrap();
+        let orbit = OrbitalElements {
+            true_anomaly: 1.0,
+            ..OrbitalElements::new(30.0, 0.2, 0.3, 0.0)
+        };
+        let body_id = manager.create_body_in_orbit(CelestialType::Asteroid, parent_id, orbit, &mut resources).unwrap();
+        
+        let body = manager.get_body(body_id).unwrap();
+        assert_eq!(body.parent_id, Some(parent_id));
+        assert!(body.physics.velocity.magnitude() > 0.0);
+        
+        let elements = manager.orbital_elements(body_id).unwrap();
+        assert!((elements.semi_major_axis - 30.0).abs() < 1e-6);
+        assert!((elements.eccentricity - 0.2).abs() < 1e-6);
+        assert!((elements.inclination - 0.3).abs() < 1e-6);
+        assert!(manager.orbital_elements(parent_id).is_none());
+    }
+    
+    #[test]
+    fn test_create_body_in_orbit_rejects_invalid_orbits() {
+        let mut manager = CelestialBodyManager::new(50);
+        let mut resources = Resources::new();
+        resources.cosmic_dust = 1000;
+        
+        let orbit = OrbitalElements::new(30.0, 0.0, 0.0, 0.0);
+        let missing = manager.create_body_in_orbit(CelestialType::Asteroid, Uuid::new_v4(), orbit, &mut resources);
+        assert_eq!(missing.unwrap_err(), GameError::BodyNotFound);
+        
+        let parent_id = manager.create_body(CelestialType::Comet, Vec3Fixed::zeros(), &mut resources).unwrap();
+        let grazing = OrbitalElements::new(12.0, 0.5, 0.0, 0.0);
+        let result = manager.create_body_in_orbit(CelestialType::Asteroid, parent_id, grazing, &mut resources);
+        assert_eq!(result.unwrap_err(), GameError::TooClose);
+        assert_eq!(resources.cosmic_dust, 500); // 失敗時は消費しない
+    }
+    
+    #[test]
+    fn test_production_modifiers_follow_neighbours() {
+        let mut manager = CelestialBodyManager::new(50);
+        let mut resources = Resources::new();
+        resources.cosmic_dust = 20000;
+        
+        let orbit_position = create_sun(&mut manager, &mut resources);
+        let planet = CelestialType::Planet(PlanetData {
+            planet_type: PlanetType::Rocky,
+            atmosphere: AtmosphereType::Oxygen,
+            water_coverage: 70,
+            temperature_range: (15, 25),
+            habitability: 0,
+        });
+        let planet_id = manager.create_body(planet, orbit_position, &mut resources).unwrap();
+        let moon_id = manager.create_body(CelestialType::Moon, orbit_position + Vec3Fixed::new(0.0, 20_000.0, 0.0), &mut resources).unwrap();
+        
+        manager.update_life_systems(50);
+        
+        // 生命のない惑星でも日射のエネルギーを得て、衛星の潮汐の補正を受ける
+        let modifiers = &manager.get_body(planet_id).unwrap().resources.modifiers;
+        assert!(modifiers.iter().any(|m| m.source == ModifierSource::Tidal { moon_id }));
+        assert!(modifiers.iter().any(|m| matches!(m.source, ModifierSource::HabitableZone { .. })));
+        let breakdown = manager.production_breakdown(planet_id).unwrap();
+        assert_eq!(breakdown.base_rates.energy_per_tick, 0);
+        assert!(breakdown.effective_rates.energy_per_tick > 0);
+        
+        // 衛星を失うと潮汐の補正もなくなる
+        manager.remove_body(moon_id).unwrap();
+        let breakdown = manager.production_breakdown(planet_id).unwrap();
+        assert!(breakdown.modifiers.iter().all(|m| !matches!(m.source, ModifierSource::Tidal { .. })));
+    }
+    
+    #[test]
+    fn test_type_limits_and_costs_apply_per_category() {
+        let mut manager = CelestialBodyManager::new(50);
+        manager.limits.max_bodies_per_type.insert(BodyCategory::Star, 1);
+        let mut resources = Resources::new();
+        resources.cosmic_dust = 100000;
+        
+        create_sun(&mut manager, &mut resources);
+        assert_eq!(resources.cosmic_dust, 90000);
+        
+        // 温度が違っても同じ恒星の上限に数えられる
+        let cooler_star = CelestialType::Star(StarData {
+            spectral_type: SpectralType::G,
+            temperature: 5300,
+            luminosity: 0,
+            age: 0,
+            lifespan: 0,
+            phase: StellarPhase::MainSequence,
+        });
+        let position = Vec3Fixed::new(-80000.0, 0.0, 0.0);
+        let result = manager.create_body(cooler_star.clone(), position, &mut resources);
+        assert!(matches!(
+            &result,
+            Err(GameError::BodyTypeLimitReached { body_type, limit: 1 }) if body_type == "Star"
+        ));
+        assert_eq!(resources.cosmic_dust, 90000);
+        
+        // 研究で上限とコストが変わる
+        manager.set_type_limit_bonuses(HashMap::from([(BodyCategory::Star, 1)]));
+        manager.set_creation_cost_multiplier(0.5);
+        manager.create_body(cooler_star, position, &mut resources).unwrap();
+        assert_eq!(resources.cosmic_dust, 85000);
+        assert_eq!(manager.count_bodies(BodyCategory::Star), 2);
+        assert_eq!(manager.max_bodies_of(BodyCategory::Star), Some(2));
+        assert_eq!(manager.creation_cost(BodyCategory::BlackHole).energy, 5000);
+    }
+    
+    #[test]
+    fn test_separation_check_follows_spatial_index() {
+        let mut manager = CelestialBodyManager::new(50);
+        let mut resources = Resources::new();
+        resources.cosmic_dust = 1000;
+        
+        let first = manager.create_body(CelestialType::Asteroid, Vec3Fixed::zeros(), &mut resources).unwrap();
+        let nearby = Vec3Fixed::new(8.0, 0.0, 0.0);
+        let result = manager.create_body(CelestialType::Asteroid, nearby, &mut resources);
+        assert!(matches!(result, Err(GameError::TooClose)));
+        assert_eq!(manager.spatial_index().nearest(nearby, 1), vec![first]);
+        
+        // 削除した天体はインデックスからも外れる
+        manager.remove_body(first).unwrap();
+        assert!(manager.spatial_index().is_empty());
+        manager.create_body(CelestialType::Asteroid, nearby, &mut resources).unwrap();
+        
+        // 直接書き換えた位置は同期後に反映される
+        for body in manager.get_all_bodies_mut().values_mut() {
+            body.physics.position = Vec3Fixed::new(500.0, 0.0, 0.0);
+        }
+        manager.sync_spatial_index();
+        assert!(manager.create_body(CelestialType::Asteroid, nearby, &mut resources).is_ok());
+        assert_eq!(manager.spatial_index().len(), 2);
+    }
+    
     #[test]
     fn test_position_validation() {
         let manager = CelestialBodyManager::new(50);
@@ -684,11 +1130,40 @@ mod tests {
         assert_eq!(result.unwrap_err(), GameError::OutOfBounds);
     }
     
+    #[test]
+    fn test_life_transition_reported() {
+        let mut manager = CelestialBodyManager::new(50);
+        let mut resources = Resources::new();
+        resources.cosmic_dust = 20000;
+        
+        let planet_data = PlanetData {
+            planet_type: PlanetType::Rocky,
+            atmosphere: AtmosphereType::Oxygen,
+            water_coverage: 70,
+            temperature_range: (15, 25),
+            habitability: 80,
+        };
+        let position = create_sun(&mut manager, &mut resources);
+        let body_id = manager
+            .create_body(CelestialType::Planet(planet_data), position, &mut resources)
+            .unwrap();
+        
+        // 2ティック分で微生物が発生する
+        let transitions = manager.update_life_systems(100);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].body_id, body_id);
+        assert_eq!(transitions[0].from_stage, LifeStage::None);
+        assert_eq!(transitions[0].to_stage.name(), "Microbial");
+        
+        // 段階が変わらない更新では報告しない
+        assert!(manager.update_life_systems(50).is_empty());
+    }
+    
     #[test]
     fn test_life_evolution_microbial() {
         let mut manager = CelestialBodyManager::new(50);
         let mut resources = Resources::new();
-        resources.cosmic_dust = 10000;
+        resources.cosmic_dust = 20000;
         
         let planet_data = PlanetData {
             planet_type: PlanetType::Rocky,
@@ -698,14 +1173,17 @@ mod tests {
             habitability: 80,
         };
         
-        let position = Vec3Fixed::new(0.0, 0.0, 0.0);
+        let position = create_sun(&mut manager, &mut resources);
         let body_id = manager.create_body(CelestialType::Planet(planet_data), position, &mut resources).unwrap();
         
         // 生命進化をシミュレート
         manager.update_life_systems(60000); // 1分 = 1200ティック
+        assert_eq!(manager.get_body(body_id).unwrap().lifecycle.life_stage.name(), "Microbial");
+        manager.update_life_systems(100);
         
         let body = manager.get_body(body_id).unwrap();
         // 進化が発生していることを確認
         assert!(body.lifecycle.evolution_timer > 0);
+        assert!(body.lifecycle.population > 1000);
     }
 }
\ No newline at end of file
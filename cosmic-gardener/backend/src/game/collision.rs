@@ -0,0 +1,502 @@
+//! 衝突の解決
+//!
+//! 相対速度・質量比・天体タイプから衝突の結果（合体・破砕・かすめ衝突・潮汐破壊）を決め、
+//! 天体の更新と破片の生成を行う。運動量は破片を含めて保存される。
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{GameError, Result};
+use crate::game::accretion;
+use crate::game::celestial_bodies::{BodyId, CelestialBody, CelestialType, Vec3Fixed};
+use crate::game::determinism::DeterministicRng;
+use crate::game::physics::GRAVITATIONAL_CONSTANT;
+use crate::game::resources::{fixed, Fixed};
+use crate::models::websocket::DestructionReason;
+
+/// 衝突の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionOutcome {
+    /// 大きい天体が小さい天体を吸収する
+    Merge,
+    /// 小さい天体が小惑星の破片に砕ける
+    Fragmentation,
+    /// かすめ衝突で両方が跳ね返る
+    Ejection,
+    /// 小さい天体が潮汐力で引き裂かれ、破片の流れになる
+    TidalDisruption,
+}
+
+impl CollisionOutcome {
+    /// 結果名（実績の対象名に使う）
+    pub fn name(&self) -> &'static str {
+        match self {
+            CollisionOutcome::Merge => "merge",
+            CollisionOutcome::Fragmentation => "fragmentation",
+            CollisionOutcome::Ejection => "ejection",
+            CollisionOutcome::TidalDisruption => "tidal_disruption",
+        }
+    }
+}
+
+/// 衝突判定のパラメータ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CollisionSettings {
+    /// 破砕とみなす相対速度（相互脱出速度に対する比）
+    pub fragmentation_velocity_ratio: f64,
+    /// 破砕に必要な最低相対速度（脱出速度が極端に小さい天体向け）
+    pub min_fragmentation_velocity: f64,
+    /// かすめ衝突で跳ね返るのに必要な最低相対速度
+    pub min_ejection_velocity: f64,
+    /// かすめ衝突とみなす衝突角の正弦（0で正面、1で接線方向）
+    pub grazing_impact: f64,
+    /// 潮汐破壊が起きる質量比（小さい天体 / 大きい天体）の上限
+    pub tidal_mass_ratio: f64,
+    /// 破砕・潮汐破壊で大きい天体に降着する質量の割合
+    pub accretion_fraction: f64,
+    /// 破片の最大数
+    pub max_fragments: usize,
+    /// 破片の最小質量
+    pub min_fragment_mass: f64,
+    /// 破片の拡散速度（相対速度に対する比）
+    pub debris_velocity_ratio: f64,
+    /// かすめ衝突の反発係数
+    pub restitution: f64,
+}
+
+impl Default for CollisionSettings {
+    fn default() -> Self {
+        Self {
+            fragmentation_velocity_ratio: 3.0,
+            min_fragmentation_velocity: 1.0,
+            min_ejection_velocity: 0.5,
+            grazing_impact: 0.7,
+            tidal_mass_ratio: 0.01,
+            accretion_fraction: 0.3,
+            max_fragments: 6,
+            min_fragment_mass: 1.0,
+            debris_velocity_ratio: 0.25,
+            restitution: 0.5,
+        }
+    }
+}
+
+/// 衝突で取り除かれた天体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemovedBody {
+    pub body_id: BodyId,
+    pub reason: DestructionReason,
+    /// 吸収した天体（破片になった場合は`None`）
+    pub absorbed_by: Option<BodyId>,
+}
+
+/// 衝突の解決結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionResolution {
+    pub outcome: CollisionOutcome,
+    pub removed: Vec<RemovedBody>,
+    /// 生成された破片（`(破片ID, 元の天体ID)`）
+    pub debris: Vec<(BodyId, BodyId)>,
+}
+
+/// 衝突の結果を判定
+pub fn classify(body1: &CelestialBody, body2: &CelestialBody, settings: &CollisionSettings) -> CollisionOutcome {
+    let (larger, smaller) = order_by_mass(body1, body2);
+
+    // ブラックホールは常に吸収する
+    if is_black_hole(larger) || is_black_hole(smaller) {
+        return CollisionOutcome::Merge;
+    }
+
+    let normal = smaller.physics.position - larger.physics.position;
+    let relative_velocity = smaller.physics.velocity - larger.physics.velocity;
+    let speed = relative_velocity.magnitude();
+    let escape_speed = escape_velocity(larger, smaller);
+    let fragmentation_speed = (escape_speed * settings.fragmentation_velocity_ratio).max(settings.min_fragmentation_velocity);
+
+    let mass_ratio = mass_f64(smaller) / mass_f64(larger).max(f64::MIN_POSITIVE);
+    if speed < fragmentation_speed
+        && mass_ratio < settings.tidal_mass_ratio
+        && can_tidally_disrupt(&larger.body_type)
+        && is_tidally_fragile(&smaller.body_type)
+    {
+        return CollisionOutcome::TidalDisruption;
+    }
+
+    // 衝突角の正弦（相対速度と中心間方向のなす角）
+    let impact_sine = if speed > 0.0 && normal.magnitude() > 0.0 {
+        normal.cross(&relative_velocity).magnitude() / (normal.magnitude() * speed)
+    } else {
+        0.0
+    };
+    if impact_sine > settings.grazing_impact && speed > escape_speed.max(settings.min_ejection_velocity) {
+        return CollisionOutcome::Ejection;
+    }
+
+    // 恒星は砕けずに飲み込む
+    if speed > fragmentation_speed && !matches!(larger.body_type, CelestialType::Star(_)) {
+        return CollisionOutcome::Fragmentation;
+    }
+
+    CollisionOutcome::Merge
+}
+
+/// 衝突を解決し、天体を更新・削除・生成する
+pub fn resolve(
+    bodies: &mut HashMap<BodyId, CelestialBody>,
+    id1: BodyId,
+    id2: BodyId,
+    settings: &CollisionSettings,
+) -> Result<CollisionResolution> {
+    let body1 = bodies.get(&id1).ok_or(GameError::BodyNotFound)?.clone();
+    let body2 = bodies.get(&id2).ok_or(GameError::BodyNotFound)?.clone();
+    let (larger, smaller) = order_by_mass(&body1, &body2);
+
+    let outcome = classify(&body1, &body2, settings);
+    let resolution = match outcome {
+        CollisionOutcome::Merge if is_black_hole(smaller) && !is_black_hole(larger) => merge(bodies, smaller, larger),
+        CollisionOutcome::Merge => merge(bodies, larger, smaller),
+        CollisionOutcome::Ejection => {
+            bounce(bodies, larger, smaller, settings);
+            CollisionResolution { outcome, removed: Vec::new(), debris: Vec::new() }
+        }
+        CollisionOutcome::Fragmentation | CollisionOutcome::TidalDisruption => {
+            // 破片が2つ以上作れない場合は合体として扱う
+            shatter(bodies, larger, smaller, outcome, settings).unwrap_or_else(|| merge(bodies, larger, smaller))
+        }
+    };
+
+    Ok(resolution)
+}
+
+/// 合体（`larger`が`smaller`を吸収する。体積保存で半径を更新、ブラックホールは質量に比例）
+fn merge(bodies: &mut HashMap<BodyId, CelestialBody>, larger: &CelestialBody, smaller: &CelestialBody) -> CollisionResolution {
+    let m1 = mass_f64(larger);
+    let m2 = mass_f64(smaller);
+    let total = (m1 + m2).max(f64::MIN_POSITIVE);
+    let position = (larger.physics.position * m1 + smaller.physics.position * m2) / total;
+
+    if let Some(survivor) = bodies.get_mut(&larger.id) {
+        if is_black_hole(larger) {
+            accretion::add_mass(survivor, smaller.physics.mass, smaller.physics.velocity);
+        } else {
+            survivor.physics.mass = larger.physics.mass.saturating_add(smaller.physics.mass);
+            survivor.physics.velocity = (larger.physics.velocity * m1 + smaller.physics.velocity * m2) / total;
+            survivor.physics.radius = fixed::from_f64((radius_f64(larger).powi(3) + radius_f64(smaller).powi(3)).cbrt());
+        }
+        survivor.physics.position = position;
+    }
+    bodies.remove(&smaller.id);
+
+    let reason = if is_black_hole(larger) {
+        DestructionReason::BlackHoleAbsorption
+    } else {
+        DestructionReason::Collision
+    };
+
+    CollisionResolution {
+        outcome: CollisionOutcome::Merge,
+        removed: vec![RemovedBody { body_id: smaller.id, reason, absorbed_by: Some(larger.id) }],
+        debris: Vec::new(),
+    }
+}
+
+/// かすめ衝突（中心間方向に反発させ、重なりを解消する）
+fn bounce(bodies: &mut HashMap<BodyId, CelestialBody>, larger: &CelestialBody, smaller: &CelestialBody, settings: &CollisionSettings) {
+    let m1 = mass_f64(larger);
+    let m2 = mass_f64(smaller);
+    let offset = smaller.physics.position - larger.physics.position;
+    let Some(normal) = offset.try_normalize(0.0) else {
+        return;
+    };
+
+    let approach_speed = (smaller.physics.velocity - larger.physics.velocity).dot(&normal);
+    let impulse = if approach_speed < 0.0 {
+        -(1.0 + settings.restitution) * approach_speed / (1.0 / m1 + 1.0 / m2)
+    } else {
+        0.0
+    };
+    let overlap = (radius_f64(larger) + radius_f64(smaller) - offset.magnitude()).max(0.0);
+
+    if let Some(body) = bodies.get_mut(&larger.id) {
+        body.physics.velocity -= normal * (impulse / m1);
+        body.physics.position -= normal * (overlap * m2 / (m1 + m2));
+    }
+    if let Some(body) = bodies.get_mut(&smaller.id) {
+        body.physics.velocity += normal * (impulse / m2);
+        body.physics.position += normal * (overlap * m1 / (m1 + m2));
+    }
+}
+
+/// 小さい天体を破片に砕く（一部は大きい天体に降着する）
+fn shatter(
+    bodies: &mut HashMap<BodyId, CelestialBody>,
+    larger: &CelestialBody,
+    smaller: &CelestialBody,
+    outcome: CollisionOutcome,
+    settings: &CollisionSettings,
+) -> Option<CollisionResolution> {
+    let accreted: Fixed = (smaller.physics.mass as f64 * settings.accretion_fraction.clamp(0.0, 1.0)) as Fixed;
+    let debris_mass = fixed::to_f64(smaller.physics.mass - accreted);
+    let fragment_count = ((debris_mass / settings.min_fragment_mass).floor() as usize).min(settings.max_fragments);
+    if fragment_count < 2 {
+        return None;
+    }
+
+    let fragment_mass = debris_mass / fragment_count as f64;
+    let fragment_radius = radius_f64(smaller) * (fragment_mass / mass_f64(smaller)).cbrt();
+
+    // 降着分の運動量を大きい天体に移す
+    let m1 = mass_f64(larger);
+    let accreted_f64 = fixed::to_f64(accreted);
+    let survivor_velocity = (larger.physics.velocity * m1 + smaller.physics.velocity * accreted_f64) / (m1 + accreted_f64);
+    if let Some(survivor) = bodies.get_mut(&larger.id) {
+        survivor.physics.mass = larger.physics.mass.saturating_add(accreted);
+        survivor.physics.velocity = survivor_velocity;
+        survivor.physics.radius = fixed::from_f64((radius_f64(larger).powi(3)
+            + radius_f64(smaller).powi(3) * accreted_f64 / mass_f64(smaller)).cbrt());
+    }
+    bodies.remove(&smaller.id);
+
+    let normal = (smaller.physics.position - larger.physics.position)
+        .try_normalize(0.0)
+        .unwrap_or_else(Vec3Fixed::x);
+    let relative_velocity = smaller.physics.velocity - larger.physics.velocity;
+    let dispersal_speed = relative_velocity.magnitude() * settings.debris_velocity_ratio;
+
+    // 破片同士・大きい天体と重ならない位置に雲の中心を置く
+    let cloud_radius = 2.0 * fragment_radius * fragment_count as f64;
+    let cloud_center = larger.physics.position + normal * (radius_f64(larger) + cloud_radius + 2.0 * fragment_radius);
+
+    let mut rng = DeterministicRng::new(collision_seed(larger.id, smaller.id));
+    let directions = match outcome {
+        CollisionOutcome::TidalDisruption => stream_directions(fragment_count, &normal, &relative_velocity),
+        _ => sphere_directions(fragment_count, rng.next_f64() * std::f64::consts::TAU),
+    };
+
+    // 拡散速度の合計が0になるよう平均を引き、破片全体の運動量を元の天体の残り分に一致させる
+    let mean_direction = directions.iter().fold(Vec3Fixed::zeros(), |acc, (_, v)| acc + v) / fragment_count as f64;
+
+    let mut debris = Vec::with_capacity(fragment_count);
+    for (offset, direction) in directions {
+        let id = rng.next_uuid();
+        let mut fragment = CelestialBody::new(
+            id,
+            CelestialType::Asteroid,
+            cloud_center + offset * cloud_radius,
+            fixed::from_f64(fragment_mass),
+            fixed::from_f64(fragment_radius),
+        );
+        fragment.physics.velocity = smaller.physics.velocity + (direction - mean_direction) * dispersal_speed;
+        bodies.insert(id, fragment);
+        debris.push((id, smaller.id));
+    }
+
+    Some(CollisionResolution {
+        outcome,
+        removed: vec![RemovedBody { body_id: smaller.id, reason: DestructionReason::Collision, absorbed_by: None }],
+        debris,
+    })
+}
+
+/// 球面上にほぼ均等に並べた方向（フィボナッチ格子、`(位置, 速度方向)`）
+fn sphere_directions(count: usize, phase: f64) -> Vec<(Vec3Fixed, Vec3Fixed)> {
+    let golden_angle = std::f64::consts::PI * (3.0 - 5.0_f64.sqrt());
+    (0..count)
+        .map(|i| {
+            let z = 1.0 - 2.0 * (i as f64 + 0.5) / count as f64;
+            let r = (1.0 - z * z).sqrt();
+            let angle = phase + golden_angle * i as f64;
+            let direction = Vec3Fixed::new(r * angle.cos(), r * angle.sin(), z);
+            (direction, direction)
+        })
+        .collect()
+}
+
+/// 接線方向に一列に並べた破片の流れ（`(位置, 速度方向)`）
+fn stream_directions(count: usize, normal: &Vec3Fixed, relative_velocity: &Vec3Fixed) -> Vec<(Vec3Fixed, Vec3Fixed)> {
+    let tangent = (relative_velocity - normal * relative_velocity.dot(normal))
+        .try_normalize(1e-12)
+        .unwrap_or_else(|| normal.cross(&Vec3Fixed::z()).try_normalize(1e-12).unwrap_or_else(Vec3Fixed::y));
+    let center = (count as f64 - 1.0) / 2.0;
+    (0..count)
+        .map(|i| {
+            let step = (i as f64 - center) / count as f64;
+            (tangent * (2.0 * step), tangent * step)
+        })
+        .collect()
+}
+
+/// 衝突の組から決まる破片IDのシード
+fn collision_seed(id1: BodyId, id2: BodyId) -> u64 {
+    let (a_high, a_low) = id1.as_u64_pair();
+    let (b_high, b_low) = id2.as_u64_pair();
+    a_high ^ a_low.rotate_left(17) ^ b_high.rotate_left(31) ^ b_low.rotate_left(47)
+}
+
+/// 相互脱出速度
+fn escape_velocity(body1: &CelestialBody, body2: &CelestialBody) -> f64 {
+    let distance = (radius_f64(body1) + radius_f64(body2)).max(f64::MIN_POSITIVE);
+    (2.0 * GRAVITATIONAL_CONSTANT * (mass_f64(body1) + mass_f64(body2)) / distance).sqrt()
+}
+
+/// 質量の大きい順（同じ場合はIDの小さい方を大きいとみなす）
+fn order_by_mass<'a>(body1: &'a CelestialBody, body2: &'a CelestialBody) -> (&'a CelestialBody, &'a CelestialBody) {
+    if (body1.physics.mass, body2.id) > (body2.physics.mass, body1.id) {
+        (body1, body2)
+    } else {
+        (body2, body1)
+    }
+}
+
+fn is_black_hole(body: &CelestialBody) -> bool {
+    matches!(body.body_type, CelestialType::BlackHole(_))
+}
+
+/// 潮汐力で小天体を引き裂ける天体
+fn can_tidally_disrupt(body_type: &CelestialType) -> bool {
+    matches!(body_type, CelestialType::Star(_) | CelestialType::Planet(_))
+}
+
+/// 潮汐力で引き裂かれる天体（小惑星は小さく固いので対象外）
+fn is_tidally_fragile(body_type: &CelestialType) -> bool {
+    matches!(
+        body_type,
+        CelestialType::Planet(_) | CelestialType::Moon | CelestialType::DwarfPlanet | CelestialType::Comet
+    )
+}
+
+fn mass_f64(body: &CelestialBody) -> f64 {
+    fixed::to_f64(body.physics.mass)
+}
+
+fn radius_f64(body: &CelestialBody) -> f64 {
+    fixed::to_f64(body.physics.radius)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn body(body_type: CelestialType, position: Vec3Fixed, velocity: Vec3Fixed, mass: f64, radius: f64) -> CelestialBody {
+        let mut body = CelestialBody::new(Uuid::new_v4(), body_type, position, fixed::from_f64(mass), fixed::from_f64(radius));
+        body.physics.velocity = velocity;
+        body
+    }
+
+    fn momentum(bodies: &HashMap<BodyId, CelestialBody>) -> Vec3Fixed {
+        bodies.values().fold(Vec3Fixed::zeros(), |acc, b| acc + b.physics.velocity * mass_f64(b))
+    }
+
+    fn insert(bodies: &mut HashMap<BodyId, CelestialBody>, body: CelestialBody) -> BodyId {
+        let id = body.id;
+        bodies.insert(id, body);
+        id
+    }
+
+    #[test]
+    fn test_slow_collision_merges_with_volume_conservation() {
+        let mut bodies = HashMap::new();
+        let large = insert(&mut bodies, body(CelestialType::Asteroid, Vec3Fixed::zeros(), Vec3Fixed::zeros(), 2000.0, 2.0));
+        let small = insert(&mut bodies, body(CelestialType::Asteroid, Vec3Fixed::new(2.5, 0.0, 0.0), Vec3Fixed::new(-0.1, 0.0, 0.0), 1000.0, 1.0));
+        let before = momentum(&bodies);
+
+        let resolution = resolve(&mut bodies, large, small, &CollisionSettings::default()).unwrap();
+
+        assert_eq!(resolution.outcome, CollisionOutcome::Merge);
+        assert_eq!(resolution.removed, vec![RemovedBody { body_id: small, reason: DestructionReason::Collision, absorbed_by: Some(large) }]);
+        assert!((momentum(&bodies) - before).magnitude() < 1e-6);
+        assert!((radius_f64(&bodies[&large]) - 9.0_f64.cbrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fast_head_on_collision_fragments_and_conserves_momentum() {
+        let mut bodies = HashMap::new();
+        let large = insert(&mut bodies, body(CelestialType::DwarfPlanet, Vec3Fixed::zeros(), Vec3Fixed::zeros(), 5000.0, 2.0));
+        let small = insert(&mut bodies, body(CelestialType::Asteroid, Vec3Fixed::new(2.5, 0.0, 0.0), Vec3Fixed::new(-20.0, 0.0, 0.0), 100.0, 1.0));
+        let mass_before: Fixed = bodies.values().map(|b| b.physics.mass).sum();
+        let before = momentum(&bodies);
+
+        let resolution = resolve(&mut bodies, large, small, &CollisionSettings::default()).unwrap();
+
+        assert_eq!(resolution.outcome, CollisionOutcome::Fragmentation);
+        assert_eq!(resolution.debris.len(), CollisionSettings::default().max_fragments);
+        assert!(!bodies.contains_key(&small));
+        assert_eq!(bodies.len(), 1 + resolution.debris.len());
+        assert!((momentum(&bodies) - before).magnitude() < 1e-6 * before.magnitude());
+
+        let mass_after: Fixed = bodies.values().map(|b| b.physics.mass).sum();
+        assert!((fixed::to_f64(mass_after) - fixed::to_f64(mass_before)).abs() < 1e-6);
+
+        // 破片同士も大きい天体とも重ならない
+        let all: Vec<&CelestialBody> = bodies.values().collect();
+        for (i, a) in all.iter().enumerate() {
+            for b in &all[i + 1..] {
+                assert!((a.physics.position - b.physics.position).magnitude() >= radius_f64(a) + radius_f64(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_grazing_collision_ejects_both_bodies() {
+        let mut bodies = HashMap::new();
+        let large = insert(&mut bodies, body(CelestialType::Moon, Vec3Fixed::zeros(), Vec3Fixed::zeros(), 5000.0, 2.0));
+        let small = insert(&mut bodies, body(CelestialType::Asteroid, Vec3Fixed::new(0.5, 2.8, 0.0), Vec3Fixed::new(-2.0, -0.5, 0.0), 100.0, 1.0));
+        let before = momentum(&bodies);
+
+        let resolution = resolve(&mut bodies, large, small, &CollisionSettings::default()).unwrap();
+
+        assert_eq!(resolution.outcome, CollisionOutcome::Ejection);
+        assert!(resolution.removed.is_empty());
+        assert_eq!(bodies.len(), 2);
+        assert!((momentum(&bodies) - before).magnitude() < 1e-6 * before.magnitude());
+        let separation = (bodies[&small].physics.position - bodies[&large].physics.position).magnitude();
+        assert!(separation >= 3.0 - 1e-9);
+    }
+
+    #[test]
+    fn test_comet_grazing_star_is_tidally_disrupted() {
+        use crate::game::celestial_bodies::{SpectralType, StarData};
+
+        let star_type = CelestialType::Star(StarData {
+            spectral_type: SpectralType::G,
+            temperature: 5778,
+            luminosity: fixed::from_f64(1.0),
+            age: 0,
+            lifespan: 10_000_000,
+            phase: Default::default(),
+        });
+        let mut bodies = HashMap::new();
+        let star = insert(&mut bodies, body(star_type, Vec3Fixed::zeros(), Vec3Fixed::zeros(), 1.0e6, 5.0));
+        let comet = insert(&mut bodies, body(CelestialType::Comet, Vec3Fixed::new(5.5, 0.0, 0.0), Vec3Fixed::new(0.0, 0.2, 0.0), 100.0, 1.0));
+        let before = momentum(&bodies);
+
+        let resolution = resolve(&mut bodies, star, comet, &CollisionSettings::default()).unwrap();
+
+        assert_eq!(resolution.outcome, CollisionOutcome::TidalDisruption);
+        assert!(resolution.debris.iter().all(|(id, source)| *source == comet && bodies[id].body_type == CelestialType::Asteroid));
+        assert!((momentum(&bodies) - before).magnitude() < 1e-6 * before.magnitude());
+    }
+
+    #[test]
+    fn test_black_hole_absorbs_regardless_of_speed() {
+        use crate::game::celestial_bodies::BlackHoleData;
+
+        let black_hole_type = CelestialType::BlackHole(BlackHoleData {
+            schwarzschild_radius: fixed::from_f64(1.0),
+            accretion_rate: fixed::from_f64(0.1),
+            formation_time: chrono::Utc::now(),
+        });
+        let mut bodies = HashMap::new();
+        let black_hole = insert(&mut bodies, body(black_hole_type, Vec3Fixed::zeros(), Vec3Fixed::zeros(), 1.0e6, 1.0));
+        let asteroid = insert(&mut bodies, body(CelestialType::Asteroid, Vec3Fixed::new(1.5, 0.0, 0.0), Vec3Fixed::new(-50.0, 0.0, 0.0), 1.0e3, 1.0));
+
+        let resolution = resolve(&mut bodies, black_hole, asteroid, &CollisionSettings::default()).unwrap();
+
+        assert_eq!(resolution.outcome, CollisionOutcome::Merge);
+        assert_eq!(resolution.removed[0].reason, DestructionReason::BlackHoleAbsorption);
+        assert!(radius_f64(&bodies[&black_hole]) > 1.0);
+    }
+}
@@ -0,0 +1,172 @@
+//! ティック単位で適用するコマンドキュー
+//!
+//! ソケット側のタスクは状態を直接書き換えず、有界のキューにコマンドを積むだけにする。
+//! ゲームループは各ティックの開始時にキューを取り出し、取り出した順に通し番号を振って適用する。
+//! 適用結果は通し番号と適用したティックを添えて送信元に返すので、適用順は決定的でログから追える。
+
+use tokio::sync::{mpsc, oneshot};
+use tracing::debug;
+
+use crate::errors::{GameError, Result};
+
+/// キューの既定の容量
+pub const DEFAULT_COMMAND_QUEUE_CAPACITY: usize = 256;
+
+/// コマンドの適用結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandAck<T> {
+    /// ゲームループが振った通し番号（適用順）
+    pub sequence: u64,
+    /// 適用したティック
+    pub tick: u64,
+    pub result: T,
+}
+
+type Envelope<C, T> = (C, oneshot::Sender<CommandAck<T>>);
+
+/// コマンドの送信側（ソケットのタスクごとに複製して使う）
+pub struct CommandSender<C, T> {
+    sender: mpsc::Sender<Envelope<C, T>>,
+}
+
+impl<C, T> Clone for CommandSender<C, T> {
+    fn clone(&self) -> Self {
+        Self { sender: self.sender.clone() }
+    }
+}
+
+impl<C, T> CommandSender<C, T> {
+    /// コマンドを積み、適用結果の受信側を返す
+    ///
+    /// キューが一杯のときは待たずに`RateLimitExceeded`を返す。
+    pub fn try_submit(&self, command: C) -> Result<oneshot::Receiver<CommandAck<T>>> {
+        let (ack_sender, ack_receiver) = oneshot::channel();
+        match self.sender.try_send((command, ack_sender)) {
+            Ok(()) => Ok(ack_receiver),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                Err(GameError::RateLimitExceeded("command queue is full".to_string()))
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                Err(GameError::SystemError("command queue is closed".to_string()))
+            }
+        }
+    }
+
+    /// コマンドを積み、ゲームループが適用するまで待つ
+    pub async fn submit(&self, command: C) -> Result<CommandAck<T>> {
+        self.try_submit(command)?
+            .await
+            .map_err(|_| GameError::SystemError("command was dropped before it was applied".to_string()))
+    }
+}
+
+/// 取り出されて適用を待つコマンド
+pub struct PendingCommand<C, T> {
+    pub command: C,
+    sequence: u64,
+    ack: oneshot::Sender<CommandAck<T>>,
+}
+
+impl<C, T> PendingCommand<C, T> {
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// 適用結果を送信元へ返す（送信元が切断済みなら`false`）
+    pub fn acknowledge(self, tick: u64, result: T) -> bool {
+        send_ack(self.ack, self.sequence, tick, result)
+    }
+}
+
+fn send_ack<T>(ack: oneshot::Sender<CommandAck<T>>, sequence: u64, tick: u64, result: T) -> bool {
+    debug!("[COMMAND_QUEUE] Applied command #{} on tick {}", sequence, tick);
+    ack.send(CommandAck { sequence, tick, result }).is_ok()
+}
+
+/// コマンドの受信側（ゲームループが所有する）
+pub struct CommandQueue<C, T> {
+    receiver: mpsc::Receiver<Envelope<C, T>>,
+    capacity: usize,
+    next_sequence: u64,
+}
+
+/// 容量`capacity`のコマンドキューを作成
+pub fn command_queue<C, T>(capacity: usize) -> (CommandSender<C, T>, CommandQueue<C, T>) {
+    let capacity = capacity.max(1);
+    let (sender, receiver) = mpsc::channel(capacity);
+    (
+        CommandSender { sender },
+        CommandQueue { receiver, capacity, next_sequence: 0 },
+    )
+}
+
+impl<C, T> CommandQueue<C, T> {
+    /// 次に振る通し番号（これまでに取り出したコマンド数）
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// ティック開始時点で積まれているコマンドを取り出す
+    ///
+    /// 取り出し中に積まれたコマンドでティックが延びないよう、1回に取り出すのは容量分まで。
+    pub fn drain(&mut self) -> Vec<PendingCommand<C, T>> {
+        let mut pending = Vec::new();
+        while pending.len() < self.capacity {
+            let Ok((command, ack)) = self.receiver.try_recv() else {
+                break;
+            };
+            pending.push(PendingCommand { command, sequence: self.next_sequence, ack });
+            self.next_sequence += 1;
+        }
+        pending
+    }
+
+    /// 積まれているコマンドを取り出して順に適用し、適用した数を返す
+    pub fn apply_pending(&mut self, tick: u64, mut apply: impl FnMut(C) -> T) -> usize {
+        let pending = self.drain();
+        let applied = pending.len();
+        for PendingCommand { command, sequence, ack } in pending {
+            let result = apply(command);
+            send_ack(ack, sequence, tick, result);
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_commands_are_acknowledged_in_order_with_tick() {
+        let (sender, mut queue) = command_queue::<u32, u32>(8);
+        let first = sender.try_submit(1).unwrap();
+        let second = sender.clone().try_submit(2).unwrap();
+
+        // 取り出した順に通し番号が振られ、後から積んだものは次のティックで適用される
+        let mut total = 0;
+        assert_eq!(queue.apply_pending(7, |value| { total += value; total }), 2);
+        let third = sender.try_submit(3).unwrap();
+        assert_eq!(queue.apply_pending(8, |value| { total += value; total }), 1);
+
+        assert_eq!(first.await.unwrap(), CommandAck { sequence: 0, tick: 7, result: 1 });
+        assert_eq!(second.await.unwrap(), CommandAck { sequence: 1, tick: 7, result: 3 });
+        assert_eq!(third.await.unwrap(), CommandAck { sequence: 2, tick: 8, result: 6 });
+        assert_eq!(queue.next_sequence(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_commands() {
+        let (sender, mut queue) = command_queue::<u32, ()>(2);
+        let _first = sender.try_submit(1).unwrap();
+        let _second = sender.try_submit(2).unwrap();
+        assert!(matches!(sender.try_submit(3), Err(GameError::RateLimitExceeded(_))));
+
+        // 取り出せば再び積める
+        assert_eq!(queue.drain().len(), 2);
+        assert!(sender.try_submit(3).is_ok());
+
+        drop(queue);
+        assert!(matches!(sender.try_submit(4), Err(GameError::SystemError(_))));
+    }
+}
@@ -1,38 +1,207 @@
 //! 並行データ構造を使用した高性能ゲームループ
+//!
+//! プレイヤー（または共有ユニバース）ごとに独立したシミュレーション世界を持ち、
+//! 世界同士は重力も衝突も及ぼし合わない。各世界はrayonのワーカーで並列に更新し、
+//! 操作のない世界は停止して、再開時にオフライン進行で追いつく。
+//! ティックは`tick_budget`のスケジューラで予算を管理し、負荷が高い間は処理を段階的に間引く。
 
 use dashmap::DashMap;
-use std::sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering}};
 use std::time::{Duration, Instant};
-use tokio::time::{interval, sleep};
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use tokio::time::{interval, sleep, MissedTickBehavior};
 use uuid::Uuid;
 use anyhow::Result;
 use tracing::{info, warn, error, debug, instrument};
 
 use crate::game::celestial_bodies::{CelestialBody, BodyId, CelestialBodyManager};
-use crate::game::resources::ResourceManager;
+use crate::game::command_queue::{command_queue, CommandQueue, CommandSender, DEFAULT_COMMAND_QUEUE_CAPACITY};
+use crate::game::offline::{OfflineProgressCalculator, OfflineProgressConfig, OfflineReport};
+use crate::game::physics::TICK_DURATION;
+use crate::game::resources::{fixed, ResourceManager, ResourceType};
 use crate::game::physics_simd::SimdPhysicsEngine;
+use crate::game::physics_store::PhysicsStore;
+use crate::game::tick_budget::{DegradationLevel, Subsystem, TickBudgetConfig, TickReport, TickScheduler};
 use crate::services::metrics::MetricsService;
 use crate::middleware::metrics::{PhysicsMetricsRecorder, GameMetricsRecorder};
 
+/// シミュレーション世界のID（個人の世界ではプレイヤーIDと同じ）
+pub type WorldId = Uuid;
+
+/// 世界の実行状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldStatus {
+    Active,
+    /// 参加者が全員離れて停止中（再開時にオフライン進行で追いつく）
+    Suspended { since: DateTime<Utc> },
+}
+
+/// 独立したシミュレーション世界
+///
+/// 天体・リソース・物理エンジンを世界ごとに持つ。天体の位置と速度は世界が所有する`PhysicsStore`が正で、
+/// 物理演算のたびに`celestial_manager`の天体へ書き戻す。
+pub struct PlayerWorld {
+    pub world_id: WorldId,
+    /// 参加しているプレイヤー
+    pub members: HashSet<Uuid>,
+    pub celestial_manager: CelestialBodyManager,
+    pub resource_manager: ResourceManager,
+    physics_engine: SimdPhysicsEngine,
+    /// 物理状態の配列（天体の増減は物理演算の前に反映する）
+    physics: PhysicsStore,
+    status: WorldStatus,
+    /// 参加者の最後の操作
+    pub last_activity: DateTime<Utc>,
+}
+
+impl PlayerWorld {
+    pub fn new(world_id: WorldId, metrics_service: Arc<MetricsService>) -> Self {
+        let tick_duration_ms = (TICK_DURATION * 1000.0) as u64;
+        Self {
+            world_id,
+            members: HashSet::new(),
+            celestial_manager: CelestialBodyManager::new(tick_duration_ms),
+            resource_manager: ResourceManager::new(tick_duration_ms),
+            physics_engine: SimdPhysicsEngine::new(metrics_service),
+            physics: PhysicsStore::new(),
+            status: WorldStatus::Active,
+            last_activity: Utc::now(),
+        }
+    }
+
+    /// 実行状態
+    pub fn status(&self) -> WorldStatus {
+        self.status
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == WorldStatus::Active
+    }
+
+    /// 物理演算を進める（停止中は何もしない、更新した天体数を返す）
+    pub fn step_physics(&mut self, delta_time: f64) -> Result<usize> {
+        if !self.is_active() {
+            return Ok(0);
+        }
+
+        // 配列をその場で進め、天体には位置と速度だけを書き戻す
+        let (bodies, spatial_index) = self.celestial_manager.bodies_and_index_mut();
+        self.physics.sync(bodies);
+        self.physics_engine.update_store(&mut self.physics, spatial_index, delta_time)?;
+        self.physics.publish(bodies);
+        let body_count = bodies.len();
+        self.celestial_manager.sync_spatial_index();
+
+        Ok(body_count)
+    }
+
+    /// Barnes-Hut近似の開き角を設定
+    ///
+    /// 開き角が変わった場合は、配列に残っている加速度を次のkickで計算し直す。
+    pub fn set_theta(&mut self, theta: f64) {
+        let previous = self.physics_engine.theta;
+        self.physics_engine.set_theta(theta);
+        if self.physics_engine.theta != previous {
+            self.physics.invalidate_forces();
+        }
+    }
+
+    /// 生命とリソースを進める（停止中は何もしない、1ティックあたりの生産量の合計を返す）
+    ///
+    /// `update_life`が`false`の間は生命を進めず、リソースだけを蓄積する。
+    pub fn step_resources(&mut self, delta_time_ms: u64, update_life: bool) -> f64 {
+        if !self.is_active() {
+            return 0.0;
+        }
+
+        if update_life {
+            self.celestial_manager.update_life_systems(delta_time_ms);
+        }
+        self.resource_manager.set_body_production(self.celestial_manager.total_production());
+        self.resource_manager.accumulate_resources(delta_time_ms);
+
+        let rates = &self.resource_manager.get_game_state().production_rates;
+        ResourceType::all().into_iter().map(|resource_type| fixed::to_f64(rates.get(resource_type))).sum()
+    }
+
+    /// 世界を停止
+    pub fn suspend(&mut self, now: DateTime<Utc>) {
+        if self.is_active() {
+            self.status = WorldStatus::Suspended { since: now };
+            info!("World {} suspended", self.world_id);
+        }
+    }
+
+    /// 世界を再開し、停止していた間の進行を適用
+    pub fn resume(&mut self, calculator: &OfflineProgressCalculator, now: DateTime<Utc>) -> Option<OfflineReport> {
+        let WorldStatus::Suspended { since } = self.status else {
+            return None;
+        };
+        self.status = WorldStatus::Active;
+        info!("World {} resumed after {}s", self.world_id, (now - since).num_seconds());
+
+        let report = calculator.apply(&mut self.resource_manager, &mut self.celestial_manager, now);
+        // 停止中の進行で動いた天体の位置に配列と索引を合わせる
+        self.physics.load(self.celestial_manager.get_all_bodies());
+        self.celestial_manager.sync_spatial_index();
+        report
+    }
+}
+
+/// 共有される世界（ワーカーからはロックして更新する）
+pub type SharedWorld = Arc<Mutex<PlayerWorld>>;
+
+/// 世界のロック（更新中にパニックした世界も読み書きできるようにする）
+fn lock_world(world: &SharedWorld) -> MutexGuard<'_, PlayerWorld> {
+    world.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 /// 並行ゲーム状態
-#[derive(Debug)]
 pub struct ConcurrentGameState {
-    /// 天体データ（並行アクセス対応）
-    pub celestial_bodies: Arc<DashMap<BodyId, CelestialBody>>,
-    /// プレイヤー別リソース管理
-    pub player_resources: Arc<DashMap<Uuid, ResourceManager>>,
+    /// シミュレーション世界（並行アクセス対応）
+    pub worlds: Arc<DashMap<WorldId, SharedWorld>>,
+    /// プレイヤーが参加している世界
+    pub player_worlds: Arc<DashMap<Uuid, WorldId>>,
     /// アクティブプレイヤー
     pub active_players: Arc<DashMap<Uuid, PlayerSession>>,
     /// ゲームティック
     pub game_tick: AtomicU64,
     /// 実行中フラグ
     pub running: AtomicBool,
-    /// 物理演算エンジン
-    pub physics_engine: Arc<tokio::sync::RwLock<SimdPhysicsEngine>>,
+    /// 世界ごとの天体数上限
+    pub max_bodies_per_world: usize,
+    /// 停止していた世界の再開時の進行計算
+    offline_calculator: OfflineProgressCalculator,
+    /// 負荷による縮退の段階（`DegradationLevel`）
+    degradation: AtomicU8,
+    /// 物理演算のBarnes-Hut近似の開き角（`f64`のビット列）
+    physics_theta: AtomicU64,
+    /// 縮退時に生命の更新を止める放置中の世界の判定時間
+    idle_after: chrono::Duration,
+    metrics_service: Arc<MetricsService>,
     /// メトリクス記録
     pub metrics_recorder: GameMetricsRecorder,
 }
 
+/// 世界を変更するコマンド（物理ループが各ティックの開始時に適用する）
+#[derive(Debug, Clone)]
+pub enum WorldCommand {
+    AddBody {
+        player_id: Uuid,
+        body: CelestialBody,
+    },
+    RemoveBody {
+        player_id: Uuid,
+        body_id: BodyId,
+    },
+}
+
+/// 世界へのコマンドの送信側
+pub type WorldCommandSender = CommandSender<WorldCommand, Result<()>>;
+
 /// プレイヤーセッション情報
 #[derive(Debug, Clone)]
 pub struct PlayerSession {
@@ -50,8 +219,19 @@ pub struct GameLoopConfig {
     pub resource_update_interval: Duration,
     pub metrics_update_interval: Duration,
     pub player_timeout: Duration,
+    /// 参加者がいなくなってから世界を停止するまでの時間
+    pub world_idle_timeout: Duration,
+    /// 世界ごとの天体数上限
     pub max_celestial_bodies: usize,
     pub auto_save_interval: Duration,
+    /// 停止していた世界の再開時の進行
+    pub offline_progress: OfflineProgressConfig,
+    /// 1ティックの間に積めるコマンド数
+    pub command_queue_capacity: usize,
+    /// スナップショットをブロードキャストする間隔
+    pub broadcast_interval: Duration,
+    /// ティック予算と縮退
+    pub tick_budget: TickBudgetConfig,
 }
 
 impl Default for GameLoopConfig {
@@ -62,8 +242,13 @@ impl Default for GameLoopConfig {
             resource_update_interval: Duration::from_millis(100), // 10 Hz
             metrics_update_interval: Duration::from_secs(5),
             player_timeout: Duration::from_secs(300), // 5分
+            world_idle_timeout: Duration::from_secs(600), // 10分
             max_celestial_bodies: 10000,
             auto_save_interval: Duration::from_secs(30),
+            offline_progress: OfflineProgressConfig::default(),
+            command_queue_capacity: DEFAULT_COMMAND_QUEUE_CAPACITY,
+            broadcast_interval: Duration::from_millis(100), // 10 Hz
+            tick_budget: TickBudgetConfig::default(),
         }
     }
 }
@@ -71,24 +256,31 @@ impl Default for GameLoopConfig {
 impl ConcurrentGameState {
     /// 新しい並行ゲーム状態を作成
     pub fn new(metrics_service: Arc<MetricsService>) -> Self {
-        let physics_engine = Arc::new(tokio::sync::RwLock::new(
-            SimdPhysicsEngine::new(metrics_service.clone())
-        ));
-        let metrics_recorder = GameMetricsRecorder::new(metrics_service);
+        Self::with_config(metrics_service, &GameLoopConfig::default())
+    }
+
+    /// 設定を指定して並行ゲーム状態を作成
+    pub fn with_config(metrics_service: Arc<MetricsService>, config: &GameLoopConfig) -> Self {
+        let metrics_recorder = GameMetricsRecorder::new(metrics_service.clone());
 
         Self {
-            celestial_bodies: Arc::new(DashMap::new()),
-            player_resources: Arc::new(DashMap::new()),
+            worlds: Arc::new(DashMap::new()),
+            player_worlds: Arc::new(DashMap::new()),
             active_players: Arc::new(DashMap::new()),
             game_tick: AtomicU64::new(0),
             running: AtomicBool::new(false),
-            physics_engine,
+            max_bodies_per_world: config.max_celestial_bodies,
+            offline_calculator: OfflineProgressCalculator::new(config.offline_progress.clone()),
+            degradation: AtomicU8::new(DegradationLevel::Normal.as_u8()),
+            physics_theta: AtomicU64::new(config.tick_budget.theta.to_bits()),
+            idle_after: chrono::Duration::from_std(config.tick_budget.idle_after).unwrap_or(chrono::Duration::MAX),
+            metrics_service,
             metrics_recorder,
         }
     }
 
-    /// プレイヤーを追加
-    pub fn add_player(&self, player_id: Uuid, websocket_session_id: Option<Uuid>) {
+    /// プレイヤーを追加（参加している世界がなければ個人の世界を作る）
+    pub fn add_player(&self, player_id: Uuid, websocket_session_id: Option<Uuid>) -> Option<OfflineReport> {
         let session = PlayerSession {
             player_id,
             connected_at: chrono::Utc::now(),
@@ -98,18 +290,42 @@ impl ConcurrentGameState {
         
         self.active_players.insert(player_id, session);
         
-        // プレイヤー用リソースマネージャーを作成
-        if !self.player_resources.contains_key(&player_id) {
-            self.player_resources.insert(player_id, ResourceManager::new());
-        }
+        let world_id = self.player_worlds.get(&player_id).map(|entry| *entry.value()).unwrap_or(player_id);
+        let report = self.join_world(player_id, world_id);
         
-        info!("Player {} added to game", player_id);
+        info!("Player {} added to game in world {}", player_id, world_id);
         self.update_player_metrics();
+        report
+    }
+
+    /// プレイヤーを世界に参加させる（共有ユニバースは同じ`world_id`で参加する）
+    ///
+    /// 世界が停止していた場合は再開し、その間の進行のレポートを返す。
+    pub fn join_world(&self, player_id: Uuid, world_id: WorldId) -> Option<OfflineReport> {
+        // 別の世界から移る場合は元の世界の参加者から外す
+        if let Some(previous) = self.player_worlds.insert(player_id, world_id) {
+            if previous != world_id {
+                if let Some(world) = self.world(&previous) {
+                    lock_world(&world).members.remove(&player_id);
+                }
+            }
+        }
+
+        let world = self.worlds
+            .entry(world_id)
+            .or_insert_with(|| Arc::new(Mutex::new(PlayerWorld::new(world_id, self.metrics_service.clone()))))
+            .clone();
+        let mut world = lock_world(&world);
+        let now = Utc::now();
+        world.members.insert(player_id);
+        world.last_activity = now;
+        world.resume(&self.offline_calculator, now)
     }
 
-    /// プレイヤーを削除
+    /// プレイヤーを削除（世界は停止されるまで進行を続ける）
     pub fn remove_player(&self, player_id: &Uuid) {
         self.active_players.remove(player_id);
+        self.touch_world(player_id);
         info!("Player {} removed from game", player_id);
         self.update_player_metrics();
     }
@@ -119,38 +335,167 @@ impl ConcurrentGameState {
         if let Some(mut session) = self.active_players.get_mut(player_id) {
             session.last_activity = chrono::Utc::now();
         }
+        self.touch_world(player_id);
     }
 
-    /// 天体を追加
-    pub fn add_celestial_body(&self, body: CelestialBody) -> Result<()> {
-        if self.celestial_bodies.len() >= 10000 { // 制限チェック
+    /// プレイヤーの世界の最終活動時刻を更新
+    fn touch_world(&self, player_id: &Uuid) {
+        if let Some(world) = self.player_world(player_id) {
+            lock_world(&world).last_activity = Utc::now();
+        }
+    }
+
+    /// 世界を取得
+    pub fn world(&self, world_id: &WorldId) -> Option<SharedWorld> {
+        self.worlds.get(world_id).map(|entry| entry.value().clone())
+    }
+
+    /// プレイヤーが参加している世界を取得
+    pub fn player_world(&self, player_id: &Uuid) -> Option<SharedWorld> {
+        let world_id = *self.player_worlds.get(player_id)?;
+        self.world(&world_id)
+    }
+
+    /// 天体をプレイヤーの世界に追加（コマンドキューから適用する場合のみ）
+    fn add_celestial_body(&self, player_id: &Uuid, body: CelestialBody) -> Result<()> {
+        let world = self.player_world(player_id)
+            .ok_or_else(|| anyhow::anyhow!("Player {} has no world", player_id))?;
+        let mut world = lock_world(&world);
+        
+        if world.celestial_manager.get_body_count() >= self.max_bodies_per_world { // 制限チェック
             return Err(anyhow::anyhow!("Maximum celestial bodies limit reached"));
         }
         
-        self.celestial_bodies.insert(body.id, body);
-        debug!("Celestial body added, total count: {}", self.celestial_bodies.len());
+        world.celestial_manager.get_all_bodies_mut().insert(body.id, body);
+        world.celestial_manager.sync_spatial_index();
+        debug!("Celestial body added to world {}, count: {}", world.world_id, world.celestial_manager.get_body_count());
+        drop(world);
         self.update_celestial_body_metrics();
         Ok(())
     }
 
-    /// 天体を削除
-    pub fn remove_celestial_body(&self, body_id: &BodyId) -> Option<CelestialBody> {
-        let removed = self.celestial_bodies.remove(body_id);
+    /// 天体をプレイヤーの世界から削除（コマンドキューから適用する場合のみ）
+    fn remove_celestial_body(&self, player_id: &Uuid, body_id: &BodyId) -> Option<CelestialBody> {
+        let world = self.player_world(player_id)?;
+        let removed = {
+            let mut world = lock_world(&world);
+            let removed = world.celestial_manager.get_all_bodies_mut().remove(body_id);
+            world.celestial_manager.sync_spatial_index();
+            removed
+        };
         if removed.is_some() {
-            debug!("Celestial body removed, total count: {}", self.celestial_bodies.len());
+            debug!("Celestial body removed, total count: {}", self.celestial_body_count());
             self.update_celestial_body_metrics();
         }
-        removed.map(|(_, body)| body)
+        removed
+    }
+
+    /// キューから取り出したコマンドを適用
+    ///
+    /// 世界の天体を増減させる入口はここだけで、外部からは`ConcurrentGameLoop::command_sender`で積む。
+    fn apply_command(&self, command: WorldCommand) -> Result<()> {
+        match command {
+            WorldCommand::AddBody { player_id, body } => {
+                self.touch_world(&player_id);
+                self.add_celestial_body(&player_id, body)
+            }
+            WorldCommand::RemoveBody { player_id, body_id } => {
+                self.touch_world(&player_id);
+                self.remove_celestial_body(&player_id, &body_id)
+                    .map(|_| ())
+                    .ok_or_else(|| anyhow::anyhow!("Celestial body {} not found", body_id))
+            }
+        }
     }
 
-    /// 天体データを取得
-    pub fn get_celestial_body(&self, body_id: &BodyId) -> Option<CelestialBody> {
-        self.celestial_bodies.get(body_id).map(|entry| entry.value().clone())
+    /// プレイヤーの世界の天体データを取得
+    pub fn get_celestial_body(&self, player_id: &Uuid, body_id: &BodyId) -> Option<CelestialBody> {
+        let world = self.player_world(player_id)?;
+        let world = lock_world(&world);
+        world.celestial_manager.get_body(*body_id).cloned()
     }
 
-    /// 全天体数を取得
+    /// 全世界の天体数を取得
     pub fn celestial_body_count(&self) -> usize {
-        self.celestial_bodies.len()
+        self.all_worlds().iter().map(|world| lock_world(world).celestial_manager.get_body_count()).sum()
+    }
+
+    /// 世界の数を取得
+    pub fn world_count(&self) -> usize {
+        self.worlds.len()
+    }
+
+    /// 実行中の世界の数を取得
+    pub fn active_world_count(&self) -> usize {
+        self.active_worlds().len()
+    }
+
+    /// 全世界（ID順、ワーカーへの割り当て順を実行ごとに揃える）
+    fn all_worlds(&self) -> Vec<SharedWorld> {
+        let mut worlds: Vec<(WorldId, SharedWorld)> = self.worlds
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        worlds.sort_unstable_by_key(|(world_id, _)| *world_id);
+        worlds.into_iter().map(|(_, world)| world).collect()
+    }
+
+    /// 実行中の世界（ID順）
+    fn active_worlds(&self) -> Vec<SharedWorld> {
+        self.all_worlds().into_iter().filter(|world| lock_world(world).is_active()).collect()
+    }
+
+    /// 実行中の全世界の物理演算を並列に進める（更新した天体数の合計を返す）
+    pub fn step_physics(&self, delta_time: f64) -> usize {
+        let theta = f64::from_bits(self.physics_theta.load(Ordering::Relaxed));
+        self.active_worlds()
+            .par_iter()
+            .map(|world| {
+                let mut world = lock_world(world);
+                world.set_theta(theta);
+                world.step_physics(delta_time).unwrap_or_else(|e| {
+                    warn!("Physics update failed in world {}: {}", world.world_id, e);
+                    0
+                })
+            })
+            .sum()
+    }
+
+    /// 実行中の全世界の生命とリソースを並列に進める（1ティックあたりの生産量の合計を返す）
+    ///
+    /// 縮退中は放置中の世界の生命を進めない。
+    pub fn step_resources(&self, delta_time_ms: u64) -> f64 {
+        let skip_idle_life = self.degradation().skips_idle_life();
+        let now = Utc::now();
+        self.active_worlds()
+            .par_iter()
+            .map(|world| {
+                let mut world = lock_world(world);
+                let update_life = !(skip_idle_life && self.is_idle(&world, now));
+                world.step_resources(delta_time_ms, update_life)
+            })
+            .sum()
+    }
+
+    /// 縮退の段階とBarnes-Hut近似の開き角を設定（次に進める物理演算とリソースから反映する）
+    pub fn set_degradation(&self, level: DegradationLevel, theta: f64) {
+        self.degradation.store(level.as_u8(), Ordering::Relaxed);
+        self.physics_theta.store(theta.to_bits(), Ordering::Relaxed);
+    }
+
+    /// 縮退の段階
+    pub fn degradation(&self) -> DegradationLevel {
+        DegradationLevel::from_u8(self.degradation.load(Ordering::Relaxed))
+    }
+
+    /// 接続中の参加者がいる世界か
+    fn has_connected_member(&self, world: &PlayerWorld) -> bool {
+        world.members.iter().any(|player_id| self.active_players.contains_key(player_id))
+    }
+
+    /// 接続中の参加者がいないか、最後の操作から`idle_after`が経った世界か
+    fn is_idle(&self, world: &PlayerWorld, now: DateTime<Utc>) -> bool {
+        !self.has_connected_member(world) || now - world.last_activity >= self.idle_after
     }
 
     /// アクティブプレイヤー数を取得
@@ -210,12 +555,29 @@ impl ConcurrentGameState {
         }
     }
 
+    /// 接続中の参加者がいない世界を停止（停止した世界の数を返す）
+    ///
+    /// 参加者の最後の操作から`idle_timeout`が経つまでは進行を続ける。
+    pub fn suspend_idle_worlds(&self, idle_timeout: Duration, now: DateTime<Utc>) -> usize {
+        let idle_timeout = chrono::Duration::from_std(idle_timeout).unwrap_or(chrono::Duration::MAX);
+        let mut suspended = 0;
+        for world in self.active_worlds() {
+            let mut world = lock_world(&world);
+            if !self.has_connected_member(&world) && now - world.last_activity >= idle_timeout {
+                world.suspend(now);
+                suspended += 1;
+            }
+        }
+        suspended
+    }
+
     /// ゲーム状態のスナップショットを作成
     pub fn create_snapshot(&self) -> GameStateSnapshot {
         GameStateSnapshot {
             tick: self.get_game_tick(),
             celestial_body_count: self.celestial_body_count(),
             active_player_count: self.active_player_count(),
+            active_world_count: self.active_world_count(),
             timestamp: chrono::Utc::now(),
         }
     }
@@ -227,6 +589,7 @@ pub struct GameStateSnapshot {
     pub tick: u64,
     pub celestial_body_count: usize,
     pub active_player_count: usize,
+    pub active_world_count: usize,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
@@ -235,85 +598,67 @@ pub struct ConcurrentGameLoop {
     state: Arc<ConcurrentGameState>,
     config: GameLoopConfig,
     physics_metrics: PhysicsMetricsRecorder,
+    command_sender: WorldCommandSender,
+    command_queue: Mutex<CommandQueue<WorldCommand, Result<()>>>,
+    snapshot_sender: broadcast::Sender<GameStateSnapshot>,
 }
 
 impl ConcurrentGameLoop {
     /// 新しいゲームループを作成
     pub fn new(metrics_service: Arc<MetricsService>, config: GameLoopConfig) -> Self {
-        let state = Arc::new(ConcurrentGameState::new(metrics_service.clone()));
+        let state = Arc::new(ConcurrentGameState::with_config(metrics_service.clone(), &config));
         let physics_metrics = PhysicsMetricsRecorder::new(metrics_service);
+        let (command_sender, command_queue) = command_queue(config.command_queue_capacity);
+        let (snapshot_sender, _) = broadcast::channel(16);
 
         Self {
             state,
             config,
             physics_metrics,
+            command_sender,
+            command_queue: Mutex::new(command_queue),
+            snapshot_sender,
         }
     }
 
     /// ゲームループを開始
     #[instrument(skip(self))]
-    pub async fn start(&mut self) -> Result<()> {
+    pub async fn start(&self) -> Result<()> {
         info!("Starting concurrent game loop with target TPS: {}", self.config.target_tps);
         
         self.state.set_running(true);
 
         // 複数のタスクを並行実行
-        let physics_task = self.start_physics_loop();
-        let resource_task = self.start_resource_loop();
+        let tick_task = self.start_tick_loop();
         let metrics_task = self.start_metrics_loop();
         let cleanup_task = self.start_cleanup_loop();
 
         // 全てのタスクを並行実行
-        tokio::try_join!(physics_task, resource_task, metrics_task, cleanup_task)?;
+        tokio::try_join!(tick_task, metrics_task, cleanup_task)?;
 
         Ok(())
     }
 
-    /// 物理演算ループ
-    async fn start_physics_loop(&self) -> Result<()> {
+    /// ティックループ
+    ///
+    /// 1ティックの予算は`1 / target_tps`秒。遅れは`physics_update_interval`単位のステップで取り戻す。
+    async fn start_tick_loop(&self) -> Result<()> {
         let mut interval = interval(self.config.physics_update_interval);
+        // 遅れはスケジューラが上限付きで取り戻すので、間に合わなかった発火はまとめて撃たない
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut scheduler = self.tick_scheduler();
         
-        info!("Physics loop started with interval: {:?}", self.config.physics_update_interval);
+        info!("Tick loop started with interval: {:?}, budget: {:?}", self.config.physics_update_interval, scheduler.budget());
         
+        let mut last_tick = Instant::now();
         while self.state.is_running() {
             interval.tick().await;
             
-            let start_time = Instant::now();
-            
-            // 物理演算を実行
-            let result = self.update_physics().await;
-            
-            let duration = start_time.elapsed();
+            let now = Instant::now();
+            let elapsed = now - last_tick;
+            last_tick = now;
             
-            match result {
-                Ok(collision_checks) => {
-                    self.physics_metrics.record_physics_calculation(
-                        duration.as_secs_f64(),
-                        self.state.celestial_body_count(),
-                        collision_checks,
-                    );
-                }
-                Err(e) => {
-                    error!("Physics update failed: {}", e);
-                }
-            }
-            
-            self.state.increment_tick();
-        }
-        
-        Ok(())
-    }
-
-    /// リソース更新ループ
-    async fn start_resource_loop(&self) -> Result<()> {
-        let mut interval = interval(self.config.resource_update_interval);
-        
-        info!("Resource loop started with interval: {:?}", self.config.resource_update_interval);
-        
-        while self.state.is_running() {
-            interval.tick().await;
-            
-            self.update_resources().await;
+            self.run_tick(&mut scheduler, elapsed).await;
         }
         
         Ok(())
@@ -344,55 +689,138 @@ impl ConcurrentGameLoop {
             interval.tick().await;
             
             self.state.cleanup_inactive_players(self.config.player_timeout);
+            
+            let suspended = self.state.suspend_idle_worlds(self.config.world_idle_timeout, chrono::Utc::now());
+            if suspended > 0 {
+                info!("Suspended {} idle worlds", suspended);
+            }
         }
         
         Ok(())
     }
 
-    /// 物理演算を更新
-    async fn update_physics(&self) -> Result<usize> {
-        let celestial_bodies = &self.state.celestial_bodies;
+    /// ティック予算のスケジューラを作成
+    fn tick_scheduler(&self) -> TickScheduler {
+        let budget = Duration::from_secs_f64(1.0 / self.config.target_tps.max(1) as f64);
+        TickScheduler::new(self.config.physics_update_interval, budget, self.config.tick_budget.clone())
+    }
+
+    /// `interval`が何ステップ分か（1未満は1）
+    fn ticks_per(&self, interval: Duration) -> u64 {
+        (interval.as_nanos() / self.config.physics_update_interval.as_nanos().max(1)).max(1) as u64
+    }
+
+    /// 前回のティックから`elapsed`経った時点のティックを処理し、計測結果を返す
+    ///
+    /// コマンド・物理演算・リソース・ブロードキャストの処理時間を計り、予算の超過が続いたら
+    /// スケジューラの段階に従って次のティックから処理を間引く。
+    async fn run_tick(&self, scheduler: &mut TickScheduler, elapsed: Duration) -> TickReport {
+        let plan = scheduler.plan_steps(elapsed);
+        if plan.dropped > 0 {
+            warn!("Game loop fell behind, dropped {} steps", plan.dropped);
+        }
+        let level = scheduler.level();
+        self.state.set_degradation(level, scheduler.theta());
         
-        if celestial_bodies.is_empty() {
-            return Ok(0);
+        // ティック開始時に積まれていたコマンドを受け付け順に適用
+        scheduler.measure(Subsystem::Commands, || self.apply_queued_commands());
+        
+        if plan.steps > 0 {
+            let tick_before = self.state.get_game_tick();
+            
+            // 物理演算を実行（遅れている分はまとめて進める）
+            let start_time = Instant::now();
+            let result = self.update_physics(plan.steps).await;
+            let duration = start_time.elapsed();
+            scheduler.record(Subsystem::Physics, duration);
+            
+            match result {
+                Ok(collision_checks) => {
+                    self.physics_metrics.record_physics_calculation(
+                        duration.as_secs_f64(),
+                        self.state.celestial_body_count(),
+                        collision_checks,
+                    );
+                }
+                Err(e) => {
+                    error!("Physics update failed: {}", e);
+                }
+            }
+            
+            for _ in 0..plan.steps {
+                self.state.increment_tick();
+            }
+            let tick_after = self.state.get_game_tick();
+            
+            // リソースは`resource_update_interval`分のティックごとにまとめて進める
+            let resource_ticks = self.ticks_per(self.config.resource_update_interval);
+            let resource_updates = tick_after / resource_ticks - tick_before / resource_ticks;
+            if resource_updates > 0 {
+                let delta_time_ms = resource_updates * resource_ticks * self.config.physics_update_interval.as_millis() as u64;
+                let start_time = Instant::now();
+                self.update_resources(delta_time_ms).await;
+                scheduler.record(Subsystem::Resources, start_time.elapsed());
+            }
+            
+            let broadcast_ticks = scheduler.broadcast_interval(self.ticks_per(self.config.broadcast_interval));
+            if tick_after / broadcast_ticks > tick_before / broadcast_ticks {
+                scheduler.measure(Subsystem::Broadcast, || self.broadcast_snapshot());
+            }
+        }
+        
+        let report = scheduler.finish_tick();
+        if report.level != level {
+            warn!("Tick budget {:?} (last tick took {:?}), degradation level {:?} -> {:?}",
+                  scheduler.budget(), report.total, level, report.level);
         }
+        let costs: Vec<(&str, f64)> = report.costs
+            .iter()
+            .map(|(subsystem, cost)| (subsystem.name(), cost.as_secs_f64()))
+            .collect();
+        self.state.metrics_recorder.record_tick_budget(&costs, report.overrun, plan.dropped, report.level.as_u8());
+        report
+    }
 
-        // DashMapから標準HashMapに変換（物理演算用）
-        let mut bodies_map = std::collections::HashMap::new();
-        for entry in celestial_bodies.iter() {
-            bodies_map.insert(*entry.key(), entry.value().clone());
+    /// ゲーム状態のスナップショットを購読者に送る（購読者がいなければ何もしない）
+    fn broadcast_snapshot(&self) {
+        if self.snapshot_sender.receiver_count() > 0 {
+            let _ = self.snapshot_sender.send(self.state.create_snapshot());
         }
+    }
 
-        // 物理演算を実行
-        let mut physics_engine = self.state.physics_engine.write().await;
-        physics_engine.update_optimized(&mut bodies_map, self.config.physics_update_interval.as_secs_f64())?;
+    /// 積まれているコマンドを現在のティックで適用
+    fn apply_queued_commands(&self) -> usize {
+        let tick = self.state.get_game_tick();
+        let mut queue = self.command_queue.lock().unwrap_or_else(PoisonError::into_inner);
+        queue.apply_pending(tick, |command| {
+            debug!("Applying {:?} on tick {}", command, tick);
+            self.state.apply_command(command)
+        })
+    }
 
-        // 結果をDashMapに書き戻し
-        let mut collision_checks = 0;
-        for (id, updated_body) in bodies_map {
-            if let Some(mut entry) = celestial_bodies.get_mut(&id) {
-                *entry = updated_body;
-                collision_checks += 1;
-            }
-        }
+    /// 物理演算を`steps`ステップ進める
+    ///
+    /// 世界ごとに独立して計算し、rayonのワーカーに振り分ける（非同期ランタイムは塞がない）。
+    async fn update_physics(&self, steps: u32) -> Result<usize> {
+        let state = self.state.clone();
+        let delta_time = self.config.physics_update_interval.as_secs_f64();
+        let collision_checks = tokio::task::spawn_blocking(move || {
+            (0..steps).map(|_| state.step_physics(delta_time)).sum::<usize>()
+        }).await?;
 
         Ok(collision_checks)
     }
 
-    /// リソースを更新
-    async fn update_resources(&self) {
-        let mut total_generation_rate = 0.0;
-        
-        for mut entry in self.state.player_resources.iter_mut() {
-            let resource_manager = entry.value_mut();
-            
-            // リソース生成率を計算
-            let generation_rate = resource_manager.calculate_generation_rate();
-            total_generation_rate += generation_rate;
-            
-            // リソースを更新
-            resource_manager.update_resources(self.config.resource_update_interval.as_secs_f64());
-        }
+    /// リソースを`delta_time_ms`分進める
+    async fn update_resources(&self, delta_time_ms: u64) {
+        let state = self.state.clone();
+        let total_generation_rate = match tokio::task::spawn_blocking(move || state.step_resources(delta_time_ms)).await {
+            Ok(rate) => rate,
+            Err(e) => {
+                error!("Resource update failed: {}", e);
+                0.0
+            }
+        };
         
         // メトリクスを更新
         self.state.metrics_recorder.record_game_state(
@@ -406,8 +834,8 @@ impl ConcurrentGameLoop {
     async fn update_metrics(&self) {
         let snapshot = self.state.create_snapshot();
         
-        debug!("Game state snapshot: tick={}, bodies={}, players={}", 
-               snapshot.tick, snapshot.celestial_body_count, snapshot.active_player_count);
+        debug!("Game state snapshot: tick={}, bodies={}, players={}, active_worlds={}", 
+               snapshot.tick, snapshot.celestial_body_count, snapshot.active_player_count, snapshot.active_world_count);
         
         // 追加のメトリクス更新があればここで実行
     }
@@ -418,6 +846,16 @@ impl ConcurrentGameLoop {
         self.state.set_running(false);
     }
 
+    /// ゲーム状態のスナップショットを購読（負荷が高い間は間隔が延びる）
+    pub fn subscribe_snapshots(&self) -> broadcast::Receiver<GameStateSnapshot> {
+        self.snapshot_sender.subscribe()
+    }
+
+    /// 世界を変更するコマンドの送信側（ソケットのタスクに渡す）
+    pub fn command_sender(&self) -> WorldCommandSender {
+        self.command_sender.clone()
+    }
+
     /// ゲーム状態への参照を取得
     pub fn get_state(&self) -> &Arc<ConcurrentGameState> {
         &self.state
@@ -493,6 +931,8 @@ mod tests {
         let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
         
         let state = ConcurrentGameState::new(metrics_service);
+        let player_id = Uuid::new_v4();
+        state.add_player(player_id, None);
         let body_id = Uuid::new_v4();
         
         let body = crate::game::celestial_bodies::CelestialBody::new(
@@ -504,17 +944,175 @@ mod tests {
         );
         
         // 天体を追加
-        state.add_celestial_body(body.clone()).unwrap();
+        state.add_celestial_body(&player_id, body.clone()).unwrap();
         assert_eq!(state.celestial_body_count(), 1);
         
         // 天体を取得
-        let retrieved = state.get_celestial_body(&body_id);
+        let retrieved = state.get_celestial_body(&player_id, &body_id);
         assert!(retrieved.is_some());
         assert_eq!(retrieved.unwrap().id, body_id);
         
         // 天体を削除
-        let removed = state.remove_celestial_body(&body_id);
+        let removed = state.remove_celestial_body(&player_id, &body_id);
         assert!(removed.is_some());
         assert_eq!(state.celestial_body_count(), 0);
     }
+
+    fn heavy_body(position: nalgebra::Vector3<f64>) -> CelestialBody {
+        CelestialBody::new(
+            Uuid::new_v4(),
+            crate::game::celestial_bodies::CelestialType::Asteroid,
+            position,
+            fixed::from_f64(1.0e6),
+            fixed::from_f64(1.0),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_player_worlds_are_isolated() {
+        let metrics_config = crate::services::metrics::MetricsConfig::default();
+        let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
+        
+        let state = ConcurrentGameState::new(metrics_service);
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        state.add_player(alice, None);
+        // 共有ユニバースにはプレイヤーが同じ世界IDで参加する
+        let universe_id = Uuid::new_v4();
+        for player_id in [bob, carol] {
+            state.join_world(player_id, universe_id);
+            state.add_player(player_id, None);
+        }
+        assert_eq!(state.world_count(), 2);
+        
+        let lone = heavy_body(nalgebra::Vector3::new(0.0, 0.0, 0.0));
+        let (first, second) = (heavy_body(nalgebra::Vector3::new(0.0, 0.0, 0.0)), heavy_body(nalgebra::Vector3::new(10.0, 0.0, 0.0)));
+        let (lone_id, first_id) = (lone.id, first.id);
+        state.add_celestial_body(&alice, lone).unwrap();
+        state.add_celestial_body(&bob, first).unwrap();
+        state.add_celestial_body(&carol, second).unwrap();
+        
+        state.step_physics(0.05);
+        
+        // 別の世界の天体には引かれず、同じ世界の天体には引かれる
+        let lone = state.get_celestial_body(&alice, &lone_id).unwrap();
+        assert_eq!(lone.physics.velocity, nalgebra::Vector3::zeros());
+        let first = state.get_celestial_body(&carol, &first_id).unwrap();
+        assert!(first.physics.velocity.x > 0.0);
+        assert!(state.get_celestial_body(&alice, &first_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_idle_world_is_suspended_and_catches_up() {
+        let metrics_config = crate::services::metrics::MetricsConfig::default();
+        let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
+        
+        let state = ConcurrentGameState::new(metrics_service);
+        let player_id = Uuid::new_v4();
+        state.add_player(player_id, None);
+        let idle_timeout = Duration::from_secs(600);
+        
+        // 接続中の世界は停止しない
+        let later = chrono::Utc::now() + chrono::Duration::hours(1);
+        assert_eq!(state.suspend_idle_worlds(idle_timeout, later), 0);
+        
+        state.remove_player(&player_id);
+        assert_eq!(state.suspend_idle_worlds(idle_timeout, chrono::Utc::now()), 0);
+        assert_eq!(state.suspend_idle_worlds(idle_timeout, later), 1);
+        assert_eq!(state.active_world_count(), 0);
+        
+        // 停止中は進行しない
+        let world = state.player_world(&player_id).unwrap();
+        let dust_before = lock_world(&world).resource_manager.get_resources().cosmic_dust;
+        assert_eq!(state.step_resources(1000), 0.0);
+        assert_eq!(lock_world(&world).resource_manager.get_resources().cosmic_dust, dust_before);
+        
+        // 停止していた間の進行は再開時にまとめて適用される
+        lock_world(&world).resource_manager.get_game_state_mut().last_update = chrono::Utc::now() - chrono::Duration::hours(1);
+        let report = state.add_player(player_id, None).expect("resuming should apply offline progress");
+        assert!(report.resources_gained.cosmic_dust > 0);
+        assert!(matches!(lock_world(&world).status(), WorldStatus::Active));
+    }
+
+    #[tokio::test]
+    async fn test_queued_commands_apply_at_tick_start() {
+        let metrics_config = crate::services::metrics::MetricsConfig::default();
+        let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
+        
+        let game_loop = ConcurrentGameLoop::new(metrics_service, GameLoopConfig::default());
+        let player_id = Uuid::new_v4();
+        game_loop.get_state().add_player(player_id, None);
+        game_loop.get_state().increment_tick();
+        
+        let body = heavy_body(nalgebra::Vector3::new(0.0, 0.0, 0.0));
+        let body_id = body.id;
+        let sender = game_loop.command_sender();
+        let added = sender.try_submit(WorldCommand::AddBody { player_id, body }).unwrap();
+        let removed = sender.try_submit(WorldCommand::RemoveBody { player_id, body_id }).unwrap();
+        let missing = sender.try_submit(WorldCommand::RemoveBody { player_id, body_id }).unwrap();
+        
+        // 積んだだけでは世界は変わらず、ティック開始時に積んだ順で適用される
+        assert_eq!(game_loop.get_state().celestial_body_count(), 0);
+        assert_eq!(game_loop.apply_queued_commands(), 3);
+        
+        let (added, removed, missing) = (added.await.unwrap(), removed.await.unwrap(), missing.await.unwrap());
+        assert_eq!((added.sequence, added.tick), (0, 1));
+        assert!(added.result.is_ok());
+        assert_eq!((removed.sequence, removed.tick), (1, 1));
+        assert!(removed.result.is_ok());
+        assert_eq!(missing.sequence, 2);
+        assert!(missing.result.is_err());
+        assert_eq!(game_loop.get_state().celestial_body_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tick_budget_degrades_and_catches_up() {
+        let metrics_config = crate::services::metrics::MetricsConfig::default();
+        let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
+        
+        // 予算を1マイクロ秒にして毎ティック超過させる
+        let config = GameLoopConfig {
+            target_tps: 1_000_000,
+            tick_budget: TickBudgetConfig { degrade_after: 1, ..TickBudgetConfig::default() },
+            ..GameLoopConfig::default()
+        };
+        let step = config.physics_update_interval;
+        let game_loop = ConcurrentGameLoop::new(metrics_service.clone(), config);
+        let player_id = Uuid::new_v4();
+        game_loop.get_state().add_player(player_id, None);
+        for i in 0..8 {
+            let body = heavy_body(nalgebra::Vector3::new(i as f64 * 10.0, 0.0, 0.0));
+            game_loop.get_state().add_celestial_body(&player_id, body).unwrap();
+        }
+        let mut snapshots = game_loop.subscribe_snapshots();
+        let mut scheduler = game_loop.tick_scheduler();
+        
+        // 超過が続くと決められた順に段階が下がる
+        let mut levels = Vec::new();
+        for _ in 0..3 {
+            let report = game_loop.run_tick(&mut scheduler, step).await;
+            assert!(report.overrun);
+            levels.push(report.level);
+        }
+        assert_eq!(levels, vec![
+            DegradationLevel::ReducedAccuracy,
+            DegradationLevel::SkipIdleLife,
+            DegradationLevel::ThrottledBroadcast,
+        ]);
+        assert_eq!(game_loop.get_state().get_game_tick(), 3);
+        
+        // 遅れは上限のステップ数まで取り戻し、超えた分は捨てる
+        game_loop.run_tick(&mut scheduler, step * 10).await;
+        let state = game_loop.get_state();
+        assert_eq!(state.get_game_tick(), 7);
+        assert_eq!(state.degradation(), DegradationLevel::ThrottledBroadcast);
+        assert_eq!(f64::from_bits(state.physics_theta.load(Ordering::Relaxed)), 1.0);
+        
+        // 通常の間隔（6ティック）は越えたが、ブロードキャストは間引かれている
+        assert!(matches!(snapshots.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+        
+        let summary = metrics_service.get_metrics_summary();
+        assert_eq!(summary.tick_overruns_total, 4);
+        assert_eq!(summary.tick_dropped_steps_total, 6);
+        assert_eq!(summary.tick_degradation_level, DegradationLevel::ThrottledBroadcast.as_u8() as i64);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,177 @@
+//! 決定論的シミュレーション
+//!
+//! 同じスナップショットと同じコマンドログから、ビット単位で同一の結果を再現するための部品。
+//! 天体の処理順を安定させ、確率的な処理（天体IDの採番など）はシード付き乱数で行う。
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use uuid::{Builder, Uuid};
+
+use crate::game::celestial_bodies::{BodyId, CelestialType, Vec3Fixed};
+use crate::game::research::TechnologyId;
+use crate::game::resources::UpgradeType;
+
+/// シード付き擬似乱数生成器（SplitMix64）
+///
+/// 状態が`u64`1つだけなので、スナップショットにそのまま保存できる。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeterministicRng {
+    state: u64,
+}
+
+impl DeterministicRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// 現在の内部状態（保存と復元用）
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1) の一様乱数
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// 乱数から生成したUUID（v4形式）
+    pub fn next_uuid(&mut self) -> Uuid {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.next_u64().to_le_bytes());
+        bytes[8..].copy_from_slice(&self.next_u64().to_le_bytes());
+        Builder::from_random_bytes(bytes).into_uuid()
+    }
+}
+
+/// 天体IDの順に並べたキー（HashMapの反復順に依存しないため）
+pub fn sorted_body_ids<V>(bodies: &HashMap<BodyId, V>) -> Vec<BodyId> {
+    let mut ids: Vec<BodyId> = bodies.keys().copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// 再生可能なプレイヤーコマンド
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReplayCommand {
+    CreateCelestialBody {
+        body_type: CelestialType,
+        position: Vec3Fixed,
+    },
+    RemoveCelestialBody {
+        body_id: BodyId,
+    },
+    PurchaseUpgrade {
+        upgrade_type: UpgradeType,
+    },
+    StartResearch {
+        technology_id: TechnologyId,
+    },
+    CancelResearch {
+        technology_id: TechnologyId,
+    },
+}
+
+/// コマンドログ（ティックごとに記録順で再生する）
+///
+/// 再生はティックを順に進めながら各ティックのコマンドを引くので、ティックで索引しておく。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommandLog {
+    /// ティックごとのコマンド（そのティックの更新の前に記録順で適用する）
+    commands: BTreeMap<u64, Vec<ReplayCommand>>,
+}
+
+impl CommandLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// コマンドを記録
+    pub fn record(&mut self, tick: u64, command: ReplayCommand) {
+        self.commands.entry(tick).or_default().push(command);
+    }
+
+    /// 指定ティックのコマンドを記録順に列挙
+    pub fn commands_at(&self, tick: u64) -> impl Iterator<Item = &ReplayCommand> + '_ {
+        self.commands.get(&tick).into_iter().flatten()
+    }
+
+    /// `from_tick`から`to_tick`の直前までのコマンドをティック順に列挙
+    pub fn commands_between(&self, from_tick: u64, to_tick: u64) -> impl Iterator<Item = (u64, &ReplayCommand)> + '_ {
+        self.commands
+            .range(from_tick..to_tick.max(from_tick))
+            .flat_map(|(tick, commands)| commands.iter().map(move |command| (*tick, command)))
+    }
+
+    /// 記録されたコマンドの数
+    pub fn len(&self) -> usize {
+        self.commands.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// 最後に記録されたティック
+    pub fn last_tick(&self) -> Option<u64> {
+        self.commands.keys().next_back().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_reproducible_from_state() {
+        let mut rng = DeterministicRng::new(42);
+        rng.next_u64();
+
+        let mut restored = DeterministicRng::new(rng.state());
+        assert_eq!(rng.next_u64(), restored.next_u64());
+        assert_eq!(rng.next_uuid(), restored.next_uuid());
+
+        let value = rng.next_f64();
+        assert!((0.0..1.0).contains(&value));
+    }
+
+    #[test]
+    fn test_sorted_body_ids() {
+        let mut bodies = HashMap::new();
+        let mut rng = DeterministicRng::new(7);
+        for _ in 0..16 {
+            bodies.insert(rng.next_uuid(), ());
+        }
+
+        let ids = sorted_body_ids(&bodies);
+        assert_eq!(ids.len(), 16);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_command_log_indexes_by_tick() {
+        let mut log = CommandLog::new();
+        let upgrade = |upgrade_type| ReplayCommand::PurchaseUpgrade { upgrade_type };
+        log.record(12, upgrade(UpgradeType::EnergyEfficiency));
+        log.record(3, upgrade(UpgradeType::DustProduction));
+        log.record(12, upgrade(UpgradeType::DustProduction));
+
+        // 同じティックのコマンドは記録順、ティックは記録順によらず昇順
+        let at_twelve: Vec<_> = log.commands_at(12).cloned().collect();
+        assert_eq!(at_twelve, vec![upgrade(UpgradeType::EnergyEfficiency), upgrade(UpgradeType::DustProduction)]);
+        assert_eq!(log.commands_at(4).count(), 0);
+
+        let ticks: Vec<u64> = log.commands_between(0, 13).map(|(tick, _)| tick).collect();
+        assert_eq!(ticks, vec![3, 12, 12]);
+        assert_eq!(log.commands_between(4, 12).count(), 0);
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.last_tick(), Some(12));
+    }
+}
@@ -8,11 +8,25 @@ use chrono::{DateTime, Utc};
 use tracing::{info, warn, error, debug};
 
 use crate::errors::{GameError, Result};
-use crate::game::resources::{ResourceManager, Resources};
-use crate::game::celestial_bodies::{CelestialBodyManager, CelestialBody, BodyId};
-use crate::game::physics::PhysicsEngine;
+use crate::game::resources::{fixed, ProductionSummary, ResourceManager, ResourceType, Resources};
+use crate::game::celestial_bodies::{CelestialBodyManager, CelestialBody, BodyId, LifeStage};
+use crate::game::determinism::{CommandLog, DeterministicRng, ReplayCommand};
+use crate::game::integrator::IntegratorKind;
+use crate::game::timestep::TimestepSettings;
+use crate::game::physics::{PhysicsEngine, PhysicsEvent};
+use crate::game::collision::CollisionOutcome;
+use crate::game::stellar_evolution::StellarPhase;
+use crate::game::life::{self, ExtinctionCause, LifeEventKind, LifeTransition};
 use crate::game::validation::{ValidationEngine, PlayerId};
 use crate::game::persistence::{PersistenceManager, GameStateSnapshot, GameStateDelta};
+use crate::game::offline::{OfflineProgressCalculator, OfflineProgressConfig, OfflineReport};
+use crate::game::upgrade_catalog::UpgradeCatalogHandle;
+use crate::game::research::{ResearchManager, ResearchTree, TechnologyId};
+use crate::game::prestige::{PrestigeCalculator, PrestigeConfig, PrestigeRecord};
+use crate::game::achievements::{
+    AchievementCatalog, AchievementTracker, AchievementTrigger, AchievementUnlock, PlayerMetrics, TriggerKind,
+};
+use crate::models::websocket::DestructionReason;
 
 /// ゲームループの設定
 #[derive(Debug, Clone)]
@@ -23,6 +37,10 @@ pub struct GameLoopConfig {
     pub cleanup_interval: Duration,
     pub max_players: usize,
     pub performance_monitoring: bool,
+    pub offline_progress: OfflineProgressConfig,
+    pub prestige: PrestigeConfig,
+    /// 決定論モードのシード（`Some`の場合はコマンドログを記録し再生可能にする）
+    pub deterministic_seed: Option<u64>,
 }
 
 impl Default for GameLoopConfig {
@@ -34,6 +52,35 @@ impl Default for GameLoopConfig {
             cleanup_interval: Duration::from_secs(3600), // 1時間
             max_players: 1000,
             performance_monitoring: true,
+            offline_progress: OfflineProgressConfig::default(),
+            prestige: PrestigeConfig::default(),
+            deterministic_seed: None,
+        }
+    }
+}
+
+/// 全プレイヤーで共有する定義データ
+#[derive(Debug, Clone)]
+pub struct SharedDefinitions {
+    pub upgrade_catalog: UpgradeCatalogHandle,
+    pub research_tree: Arc<ResearchTree>,
+    pub research_queue_length: usize,
+    pub achievement_catalog: Arc<AchievementCatalog>,
+    /// 物理演算の時間積分法
+    pub integrator: IntegratorKind,
+    /// 接近時のティック分割
+    pub timestep: TimestepSettings,
+}
+
+impl Default for SharedDefinitions {
+    fn default() -> Self {
+        Self {
+            upgrade_catalog: UpgradeCatalogHandle::default(),
+            research_tree: Arc::new(ResearchTree::default()),
+            research_queue_length: 5,
+            achievement_catalog: Arc::new(AchievementCatalog::default()),
+            integrator: IntegratorKind::default(),
+            timestep: TimestepSettings::default(),
         }
     }
 }
@@ -60,10 +107,55 @@ pub enum GameCommand {
     LoadGame {
         player_id: PlayerId,
     },
+    Prestige {
+        player_id: PlayerId,
+    },
+    StartResearch {
+        player_id: PlayerId,
+        technology_id: TechnologyId,
+    },
+    CancelResearch {
+        player_id: PlayerId,
+        technology_id: TechnologyId,
+    },
     GetState {
         player_id: PlayerId,
         response_sender: tokio::sync::oneshot::Sender<Result<GameStateSnapshot, GameError>>,
     },
+    GetProductionBreakdown {
+        player_id: PlayerId,
+        response_sender: tokio::sync::oneshot::Sender<Result<ProductionSummary, GameError>>,
+    },
+}
+
+impl GameCommand {
+    /// 決定論モードで記録・再生するコマンドへの変換（状態を変えないコマンドは`None`）
+    pub fn replay_command(&self) -> Option<(PlayerId, ReplayCommand)> {
+        let (player_id, command) = match self {
+            GameCommand::CreateCelestialBody { player_id, body_type, position } => (
+                *player_id,
+                ReplayCommand::CreateCelestialBody { body_type: body_type.clone(), position: *position },
+            ),
+            GameCommand::RemoveCelestialBody { player_id, body_id } => (
+                *player_id,
+                ReplayCommand::RemoveCelestialBody { body_id: *body_id },
+            ),
+            GameCommand::PurchaseUpgrade { player_id, upgrade_type } => (
+                *player_id,
+                ReplayCommand::PurchaseUpgrade { upgrade_type: *upgrade_type },
+            ),
+            GameCommand::StartResearch { player_id, technology_id } => (
+                *player_id,
+                ReplayCommand::StartResearch { technology_id: technology_id.clone() },
+            ),
+            GameCommand::CancelResearch { player_id, technology_id } => (
+                *player_id,
+                ReplayCommand::CancelResearch { technology_id: technology_id.clone() },
+            ),
+            _ => return None,
+        };
+        Some((player_id, command))
+    }
 }
 
 /// ゲームイベント
@@ -77,12 +169,24 @@ pub enum GameEvent {
     CelestialBodyDestroyed {
         player_id: PlayerId,
         body_id: BodyId,
+        reason: DestructionReason,
     },
     LifeEvolved {
         player_id: PlayerId,
         body_id: BodyId,
         new_stage: crate::game::celestial_bodies::LifeStage,
     },
+    LifeDeclined {
+        player_id: PlayerId,
+        body_id: BodyId,
+        new_stage: crate::game::celestial_bodies::LifeStage,
+        cause: ExtinctionCause,
+    },
+    TechAdvanced {
+        player_id: PlayerId,
+        body_id: BodyId,
+        tech_level: u32,
+    },
     UpgradePurchased {
         player_id: PlayerId,
         upgrade_type: crate::game::resources::UpgradeType,
@@ -91,6 +195,37 @@ pub enum GameEvent {
         player_id: PlayerId,
         body1_id: BodyId,
         body2_id: BodyId,
+        outcome: CollisionOutcome,
+    },
+    BodyAbsorbed {
+        player_id: PlayerId,
+        survivor_id: BodyId,
+        absorbed_id: BodyId,
+    },
+    BodyLeftBounds {
+        player_id: PlayerId,
+        body_id: BodyId,
+    },
+    DebrisCreated {
+        player_id: PlayerId,
+        body_id: BodyId,
+        source_id: BodyId,
+    },
+    StellarEvolved {
+        player_id: PlayerId,
+        body_id: BodyId,
+        phase: StellarPhase,
+    },
+    Supernova {
+        player_id: PlayerId,
+        body_id: BodyId,
+        dust_released: u64,
+        affected_planets: Vec<BodyId>,
+    },
+    ResourceMilestone {
+        player_id: PlayerId,
+        resource_type: ResourceType,
+        amount: u64,
     },
     GameSaved {
         player_id: PlayerId,
@@ -100,6 +235,110 @@ pub enum GameEvent {
         player_id: PlayerId,
         tick: u64,
     },
+    OfflineProgressApplied {
+        player_id: PlayerId,
+        report: OfflineReport,
+    },
+    PrestigePerformed {
+        player_id: PlayerId,
+        record: PrestigeRecord,
+    },
+    ResearchUnlocked {
+        player_id: PlayerId,
+        research_id: TechnologyId,
+    },
+    AchievementUnlocked {
+        player_id: PlayerId,
+        achievement: AchievementUnlock,
+    },
+}
+
+impl GameEvent {
+    /// 生命の変化をイベントに変換
+    pub fn from_life_transition(player_id: PlayerId, transition: LifeTransition) -> Self {
+        let LifeTransition { body_id, kind, to_stage, cause, .. } = transition;
+        match (kind, to_stage, cause) {
+            (LifeEventKind::TechAdvance, LifeStage::Intelligent { tech_level, .. }, _) => {
+                GameEvent::TechAdvanced { player_id, body_id, tech_level }
+            }
+            (LifeEventKind::Regression | LifeEventKind::Extinction, new_stage, Some(cause)) => {
+                GameEvent::LifeDeclined { player_id, body_id, new_stage, cause }
+            }
+            (_, new_stage, _) => GameEvent::LifeEvolved { player_id, body_id, new_stage },
+        }
+    }
+    
+    /// イベント対象のプレイヤー
+    pub fn player_id(&self) -> PlayerId {
+        match self {
+            GameEvent::CelestialBodyCreated { player_id, .. }
+            | GameEvent::CelestialBodyDestroyed { player_id, .. }
+            | GameEvent::LifeEvolved { player_id, .. }
+            | GameEvent::LifeDeclined { player_id, .. }
+            | GameEvent::TechAdvanced { player_id, .. }
+            | GameEvent::UpgradePurchased { player_id, .. }
+            | GameEvent::CollisionDetected { player_id, .. }
+            | GameEvent::BodyAbsorbed { player_id, .. }
+            | GameEvent::BodyLeftBounds { player_id, .. }
+            | GameEvent::DebrisCreated { player_id, .. }
+            | GameEvent::StellarEvolved { player_id, .. }
+            | GameEvent::Supernova { player_id, .. }
+            | GameEvent::ResourceMilestone { player_id, .. }
+            | GameEvent::GameSaved { player_id, .. }
+            | GameEvent::GameLoaded { player_id, .. }
+            | GameEvent::OfflineProgressApplied { player_id, .. }
+            | GameEvent::PrestigePerformed { player_id, .. }
+            | GameEvent::ResearchUnlocked { player_id, .. }
+            | GameEvent::AchievementUnlocked { player_id, .. } => *player_id,
+        }
+    }
+    
+    /// 実績判定の入力への変換（対象外のイベントは`None`）
+    pub fn achievement_trigger(&self) -> Option<AchievementTrigger> {
+        let trigger = match self {
+            GameEvent::CelestialBodyCreated { body_type, .. } => {
+                AchievementTrigger::with_subject(TriggerKind::BodyCreated, body_type.name())
+            }
+            GameEvent::CelestialBodyDestroyed { reason: DestructionReason::ManualDestruction, .. } => {
+                AchievementTrigger::new(TriggerKind::BodyDestroyed)
+            }
+            GameEvent::LifeEvolved { new_stage, .. } => {
+                AchievementTrigger::with_subject(TriggerKind::LifeEvolved, new_stage.name())
+            }
+            GameEvent::LifeDeclined { new_stage: LifeStage::None, cause, .. } => {
+                AchievementTrigger::with_subject(TriggerKind::Extinction, cause.name())
+            }
+            GameEvent::TechAdvanced { .. } => AchievementTrigger::new(TriggerKind::TechAdvanced),
+            GameEvent::UpgradePurchased { upgrade_type, .. } => {
+                AchievementTrigger::with_subject(TriggerKind::UpgradePurchased, format!("{:?}", upgrade_type))
+            }
+            GameEvent::CollisionDetected { outcome, .. } => {
+                AchievementTrigger::with_subject(TriggerKind::Collision, outcome.name())
+            }
+            GameEvent::BodyAbsorbed { .. } => AchievementTrigger::new(TriggerKind::BodyAbsorbed),
+            GameEvent::BodyLeftBounds { .. } => AchievementTrigger::new(TriggerKind::BodyLeftBounds),
+            GameEvent::StellarEvolved { phase, .. } => {
+                AchievementTrigger::with_subject(TriggerKind::StellarEvolved, phase.name())
+            }
+            GameEvent::Supernova { .. } => AchievementTrigger::new(TriggerKind::Supernova),
+            GameEvent::ResourceMilestone { resource_type, .. } => {
+                AchievementTrigger::with_subject(TriggerKind::ResourceMilestone, format!("{:?}", resource_type))
+            }
+            GameEvent::ResearchUnlocked { research_id, .. } => {
+                AchievementTrigger::with_subject(TriggerKind::ResearchUnlocked, research_id.clone())
+            }
+            GameEvent::PrestigePerformed { .. } => AchievementTrigger::new(TriggerKind::PrestigePerformed),
+            GameEvent::OfflineProgressApplied { .. } => AchievementTrigger::new(TriggerKind::OfflineProgress),
+            GameEvent::GameLoaded { .. } => AchievementTrigger::new(TriggerKind::GameLoaded),
+            // 衝突による削除は`CollisionDetected`と`BodyAbsorbed`で数える
+            GameEvent::CelestialBodyDestroyed { .. }
+            | GameEvent::LifeDeclined { .. }
+            | GameEvent::DebrisCreated { .. }
+            | GameEvent::GameSaved { .. }
+            | GameEvent::AchievementUnlocked { .. } => return None,
+        };
+        Some(trigger)
+    }
 }
 
 /// プレイヤーの状態
@@ -109,37 +348,228 @@ pub struct PlayerState {
     pub resource_manager: ResourceManager,
     pub celestial_manager: CelestialBodyManager,
     pub physics_engine: PhysicsEngine,
+    pub research_manager: ResearchManager,
+    pub achievements: AchievementTracker,
     pub last_save_tick: u64,
     pub active: bool,
     pub last_activity: DateTime<Utc>,
+    /// 決定論モードで記録したコマンド（`None`の場合は通常モード）
+    pub command_log: Option<CommandLog>,
 }
 
 impl PlayerState {
     pub fn new(player_id: PlayerId, tick_duration_ms: u64) -> Self {
+        Self::with_definitions(player_id, tick_duration_ms, &SharedDefinitions::default())
+    }
+    
+    pub fn with_definitions(player_id: PlayerId, tick_duration_ms: u64, definitions: &SharedDefinitions) -> Self {
+        let celestial_manager = CelestialBodyManager::new(tick_duration_ms);
+        
+        // 作成可能範囲の外に出た天体を報告する
+        let mut physics_engine = PhysicsEngine::new();
+        physics_engine.set_world_radius(Some(fixed::to_f64(celestial_manager.limits.max_position)));
+        physics_engine.set_integrator(definitions.integrator);
+        physics_engine.set_timestep_settings(definitions.timestep);
+        
         Self {
             player_id,
-            resource_manager: ResourceManager::new(tick_duration_ms),
-            celestial_manager: CelestialBodyManager::new(tick_duration_ms),
-            physics_engine: PhysicsEngine::new(),
+            resource_manager: ResourceManager::with_catalog(tick_duration_ms, definitions.upgrade_catalog.clone()),
+            celestial_manager,
+            physics_engine,
+            research_manager: ResearchManager::new(
+                definitions.research_tree.clone(),
+                definitions.research_queue_length,
+            ),
+            achievements: AchievementTracker::new(definitions.achievement_catalog.clone()),
             last_save_tick: 0,
             active: true,
             last_activity: Utc::now(),
+            command_log: None,
+        }
+    }
+    
+    /// 決定論モードの有効化
+    ///
+    /// 天体IDをシード付き乱数で採番し、以降のコマンドをログに記録する。
+    pub fn enable_deterministic_mode(&mut self, seed: u64) {
+        self.celestial_manager.set_rng(Some(DeterministicRng::new(seed)));
+        self.command_log = Some(CommandLog::new());
+    }
+    
+    /// 決定論モードであればコマンドを記録
+    pub fn record_command(&mut self, tick: u64, command: ReplayCommand) {
+        if let Some(log) = self.command_log.as_mut() {
+            log.record(tick, command);
         }
     }
     
+    /// 記録されたコマンドの適用
+    pub fn apply_command(&mut self, command: &ReplayCommand) -> Result<Vec<GameEvent>> {
+        let player_id = self.player_id;
+        let event = match command {
+            ReplayCommand::CreateCelestialBody { body_type, position } => {
+                let body_id = self.celestial_manager.create_body(
+                    body_type.clone(),
+                    *position,
+                    self.resource_manager.get_resources_mut(),
+                )?;
+                Some(GameEvent::CelestialBodyCreated { player_id, body_id, body_type: body_type.clone() })
+            }
+            ReplayCommand::RemoveCelestialBody { body_id } => {
+                self.celestial_manager.remove_body(*body_id)?;
+                Some(GameEvent::CelestialBodyDestroyed {
+                    player_id,
+                    body_id: *body_id,
+                    reason: DestructionReason::ManualDestruction,
+                })
+            }
+            ReplayCommand::PurchaseUpgrade { upgrade_type } => {
+                self.resource_manager.apply_upgrade(*upgrade_type)?;
+                Some(GameEvent::UpgradePurchased { player_id, upgrade_type: *upgrade_type })
+            }
+            ReplayCommand::StartResearch { technology_id } => {
+                self.research_manager.enqueue(technology_id, self.resource_manager.get_resources_mut())?;
+                None
+            }
+            ReplayCommand::CancelResearch { technology_id } => {
+                self.research_manager.cancel(technology_id, self.resource_manager.get_resources_mut())?;
+                None
+            }
+        };
+        Ok(event.into_iter().collect())
+    }
+    
+    /// コマンドログを再生しながら`from_tick`から`to_tick`の直前まで進める
+    ///
+    /// 各ティックでは、そのティックに記録されたコマンドを記録順に適用してから更新する。
+    /// 記録時に失敗したコマンドは再生時も同じように失敗するため、エラーは無視する。
+    ///
+    /// 実績もゲームループと同じ順序で処理する。解除時刻は基点（復元直後であればスナップショットの
+    /// 時刻）から経過ティック数で決める。
+    pub fn replay(&mut self, log: &CommandLog, from_tick: u64, to_tick: u64, delta_time_ms: u64) -> Result<Vec<GameEvent>> {
+        let started_at = self.resource_manager.get_game_state().last_update;
+        let mut events = Vec::new();
+        let mut commands = log.commands_between(from_tick, to_tick).peekable();
+        for tick in from_tick..to_tick {
+            let now = started_at + chrono::Duration::milliseconds(((tick - from_tick) * delta_time_ms) as i64);
+            
+            let mut command_events = Vec::new();
+            while let Some((_, command)) = commands.next_if(|(command_tick, _)| *command_tick == tick) {
+                match self.apply_command(command) {
+                    Ok(applied) => command_events.extend(applied),
+                    Err(e) => debug!("[GAME_LOOP] Replayed command failed at tick {}: {}", tick, e),
+                }
+            }
+            let unlocks = self.record_achievement_triggers(&command_events, now);
+            events.extend(command_events);
+            events.extend(self.unlock_events(unlocks));
+            
+            let tick_events = self.update(delta_time_ms)?;
+            let unlocks = self.process_achievements(&tick_events, now);
+            events.extend(tick_events);
+            events.extend(self.unlock_events(unlocks));
+        }
+        Ok(events)
+    }
+    
     /// プレイヤー状態の更新
     pub fn update(&mut self, delta_time_ms: u64) -> Result<Vec<GameEvent>> {
         let mut events = Vec::new();
         
-        // リソースの蓄積
-        self.resource_manager.accumulate_resources(delta_time_ms);
+        // リソースの蓄積（天体からの生産を合算してから）
+        self.resource_manager.set_body_production(self.celestial_manager.total_production());
+        let milestones = self.resource_manager.accumulate_resources(delta_time_ms);
+        events.extend(milestones.into_iter().map(|milestone| GameEvent::ResourceMilestone {
+            player_id: self.player_id,
+            resource_type: milestone.resource_type,
+            amount: milestone.amount,
+        }));
+        
+        // 研究の進行
+        let unlocked = self.research_manager.update(delta_time_ms);
+        if !unlocked.is_empty() {
+            self.research_manager.effects().apply(&mut self.resource_manager, &mut self.celestial_manager);
+            events.extend(unlocked.into_iter().map(|research_id| GameEvent::ResearchUnlocked {
+                player_id: self.player_id,
+                research_id,
+            }));
+        }
         
         // 天体システムの更新
-        self.celestial_manager.update_life_systems(delta_time_ms);
+        let transitions = self.celestial_manager.update_life_systems(delta_time_ms);
+        events.extend(transitions.into_iter().map(|transition| GameEvent::from_life_transition(self.player_id, transition)));
+        
+        // 恒星進化の更新（超新星で撒き散らされた塵はプレイヤーが回収する）
+        for transition in self.celestial_manager.update_stellar_evolution(delta_time_ms) {
+            events.push(GameEvent::StellarEvolved {
+                player_id: self.player_id,
+                body_id: transition.body_id,
+                phase: transition.to_phase,
+            });
+            if let Some(supernova) = transition.supernova {
+                self.resource_manager.grant(ResourceType::CosmicDust, supernova.dust_released);
+                events.extend(supernova.life_transitions.into_iter()
+                    .map(|transition| GameEvent::from_life_transition(self.player_id, transition)));
+                events.push(GameEvent::Supernova {
+                    player_id: self.player_id,
+                    body_id: transition.body_id,
+                    dust_released: supernova.dust_released,
+                    affected_planets: supernova.affected_planets,
+                });
+            }
+        }
         
         // 物理演算の更新
         let physics_delta = delta_time_ms as f64 / 1000.0;
-        self.physics_engine.update(self.celestial_manager.get_all_bodies_mut(), physics_delta)?;
+        let (bodies, spatial_index) = self.celestial_manager.bodies_and_index_mut();
+        let physics_events = self.physics_engine.update_with_index(bodies, spatial_index, physics_delta)?;
+        for physics_event in physics_events {
+            match physics_event {
+                PhysicsEvent::Collision { body1_id, body2_id, outcome } => {
+                    events.push(GameEvent::CollisionDetected {
+                        player_id: self.player_id,
+                        body1_id,
+                        body2_id,
+                        outcome,
+                    });
+                    
+                    // 衝突を生き延びた天体の生命も大きな被害を受ける
+                    for body_id in [body1_id, body2_id] {
+                        let transitions = self.celestial_manager
+                            .apply_life_catastrophe(body_id, life::collision_severity(outcome), ExtinctionCause::Collision);
+                        events.extend(transitions.into_iter()
+                            .map(|transition| GameEvent::from_life_transition(self.player_id, transition)));
+                    }
+                }
+                PhysicsEvent::BodyDestroyed { body_id, reason, absorbed_by } => {
+                    if let Some(survivor_id) = absorbed_by {
+                        events.push(GameEvent::BodyAbsorbed {
+                            player_id: self.player_id,
+                            survivor_id,
+                            absorbed_id: body_id,
+                        });
+                    }
+                    events.push(GameEvent::CelestialBodyDestroyed {
+                        player_id: self.player_id,
+                        body_id,
+                        reason,
+                    });
+                }
+                PhysicsEvent::DebrisCreated { body_id, source_id } => {
+                    events.push(GameEvent::DebrisCreated {
+                        player_id: self.player_id,
+                        body_id,
+                        source_id,
+                    });
+                }
+                PhysicsEvent::LeftBounds { body_id } => {
+                    events.push(GameEvent::BodyLeftBounds {
+                        player_id: self.player_id,
+                        body_id,
+                    });
+                }
+            }
+        }
         
         // 最終活動時間の更新
         self.last_activity = Utc::now();
@@ -147,6 +577,38 @@ impl PlayerState {
         Ok(events)
     }
     
+    /// 実績の判定（イベントによる条件と、現在の状態によるしきい値条件）
+    pub fn process_achievements(&mut self, events: &[GameEvent], now: DateTime<Utc>) -> Vec<AchievementUnlock> {
+        let mut unlocks = self.record_achievement_triggers(events, now);
+        
+        let metrics = PlayerMetrics::collect(
+            &self.resource_manager,
+            &self.celestial_manager,
+            self.research_manager.progress().unlocked.len(),
+        );
+        unlocks.extend(self.achievements.evaluate(&metrics, now));
+        
+        unlocks
+    }
+    
+    /// イベントから実績のトリガーだけを記録（閾値の評価はティックの終わりに行う）
+    pub fn record_achievement_triggers(&mut self, events: &[GameEvent], now: DateTime<Utc>) -> Vec<AchievementUnlock> {
+        let mut unlocks = Vec::new();
+        
+        for event in events.iter().filter(|event| event.player_id() == self.player_id) {
+            if let Some(trigger) = event.achievement_trigger() {
+                unlocks.extend(self.achievements.record(&trigger, now));
+            }
+        }
+        
+        unlocks
+    }
+    
+    /// 解除された実績の通知イベント
+    fn unlock_events(&self, unlocks: Vec<AchievementUnlock>) -> impl Iterator<Item = GameEvent> + '_ {
+        unlocks.into_iter().map(|achievement| GameEvent::AchievementUnlocked { player_id: self.player_id, achievement })
+    }
+    
     /// ゲーム状態のスナップショット作成
     pub fn create_snapshot(&self, tick: u64) -> GameStateSnapshot {
         GameStateSnapshot::new(
@@ -159,6 +621,13 @@ impl PlayerState {
             self.celestial_manager.get_all_bodies().clone(),
             self.physics_engine.get_state().clone(),
         )
+        .with_progression(
+            self.resource_manager.get_game_state().lifetime_totals.clone(),
+            self.resource_manager.get_game_state().prestige.clone(),
+        )
+        .with_research(self.research_manager.progress().clone())
+        .with_achievements(self.achievements.progress().clone())
+        .with_rng_state(self.celestial_manager.rng().map(|rng| rng.state()))
     }
     
     /// スナップショットから状態を復元
@@ -169,16 +638,31 @@ impl PlayerState {
             production_rates: snapshot.production_rates.clone(),
             accumulators: snapshot.accumulators.clone(),
             upgrade_levels: snapshot.upgrade_levels.clone(),
+            lifetime_totals: snapshot.lifetime_totals.clone(),
+            prestige: snapshot.prestige.clone(),
             last_update: snapshot.timestamp,
         };
         self.resource_manager.set_game_state(game_state);
         
         // 天体マネージャーの復元
         self.celestial_manager.bodies = snapshot.bodies.clone();
+        self.celestial_manager.sync_spatial_index();
         
         // 物理エンジンの復元
         self.physics_engine.state = snapshot.physics_state.clone();
         
+        // 研究進捗の復元と効果の再適用
+        self.research_manager.set_progress(snapshot.research.clone());
+        self.research_manager.effects().apply(&mut self.resource_manager, &mut self.celestial_manager);
+        
+        // 実績進行状況の復元
+        self.achievements.set_progress(snapshot.achievements.clone());
+        
+        // 決定論モードの乱数の復元
+        if let Some(rng_state) = snapshot.rng_state {
+            self.celestial_manager.set_rng(Some(DeterministicRng::new(rng_state)));
+        }
+        
         self.last_save_tick = snapshot.tick;
         
         Ok(())
@@ -226,6 +710,7 @@ pub struct GameLoop {
     current_tick: u64,
     tick_duration: Duration,
     performance_metrics: PerformanceMetrics,
+    definitions: SharedDefinitions,
     running: bool,
 }
 
@@ -248,10 +733,17 @@ impl GameLoop {
             current_tick: 0,
             tick_duration,
             performance_metrics: PerformanceMetrics::new(),
+            definitions: SharedDefinitions::default(),
             running: false,
         }
     }
     
+    /// 共有定義データ（アップグレードカタログ・研究ツリー）の設定
+    pub fn with_definitions(mut self, definitions: SharedDefinitions) -> Self {
+        self.definitions = definitions;
+        self
+    }
+    
     /// ゲームループの開始
     pub async fn start(&mut self) -> Result<()> {
         info!("[GAME_LOOP] Starting game loop with tick rate: {} Hz", self.config.tick_rate);
@@ -303,7 +795,9 @@ impl GameLoop {
         // プレイヤー状態の更新
         let mut players = self.players.write().await;
         let mut events = Vec::new();
+        let mut unlocks = Vec::new();
         let mut total_bodies = 0;
+        let now = Utc::now();
         
         for (player_id, player_state) in players.iter_mut() {
             if !player_state.active {
@@ -322,6 +816,8 @@ impl GameLoop {
             // プレイヤー状態の更新
             match player_state.update(delta_time_ms) {
                 Ok(player_events) => {
+                    let player_unlocks = player_state.process_achievements(&player_events, now);
+                    unlocks.extend(player_unlocks.into_iter().map(|unlock| (*player_id, unlock)));
                     events.extend(player_events);
                     total_bodies += player_state.celestial_manager.get_body_count();
                 }
@@ -331,17 +827,17 @@ impl GameLoop {
             }
         }
         
+        let player_count = players.len();
+        drop(players);
+        
         // イベントの送信
-        for event in events {
-            if let Err(e) = self.event_sender.send(event).await {
-                warn!("[GAME_LOOP] Failed to send event: {}", e);
-            }
-        }
+        events.extend(self.record_unlocks(unlocks).await);
+        self.send_events(events).await;
         
         // パフォーマンスメトリクスの更新
         let tick_duration = tick_start.elapsed();
         self.performance_metrics.tick_duration = tick_duration;
-        self.performance_metrics.active_players = players.len();
+        self.performance_metrics.active_players = player_count;
         self.performance_metrics.total_bodies = total_bodies;
         
         if tick_duration > self.performance_metrics.max_tick_duration {
@@ -377,6 +873,13 @@ impl GameLoop {
     
     /// コマンドの処理
     async fn handle_command(&mut self, command: GameCommand) -> Result<()> {
+        // 決定論モードのプレイヤーは再生用にコマンドを記録する
+        if let Some((player_id, replay_command)) = command.replay_command() {
+            if let Some(player) = self.players.write().await.get_mut(&player_id) {
+                player.record_command(self.current_tick, replay_command);
+            }
+        }
+        
         match command {
             GameCommand::CreateCelestialBody { player_id, body_type, position } => {
                 self.handle_create_body(player_id, body_type, position).await?;
@@ -393,10 +896,23 @@ impl GameLoop {
             GameCommand::LoadGame { player_id } => {
                 self.handle_load_game(player_id).await?;
             }
+            GameCommand::Prestige { player_id } => {
+                self.handle_prestige(player_id).await?;
+            }
+            GameCommand::StartResearch { player_id, technology_id } => {
+                self.handle_start_research(player_id, technology_id).await?;
+            }
+            GameCommand::CancelResearch { player_id, technology_id } => {
+                self.handle_cancel_research(player_id, technology_id).await?;
+            }
             GameCommand::GetState { player_id, response_sender } => {
                 let result = self.handle_get_state(player_id).await;
                 let _ = response_sender.send(result);
             }
+            GameCommand::GetProductionBreakdown { player_id, response_sender } => {
+                let result = self.handle_get_production_breakdown(player_id).await;
+                let _ = response_sender.send(result);
+            }
         }
         Ok(())
     }
@@ -422,14 +938,14 @@ impl GameLoop {
             player_id,
             &body_type,
             &position,
-            player.celestial_manager.get_all_bodies(),
+            player.celestial_manager.spatial_index(),
         )?;
         
         // 天体の作成
         let body_id = player.celestial_manager.create_body(
             body_type.clone(),
             position,
-            &mut player.resource_manager.get_game_state().resources,
+            player.resource_manager.get_resources_mut(),
         )?;
         
         // イベントの送信
@@ -439,9 +955,8 @@ impl GameLoop {
             body_type,
         };
         
-        if let Err(e) = self.event_sender.send(event).await {
-            warn!("[GAME_LOOP] Failed to send event: {}", e);
-        }
+        drop(players);
+        self.publish_events(vec![event]).await;
         
         Ok(())
     }
@@ -461,11 +976,11 @@ impl GameLoop {
         let event = GameEvent::CelestialBodyDestroyed {
             player_id,
             body_id,
+            reason: DestructionReason::ManualDestruction,
         };
         
-        if let Err(e) = self.event_sender.send(event).await {
-            warn!("[GAME_LOOP] Failed to send event: {}", e);
-        }
+        drop(players);
+        self.publish_events(vec![event]).await;
         
         Ok(())
     }
@@ -491,9 +1006,73 @@ impl GameLoop {
             upgrade_type,
         };
         
-        if let Err(e) = self.event_sender.send(event).await {
-            warn!("[GAME_LOOP] Failed to send event: {}", e);
+        drop(players);
+        self.publish_events(vec![event]).await;
+        
+        Ok(())
+    }
+    
+    /// 研究開始の処理
+    async fn handle_start_research(&mut self, player_id: PlayerId, technology_id: TechnologyId) -> Result<()> {
+        let mut players = self.players.write().await;
+        
+        let player = players.get_mut(&player_id)
+            .ok_or_else(|| {
+                warn!("[GAME_LOOP] Player not found: {}", player_id);
+                GameError::not_found(format!("Player {} not found", player_id))
+            })?;
+        
+        player.research_manager.enqueue(&technology_id, player.resource_manager.get_resources_mut())
+    }
+    
+    /// 研究取り消しの処理
+    async fn handle_cancel_research(&mut self, player_id: PlayerId, technology_id: TechnologyId) -> Result<()> {
+        let mut players = self.players.write().await;
+        
+        let player = players.get_mut(&player_id)
+            .ok_or_else(|| {
+                warn!("[GAME_LOOP] Player not found: {}", player_id);
+                GameError::not_found(format!("Player {} not found", player_id))
+            })?;
+        
+        player.research_manager.cancel(&technology_id, player.resource_manager.get_resources_mut())
+    }
+    
+    /// プレステージの処理
+    async fn handle_prestige(&mut self, player_id: PlayerId) -> Result<()> {
+        let mut players = self.players.write().await;
+        
+        let player = players.get_mut(&player_id)
+            .ok_or_else(|| {
+                warn!("[GAME_LOOP] Player not found: {}", player_id);
+                GameError::not_found(format!("Player {} not found", player_id))
+            })?;
+        
+        // 永続化に失敗した場合に戻すための状態
+        let rollback = player.create_snapshot(self.current_tick);
+        
+        let calculator = PrestigeCalculator::new(self.config.prestige.clone());
+        let record = calculator.perform(
+            &mut player.resource_manager,
+            &mut player.celestial_manager,
+            Utc::now(),
+        )?;
+        
+        let snapshot = player.create_snapshot(self.current_tick);
+        if let Err(e) = self.persistence_manager.record_prestige(player_id, &record, snapshot).await {
+            error!("[GAME_LOOP] Failed to record prestige, rolling back: {}", e);
+            player.restore_from_snapshot(&rollback)?;
+            return Err(e);
         }
+        player.last_save_tick = self.current_tick;
+        
+        let event = GameEvent::PrestigePerformed {
+            player_id,
+            record,
+        };
+        
+        drop(players);
+        self.publish_events(vec![event]).await;
         
         Ok(())
     }
@@ -516,9 +1095,8 @@ impl GameLoop {
             tick: self.current_tick,
         };
         
-        if let Err(e) = self.event_sender.send(event).await {
-            warn!("[GAME_LOOP] Failed to send event: {}", e);
-        }
+        drop(players);
+        self.publish_events(vec![event]).await;
         
         self.performance_metrics.save_operations += 1;
         
@@ -533,26 +1111,107 @@ impl GameLoop {
             let mut players = self.players.write().await;
             
             let player = players.entry(player_id).or_insert_with(|| {
-                PlayerState::new(player_id, self.tick_duration.as_millis() as u64)
+                PlayerState::with_definitions(player_id, self.tick_duration.as_millis() as u64, &self.definitions)
             });
             
             player.restore_from_snapshot(&snapshot)?;
             
-            let event = GameEvent::GameLoaded {
+            // 解除済み実績はデータベースの記録を取り込む
+            let unlocked = self.persistence_manager.load_achievements(player_id).await?;
+            player.achievements.merge_unlocked(unlocked);
+            
+            // プレステージ状態はサーバー側の記録を正とする
+            if let Some(prestige) = self.persistence_manager.load_prestige_state(player_id).await? {
+                if prestige != player.resource_manager.get_game_state().prestige {
+                    warn!("[GAME_LOOP] Prestige state mismatch for player {}, using server record", player_id);
+                    let resource_manager = &mut player.resource_manager;
+                    resource_manager.get_game_state_mut().prestige = prestige;
+                    let rates = resource_manager.calculate_production_rates(resource_manager.get_game_state());
+                    resource_manager.get_game_state_mut().production_rates = rates;
+                }
+            }
+            
+            // 保存時刻から現在までの進行を適用
+            let calculator = OfflineProgressCalculator::new(self.config.offline_progress.clone());
+            let offline_report = calculator.apply(
+                &mut player.resource_manager,
+                &mut player.celestial_manager,
+                Utc::now(),
+            );
+            
+            let mut events = vec![GameEvent::GameLoaded {
                 player_id,
                 tick: snapshot.tick,
-            };
+            }];
             
-            if let Err(e) = self.event_sender.send(event).await {
-                warn!("[GAME_LOOP] Failed to send event: {}", e);
+            if let Some(report) = offline_report {
+                events.push(GameEvent::OfflineProgressApplied { player_id, report });
             }
             
+            drop(players);
+            self.publish_events(events).await;
+            
             self.performance_metrics.load_operations += 1;
         }
         
         Ok(())
     }
     
+    /// イベントの配信（実績の判定を含む）
+    async fn publish_events(&mut self, mut events: Vec<GameEvent>) {
+        let now = Utc::now();
+        let mut unlocks = Vec::new();
+        
+        {
+            let mut players = self.players.write().await;
+            for event in &events {
+                let Some(trigger) = event.achievement_trigger() else {
+                    continue;
+                };
+                let player_id = event.player_id();
+                if let Some(player) = players.get_mut(&player_id) {
+                    let player_unlocks = player.achievements.record(&trigger, now);
+                    unlocks.extend(player_unlocks.into_iter().map(|unlock| (player_id, unlock)));
+                }
+            }
+        }
+        
+        events.extend(self.record_unlocks(unlocks).await);
+        self.send_events(events).await;
+    }
+    
+    /// 実績解除の永続化と通知イベントの作成
+    async fn record_unlocks(&self, unlocks: Vec<(PlayerId, AchievementUnlock)>) -> Vec<GameEvent> {
+        let mut by_player: HashMap<PlayerId, Vec<AchievementUnlock>> = HashMap::new();
+        for (player_id, unlock) in unlocks {
+            by_player.entry(player_id).or_default().push(unlock);
+        }
+        
+        let mut events = Vec::new();
+        for (player_id, player_unlocks) in by_player {
+            // 記録に失敗してもスナップショットには解除状態が残るため、通知は行う
+            if let Err(e) = self.persistence_manager.record_achievements(player_id, &player_unlocks).await {
+                error!("[GAME_LOOP] Failed to record achievements for player {}: {}", player_id, e);
+            }
+            
+            events.extend(player_unlocks.into_iter().map(|achievement| GameEvent::AchievementUnlocked {
+                player_id,
+                achievement,
+            }));
+        }
+        
+        events
+    }
+    
+    /// イベントチャネルへの送信
+    async fn send_events(&self, events: Vec<GameEvent>) {
+        for event in events {
+            if let Err(e) = self.event_sender.send(event).await {
+                warn!("[GAME_LOOP] Failed to send event: {}", e);
+            }
+        }
+    }
+    
     /// ゲーム状態取得の処理
     async fn handle_get_state(&self, player_id: PlayerId) -> Result<GameStateSnapshot> {
         let players = self.players.read().await;
@@ -566,6 +1225,19 @@ impl GameLoop {
         Ok(player.create_snapshot(self.current_tick))
     }
     
+    /// 生産の内訳取得の処理
+    async fn handle_get_production_breakdown(&self, player_id: PlayerId) -> Result<ProductionSummary> {
+        let players = self.players.read().await;
+        
+        let player = players.get(&player_id)
+            .ok_or_else(|| {
+                warn!("[GAME_LOOP] Player not found: {}", player_id);
+                GameError::not_found(format!("Player {} not found", player_id))
+            })?;
+        
+        Ok(player.resource_manager.production_summary(player.celestial_manager.body_contributions()))
+    }
+    
     /// 自動保存
     async fn auto_save(&mut self) -> Result<()> {
         let players = self.players.read().await;
@@ -612,7 +1284,16 @@ impl GameLoop {
             return Err(GameError::business_logic("Player limit reached"));
         }
         
-        let player_state = PlayerState::new(player_id, self.tick_duration.as_millis() as u64);
+        let mut player_state = PlayerState::with_definitions(
+            player_id,
+            self.tick_duration.as_millis() as u64,
+            &self.definitions,
+        );
+        if let Some(seed) = self.config.deterministic_seed {
+            // プレイヤーごとに異なる系列になるようIDと混ぜる
+            let (high, low) = player_id.as_u64_pair();
+            player_state.enable_deterministic_mode(seed ^ high ^ low);
+        }
         players.insert(player_id, player_state);
         
         Ok(())
@@ -657,6 +1338,191 @@ mod tests {
         assert_eq!(events.len(), 0);
     }
     
+    #[test]
+    fn test_player_state_update_emits_life_events() {
+        use crate::game::celestial_bodies::{AtmosphereType, CelestialType, LifeStage, PlanetData, PlanetType, SpectralType, StarData, Vec3Fixed};
+        use crate::game::habitability::HabitabilitySettings;
+        
+        let player_id = Uuid::new_v4();
+        let mut player_state = PlayerState::new(player_id, 50);
+        
+        let mut resources = Resources { cosmic_dust: 20_000, ..Default::default() };
+        // 生命は主星のハビタブルゾーンでしか進化しない
+        let sun = CelestialType::Star(StarData {
+            spectral_type: SpectralType::G,
+            temperature: 5800,
+            luminosity: 0,
+            age: 0,
+            lifespan: 0,
+            phase: Default::default(),
+        });
+        player_state.celestial_manager.create_body(sun, Vec3Fixed::zeros(), &mut resources).unwrap();
+        let orbit_radius = HabitabilitySettings::default().astronomical_unit;
+        let planet = PlanetData {
+            planet_type: PlanetType::Rocky,
+            atmosphere: AtmosphereType::Oxygen,
+            water_coverage: 70,
+            temperature_range: (15, 25),
+            habitability: 80,
+        };
+        let planet_id = player_state.celestial_manager
+            .create_body(CelestialType::Planet(planet), Vec3Fixed::new(orbit_radius, 0.0, 0.0), &mut resources)
+            .unwrap();
+        
+        let events = player_state.update(100).unwrap();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            GameEvent::LifeEvolved { body_id, new_stage: LifeStage::Microbial { .. }, .. } if *body_id == planet_id
+        )));
+        
+        // 生命誕生のイベントから実績が解除される
+        let unlocks = player_state.process_achievements(&events, Utc::now());
+        assert!(unlocks.iter().any(|unlock| unlock.achievement_id == "genesis"));
+    }
+    
+    #[test]
+    fn test_body_production_joins_player_rates() {
+        use crate::game::celestial_bodies::{BodyCategory, CelestialType, Vec3Fixed};
+        
+        let mut player_state = PlayerState::new(Uuid::new_v4(), 50);
+        let mut resources = Resources { cosmic_dust: 1000, ..Default::default() };
+        let asteroid_id = player_state.celestial_manager
+            .create_body(CelestialType::Asteroid, Vec3Fixed::new(100.0, 0.0, 0.0), &mut resources)
+            .unwrap();
+        let upgrade_dust = player_state.resource_manager.production_summary(Vec::new()).upgrade_rates.dust_per_tick;
+        
+        player_state.update(50).unwrap();
+        
+        // 小惑星のダストがプレイヤーの生産に合算され、内訳に寄与として現れる
+        let summary = player_state.resource_manager.production_summary(player_state.celestial_manager.body_contributions());
+        assert_eq!(summary.total_rates.dust_per_tick, upgrade_dust + fixed::from_f64(0.5));
+        assert_eq!(summary.bodies.len(), 1);
+        assert_eq!(summary.bodies[0].body_id, asteroid_id);
+        assert_eq!(summary.bodies[0].category, BodyCategory::Asteroid);
+    }
+    
+    #[test]
+    fn test_deterministic_replay_is_bit_identical() {
+        use crate::game::celestial_bodies::{CelestialType, Vec3Fixed};
+        use crate::game::resources::UpgradeType;
+        
+        let player_id = Uuid::new_v4();
+        let mut live = PlayerState::new(player_id, 50);
+        live.enable_deterministic_mode(0x5eed);
+        live.resource_manager.get_resources_mut().cosmic_dust = 100_000;
+        live.resource_manager.get_resources_mut().energy = 100_000;
+        
+        // 重力で相互作用する天体をいくつか置いた状態を基点とする
+        for i in 0..6 {
+            let position = Vec3Fixed::new(i as f64 * 40.0, (i % 3) as f64 * 25.0, (i % 2) as f64 * 15.0);
+            live.apply_command(&ReplayCommand::CreateCelestialBody { body_type: CelestialType::Moon, position }).unwrap();
+        }
+        let base = live.create_snapshot(100);
+        
+        // 基点以降はコマンドをティック付きで記録しながら進める
+        let commands = [
+            (103, ReplayCommand::CreateCelestialBody { body_type: CelestialType::Asteroid, position: Vec3Fixed::new(-60.0, 10.0, 0.0) }),
+            (110, ReplayCommand::PurchaseUpgrade { upgrade_type: UpgradeType::DustProduction }),
+            (115, ReplayCommand::CreateCelestialBody { body_type: CelestialType::Comet, position: Vec3Fixed::new(0.0, -80.0, 30.0) }),
+        ];
+        for (tick, command) in commands.iter() {
+            live.record_command(*tick, command.clone());
+        }
+        let log = live.command_log.clone().unwrap();
+        live.replay(&log, 100, 200, 50).unwrap();
+        let expected = live.create_snapshot(200).calculate_checksum();
+        
+        // 同じスナップショットとコマンドログから2回再生しても、ビット単位で同じ結果になる
+        for _ in 0..2 {
+            let mut replayed = PlayerState::new(player_id, 50);
+            replayed.restore_from_snapshot(&base).unwrap();
+            replayed.replay(&log, base.tick, 200, 50).unwrap();
+            
+            let snapshot = replayed.create_snapshot(200);
+            assert_eq!(snapshot.calculate_checksum(), expected);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_live_commands_replay_to_same_checksum() {
+        use crate::game::celestial_bodies::{CelestialType, SpectralType, StarData, Vec3Fixed};
+        use crate::game::persistence::PersistenceConfig;
+        use crate::game::resources::UpgradeType;
+        
+        // 実績の記録に失敗してもゲームは進むので、接続しないプールで十分
+        let db_pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(10))
+            .connect_lazy("postgresql://localhost/cosmic_gardener_test")
+            .unwrap();
+        let config = GameLoopConfig {
+            deterministic_seed: Some(0x5eed),
+            performance_monitoring: false,
+            ..Default::default()
+        };
+        let (_command_sender, command_receiver) = mpsc::channel(16);
+        let (event_sender, mut event_receiver) = mpsc::channel(1024);
+        let mut game_loop = GameLoop::new(
+            config,
+            PersistenceManager::new(db_pool, PersistenceConfig::default()),
+            command_receiver,
+            event_sender,
+        );
+        
+        let player_id = Uuid::new_v4();
+        game_loop.add_player(player_id).await.unwrap();
+        let base = {
+            let mut players = game_loop.players.write().await;
+            let player = players.get_mut(&player_id).unwrap();
+            player.resource_manager.get_resources_mut().cosmic_dust = 100_000;
+            player.resource_manager.get_resources_mut().energy = 100_000;
+            player.create_snapshot(game_loop.current_tick)
+        };
+        
+        // 実際のコマンド処理の経路で、ティックの合間にコマンドを受け付けながら進める。
+        // 恒星の作成で実績が解除されるので、再生でも同じ実績が解除されなければならない
+        let sun = CelestialType::Star(StarData {
+            spectral_type: SpectralType::G,
+            temperature: 5800,
+            luminosity: 0,
+            age: 0,
+            lifespan: 0,
+            phase: Default::default(),
+        });
+        let commands = [
+            (5, GameCommand::CreateCelestialBody { player_id, body_type: sun, position: Vec3Fixed::new(0.0, 0.0, -20_000.0) }),
+            (5, GameCommand::CreateCelestialBody { player_id, body_type: CelestialType::Asteroid, position: Vec3Fixed::new(-60.0, 10.0, 0.0) }),
+            (20, GameCommand::PurchaseUpgrade { player_id, upgrade_type: UpgradeType::DustProduction }),
+            (35, GameCommand::CreateCelestialBody { player_id, body_type: CelestialType::Comet, position: Vec3Fixed::new(0.0, -80.0, 30.0) }),
+        ];
+        let end_tick = base.tick + 60;
+        let mut pending = commands.into_iter().peekable();
+        while game_loop.current_tick < end_tick {
+            while let Some((_, command)) = pending.next_if(|(tick, _)| *tick == game_loop.current_tick) {
+                game_loop.handle_command(command).await.unwrap();
+            }
+            game_loop.tick().await.unwrap();
+            while event_receiver.try_recv().is_ok() {}
+        }
+        
+        let (log, expected) = {
+            let players = game_loop.players.read().await;
+            let player = &players[&player_id];
+            assert!(player.achievements.is_unlocked("first_star"));
+            (player.command_log.clone().unwrap(), player.create_snapshot(end_tick).calculate_checksum())
+        };
+        assert_eq!(log.len(), 4);
+        
+        // 記録されたログを基点のスナップショットから再生すると、同じ状態に行き着く
+        let mut replayed = PlayerState::new(player_id, 50);
+        replayed.restore_from_snapshot(&base).unwrap();
+        let events = replayed.replay(&log, base.tick, end_tick, 50).unwrap();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            GameEvent::AchievementUnlocked { achievement, .. } if achievement.achievement_id == "first_star"
+        )));
+        assert_eq!(replayed.create_snapshot(end_tick).calculate_checksum(), expected);
+    }
+    
     #[tokio::test]
     async fn test_snapshot_creation_and_restoration() {
         let player_id = Uuid::new_v4();
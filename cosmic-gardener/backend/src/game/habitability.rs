@@ -0,0 +1,283 @@
+//! 惑星の居住可能性
+//!
+//! 主星からの距離と光度（ハビタブルゾーン）、大気の温室効果、海洋の割合、惑星タイプから
+//! 居住可能性を評価する。評価は天体の配置から毎回計算し直すので、惑星を動かしたり
+//! 主星を失ったりすると生命が進化できるかどうかが変わる。
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::game::celestial_bodies::{AtmosphereType, BodyId, CelestialBody, CelestialType, PlanetType, Vec3Fixed};
+use crate::game::determinism::sorted_body_ids;
+use crate::game::resources::fixed;
+
+/// 生命が進化を続けられる居住可能性の下限
+pub const LIFE_EVOLUTION_THRESHOLD: u8 = 50;
+/// 生命が誕生し得る居住可能性の下限
+pub const LIFE_EMERGENCE_THRESHOLD: u8 = 30;
+
+/// 地球（1天文単位、太陽光度）の放射平衡温度（K、アルベド0.3）
+const EARTH_EQUILIBRIUM_TEMPERATURE: f64 = 255.0;
+const KELVIN_OFFSET: f64 = 273.15;
+
+/// 居住可能性評価のパラメータ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HabitabilitySettings {
+    /// 1天文単位に相当するゲーム内の距離
+    pub astronomical_unit: f64,
+    /// 液体の水が安定する地表温度の範囲（℃）
+    pub ideal_temperature: (f64, f64),
+    /// 理想範囲からこれだけ外れると温度の評価が0になる（℃）
+    pub temperature_tolerance: f64,
+}
+
+impl Default for HabitabilitySettings {
+    fn default() -> Self {
+        Self {
+            astronomical_unit: 50_000.0,
+            ideal_temperature: (0.0, 40.0),
+            temperature_tolerance: 60.0,
+        }
+    }
+}
+
+/// 居住可能性の内訳
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HabitabilityBreakdown {
+    /// 受け取る放射（地球 = 1.0）
+    pub stellar_flux: f64,
+    /// 温室効果を含めた推定地表温度（℃）
+    pub surface_temperature: f64,
+    /// 各要素の評価（0.0〜1.0）
+    pub temperature_score: f64,
+    pub atmosphere_score: f64,
+    pub water_score: f64,
+    pub planet_type_score: f64,
+    /// 総合評価（0〜100）
+    pub habitability: u8,
+}
+
+/// 光を放つ恒星
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub position: Vec3Fixed,
+    /// 太陽光度単位
+    pub luminosity: f64,
+}
+
+/// 全恒星の位置と光度（残骸の白色矮星なども含む）
+pub fn light_sources(bodies: &HashMap<BodyId, CelestialBody>) -> Vec<LightSource> {
+    sorted_body_ids(bodies)
+        .into_iter()
+        .filter_map(|id| {
+            let body = &bodies[&id];
+            match &body.body_type {
+                CelestialType::Star(star) if star.luminosity > 0 => Some(LightSource {
+                    position: body.physics.position,
+                    luminosity: fixed::to_f64(star.luminosity),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// 惑星の居住可能性を評価する（惑星以外は`None`）
+pub fn evaluate(body: &CelestialBody, lights: &[LightSource], settings: &HabitabilitySettings) -> Option<HabitabilityBreakdown> {
+    let CelestialType::Planet(planet) = &body.body_type else {
+        return None;
+    };
+
+    let stellar_flux = stellar_flux(body.physics.position, lights, settings.astronomical_unit);
+    let surface_temperature = if stellar_flux > 0.0 {
+        EARTH_EQUILIBRIUM_TEMPERATURE * stellar_flux.powf(0.25) + greenhouse_warming(&planet.atmosphere) - KELVIN_OFFSET
+    } else {
+        // 主星のない惑星は宇宙背景放射まで冷え切る
+        2.7 - KELVIN_OFFSET
+    };
+
+    let temperature_score = temperature_score(surface_temperature, settings);
+    let atmosphere_score = atmosphere_score(&planet.atmosphere);
+    let water_score = water_score(planet.water_coverage);
+    let planet_type_score = planet_type_score(&planet.planet_type);
+    let total = temperature_score * atmosphere_score * water_score * planet_type_score;
+
+    Some(HabitabilityBreakdown {
+        stellar_flux,
+        surface_temperature,
+        temperature_score,
+        atmosphere_score,
+        water_score,
+        planet_type_score,
+        habitability: (total * 100.0).round().clamp(0.0, 100.0) as u8,
+    })
+}
+
+/// 全惑星の居住可能性を評価する
+pub fn evaluate_all(
+    bodies: &HashMap<BodyId, CelestialBody>,
+    settings: &HabitabilitySettings,
+) -> HashMap<BodyId, HabitabilityBreakdown> {
+    let lights = light_sources(bodies);
+    bodies
+        .iter()
+        .filter_map(|(id, body)| evaluate(body, &lights, settings).map(|breakdown| (*id, breakdown)))
+        .collect()
+}
+
+/// 全恒星からの放射の合計（距離の2乗に反比例）
+pub fn stellar_flux(position: Vec3Fixed, lights: &[LightSource], astronomical_unit: f64) -> f64 {
+    lights
+        .iter()
+        .filter_map(|light| {
+            let distance = (light.position - position).magnitude() / astronomical_unit;
+            (distance > f64::EPSILON).then(|| light.luminosity / (distance * distance))
+        })
+        .sum()
+}
+
+/// 大気の温室効果による昇温（K）
+fn greenhouse_warming(atmosphere: &AtmosphereType) -> f64 {
+    match atmosphere {
+        AtmosphereType::None => 0.0,
+        AtmosphereType::Thin => 5.0,
+        AtmosphereType::Oxygen => 33.0,
+        AtmosphereType::Methane => 40.0,
+        AtmosphereType::Co2 => 60.0,
+        AtmosphereType::Thick => 90.0,
+        AtmosphereType::Toxic => 150.0,
+    }
+}
+
+fn temperature_score(temperature: f64, settings: &HabitabilitySettings) -> f64 {
+    let (min, max) = settings.ideal_temperature;
+    let deviation = if temperature < min {
+        min - temperature
+    } else if temperature > max {
+        temperature - max
+    } else {
+        0.0
+    };
+    (1.0 - deviation / settings.temperature_tolerance.max(f64::MIN_POSITIVE)).clamp(0.0, 1.0)
+}
+
+/// 呼吸できるか、放射線を遮れるか
+fn atmosphere_score(atmosphere: &AtmosphereType) -> f64 {
+    match atmosphere {
+        AtmosphereType::Oxygen => 1.0,
+        AtmosphereType::Thick => 0.7,
+        AtmosphereType::Co2 | AtmosphereType::Methane => 0.6,
+        AtmosphereType::Thin => 0.5,
+        AtmosphereType::Toxic => 0.2,
+        AtmosphereType::None => 0.1,
+    }
+}
+
+/// 陸と海が両方ある惑星が最も良い
+fn water_score(water_coverage: u8) -> f64 {
+    let coverage = water_coverage.min(100) as f64;
+    if coverage <= 30.0 {
+        0.2 + 0.8 * coverage / 30.0
+    } else if coverage <= 90.0 {
+        1.0
+    } else {
+        1.0 - 0.2 * (coverage - 90.0) / 10.0
+    }
+}
+
+fn planet_type_score(planet_type: &PlanetType) -> f64 {
+    match planet_type {
+        PlanetType::Ocean => 1.0,
+        PlanetType::Rocky => 0.9,
+        PlanetType::Desert => 0.6,
+        PlanetType::Frozen => 0.4,
+        PlanetType::Lava | PlanetType::GasGiant | PlanetType::IceGiant => 0.05,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::game::celestial_bodies::PlanetData;
+
+    fn earth_like(position: Vec3Fixed) -> CelestialBody {
+        CelestialBody::new(
+            Uuid::new_v4(),
+            CelestialType::Planet(PlanetData {
+                planet_type: PlanetType::Rocky,
+                atmosphere: AtmosphereType::Oxygen,
+                water_coverage: 70,
+                temperature_range: (15, 25),
+                habitability: 0,
+            }),
+            position,
+            fixed::from_f64(1.0),
+            fixed::from_f64(1.0),
+        )
+    }
+
+    fn sun() -> LightSource {
+        LightSource { position: Vec3Fixed::zeros(), luminosity: 1.0 }
+    }
+
+    #[test]
+    fn test_earth_analog_is_habitable() {
+        let settings = HabitabilitySettings::default();
+        let planet = earth_like(Vec3Fixed::new(settings.astronomical_unit, 0.0, 0.0));
+
+        let breakdown = evaluate(&planet, &[sun()], &settings).unwrap();
+
+        assert!((breakdown.stellar_flux - 1.0).abs() < 1e-9);
+        assert!((breakdown.surface_temperature - 14.85).abs() < 0.1);
+        assert_eq!(breakdown.temperature_score, 1.0);
+        assert_eq!(breakdown.habitability, 90);
+    }
+
+    #[test]
+    fn test_distance_and_losing_the_star_change_habitability() {
+        let settings = HabitabilitySettings::default();
+        let au = settings.astronomical_unit;
+        let habitability = |position: Vec3Fixed, lights: &[LightSource]| {
+            evaluate(&earth_like(position), lights, &settings).unwrap().habitability
+        };
+
+        let in_zone = habitability(Vec3Fixed::new(au, 0.0, 0.0), &[sun()]);
+        let too_close = habitability(Vec3Fixed::new(au * 0.3, 0.0, 0.0), &[sun()]);
+        let too_far = habitability(Vec3Fixed::new(au * 5.0, 0.0, 0.0), &[sun()]);
+        let rogue = habitability(Vec3Fixed::new(au, 0.0, 0.0), &[]);
+
+        assert!(in_zone > LIFE_EVOLUTION_THRESHOLD);
+        assert!(too_close < LIFE_EVOLUTION_THRESHOLD);
+        assert!(too_far < LIFE_EVOLUTION_THRESHOLD);
+        assert_eq!(rogue, 0);
+    }
+
+    #[test]
+    fn test_atmosphere_water_and_type_matter() {
+        let settings = HabitabilitySettings::default();
+        let lights = [sun()];
+        let mut planet = earth_like(Vec3Fixed::new(settings.astronomical_unit, 0.0, 0.0));
+        let baseline = evaluate(&planet, &lights, &settings).unwrap().habitability;
+
+        if let CelestialType::Planet(data) = &mut planet.body_type {
+            data.atmosphere = AtmosphereType::Toxic;
+        }
+        let toxic = evaluate(&planet, &lights, &settings).unwrap();
+        // 強い温室効果で高温になり、呼吸もできない
+        assert!(toxic.surface_temperature > 100.0);
+        assert!(toxic.habitability < baseline);
+
+        if let CelestialType::Planet(data) = &mut planet.body_type {
+            data.atmosphere = AtmosphereType::Oxygen;
+            data.water_coverage = 0;
+            data.planet_type = PlanetType::Desert;
+        }
+        let desert = evaluate(&planet, &lights, &settings).unwrap();
+        assert!(desert.habitability < LIFE_EMERGENCE_THRESHOLD);
+
+        // 惑星以外は評価しない
+        let moon = CelestialBody::new(Uuid::new_v4(), CelestialType::Moon, Vec3Fixed::zeros(), 1, 1);
+        assert!(evaluate(&moon, &lights, &settings).is_none());
+    }
+}
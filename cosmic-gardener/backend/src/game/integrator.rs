@@ -0,0 +1,342 @@
+//! 時間積分法
+//!
+//! 位置と速度の配列を加速度場に従って1ステップ進める。シンプレクティック積分法を使うことで、
+//! 長時間の放置でも軌道が外側へ渦巻いたりエネルギーがドリフトしたりしないようにする。
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::celestial_bodies::Vec3Fixed;
+
+/// 位置の配列からそれぞれの加速度を計算する関数
+pub type AccelerationFn<'a> = dyn FnMut(&[Vec3Fixed]) -> Vec<Vec3Fixed> + 'a;
+
+/// 積分法が進める位相空間（位置と速度）
+///
+/// 配列の持ち方は実装に任せ、積分法はkickとdriftの順序と係数だけを決める。
+pub trait PhaseSpace {
+    /// 現在の位置での加速度で速度を`dt`だけ進める
+    ///
+    /// 前回のkickから位置が変わっていなければ、その時の加速度を使い回してよい。
+    fn kick(&mut self, dt: f64);
+
+    /// 現在の速度で位置を`dt`だけ進める
+    fn drift(&mut self, dt: f64);
+
+    /// 以降のkickとdriftで進める天体を格納順の印で限定する（`None`なら全天体）
+    ///
+    /// 進めない天体も加速度の計算には現在の位置で加わる。
+    fn restrict(&mut self, active: Option<Vec<bool>>);
+}
+
+/// 時間積分法
+pub trait Integrator: Send + Sync {
+    /// 積分法の名前
+    fn name(&self) -> &'static str;
+
+    /// 1ステップあたりの加速度評価回数（同じ位相空間で続けて進めた場合）
+    fn force_evaluations(&self) -> usize;
+
+    /// 位相空間を`dt`だけ進める
+    fn advance(&self, state: &mut dyn PhaseSpace, dt: f64);
+
+    /// 位置と速度を`dt`だけ進める
+    fn step(&self, positions: &mut [Vec3Fixed], velocities: &mut [Vec3Fixed], dt: f64, acceleration: &mut AccelerationFn<'_>) {
+        self.advance(&mut SlicePhaseSpace::new(positions, velocities, acceleration), dt);
+    }
+}
+
+/// 位置と速度の配列、加速度を返す関数からなる位相空間
+///
+/// 最後に計算した加速度を位置が変わるまで持ち続ける。ステップの最後のkickと次のステップの
+/// 最初のkickは同じ位置で評価するので、同じ位相空間で続けて進めれば1回分の評価で済む。
+pub struct SlicePhaseSpace<'a, 'f> {
+    positions: &'a mut [Vec3Fixed],
+    velocities: &'a mut [Vec3Fixed],
+    acceleration: &'a mut AccelerationFn<'f>,
+    /// 現在の位置での加速度（driftした後は`None`）
+    accelerations: Option<Vec<Vec3Fixed>>,
+    /// kickとdriftで進める天体（`None`なら全天体）
+    active: Option<Vec<bool>>,
+}
+
+impl<'a, 'f> SlicePhaseSpace<'a, 'f> {
+    pub fn new(positions: &'a mut [Vec3Fixed], velocities: &'a mut [Vec3Fixed], acceleration: &'a mut AccelerationFn<'f>) -> Self {
+        Self { positions, velocities, acceleration, accelerations: None, active: None }
+    }
+
+    /// 現在の位置での加速度が分かっている場合は、最初のkickでそれを使う
+    pub fn with_accelerations(mut self, accelerations: Vec<Vec3Fixed>) -> Self {
+        if accelerations.len() == self.positions.len() {
+            self.accelerations = Some(accelerations);
+        }
+        self
+    }
+
+    /// 現在の位置での加速度（最後の操作がdriftの場合は`None`）
+    pub fn into_accelerations(self) -> Option<Vec<Vec3Fixed>> {
+        self.accelerations
+    }
+}
+
+impl PhaseSpace for SlicePhaseSpace<'_, '_> {
+    fn kick(&mut self, dt: f64) {
+        let positions = &*self.positions;
+        let acceleration = &mut self.acceleration;
+        let accelerations = self.accelerations.get_or_insert_with(|| acceleration(positions));
+        kick(self.velocities, accelerations, dt, self.active.as_deref());
+    }
+
+    fn drift(&mut self, dt: f64) {
+        drift(self.positions, self.velocities, dt, self.active.as_deref());
+        self.accelerations = None;
+    }
+
+    fn restrict(&mut self, active: Option<Vec<bool>>) {
+        self.active = active;
+    }
+}
+
+/// 設定から選択する積分法の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegratorKind {
+    /// 半陰的オイラー法（1次、従来の挙動）
+    Euler,
+    /// 速度ベルレ法（2次）
+    #[default]
+    VelocityVerlet,
+    /// 吉田の4次シンプレクティック積分法
+    Yoshida4,
+}
+
+impl IntegratorKind {
+    /// 対応する積分法の実装
+    pub fn integrator(self) -> &'static dyn Integrator {
+        match self {
+            IntegratorKind::Euler => &SemiImplicitEuler,
+            IntegratorKind::VelocityVerlet => &VelocityVerlet,
+            IntegratorKind::Yoshida4 => &Yoshida4,
+        }
+    }
+}
+
+/// 半陰的オイラー法（速度を更新してから新しい速度で位置を進める）
+#[derive(Debug, Clone, Copy)]
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn name(&self) -> &'static str {
+        "euler"
+    }
+
+    fn force_evaluations(&self) -> usize {
+        1
+    }
+
+    fn advance(&self, state: &mut dyn PhaseSpace, dt: f64) {
+        state.kick(dt);
+        state.drift(dt);
+    }
+}
+
+/// 速度ベルレ法（kick-drift-kick）
+///
+/// 最後のkickの加速度が次のステップの最初のkickにそのまま使える（FSAL）。
+#[derive(Debug, Clone, Copy)]
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn name(&self) -> &'static str {
+        "velocity_verlet"
+    }
+
+    fn force_evaluations(&self) -> usize {
+        1
+    }
+
+    fn advance(&self, state: &mut dyn PhaseSpace, dt: f64) {
+        state.kick(dt * 0.5);
+        state.drift(dt);
+        state.kick(dt * 0.5);
+    }
+}
+
+/// 吉田の4次シンプレクティック積分法（drift-kick-drift の3段合成）
+#[derive(Debug, Clone, Copy)]
+pub struct Yoshida4;
+
+impl Yoshida4 {
+    /// (drift係数, kick係数) の組。最後の段はdriftのみ
+    fn coefficients() -> ([f64; 4], [f64; 3]) {
+        let cbrt2 = 2.0_f64.cbrt();
+        let w1 = 1.0 / (2.0 - cbrt2);
+        let w0 = -cbrt2 / (2.0 - cbrt2);
+        (
+            [w1 * 0.5, (w0 + w1) * 0.5, (w0 + w1) * 0.5, w1 * 0.5],
+            [w1, w0, w1],
+        )
+    }
+}
+
+impl Integrator for Yoshida4 {
+    fn name(&self) -> &'static str {
+        "yoshida4"
+    }
+
+    fn force_evaluations(&self) -> usize {
+        3
+    }
+
+    fn advance(&self, state: &mut dyn PhaseSpace, dt: f64) {
+        let (drifts, kicks) = Self::coefficients();
+        for (stage, drift_coefficient) in drifts.iter().enumerate() {
+            state.drift(dt * drift_coefficient);
+            if let Some(kick_coefficient) = kicks.get(stage) {
+                state.kick(dt * kick_coefficient);
+            }
+        }
+    }
+}
+
+/// 加速度で速度を更新（`active`が偽の天体は据え置く）
+fn kick(velocities: &mut [Vec3Fixed], accelerations: &[Vec3Fixed], dt: f64, active: Option<&[bool]>) {
+    for (index, (velocity, acceleration)) in velocities.iter_mut().zip(accelerations).enumerate() {
+        if is_active(active, index) {
+            *velocity += acceleration * dt;
+        }
+    }
+}
+
+/// 速度で位置を更新（`active`が偽の天体は据え置く）
+fn drift(positions: &mut [Vec3Fixed], velocities: &[Vec3Fixed], dt: f64, active: Option<&[bool]>) {
+    for (index, (position, velocity)) in positions.iter_mut().zip(velocities).enumerate() {
+        if is_active(active, index) {
+            *position += velocity * dt;
+        }
+    }
+}
+
+/// 格納順`index`の天体を進めるか
+pub(crate) fn is_active(active: Option<&[bool]>, index: usize) -> bool {
+    active.is_none_or(|active| active[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const G: f64 = 6.67430e-11;
+    const MASSES: [f64; 2] = [1.0e12, 1.0e9];
+
+    fn two_body_acceleration(positions: &[Vec3Fixed]) -> Vec<Vec3Fixed> {
+        let r = positions[1] - positions[0];
+        let distance = r.magnitude();
+        let direction = r / (distance * distance * distance);
+        vec![direction * (G * MASSES[1]), -direction * (G * MASSES[0])]
+    }
+
+    fn total_energy(positions: &[Vec3Fixed], velocities: &[Vec3Fixed]) -> f64 {
+        let kinetic: f64 = velocities.iter().zip(MASSES).map(|(v, m)| 0.5 * m * v.magnitude_squared()).sum();
+        let potential = -G * MASSES[0] * MASSES[1] / (positions[1] - positions[0]).magnitude();
+        kinetic + potential
+    }
+
+    /// 遠点から始まる楕円軌道の初期状態（重心静止、`speed_ratio`は円軌道速度に対する比）
+    fn elliptical_orbit(radius: f64, speed_ratio: f64) -> (Vec<Vec3Fixed>, Vec<Vec3Fixed>) {
+        let total_mass = MASSES[0] + MASSES[1];
+        let relative_speed = (G * total_mass / radius).sqrt() * speed_ratio;
+        let positions = vec![
+            Vec3Fixed::new(-radius * MASSES[1] / total_mass, 0.0, 0.0),
+            Vec3Fixed::new(radius * MASSES[0] / total_mass, 0.0, 0.0),
+        ];
+        let velocities = vec![
+            Vec3Fixed::new(0.0, -relative_speed * MASSES[1] / total_mass, 0.0),
+            Vec3Fixed::new(0.0, relative_speed * MASSES[0] / total_mass, 0.0),
+        ];
+        (positions, velocities)
+    }
+
+    /// 10^6ティック積分し、最大の相対エネルギー誤差を返す
+    fn max_energy_error(kind: IntegratorKind) -> f64 {
+        // 周期は約5.4秒（約110ティック）なので、10^6ティックで約9000周する
+        let (mut positions, mut velocities) = elliptical_orbit(5.0, 0.8);
+        let initial_energy = total_energy(&positions, &velocities);
+        let integrator = kind.integrator();
+
+        let mut max_error: f64 = 0.0;
+        for tick in 0..1_000_000 {
+            integrator.step(&mut positions, &mut velocities, crate::game::physics::TICK_DURATION, &mut two_body_acceleration);
+            if tick % 1000 == 0 {
+                let error = ((total_energy(&positions, &velocities) - initial_energy) / initial_energy).abs();
+                max_error = max_error.max(error);
+            }
+        }
+        max_error
+    }
+
+    #[test]
+    fn test_velocity_verlet_energy_bounded() {
+        let error = max_energy_error(IntegratorKind::VelocityVerlet);
+        assert!(error < 1e-2, "energy error {}", error);
+    }
+
+    #[test]
+    fn test_yoshida4_energy_bounded() {
+        let error = max_energy_error(IntegratorKind::Yoshida4);
+        assert!(error < 1e-4, "energy error {}", error);
+    }
+
+    #[test]
+    fn test_integrator_kind_from_config() {
+        let kind: IntegratorKind = serde_json::from_str("\"yoshida4\"").unwrap();
+        assert_eq!(kind, IntegratorKind::Yoshida4);
+        assert_eq!(kind.integrator().force_evaluations(), 3);
+        assert_eq!(IntegratorKind::default().integrator().name(), "velocity_verlet");
+        assert_eq!(IntegratorKind::default().integrator().force_evaluations(), 1);
+    }
+
+    #[test]
+    fn test_velocity_verlet_reuses_last_accelerations() {
+        let (mut positions, mut velocities) = elliptical_orbit(5.0, 0.8);
+        let (mut expected_positions, mut expected_velocities) = (positions.clone(), velocities.clone());
+        let integrator = VelocityVerlet;
+        let dt = crate::game::physics::TICK_DURATION;
+
+        for _ in 0..10 {
+            integrator.step(&mut expected_positions, &mut expected_velocities, dt, &mut two_body_acceleration);
+        }
+
+        let mut evaluations = 0;
+        let mut counted = |positions: &[Vec3Fixed]| {
+            evaluations += 1;
+            two_body_acceleration(positions)
+        };
+        let mut state = SlicePhaseSpace::new(&mut positions, &mut velocities, &mut counted);
+        for _ in 0..10 {
+            integrator.advance(&mut state, dt);
+        }
+        assert!(state.into_accelerations().is_some());
+
+        // 最初のステップだけ開始位置での評価が加わる
+        assert_eq!(evaluations, 10 + 1);
+        assert_eq!(positions, expected_positions);
+        assert_eq!(velocities, expected_velocities);
+    }
+
+    #[test]
+    fn test_restricted_bodies_stay_put_but_still_attract() {
+        let (mut positions, mut velocities) = elliptical_orbit(5.0, 0.8);
+        let (start_positions, start_velocities) = (positions.clone(), velocities.clone());
+        let mut acceleration = two_body_acceleration;
+        let mut state = SlicePhaseSpace::new(&mut positions, &mut velocities, &mut acceleration);
+        state.restrict(Some(vec![false, true]));
+        VelocityVerlet.advance(&mut state, crate::game::physics::TICK_DURATION);
+        drop(state);
+
+        // 限定した天体だけが進み、もう一方の重力で軌道が曲がる
+        assert_eq!(positions[0], start_positions[0]);
+        assert_eq!(velocities[0], start_velocities[0]);
+        assert_ne!(velocities[1], start_velocities[1]);
+        assert!(velocities[1].x < 0.0);
+    }
+}
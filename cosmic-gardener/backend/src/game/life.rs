@@ -0,0 +1,520 @@
+//! 生命の進化
+//!
+//! 惑星の生命を環境収容力のあるロジスティック成長で増減させ、微生物 → 植物 → 動物 → 知的生命の順に
+//! 進化させる。居住可能性の低下や天災で人口が段階の維持に必要な数を割ると段階が後退し、最後は絶滅する。
+//! 知的生命は団結度に応じて技術レベルを上げ、技術レベルが生産ボーナスを解放する。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::game::achievements::{AchievementTrigger, TriggerKind};
+use crate::game::celestial_bodies::{BodyId, CelestialBody, LifeStage};
+use crate::game::collision::CollisionOutcome;
+use crate::game::habitability::{HabitabilityBreakdown, LIFE_EVOLUTION_THRESHOLD};
+use crate::game::resources::fixed;
+use crate::models::websocket::{CelestialEventResponse, CelestialEventType};
+
+/// 生命誕生までの時間（進化タイマー）と誕生時の人口
+const EMERGENCE_TIME: u64 = 1000;
+const INITIAL_POPULATION: u64 = 1000;
+/// 技術レベルが1上がるのに必要な時間（団結度50の場合、レベルに比例して長くなる）
+const TECH_ADVANCE_TIME: f64 = 30_000.0;
+/// 技術レベル1の知的生命が100万人あたりに生む思考ポイント
+const BASE_KNOWLEDGE_RATE: f64 = 0.1;
+/// 主星の光を失ったとみなす放射（地球 = 1.0）
+const STARLESS_FLUX: f64 = 0.01;
+/// これ以上の被害では生命が即座に絶滅する
+const EXTINCTION_SEVERITY: f64 = 0.95;
+/// 被害の大きさ1.0あたりに失われる団結度
+const UNITY_LOSS_PER_SEVERITY: f64 = 50.0;
+/// 人口が収容力のこの割合を超えると団結度が下がり始める
+const CROWDING_LIMIT: f64 = 0.9;
+
+/// 生命に起きた出来事の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifeEventKind {
+    /// 生命の誕生
+    Emergence,
+    /// 次の段階への進化
+    Evolution,
+    /// 前の段階への後退
+    Regression,
+    /// 絶滅
+    Extinction,
+    /// 知的生命の技術レベルの上昇
+    TechAdvance,
+}
+
+/// 生命が後退・絶滅した原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtinctionCause {
+    /// 天体の衝突
+    Collision,
+    /// 近くの超新星爆発
+    Supernova,
+    /// 主星の光を失った（恒星の死や主星から遠ざかった）
+    StarLoss,
+    /// その他の居住可能性の低下
+    HabitabilityLoss,
+}
+
+impl ExtinctionCause {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExtinctionCause::Collision => "Collision",
+            ExtinctionCause::Supernova => "Supernova",
+            ExtinctionCause::StarLoss => "StarLoss",
+            ExtinctionCause::HabitabilityLoss => "HabitabilityLoss",
+        }
+    }
+}
+
+/// 生命段階の遷移
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifeTransition {
+    pub body_id: BodyId,
+    pub kind: LifeEventKind,
+    pub from_stage: LifeStage,
+    pub to_stage: LifeStage,
+    /// 後退・絶滅の原因
+    pub cause: Option<ExtinctionCause>,
+}
+
+impl LifeTransition {
+    /// クライアントへ送る天体イベント
+    pub fn to_celestial_event(&self, timestamp: DateTime<Utc>) -> CelestialEventResponse {
+        let event_type = match self.kind {
+            LifeEventKind::Emergence => CelestialEventType::LifeEmergence,
+            _ => CelestialEventType::Evolution,
+        };
+        CelestialEventResponse {
+            event_type,
+            celestial_body_ids: vec![self.body_id],
+            event_data: serde_json::json!({
+                "kind": self.kind,
+                "from_stage": self.from_stage,
+                "to_stage": self.to_stage,
+                "cause": self.cause,
+            }),
+            timestamp,
+        }
+    }
+
+    /// 実績判定の入力（後退は対象外）
+    pub fn achievement_trigger(&self) -> Option<AchievementTrigger> {
+        match (self.kind, self.cause) {
+            (LifeEventKind::Emergence | LifeEventKind::Evolution, _) => {
+                Some(AchievementTrigger::with_subject(TriggerKind::LifeEvolved, self.to_stage.name()))
+            }
+            (LifeEventKind::Extinction, Some(cause)) => {
+                Some(AchievementTrigger::with_subject(TriggerKind::Extinction, cause.name()))
+            }
+            (LifeEventKind::TechAdvance, _) => Some(AchievementTrigger::new(TriggerKind::TechAdvanced)),
+            _ => None,
+        }
+    }
+}
+
+/// 技術レベルによる生産ボーナス
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TechBonuses {
+    /// 思考ポイントの倍率
+    pub thought_multiplier: f64,
+    /// 恒星エネルギーの利用（レベル5から）
+    pub energy_per_tick: f64,
+    /// ダークマターの研究（レベル10から）
+    pub dark_matter_per_tick: f64,
+}
+
+/// 技術レベルで解放されるボーナス
+pub fn tech_bonuses(tech_level: u32) -> TechBonuses {
+    let level = tech_level.max(1) as f64;
+    TechBonuses {
+        thought_multiplier: 1.0 + 0.25 * (level - 1.0),
+        energy_per_tick: if level >= 5.0 { 0.5 * (level - 4.0) } else { 0.0 },
+        dark_matter_per_tick: if level >= 10.0 { 0.05 * (level - 9.0) } else { 0.0 },
+    }
+}
+
+/// 団結度による効率（団結度50で1.0、0〜100で0.5〜1.5）
+pub fn unity_factor(unity: u8) -> f64 {
+    0.5 + unity.min(100) as f64 / 100.0
+}
+
+/// 知的生命の1ティックあたりの思考ポイント
+pub fn thought_rate(population: u64, knowledge_rate: f64, unity: u8) -> f64 {
+    population as f64 / 1_000_000.0 * knowledge_rate * unity_factor(unity)
+}
+
+/// 段階ごとの成長と進化の条件
+struct StageParams {
+    /// 1ティックあたりの成長率
+    growth_rate: f64,
+    /// 居住可能性100での環境収容力
+    base_capacity: f64,
+    /// 段階を維持するのに必要な人口
+    min_population: u64,
+    /// 次の段階への進化に必要な進化タイマーと人口
+    advance_at: Option<(u64, u64)>,
+}
+
+fn stage_params(stage: &LifeStage) -> StageParams {
+    let (growth_rate, base_capacity, min_population, advance_at) = match stage {
+        LifeStage::None => (0.0, 0.0, 0, None),
+        LifeStage::Microbial { .. } => (0.01, 1.0e6, 1, Some((5_000, 100_000))),
+        LifeStage::Plant { .. } => (0.05, 1.0e7, 10_000, Some((10_000, 1_000_000))),
+        LifeStage::Animal { .. } => (0.1, 1.0e8, 100_000, Some((20_000, 10_000_000))),
+        LifeStage::Intelligent { .. } => (0.02, 1.0e10, 1_000_000, None),
+    };
+    StageParams { growth_rate, base_capacity, min_population, advance_at }
+}
+
+/// 生命を`time_factor`ティック分進め、段階の変化や技術の進歩を返す
+pub fn update_life(body: &mut CelestialBody, habitability: &HabitabilityBreakdown, time_factor: f64) -> Option<LifeTransition> {
+    let body_id = body.id;
+    let lifecycle = &mut body.lifecycle;
+    lifecycle.evolution_timer += (time_factor * 1000.0) as u64;
+    let habitable = habitability.habitability > LIFE_EVOLUTION_THRESHOLD;
+
+    if lifecycle.life_stage == LifeStage::None {
+        if !habitable || lifecycle.evolution_timer <= EMERGENCE_TIME || lifecycle.population != 0 {
+            return None;
+        }
+        // 微生物の発生
+        lifecycle.life_stage = LifeStage::Microbial {
+            diversity: 1,
+            evolution_pressure: fixed::from_f64(0.1),
+        };
+        lifecycle.population = INITIAL_POPULATION;
+        lifecycle.evolution_timer = 0;
+        return Some(LifeTransition {
+            body_id,
+            kind: LifeEventKind::Emergence,
+            from_stage: LifeStage::None,
+            to_stage: lifecycle.life_stage.clone(),
+            cause: None,
+        });
+    }
+
+    // 居住可能性に比例した環境収容力へ向けたロジスティック成長（収容力を超えていれば減少する）
+    let params = stage_params(&lifecycle.life_stage);
+    let capacity = params.base_capacity * habitability.habitability as f64 / 100.0;
+    let population = logistic(lifecycle.population as f64, capacity, params.growth_rate, time_factor);
+    lifecycle.population = population as u64;
+    let crowding = if capacity > 0.0 { population / capacity } else { 1.0 };
+
+    if lifecycle.population < params.min_population {
+        let cause = if habitability.stellar_flux < STARLESS_FLUX {
+            ExtinctionCause::StarLoss
+        } else {
+            ExtinctionCause::HabitabilityLoss
+        };
+        return Some(regress(body, cause));
+    }
+
+    let from_stage = lifecycle.life_stage.clone();
+    match &mut lifecycle.life_stage {
+        LifeStage::Microbial { evolution_pressure, .. } => {
+            // 過密になるほど進化の圧力が高まる
+            *evolution_pressure = fixed::from_f64(crowding.min(1.0));
+        }
+        LifeStage::Plant { coverage, oxygen_production } => {
+            *coverage = (population / params.base_capacity * 100.0).clamp(0.0, 100.0) as u8;
+            *oxygen_production = fixed::from_f64(*coverage as f64 / 100.0);
+        }
+        LifeStage::Animal { species_count, food_chain_complexity } => {
+            *species_count = (10.0 * (1.0 + crowding.min(1.0) * 9.0)) as u32;
+            *food_chain_complexity = (1.0 + crowding.min(1.0) * 9.0) as u8;
+        }
+        LifeStage::Intelligent { tech_level, unity, knowledge_rate } => {
+            // 団結度が高いほど技術が早く進む（衰退している文明は進歩しない）
+            let progress = lifecycle.evolution_timer as f64 * unity_factor(*unity);
+            if !habitable || progress < TECH_ADVANCE_TIME * *tech_level as f64 {
+                return None;
+            }
+            *tech_level += 1;
+            *knowledge_rate = fixed::from_f64(BASE_KNOWLEDGE_RATE * tech_bonuses(*tech_level).thought_multiplier);
+            // 豊かな惑星では団結が進み、過密な惑星では対立が生まれる
+            let target = habitability.habitability as f64 - if crowding > CROWDING_LIMIT { 30.0 } else { 0.0 };
+            *unity = approach(*unity, target);
+            lifecycle.evolution_timer = 0;
+            return Some(LifeTransition {
+                body_id,
+                kind: LifeEventKind::TechAdvance,
+                from_stage,
+                to_stage: lifecycle.life_stage.clone(),
+                cause: None,
+            });
+        }
+        LifeStage::None => {}
+    }
+
+    let (required_time, required_population) = params.advance_at?;
+    if !habitable || lifecycle.evolution_timer <= required_time || lifecycle.population <= required_population {
+        return None;
+    }
+    lifecycle.life_stage = match from_stage {
+        LifeStage::Microbial { .. } => LifeStage::Plant {
+            coverage: 10,
+            oxygen_production: fixed::from_f64(0.1),
+        },
+        LifeStage::Plant { .. } => LifeStage::Animal {
+            species_count: 10,
+            food_chain_complexity: 1,
+        },
+        _ => LifeStage::Intelligent {
+            tech_level: 1,
+            unity: 50,
+            knowledge_rate: fixed::from_f64(BASE_KNOWLEDGE_RATE),
+        },
+    };
+    lifecycle.evolution_timer = 0;
+    Some(LifeTransition {
+        body_id,
+        kind: LifeEventKind::Evolution,
+        from_stage,
+        to_stage: lifecycle.life_stage.clone(),
+        cause: None,
+    })
+}
+
+/// 天災で人口の`severity`（0.0〜1.0）の割合を失わせ、維持できなくなった段階を後退させる
+pub fn apply_catastrophe(body: &mut CelestialBody, severity: f64, cause: ExtinctionCause) -> Vec<LifeTransition> {
+    let mut transitions = Vec::new();
+    if body.lifecycle.life_stage == LifeStage::None || severity <= 0.0 {
+        return transitions;
+    }
+
+    let severity = severity.min(1.0);
+    if severity >= EXTINCTION_SEVERITY {
+        body.lifecycle.population = 0;
+    } else {
+        body.lifecycle.population = (body.lifecycle.population as f64 * (1.0 - severity)) as u64;
+    }
+    if let LifeStage::Intelligent { unity, .. } = &mut body.lifecycle.life_stage {
+        *unity = unity.saturating_sub((severity * UNITY_LOSS_PER_SEVERITY) as u8);
+    }
+
+    while body.lifecycle.life_stage != LifeStage::None
+        && body.lifecycle.population < stage_params(&body.lifecycle.life_stage).min_population
+    {
+        transitions.push(regress(body, cause));
+    }
+    transitions
+}
+
+/// 衝突が生き残った天体の生命に与える被害の大きさ
+pub fn collision_severity(outcome: CollisionOutcome) -> f64 {
+    match outcome {
+        CollisionOutcome::Fragmentation => 1.0,
+        CollisionOutcome::Merge => 0.9,
+        CollisionOutcome::TidalDisruption => 0.5,
+        CollisionOutcome::Ejection => 0.3,
+    }
+}
+
+/// 1段階後退させる（微生物からの後退は絶滅）
+fn regress(body: &mut CelestialBody, cause: ExtinctionCause) -> LifeTransition {
+    let lifecycle = &mut body.lifecycle;
+    let from_stage = lifecycle.life_stage.clone();
+    lifecycle.life_stage = match from_stage {
+        LifeStage::Intelligent { .. } => LifeStage::Animal {
+            species_count: 10,
+            food_chain_complexity: 1,
+        },
+        LifeStage::Animal { .. } => LifeStage::Plant {
+            coverage: 10,
+            oxygen_production: fixed::from_f64(0.1),
+        },
+        LifeStage::Plant { .. } => LifeStage::Microbial {
+            diversity: 1,
+            evolution_pressure: fixed::from_f64(0.1),
+        },
+        LifeStage::Microbial { .. } | LifeStage::None => LifeStage::None,
+    };
+    lifecycle.evolution_timer = 0;
+
+    let kind = if lifecycle.life_stage == LifeStage::None {
+        lifecycle.population = 0;
+        LifeEventKind::Extinction
+    } else {
+        LifeEventKind::Regression
+    };
+    LifeTransition {
+        body_id: body.id,
+        kind,
+        from_stage,
+        to_stage: lifecycle.life_stage.clone(),
+        cause: Some(cause),
+    }
+}
+
+/// ロジスティック方程式の解析解（大きな時間刻みでも安定）
+fn logistic(population: f64, capacity: f64, growth_rate: f64, time: f64) -> f64 {
+    if population <= 0.0 {
+        return 0.0;
+    }
+    let decay = (-growth_rate * time).exp();
+    if capacity < 1.0 {
+        // 住めなくなった惑星では指数関数的に減っていく
+        return population * decay;
+    }
+    capacity / (1.0 + (capacity - population) / population * decay)
+}
+
+/// 目標へ差の4分の1（最低1）だけ近づける
+fn approach(current: u8, target: f64) -> u8 {
+    let target = target.clamp(0.0, 100.0);
+    let difference = target - current as f64;
+    if difference.abs() < 1.0 {
+        return current;
+    }
+    let step = (difference / 4.0).abs().max(1.0).copysign(difference);
+    (current as f64 + step).round().clamp(0.0, 100.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::game::celestial_bodies::{AtmosphereType, CelestialType, PlanetData, PlanetType, Vec3Fixed};
+
+    fn planet() -> CelestialBody {
+        CelestialBody::new(
+            Uuid::new_v4(),
+            CelestialType::Planet(PlanetData {
+                planet_type: PlanetType::Ocean,
+                atmosphere: AtmosphereType::Oxygen,
+                water_coverage: 70,
+                temperature_range: (15, 25),
+                habitability: 100,
+            }),
+            Vec3Fixed::zeros(),
+            fixed::from_f64(1.0),
+            fixed::from_f64(1.0),
+        )
+    }
+
+    fn breakdown(habitability: u8, stellar_flux: f64) -> HabitabilityBreakdown {
+        HabitabilityBreakdown {
+            stellar_flux,
+            surface_temperature: 15.0,
+            temperature_score: 1.0,
+            atmosphere_score: 1.0,
+            water_score: 1.0,
+            planet_type_score: 1.0,
+            habitability,
+        }
+    }
+
+    fn intelligent(tech_level: u32, unity: u8) -> LifeStage {
+        LifeStage::Intelligent { tech_level, unity, knowledge_rate: fixed::from_f64(BASE_KNOWLEDGE_RATE) }
+    }
+
+    #[test]
+    fn test_population_is_bounded_by_carrying_capacity() {
+        let mut body = planet();
+        let good = breakdown(100, 1.0);
+        let emergence = update_life(&mut body, &good, 2.0).unwrap();
+        assert_eq!(emergence.kind, LifeEventKind::Emergence);
+        assert_eq!(emergence.to_celestial_event(Utc::now()).event_type, CelestialEventType::LifeEmergence);
+
+        // 居住可能性50では進化できず、微生物の収容力50万で頭打ちになる
+        let moderate = breakdown(50, 1.0);
+        for _ in 0..10_000 {
+            assert!(update_life(&mut body, &moderate, 1.0).is_none());
+        }
+        assert!(body.lifecycle.population <= 500_000);
+        assert!(body.lifecycle.population > 490_000);
+    }
+
+    #[test]
+    fn test_ladder_reaches_intelligence_and_tech_advances() {
+        let mut body = planet();
+        let good = breakdown(100, 1.0);
+        let mut kinds = Vec::new();
+        for _ in 0..20_000 {
+            if let Some(transition) = update_life(&mut body, &good, 1.0) {
+                kinds.push(transition.kind);
+                if transition.kind == LifeEventKind::TechAdvance {
+                    break;
+                }
+            }
+        }
+        assert_eq!(kinds, vec![
+            LifeEventKind::Emergence,
+            LifeEventKind::Evolution,
+            LifeEventKind::Evolution,
+            LifeEventKind::Evolution,
+            LifeEventKind::TechAdvance,
+        ]);
+        let LifeStage::Intelligent { tech_level, unity, knowledge_rate } = body.lifecycle.life_stage else {
+            panic!("expected intelligent life");
+        };
+        assert_eq!(tech_level, 2);
+        assert!(unity > 50);
+        assert!(fixed::to_f64(knowledge_rate) > BASE_KNOWLEDGE_RATE);
+    }
+
+    #[test]
+    fn test_losing_the_star_regresses_to_extinction() {
+        let mut body = planet();
+        body.lifecycle.life_stage = intelligent(3, 50);
+        body.lifecycle.population = 50_000_000;
+        let dark = breakdown(0, 0.0);
+
+        let mut transitions = Vec::new();
+        for _ in 0..10_000 {
+            transitions.extend(update_life(&mut body, &dark, 10.0));
+            if body.lifecycle.life_stage == LifeStage::None {
+                break;
+            }
+        }
+        let kinds: Vec<LifeEventKind> = transitions.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            LifeEventKind::Regression,
+            LifeEventKind::Regression,
+            LifeEventKind::Regression,
+            LifeEventKind::Extinction,
+        ]);
+        assert!(transitions.iter().all(|t| t.cause == Some(ExtinctionCause::StarLoss)));
+        assert_eq!(body.lifecycle.population, 0);
+        assert_eq!(
+            transitions[3].achievement_trigger(),
+            Some(AchievementTrigger::with_subject(TriggerKind::Extinction, "StarLoss"))
+        );
+        assert_eq!(transitions[0].to_celestial_event(Utc::now()).event_type, CelestialEventType::Evolution);
+    }
+
+    #[test]
+    fn test_catastrophes_regress_and_exterminate() {
+        let mut body = planet();
+        body.lifecycle.life_stage = intelligent(2, 80);
+        body.lifecycle.population = 2_000_000;
+
+        // 人口が半減すると知的生命を維持できない
+        let transitions = apply_catastrophe(&mut body, 0.6, ExtinctionCause::Collision);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].kind, LifeEventKind::Regression);
+        assert_eq!(body.lifecycle.life_stage.name(), "Animal");
+        assert_eq!(body.lifecycle.population, 800_000);
+
+        let transitions = apply_catastrophe(&mut body, collision_severity(CollisionOutcome::Fragmentation), ExtinctionCause::Collision);
+        assert_eq!(transitions.last().unwrap().kind, LifeEventKind::Extinction);
+        assert_eq!(body.lifecycle.life_stage, LifeStage::None);
+        assert!(apply_catastrophe(&mut body, 1.0, ExtinctionCause::Supernova).is_empty());
+    }
+
+    #[test]
+    fn test_tech_and_unity_bonuses() {
+        assert_eq!(tech_bonuses(1), TechBonuses { thought_multiplier: 1.0, energy_per_tick: 0.0, dark_matter_per_tick: 0.0 });
+        assert!(tech_bonuses(5).energy_per_tick > 0.0);
+        assert_eq!(tech_bonuses(9).dark_matter_per_tick, 0.0);
+        assert!(tech_bonuses(10).dark_matter_per_tick > 0.0);
+
+        assert_eq!(thought_rate(1_000_000, BASE_KNOWLEDGE_RATE, 50), BASE_KNOWLEDGE_RATE);
+        assert!(thought_rate(1_000_000, BASE_KNOWLEDGE_RATE, 100) > thought_rate(1_000_000, BASE_KNOWLEDGE_RATE, 10));
+    }
+}
@@ -1,11 +1,44 @@
 pub mod resources;
 pub mod celestial_bodies;
 pub mod physics;
+pub mod collision;
+pub mod accretion;
+pub mod stellar_evolution;
+pub mod habitability;
+pub mod life;
+pub mod production_modifiers;
+pub mod octree;
+pub mod spatial_index;
+pub mod integrator;
+pub mod timestep;
+pub mod determinism;
+pub mod orbital;
 pub mod physics_simd;
+pub mod physics_store;
 pub mod concurrent_game_loop;
+pub mod command_queue;
+pub mod tick_budget;
+pub mod offline;
+pub mod upgrade_catalog;
+pub mod prestige;
+pub mod research;
+pub mod achievements;
 
 pub use resources::ResourceManager;
 pub use celestial_bodies::CelestialBodyManager;
 pub use physics::PhysicsEngine;
+pub use octree::Octree;
+pub use spatial_index::SpatialIndex;
+pub use integrator::IntegratorKind;
+pub use determinism::{CommandLog, DeterministicRng};
+pub use orbital::OrbitalElements;
 pub use physics_simd::SimdPhysicsEngine;
-pub use concurrent_game_loop::{ConcurrentGameLoop, ConcurrentGameState};
\ No newline at end of file
+pub use physics_store::PhysicsStore;
+pub use concurrent_game_loop::{ConcurrentGameLoop, ConcurrentGameState, PlayerWorld, WorldCommand, WorldId};
+pub use command_queue::{command_queue, CommandAck, CommandQueue, CommandSender};
+pub use tick_budget::{DegradationLevel, TickBudgetConfig, TickScheduler};
+pub use offline::{OfflineProgressCalculator, OfflineProgressConfig, OfflineReport};
+pub use upgrade_catalog::{UpgradeCatalog, UpgradeCatalogHandle};
+pub use prestige::{PrestigeCalculator, PrestigeConfig, PrestigeState};
+pub use research::{ResearchManager, ResearchTree};
+pub use achievements::{AchievementCatalog, AchievementTracker};
\ No newline at end of file
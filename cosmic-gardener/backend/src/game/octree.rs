@@ -0,0 +1,404 @@
+//! Barnes-Hut近似用のオクツリー
+//!
+//! ノードはアリーナ（`Vec`）に確保し、リーフには天体の位置と質量をそのまま保持する。
+//! スカラー版の`PhysicsEngine`とSIMD版の`SimdPhysicsEngine`の両方がこの木を共有する。
+
+use rayon::prelude::*;
+
+use crate::game::celestial_bodies::{BodyId, Vec3Fixed};
+
+/// 子ノードが存在しないことを示すインデックス
+const NO_CHILD: u32 = u32::MAX;
+/// 1つのリーフに保持する天体数の上限
+const LEAF_CAPACITY: usize = 1;
+/// 分割の深さの上限（同一座標の天体による無限分割を防ぐ）
+const MAX_DEPTH: u32 = 32;
+/// この天体数以上の部分木は子ノードを並列に構築する
+const PARALLEL_BUILD_THRESHOLD: usize = 256;
+
+/// 重力計算のパラメータ
+#[derive(Debug, Clone, Copy)]
+pub struct GravitySettings {
+    pub gravitational_constant: f64,
+    pub softening_factor: f64,
+    /// 開き角（ノードの大きさ / 距離がこれ未満なら質量中心で近似する）
+    pub theta: f64,
+}
+
+/// 木に格納される天体
+#[derive(Debug, Clone, Copy)]
+pub struct OctreeBody {
+    pub id: BodyId,
+    pub position: Vec3Fixed,
+    pub mass: f64,
+}
+
+/// オクツリーのノード
+#[derive(Debug, Clone)]
+pub struct OctreeNode {
+    /// 立方体の中心
+    pub center: Vec3Fixed,
+    /// 立方体の一辺の半分
+    pub half_size: f64,
+    pub center_of_mass: Vec3Fixed,
+    pub total_mass: f64,
+    /// 子ノードのインデックス（`NO_CHILD`は空の八分空間）
+    children: [u32; 8],
+    /// リーフが保持する天体の`Octree::bodies`内の範囲
+    body_start: u32,
+    body_count: u32,
+    leaf: bool,
+}
+
+impl OctreeNode {
+    pub fn is_leaf(&self) -> bool {
+        self.leaf
+    }
+
+    /// 子ノードのインデックスを列挙
+    pub fn children(&self) -> impl Iterator<Item = usize> + '_ {
+        self.children.iter().filter(|&&c| c != NO_CHILD).map(|&c| c as usize)
+    }
+
+    /// 点が立方体の内側にあるか
+    pub fn contains(&self, position: &Vec3Fixed) -> bool {
+        (0..3).all(|axis| (position[axis] - self.center[axis]).abs() <= self.half_size)
+    }
+}
+
+/// アリーナ確保のBarnes-Hutオクツリー
+#[derive(Debug, Clone, Default)]
+pub struct Octree {
+    nodes: Vec<OctreeNode>,
+    /// 八分空間ごとに並べ替えた天体（リーフはこの連続した範囲を参照する）
+    bodies: Vec<OctreeBody>,
+}
+
+impl Octree {
+    /// 天体の集合から木を構築
+    pub fn build(mut bodies: Vec<OctreeBody>) -> Self {
+        if bodies.is_empty() {
+            return Self::default();
+        }
+
+        // 全天体を含む立方体を計算
+        let mut min = bodies[0].position;
+        let mut max = bodies[0].position;
+        for body in bodies.iter() {
+            min = min.inf(&body.position);
+            max = max.sup(&body.position);
+        }
+        let center = (min + max) * 0.5;
+        let half_size = ((max - min).max() * 0.5).max(f64::EPSILON);
+
+        let nodes = build_subtree(&mut bodies, 0, center, half_size, 0);
+        Self { nodes, bodies }
+    }
+
+    /// 並列な配列（ID・位置・質量）から木を構築
+    pub fn from_points(ids: &[BodyId], positions: &[Vec3Fixed], masses: &[f64]) -> Self {
+        Self::build(
+            ids.iter().zip(positions).zip(masses)
+                .map(|((id, position), mass)| OctreeBody { id: *id, position: *position, mass: *mass })
+                .collect(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    /// 格納されている天体数
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn nodes(&self) -> &[OctreeNode] {
+        &self.nodes
+    }
+
+    pub fn bodies(&self) -> &[OctreeBody] {
+        &self.bodies
+    }
+
+    /// ルートノード
+    pub fn root(&self) -> Option<&OctreeNode> {
+        self.nodes.first()
+    }
+
+    /// リーフが保持する天体
+    pub fn leaf_bodies(&self, node: &OctreeNode) -> &[OctreeBody] {
+        if !node.leaf {
+            return &[];
+        }
+        let start = node.body_start as usize;
+        &self.bodies[start..start + node.body_count as usize]
+    }
+
+    /// 指定位置での重力加速度を計算（`exclude`の天体自身は除外する）
+    pub fn acceleration_at(&self, position: &Vec3Fixed, exclude: Option<BodyId>, settings: &GravitySettings) -> Vec3Fixed {
+        let mut acceleration = Vec3Fixed::zeros();
+        if self.nodes.is_empty() {
+            return acceleration;
+        }
+
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0usize);
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.total_mass <= 0.0 {
+                continue;
+            }
+
+            if node.leaf {
+                for body in self.leaf_bodies(node) {
+                    if Some(body.id) == exclude {
+                        continue;
+                    }
+                    acceleration += point_mass_acceleration(position, &body.position, body.mass, settings);
+                }
+                continue;
+            }
+
+            // 自分を含むノードは近似せずに必ず開く
+            let distance = (node.center_of_mass - position).magnitude();
+            if !node.contains(position) && 2.0 * node.half_size < settings.theta * distance {
+                acceleration += point_mass_acceleration(position, &node.center_of_mass, node.total_mass, settings);
+            } else {
+                stack.extend(node.children());
+            }
+        }
+
+        acceleration
+    }
+
+    /// 格納されている全天体の重力加速度を並列に計算
+    pub fn accelerations(&self, settings: &GravitySettings) -> Vec<(BodyId, Vec3Fixed)> {
+        self.bodies
+            .par_iter()
+            .map(|body| (body.id, self.acceleration_at(&body.position, Some(body.id), settings)))
+            .collect()
+    }
+
+    /// 指定した位置の並びでの重力加速度を並列に計算（結果は入力と同じ順序）
+    pub fn accelerations_for(&self, ids: &[BodyId], positions: &[Vec3Fixed], settings: &GravitySettings) -> Vec<Vec3Fixed> {
+        ids.par_iter()
+            .zip(positions.par_iter())
+            .map(|(id, position)| self.acceleration_at(position, Some(*id), settings))
+            .collect()
+    }
+}
+
+/// 質点による重力加速度（軟化係数込み、軟化半径以内は無視）
+pub fn point_mass_acceleration(position: &Vec3Fixed, source: &Vec3Fixed, mass: f64, settings: &GravitySettings) -> Vec3Fixed {
+    let r = source - position;
+    let distance_sq = r.magnitude_squared();
+    let softening_sq = settings.softening_factor * settings.softening_factor;
+
+    if distance_sq < softening_sq {
+        return Vec3Fixed::zeros();
+    }
+
+    let distance = distance_sq.sqrt();
+    r * (settings.gravitational_constant * mass / ((distance_sq + softening_sq) * distance))
+}
+
+/// 点が属する八分空間の番号（ビット0: x, ビット1: y, ビット2: z）
+fn octant_of(center: &Vec3Fixed, position: &Vec3Fixed) -> usize {
+    (position.x >= center.x) as usize
+        | ((position.y >= center.y) as usize) << 1
+        | ((position.z >= center.z) as usize) << 2
+}
+
+/// 八分空間の中心
+fn octant_center(center: &Vec3Fixed, half_size: f64, octant: usize) -> Vec3Fixed {
+    let quarter = half_size * 0.5;
+    let sign = |bit: usize| if octant & bit == 0 { -quarter } else { quarter };
+    center + Vec3Fixed::new(sign(1), sign(2), sign(4))
+}
+
+/// 部分木を構築し、部分木内の相対インデックスで並んだノード列を返す
+///
+/// `bodies`はこのノードが担当する天体で、`offset`は`Octree::bodies`内での先頭位置。
+fn build_subtree(bodies: &mut [OctreeBody], offset: usize, center: Vec3Fixed, half_size: f64, depth: u32) -> Vec<OctreeNode> {
+    let total_mass: f64 = bodies.iter().map(|b| b.mass).sum();
+    let center_of_mass = if total_mass > 0.0 {
+        bodies.iter().fold(Vec3Fixed::zeros(), |acc, b| acc + b.position * b.mass) / total_mass
+    } else {
+        center
+    };
+
+    let mut node = OctreeNode {
+        center,
+        half_size,
+        center_of_mass,
+        total_mass,
+        children: [NO_CHILD; 8],
+        body_start: offset as u32,
+        body_count: bodies.len() as u32,
+        leaf: true,
+    };
+
+    if bodies.len() <= LEAF_CAPACITY || depth >= MAX_DEPTH {
+        return vec![node];
+    }
+    node.leaf = false;
+    node.body_count = 0;
+
+    // 八分空間ごとに連続するよう並べ替え、担当範囲を切り出す
+    let body_count = bodies.len();
+    bodies.sort_unstable_by_key(|b| octant_of(&center, &b.position));
+
+    let mut parts = Vec::with_capacity(8);
+    let mut rest = bodies;
+    let mut start = offset;
+    for octant in 0..8 {
+        let count = rest.iter().take_while(|b| octant_of(&center, &b.position) == octant).count();
+        let (head, tail) = std::mem::take(&mut rest).split_at_mut(count);
+        rest = tail;
+        if count > 0 {
+            parts.push((octant, start, head));
+        }
+        start += count;
+    }
+
+    let build_child = |(octant, start, part): (usize, usize, &mut [OctreeBody])| {
+        let child_center = octant_center(&center, half_size, octant);
+        (octant, build_subtree(part, start, child_center, half_size * 0.5, depth + 1))
+    };
+    let subtrees: Vec<_> = if body_count >= PARALLEL_BUILD_THRESHOLD {
+        parts.into_par_iter().map(build_child).collect()
+    } else {
+        parts.into_iter().map(build_child).collect()
+    };
+
+    // 部分木を連結し、子のインデックスを絶対位置に付け替える
+    let mut nodes = Vec::with_capacity(1 + subtrees.iter().map(|(_, s)| s.len()).sum::<usize>());
+    nodes.push(node);
+    for (octant, subtree) in subtrees {
+        let base = nodes.len() as u32;
+        nodes[0].children[octant] = base;
+        nodes.extend(subtree.into_iter().map(|mut child| {
+            for index in child.children.iter_mut().filter(|c| **c != NO_CHILD) {
+                *index += base;
+            }
+            child
+        }));
+    }
+
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const SETTINGS: GravitySettings = GravitySettings {
+        gravitational_constant: 6.67430e-11,
+        softening_factor: 1e-3,
+        theta: 0.5,
+    };
+
+    /// 再現可能な擬似乱数で天体を生成
+    fn generate_bodies(count: usize) -> Vec<OctreeBody> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        (0..count)
+            .map(|_| OctreeBody {
+                id: Uuid::new_v4(),
+                position: Vec3Fixed::new(next() * 1000.0 - 500.0, next() * 1000.0 - 500.0, next() * 1000.0 - 500.0),
+                mass: 1.0e6 + next() * 1.0e9,
+            })
+            .collect()
+    }
+
+    fn direct_acceleration(bodies: &[OctreeBody], target: &OctreeBody) -> Vec3Fixed {
+        bodies
+            .iter()
+            .filter(|b| b.id != target.id)
+            .fold(Vec3Fixed::zeros(), |acc, b| acc + point_mass_acceleration(&target.position, &b.position, b.mass, &SETTINGS))
+    }
+
+    /// 全ノードを走査してリーフに格納された天体数を数える
+    fn count_leaf_bodies(tree: &Octree) -> usize {
+        tree.nodes().iter().filter(|n| n.is_leaf()).map(|n| tree.leaf_bodies(n).len()).sum()
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = Octree::build(Vec::new());
+        assert!(tree.is_empty());
+        assert!(tree.root().is_none());
+        assert_eq!(tree.acceleration_at(&Vec3Fixed::zeros(), None, &SETTINGS), Vec3Fixed::zeros());
+    }
+
+    #[test]
+    fn test_subdivision_keeps_every_body() {
+        let bodies = generate_bodies(1000);
+        let expected_mass: f64 = bodies.iter().map(|b| b.mass).sum();
+
+        let tree = Octree::build(bodies);
+        let root = tree.root().unwrap();
+
+        assert_eq!(count_leaf_bodies(&tree), 1000);
+        assert!((root.total_mass - expected_mass).abs() / expected_mass < 1e-12);
+    }
+
+    #[test]
+    fn test_coincident_bodies_share_leaf() {
+        let position = Vec3Fixed::new(1.0, 2.0, 3.0);
+        let bodies: Vec<_> = (0..3)
+            .map(|_| OctreeBody { id: Uuid::new_v4(), position, mass: 10.0 })
+            .chain(std::iter::once(OctreeBody { id: Uuid::new_v4(), position: Vec3Fixed::new(-1.0, 0.0, 0.0), mass: 10.0 }))
+            .collect();
+
+        let tree = Octree::build(bodies);
+        assert_eq!(count_leaf_bodies(&tree), 4);
+        assert_eq!(tree.root().unwrap().total_mass, 40.0);
+    }
+
+    #[test]
+    fn test_small_theta_matches_direct_summation() {
+        let bodies = generate_bodies(300);
+        let tree = Octree::build(bodies.clone());
+        let settings = GravitySettings { theta: 0.1, ..SETTINGS };
+
+        for body in bodies.iter() {
+            let expected = direct_acceleration(&bodies, body);
+            let actual = tree.acceleration_at(&body.position, Some(body.id), &settings);
+            let error = (actual - expected).magnitude() / expected.magnitude();
+            assert!(error < 1e-3, "relative error {} too large", error);
+        }
+    }
+
+    #[test]
+    fn test_default_theta_accuracy() {
+        let bodies = generate_bodies(2000);
+        let tree = Octree::build(bodies.clone());
+
+        let accelerations = tree.accelerations(&SETTINGS);
+        assert_eq!(accelerations.len(), bodies.len());
+
+        let by_id: std::collections::HashMap<_, _> = accelerations.into_iter().collect();
+        let mut total_error = 0.0;
+        let mut max_error: f64 = 0.0;
+        for body in bodies.iter() {
+            let expected = direct_acceleration(&bodies, body);
+            let error = (by_id[&body.id] - expected).magnitude() / expected.magnitude();
+            total_error += error;
+            max_error = max_error.max(error);
+        }
+
+        let mean_error = total_error / bodies.len() as f64;
+        assert!(mean_error < 1e-2, "mean relative error {}", mean_error);
+        assert!(max_error < 5e-2, "max relative error {}", max_error);
+    }
+}
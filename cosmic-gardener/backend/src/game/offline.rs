@@ -0,0 +1,381 @@
+//! オフライン進行の計算
+//!
+//! 再接続時に、不在だった期間のリソース獲得と生命進化をサーバー側で再現する。
+
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug};
+
+use crate::errors::{GameError, Result};
+use crate::game::celestial_bodies::{BodyId, CelestialBodyManager};
+use crate::game::resources::{ResourceManager, ResourceType, Resources};
+
+/// オフライン効率カーブの1段階
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineEfficiencyTier {
+    /// この段階が適用される上限（時間）
+    pub up_to_hours: f64,
+    /// 生産効率（0.0-1.0）
+    pub efficiency: f64,
+}
+
+/// オフライン進行の設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineProgressConfig {
+    pub enabled: bool,
+    /// 計上するオフライン時間の上限（時間）
+    pub max_offline_hours: f64,
+    /// これより短い不在はオフライン扱いしない（秒）
+    pub min_offline_seconds: u64,
+    /// 効率カーブ（up_to_hoursの昇順）
+    pub efficiency_curve: Vec<OfflineEfficiencyTier>,
+    /// 生命シミュレーションの1ステップ（ミリ秒）
+    pub life_step_ms: u64,
+    /// 生命シミュレーションの最大ステップ数
+    pub max_life_steps: u32,
+}
+
+impl Default for OfflineProgressConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_offline_hours: 24.0,
+            min_offline_seconds: 60,
+            efficiency_curve: vec![
+                OfflineEfficiencyTier { up_to_hours: 2.0, efficiency: 1.0 },
+                OfflineEfficiencyTier { up_to_hours: 8.0, efficiency: 0.75 },
+                OfflineEfficiencyTier { up_to_hours: 24.0, efficiency: 0.5 },
+            ],
+            life_step_ms: 60_000, // 1分
+            max_life_steps: 1440,
+        }
+    }
+}
+
+impl OfflineProgressConfig {
+    /// 設定の検証
+    pub fn validate(&self) -> Result<()> {
+        if self.max_offline_hours < 0.0 {
+            return Err(GameError::validation("max_offline_hours must not be negative"));
+        }
+
+        if self.life_step_ms == 0 || self.max_life_steps == 0 {
+            return Err(GameError::validation("Life simulation step must be positive"));
+        }
+
+        let mut previous_bound = 0.0;
+        for tier in &self.efficiency_curve {
+            if tier.up_to_hours <= previous_bound {
+                return Err(GameError::validation("Efficiency curve must be strictly ascending"));
+            }
+            if !(0.0..=1.0).contains(&tier.efficiency) {
+                return Err(GameError::validation("Efficiency must be between 0.0 and 1.0"));
+            }
+            previous_bound = tier.up_to_hours;
+        }
+
+        Ok(())
+    }
+
+    /// 上限適用後のオフライン時間（ミリ秒）
+    pub fn credited_duration_ms(&self, offline_ms: u64) -> u64 {
+        let cap_ms = (self.max_offline_hours * 3_600_000.0) as u64;
+        offline_ms.min(cap_ms)
+    }
+
+    /// 効率カーブを適用したゲーム内時間（ミリ秒）
+    pub fn effective_duration_ms(&self, credited_ms: u64) -> u64 {
+        let credited_hours = credited_ms as f64 / 3_600_000.0;
+        let mut effective_hours = 0.0;
+        let mut previous_bound = 0.0;
+        let mut last_efficiency = 1.0;
+
+        for tier in &self.efficiency_curve {
+            if credited_hours <= previous_bound {
+                break;
+            }
+            let segment = credited_hours.min(tier.up_to_hours) - previous_bound;
+            effective_hours += segment * tier.efficiency;
+            previous_bound = tier.up_to_hours;
+            last_efficiency = tier.efficiency;
+        }
+
+        // カーブの範囲外は最後の段階の効率を使用
+        if credited_hours > previous_bound {
+            effective_hours += (credited_hours - previous_bound) * last_efficiency;
+        }
+
+        (effective_hours * 3_600_000.0) as u64
+    }
+}
+
+/// オフライン中の生命段階の変化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineLifeChange {
+    pub body_id: BodyId,
+    pub from_stage: String,
+    pub to_stage: String,
+    pub population_before: u64,
+    pub population_after: u64,
+}
+
+/// クライアントに表示するオフラインレポート
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineReport {
+    pub offline_since: DateTime<Utc>,
+    pub returned_at: DateTime<Utc>,
+    /// 実際の不在時間
+    pub offline_ms: u64,
+    /// 上限適用後の時間
+    pub credited_ms: u64,
+    /// 効率適用後のゲーム内時間
+    pub effective_ms: u64,
+    pub capped: bool,
+    pub average_efficiency: f64,
+    pub resources_gained: Resources,
+    pub life_changes: Vec<OfflineLifeChange>,
+}
+
+/// オフライン進行計算器
+#[derive(Debug, Clone)]
+pub struct OfflineProgressCalculator {
+    config: OfflineProgressConfig,
+}
+
+impl OfflineProgressCalculator {
+    pub fn new(config: OfflineProgressConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &OfflineProgressConfig {
+        &self.config
+    }
+
+    /// オフライン進行を適用する
+    ///
+    /// `GameState::last_update`から`now`までの期間を再現し、レポートを返す。
+    /// 無効化されている場合や不在時間が短い場合は`None`。
+    pub fn apply(
+        &self,
+        resource_manager: &mut ResourceManager,
+        celestial_manager: &mut CelestialBodyManager,
+        now: DateTime<Utc>,
+    ) -> Option<OfflineReport> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let offline_since = resource_manager.get_game_state().last_update;
+        let offline_ms = now.signed_duration_since(offline_since).num_milliseconds().max(0) as u64;
+
+        if offline_ms < self.config.min_offline_seconds * 1000 {
+            return None;
+        }
+
+        let credited_ms = self.config.credited_duration_ms(offline_ms);
+        let effective_ms = self.config.effective_duration_ms(credited_ms);
+
+        // 進化前の状態を記録
+        let stages_before: HashMap<BodyId, (String, u64)> = celestial_manager
+            .get_all_bodies()
+            .iter()
+            .map(|(id, body)| (*id, (body.lifecycle.life_stage.name().to_string(), body.lifecycle.population)))
+            .collect();
+
+        let resources_gained = self.simulate(resource_manager, celestial_manager, effective_ms);
+
+        let mut life_changes: Vec<OfflineLifeChange> = celestial_manager
+            .get_all_bodies()
+            .iter()
+            .filter_map(|(id, body)| {
+                let (from_stage, population_before) = stages_before.get(id)?.clone();
+                let to_stage = body.lifecycle.life_stage.name();
+                if from_stage == to_stage && population_before == body.lifecycle.population {
+                    return None;
+                }
+                Some(OfflineLifeChange {
+                    body_id: *id,
+                    from_stage,
+                    to_stage: to_stage.to_string(),
+                    population_before,
+                    population_after: body.lifecycle.population,
+                })
+            })
+            .collect();
+        life_changes.sort_by_key(|change| change.body_id);
+
+        resource_manager.get_game_state_mut().last_update = now;
+
+        let average_efficiency = if credited_ms > 0 {
+            effective_ms as f64 / credited_ms as f64
+        } else {
+            0.0
+        };
+
+        info!("[OFFLINE] Applied offline progress: offline={}ms, credited={}ms, effective={}ms, gained={:?}",
+            offline_ms, credited_ms, effective_ms, resources_gained);
+
+        Some(OfflineReport {
+            offline_since,
+            returned_at: now,
+            offline_ms,
+            credited_ms,
+            effective_ms,
+            capped: credited_ms < offline_ms,
+            average_efficiency,
+            resources_gained,
+            life_changes,
+        })
+    }
+
+    /// 効率適用後の時間をステップ実行する
+    fn simulate(
+        &self,
+        resource_manager: &mut ResourceManager,
+        celestial_manager: &mut CelestialBodyManager,
+        effective_ms: u64,
+    ) -> Resources {
+        let mut gained = Resources::new();
+        if effective_ms == 0 {
+            return gained;
+        }
+
+        // ステップ数が上限を超える場合はステップ幅を広げる
+        let steps = effective_ms.div_ceil(self.config.life_step_ms)
+            .min(self.config.max_life_steps as u64)
+            .max(1);
+        let step_ms = effective_ms / steps;
+        let last_step_ms = effective_ms - step_ms * (steps - 1);
+
+        debug!("[OFFLINE] Simulating {} steps of {}ms", steps, step_ms);
+
+        for step in 0..steps {
+            let delta_ms = if step == steps - 1 { last_step_ms } else { step_ms };
+
+            // プレイヤーの生産レートに全天体の生産レートを合算
+            resource_manager.set_body_production(celestial_manager.total_production());
+            let rates = resource_manager.get_game_state().production_rates.clone();
+
+            let step_gained = resource_manager.accumulate_with_rates(&rates, delta_ms);
+            for resource_type in ResourceType::all() {
+                gained.add(resource_type, step_gained.get(resource_type));
+            }
+
+            celestial_manager.update_life_systems(delta_ms);
+            
+            // 不在中の超新星の塵も獲得量に含める
+            for transition in celestial_manager.update_stellar_evolution(delta_ms) {
+                if let Some(supernova) = transition.supernova {
+                    resource_manager.grant(ResourceType::CosmicDust, supernova.dust_released);
+                    gained.add(ResourceType::CosmicDust, supernova.dust_released);
+                }
+            }
+        }
+
+        gained
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::celestial_bodies::{CelestialType, PlanetData, PlanetType, AtmosphereType, SpectralType, StarData, Vec3Fixed};
+    use crate::game::habitability::HabitabilitySettings;
+    use crate::game::resources::{fixed, UpgradeType};
+
+    #[test]
+    fn test_effective_duration_curve() {
+        let config = OfflineProgressConfig::default();
+
+        // 最初の2時間は100%
+        assert_eq!(config.effective_duration_ms(3_600_000), 3_600_000);
+
+        // 4時間: 2h * 1.0 + 2h * 0.75 = 3.5h
+        assert_eq!(config.effective_duration_ms(4 * 3_600_000), 12_600_000);
+    }
+
+    #[test]
+    fn test_offline_cap() {
+        let config = OfflineProgressConfig::default();
+        let two_days = 48 * 3_600_000;
+        assert_eq!(config.credited_duration_ms(two_days), 24 * 3_600_000);
+    }
+
+    #[test]
+    fn test_invalid_curve_rejected() {
+        let mut config = OfflineProgressConfig::default();
+        config.efficiency_curve.swap(0, 1);
+        assert!(config.validate().is_err());
+
+        assert!(OfflineProgressConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_short_absence_ignored() {
+        let calculator = OfflineProgressCalculator::new(OfflineProgressConfig::default());
+        let mut resource_manager = ResourceManager::new(50);
+        let mut celestial_manager = CelestialBodyManager::new(50);
+
+        let now = resource_manager.get_game_state().last_update + chrono::Duration::seconds(10);
+        assert!(calculator.apply(&mut resource_manager, &mut celestial_manager, now).is_none());
+    }
+
+    #[test]
+    fn test_offline_resources_and_life() {
+        let calculator = OfflineProgressCalculator::new(OfflineProgressConfig::default());
+        let mut resource_manager = ResourceManager::new(50);
+        let mut celestial_manager = CelestialBodyManager::new(50);
+
+        // ダストはアップグレードしたカタログの基本生産と小惑星の生産の合計
+        resource_manager.get_game_state_mut().upgrade_levels.set_level(UpgradeType::DustProduction, 2);
+
+        let mut resources = Resources { cosmic_dust: 20_000, ..Default::default() };
+        let sun = CelestialType::Star(StarData {
+            spectral_type: SpectralType::G,
+            temperature: 5800,
+            luminosity: 0,
+            age: 0,
+            lifespan: 0,
+            phase: Default::default(),
+        });
+        celestial_manager.create_body(sun, Vec3Fixed::zeros(), &mut resources).unwrap();
+        let planet = PlanetData {
+            planet_type: PlanetType::Rocky,
+            atmosphere: AtmosphereType::Oxygen,
+            water_coverage: 70,
+            temperature_range: (15, 25),
+            habitability: 80,
+        };
+        let planet_id = celestial_manager
+            .create_body(CelestialType::Planet(planet), Vec3Fixed::new(HabitabilitySettings::default().astronomical_unit, 0.0, 0.0), &mut resources)
+            .unwrap();
+        let asteroid_id = celestial_manager
+            .create_body(CelestialType::Asteroid, Vec3Fixed::new(-HabitabilitySettings::default().astronomical_unit, 0.0, 0.0), &mut resources)
+            .unwrap();
+
+        let catalog_dust = resource_manager.upgrade_catalog().current()
+            .production_rates(&resource_manager.get_game_state().upgrade_levels)
+            .dust_per_tick;
+        let asteroid_dust = celestial_manager.get_body(asteroid_id).unwrap().resources.effective_rates().dust_per_tick;
+        assert_eq!(fixed::to_f64(catalog_dust), 2.0);
+        assert_eq!(fixed::to_f64(asteroid_dust), 0.5);
+        let dust_per_tick = fixed::to_f64(catalog_dust + asteroid_dust);
+
+        // 1時間の不在
+        let now = resource_manager.get_game_state().last_update + chrono::Duration::hours(1);
+        let report = calculator.apply(&mut resource_manager, &mut celestial_manager, now).unwrap();
+
+        assert!(!report.capped);
+        assert_eq!(report.effective_ms, 3_600_000);
+        // 1時間 = 72000ティック、(2.0 + 0.5) * 72000 = 180000
+        let expected_dust = (dust_per_tick * 72_000.0) as u64;
+        assert_eq!(expected_dust, 180_000);
+        assert_eq!(report.resources_gained.cosmic_dust, expected_dust);
+        assert_eq!(resource_manager.get_resources().cosmic_dust, expected_dust);
+        assert_eq!(resource_manager.get_game_state().last_update, now);
+
+        // 生命が誕生している
+        assert!(report.life_changes.iter().any(|change| change.body_id == planet_id && change.from_stage == "None"));
+    }
+}
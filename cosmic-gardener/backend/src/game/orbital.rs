@@ -0,0 +1,286 @@
+//! 軌道力学のヘルパー
+//!
+//! ケプラー軌道要素と状態ベクトル（親天体に対する相対位置・相対速度）の相互変換を行う。
+//! 新しい天体を親天体の周回軌道に投入する際の初速度の計算に使う。
+
+use std::f64::consts::TAU;
+use nalgebra::{Rotation3, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{GameError, Result};
+use crate::game::celestial_bodies::Vec3Fixed;
+
+/// 円軌道・赤道軌道とみなす閾値
+const ORBIT_EPSILON: f64 = 1e-10;
+/// ケプラー方程式の反復回数の上限
+const MAX_KEPLER_ITERATIONS: usize = 50;
+
+/// ケプラー軌道要素（角度はすべてラジアン）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OrbitalElements {
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    #[serde(default)]
+    pub longitude_of_ascending_node: f64,
+    #[serde(default)]
+    pub argument_of_periapsis: f64,
+    pub true_anomaly: f64,
+    /// 中心質量（二体問題では親天体と周回天体の質量の和）
+    pub central_mass: f64,
+}
+
+impl OrbitalElements {
+    /// 軌道長半径・離心率・軌道傾斜角から軌道要素を作成（近点から出発する）
+    pub fn new(semi_major_axis: f64, eccentricity: f64, inclination: f64, central_mass: f64) -> Self {
+        Self {
+            semi_major_axis,
+            eccentricity,
+            inclination,
+            longitude_of_ascending_node: 0.0,
+            argument_of_periapsis: 0.0,
+            true_anomaly: 0.0,
+            central_mass,
+        }
+    }
+
+    /// 楕円軌道として有効か検証
+    pub fn validate(&self) -> Result<()> {
+        if !self.semi_major_axis.is_finite() || self.semi_major_axis <= 0.0 {
+            return Err(GameError::validation("semi_major_axis must be positive"));
+        }
+        if !(0.0..1.0).contains(&self.eccentricity) {
+            return Err(GameError::validation("eccentricity must be in [0, 1)"));
+        }
+        if !self.central_mass.is_finite() || self.central_mass <= 0.0 {
+            return Err(GameError::validation("central_mass must be positive"));
+        }
+        Ok(())
+    }
+
+    /// 近点距離
+    pub fn periapsis(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity)
+    }
+
+    /// 遠点距離
+    pub fn apoapsis(&self) -> f64 {
+        self.semi_major_axis * (1.0 + self.eccentricity)
+    }
+
+    /// 公転周期
+    pub fn period(&self, gravitational_constant: f64) -> f64 {
+        TAU * (self.semi_major_axis.powi(3) / (gravitational_constant * self.central_mass)).sqrt()
+    }
+
+    /// 軌道要素から親天体に対する相対位置・相対速度を計算
+    pub fn to_state_vector(&self, gravitational_constant: f64) -> Result<(Vec3Fixed, Vec3Fixed)> {
+        self.validate()?;
+
+        let mu = gravitational_constant * self.central_mass;
+        let e = self.eccentricity;
+        let nu = self.true_anomaly;
+        let semi_latus_rectum = self.semi_major_axis * (1.0 - e * e);
+        let radius = semi_latus_rectum / (1.0 + e * nu.cos());
+
+        // 近点方向をx軸とする軌道面座標系
+        let position = Vector3::new(radius * nu.cos(), radius * nu.sin(), 0.0);
+        let velocity = Vector3::new(-nu.sin(), e + nu.cos(), 0.0) * (mu / semi_latus_rectum).sqrt();
+
+        let rotation = self.orbital_plane_rotation();
+        Ok((rotation * position, rotation * velocity))
+    }
+
+    /// 親天体に対する相対位置・相対速度から軌道要素を計算
+    pub fn from_state_vector(
+        position: &Vec3Fixed,
+        velocity: &Vec3Fixed,
+        central_mass: f64,
+        gravitational_constant: f64,
+    ) -> Result<Self> {
+        let mu = gravitational_constant * central_mass;
+        let radius = position.magnitude();
+        if !mu.is_finite() || mu <= 0.0 || radius <= 0.0 {
+            return Err(GameError::validation("orbit requires a positive central mass and separation"));
+        }
+
+        let speed_sq = velocity.magnitude_squared();
+        let energy = speed_sq / 2.0 - mu / radius;
+        if energy >= 0.0 {
+            return Err(GameError::validation("body is not gravitationally bound"));
+        }
+
+        let angular_momentum = position.cross(velocity);
+        let h = angular_momentum.magnitude();
+        if h <= 0.0 {
+            return Err(GameError::validation("radial trajectory has no orbital plane"));
+        }
+
+        let node = Vector3::z().cross(&angular_momentum);
+        let n = node.magnitude();
+        let eccentricity_vector = (position * (speed_sq - mu / radius) - velocity * position.dot(velocity)) / mu;
+        let e = eccentricity_vector.magnitude();
+
+        let inclination = (angular_momentum.z / h).clamp(-1.0, 1.0).acos();
+        let equatorial = n < ORBIT_EPSILON * h;
+        let circular = e < ORBIT_EPSILON;
+
+        let longitude_of_ascending_node = if equatorial {
+            0.0
+        } else {
+            normalize_angle(node.y.atan2(node.x))
+        };
+
+        // 基準方向（昇交点、赤道軌道ではx軸）からの角度
+        let reference = if equatorial { Vector3::x() } else { node / n };
+        let angle_from_reference = |v: &Vec3Fixed| {
+            let cos = reference.dot(v) / v.magnitude();
+            let sin = angular_momentum.dot(&reference.cross(v)) / (h * v.magnitude());
+            normalize_angle(sin.atan2(cos))
+        };
+
+        let (argument_of_periapsis, true_anomaly) = if circular {
+            (0.0, angle_from_reference(position))
+        } else {
+            let omega = angle_from_reference(&eccentricity_vector);
+            let argument_of_latitude = angle_from_reference(position);
+            (omega, normalize_angle(argument_of_latitude - omega))
+        };
+
+        Ok(Self {
+            semi_major_axis: -mu / (2.0 * energy),
+            eccentricity: e,
+            inclination,
+            longitude_of_ascending_node,
+            argument_of_periapsis,
+            true_anomaly,
+            central_mass,
+        })
+    }
+
+    /// `dt`秒後の軌道要素（ケプラー方程式による解析的な伝播）
+    pub fn propagate(&self, dt: f64, gravitational_constant: f64) -> Self {
+        let e = self.eccentricity;
+        let mean_motion = (gravitational_constant * self.central_mass / self.semi_major_axis.powi(3)).sqrt();
+
+        let eccentric_anomaly = 2.0 * ((1.0 - e).sqrt() * (self.true_anomaly / 2.0).sin())
+            .atan2((1.0 + e).sqrt() * (self.true_anomaly / 2.0).cos());
+        let mean_anomaly = normalize_angle(eccentric_anomaly - e * eccentric_anomaly.sin() + mean_motion * dt);
+
+        let eccentric_anomaly = solve_kepler(mean_anomaly, e);
+        let true_anomaly = 2.0 * ((1.0 + e).sqrt() * (eccentric_anomaly / 2.0).sin())
+            .atan2((1.0 - e).sqrt() * (eccentric_anomaly / 2.0).cos());
+
+        Self {
+            true_anomaly: normalize_angle(true_anomaly),
+            ..*self
+        }
+    }
+
+    /// 軌道面座標系から基準座標系への回転（Rz(Ω)・Rx(i)・Rz(ω)）
+    fn orbital_plane_rotation(&self) -> Rotation3<f64> {
+        Rotation3::from_axis_angle(&Vector3::z_axis(), self.longitude_of_ascending_node)
+            * Rotation3::from_axis_angle(&Vector3::x_axis(), self.inclination)
+            * Rotation3::from_axis_angle(&Vector3::z_axis(), self.argument_of_periapsis)
+    }
+}
+
+/// ケプラー方程式 M = E - e sin E をニュートン法で解く
+fn solve_kepler(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    let mut eccentric_anomaly = if eccentricity < 0.8 { mean_anomaly } else { std::f64::consts::PI };
+    for _ in 0..MAX_KEPLER_ITERATIONS {
+        let delta = (eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly)
+            / (1.0 - eccentricity * eccentric_anomaly.cos());
+        eccentric_anomaly -= delta;
+        if delta.abs() < 1e-14 {
+            break;
+        }
+    }
+    eccentric_anomaly
+}
+
+/// 角度を [0, 2π) に正規化
+fn normalize_angle(angle: f64) -> f64 {
+    let normalized = angle.rem_euclid(TAU);
+    if normalized >= TAU { 0.0 } else { normalized }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const G: f64 = crate::game::physics::GRAVITATIONAL_CONSTANT;
+    const CENTRAL_MASS: f64 = 1.0e9;
+
+    fn assert_vectors_close(a: &Vec3Fixed, b: &Vec3Fixed, tolerance: f64) {
+        assert!((a - b).magnitude() <= tolerance * b.magnitude().max(1e-12), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_circular_orbit_speed() {
+        let orbit = OrbitalElements::new(10.0, 0.0, 0.0, CENTRAL_MASS);
+        let (position, velocity) = orbit.to_state_vector(G).unwrap();
+
+        assert!((position.magnitude() - 10.0).abs() < 1e-12);
+        assert!((velocity.magnitude() - (G * CENTRAL_MASS / 10.0).sqrt()).abs() < 1e-12);
+        assert!(position.dot(&velocity).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_state_vector_round_trip() {
+        let orbit = OrbitalElements {
+            semi_major_axis: 25.0,
+            eccentricity: 0.3,
+            inclination: 0.4,
+            longitude_of_ascending_node: 1.1,
+            argument_of_periapsis: 0.7,
+            true_anomaly: 2.0,
+            central_mass: CENTRAL_MASS,
+        };
+
+        let (position, velocity) = orbit.to_state_vector(G).unwrap();
+        let recovered = OrbitalElements::from_state_vector(&position, &velocity, CENTRAL_MASS, G).unwrap();
+
+        assert!((recovered.semi_major_axis - orbit.semi_major_axis).abs() < 1e-9);
+        assert!((recovered.eccentricity - orbit.eccentricity).abs() < 1e-9);
+        assert!((recovered.inclination - orbit.inclination).abs() < 1e-9);
+        assert!((recovered.longitude_of_ascending_node - orbit.longitude_of_ascending_node).abs() < 1e-9);
+        assert!((recovered.argument_of_periapsis - orbit.argument_of_periapsis).abs() < 1e-9);
+        assert!((recovered.true_anomaly - orbit.true_anomaly).abs() < 1e-9);
+
+        let (position_again, velocity_again) = recovered.to_state_vector(G).unwrap();
+        assert_vectors_close(&position_again, &position, 1e-9);
+        assert_vectors_close(&velocity_again, &velocity, 1e-9);
+    }
+
+    #[test]
+    fn test_circular_equatorial_round_trip() {
+        let orbit = OrbitalElements { true_anomaly: 1.3, ..OrbitalElements::new(12.0, 0.0, 0.0, CENTRAL_MASS) };
+        let (position, velocity) = orbit.to_state_vector(G).unwrap();
+        let recovered = OrbitalElements::from_state_vector(&position, &velocity, CENTRAL_MASS, G).unwrap();
+
+        let (position_again, velocity_again) = recovered.to_state_vector(G).unwrap();
+        assert_vectors_close(&position_again, &position, 1e-9);
+        assert_vectors_close(&velocity_again, &velocity, 1e-9);
+    }
+
+    #[test]
+    fn test_unbound_and_invalid_orbits_rejected() {
+        let position = Vec3Fixed::new(10.0, 0.0, 0.0);
+        let escape = Vec3Fixed::new(0.0, (2.0 * G * CENTRAL_MASS / 10.0).sqrt() * 1.01, 0.0);
+        assert!(OrbitalElements::from_state_vector(&position, &escape, CENTRAL_MASS, G).is_err());
+
+        assert!(OrbitalElements::new(10.0, 1.0, 0.0, CENTRAL_MASS).to_state_vector(G).is_err());
+        assert!(OrbitalElements::new(-1.0, 0.1, 0.0, CENTRAL_MASS).to_state_vector(G).is_err());
+    }
+
+    #[test]
+    fn test_propagate_full_period_returns_to_start() {
+        let orbit = OrbitalElements { true_anomaly: 0.5, ..OrbitalElements::new(20.0, 0.6, 0.2, CENTRAL_MASS) };
+        let propagated = orbit.propagate(orbit.period(G), G);
+        assert!((propagated.true_anomaly - orbit.true_anomaly).abs() < 1e-9);
+
+        let half = orbit.propagate(orbit.period(G) / 2.0, G);
+        assert!((half.true_anomaly - orbit.true_anomaly).abs() > 1.0);
+    }
+}
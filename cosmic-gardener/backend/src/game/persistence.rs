@@ -12,7 +12,11 @@ use tracing::{info, warn, error, debug};
 use crate::errors::{GameError, Result};
 use crate::game::resources::{Resources, ProductionRates, ResourceAccumulators, UpgradeLevels};
 use crate::game::celestial_bodies::{CelestialBody, BodyId};
+use crate::game::determinism::sorted_body_ids;
 use crate::game::physics::PhysicsState;
+use crate::game::prestige::{PrestigeRecord, PrestigeState};
+use crate::game::research::ResearchProgress;
+use crate::game::achievements::{AchievementId, AchievementProgress, AchievementUnlock};
 
 /// ゲームセーブのバージョン
 pub const SAVE_VERSION: u32 = 1;
@@ -47,6 +51,17 @@ pub struct GameStateSnapshot {
     pub upgrade_levels: UpgradeLevels,
     pub bodies: HashMap<BodyId, CelestialBody>,
     pub physics_state: PhysicsState,
+    #[serde(default)]
+    pub lifetime_totals: Resources,
+    #[serde(default)]
+    pub prestige: PrestigeState,
+    #[serde(default)]
+    pub research: ResearchProgress,
+    #[serde(default)]
+    pub achievements: AchievementProgress,
+    /// 決定論モードの乱数の状態（`None`の場合は通常モード）
+    #[serde(default)]
+    pub rng_state: Option<u64>,
     pub checksum: u64,
 }
 
@@ -72,6 +87,11 @@ impl GameStateSnapshot {
             upgrade_levels,
             bodies,
             physics_state,
+            lifetime_totals: Resources::new(),
+            prestige: PrestigeState::default(),
+            research: ResearchProgress::default(),
+            achievements: AchievementProgress::default(),
+            rng_state: None,
             checksum: 0,
         };
         
@@ -81,7 +101,10 @@ impl GameStateSnapshot {
     }
     
     /// チェックサムの計算
-    fn calculate_checksum(&self) -> u64 {
+    ///
+    /// 天体はID順に、浮動小数点数はビット列としてハッシュするため、
+    /// 同じ状態であれば`HashMap`の反復順に関係なく同じ値になる。
+    pub fn calculate_checksum(&self) -> u64 {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
         
@@ -96,18 +119,62 @@ impl GameStateSnapshot {
         self.resources.dark_matter.hash(&mut hasher);
         self.resources.thought_points.hash(&mut hasher);
         
+        // プレステージ状態のハッシュ（クライアントからの改ざん防止）
+        for resource_type in crate::game::resources::ResourceType::all() {
+            self.lifetime_totals.get(resource_type).hash(&mut hasher);
+        }
+        self.prestige.prestige_count.hash(&mut hasher);
+        self.prestige.total_points.hash(&mut hasher);
+        self.research.unlocked.hash(&mut hasher);
+        // 解除時刻は実時間なので、解除済みの実績IDだけを含める
+        for achievement_id in self.achievements.unlocked.keys() {
+            achievement_id.hash(&mut hasher);
+        }
+        self.rng_state.hash(&mut hasher);
+        
         // 天体のハッシュ
-        for (id, body) in &self.bodies {
+        for id in sorted_body_ids(&self.bodies) {
+            let body = &self.bodies[&id];
             id.hash(&mut hasher);
             body.physics.mass.hash(&mut hasher);
-            body.physics.position.x.hash(&mut hasher);
-            body.physics.position.y.hash(&mut hasher);
-            body.physics.position.z.hash(&mut hasher);
+            for axis in 0..3 {
+                body.physics.position[axis].to_bits().hash(&mut hasher);
+                body.physics.velocity[axis].to_bits().hash(&mut hasher);
+            }
         }
         
         hasher.finish()
     }
     
+    /// 累計リソースとプレステージ状態の設定
+    pub fn with_progression(mut self, lifetime_totals: Resources, prestige: PrestigeState) -> Self {
+        self.lifetime_totals = lifetime_totals;
+        self.prestige = prestige;
+        self.checksum = self.calculate_checksum();
+        self
+    }
+    
+    /// 研究進捗の設定
+    pub fn with_research(mut self, research: ResearchProgress) -> Self {
+        self.research = research;
+        self.checksum = self.calculate_checksum();
+        self
+    }
+    
+    /// 実績進行状況の設定
+    pub fn with_achievements(mut self, achievements: AchievementProgress) -> Self {
+        self.achievements = achievements;
+        self.checksum = self.calculate_checksum();
+        self
+    }
+    
+    /// 決定論モードの乱数の状態の設定
+    pub fn with_rng_state(mut self, rng_state: Option<u64>) -> Self {
+        self.rng_state = rng_state;
+        self.checksum = self.calculate_checksum();
+        self
+    }
+    
     /// チェックサムの検証
     pub fn verify_checksum(&self) -> bool {
         let calculated = self.calculate_checksum();
@@ -339,6 +406,59 @@ impl PersistenceManager {
             self.config.serialization_format,
         )?;
         
+        Self::insert_snapshot(&self.db_pool, &snapshot, compressed).await?;
+        
+        // キャッシュの更新
+        self.last_snapshots.insert(snapshot.player_id, (snapshot.tick, snapshot));
+        
+        Ok(())
+    }
+    
+    /// 差分の保存
+    pub async fn save_delta(&self, delta: GameStateDelta) -> Result<()> {
+        let compressed = CompressedData::compress(
+            &delta,
+            self.config.compression_type,
+            self.config.serialization_format,
+        )?;
+        
+        let query = r#"
+            INSERT INTO game_deltas (
+                player_id, from_tick, to_tick, timestamp,
+                data, compression_type, serialization_format,
+                original_size, compressed_size
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#;
+        
+        sqlx::query(query)
+            .bind(delta.player_id)
+            .bind(delta.from_tick as i64)
+            .bind(delta.to_tick as i64)
+            .bind(delta.timestamp)
+            .bind(compressed.data)
+            .bind(serde_json::to_string(&compressed.compression_type)?)
+            .bind(serde_json::to_string(&compressed.serialization_format)?)
+            .bind(compressed.original_size as i32)
+            .bind(compressed.compressed_size as i32)
+            .execute(&self.db_pool)
+            .await
+            .map_err(|e| {
+                error!("[PERSISTENCE] Database error: {}", e);
+                GameError::Database(e)
+            })?;
+        
+        Ok(())
+    }
+    
+    /// スナップショットの挿入（プールまたはトランザクション上で実行）
+    async fn insert_snapshot<'e, E>(
+        executor: E,
+        snapshot: &GameStateSnapshot,
+        compressed: CompressedData,
+    ) -> Result<()>
+    where
+        E: sqlx::postgres::PgExecutor<'e>,
+    {
         let query = r#"
             INSERT INTO game_snapshots (
                 player_id, tick, version, timestamp, 
@@ -363,55 +483,225 @@ impl PersistenceManager {
             .bind(compressed.original_size as i32)
             .bind(compressed.compressed_size as i32)
             .bind(snapshot.checksum as i64)
-            .execute(&self.db_pool)
+            .execute(executor)
             .await
             .map_err(|e| {
                 error!("[PERSISTENCE] Database error: {}", e);
                 GameError::Database(e)
             })?;
         
-        // キャッシュの更新
-        self.last_snapshots.insert(snapshot.player_id, (snapshot.tick, snapshot));
-        
         Ok(())
     }
     
-    /// 差分の保存
-    pub async fn save_delta(&self, delta: GameStateDelta) -> Result<()> {
+    /// プレステージの記録
+    ///
+    /// 履歴の追加、永続状態の更新、天体・アップグレードの削除、リセット後スナップショットの保存を
+    /// 単一トランザクションで行う。
+    pub async fn record_prestige(
+        &mut self,
+        player_id: Uuid,
+        record: &PrestigeRecord,
+        snapshot: GameStateSnapshot,
+    ) -> Result<()> {
+        if !snapshot.verify_checksum() {
+            error!("[PERSISTENCE] Checksum mismatch for prestige snapshot");
+            return Err(GameError::validation("Checksum mismatch"));
+        }
+        
         let compressed = CompressedData::compress(
-            &delta,
+            &snapshot,
             self.config.compression_type,
             self.config.serialization_format,
         )?;
         
-        let query = r#"
-            INSERT INTO game_deltas (
-                player_id, from_tick, to_tick, timestamp,
-                data, compression_type, serialization_format,
-                original_size, compressed_size
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-        "#;
+        let db_error = |e: sqlx::Error| {
+            error!("[PERSISTENCE] Database error: {}", e);
+            GameError::Database(e)
+        };
         
-        sqlx::query(query)
-            .bind(delta.player_id)
-            .bind(delta.from_tick as i64)
-            .bind(delta.to_tick as i64)
-            .bind(delta.timestamp)
-            .bind(compressed.data)
-            .bind(serde_json::to_string(&compressed.compression_type)?)
-            .bind(serde_json::to_string(&compressed.serialization_format)?)
-            .bind(compressed.original_size as i32)
-            .bind(compressed.compressed_size as i32)
-            .execute(&self.db_pool)
+        let mut tx = self.db_pool.begin().await.map_err(db_error)?;
+        
+        // 永続状態の更新（直前のプレステージ回数が一致する場合のみ）
+        let updated = sqlx::query(r#"
+            INSERT INTO player_prestige (
+                player_id, prestige_count, total_points, production_multiplier, last_prestige_at
+            ) VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (player_id) DO UPDATE SET
+                prestige_count = EXCLUDED.prestige_count,
+                total_points = EXCLUDED.total_points,
+                production_multiplier = EXCLUDED.production_multiplier,
+                last_prestige_at = EXCLUDED.last_prestige_at,
+                updated_at = NOW()
+            WHERE player_prestige.prestige_count = EXCLUDED.prestige_count - 1
+        "#)
+            .bind(player_id)
+            .bind(record.prestige_number as i32)
+            .bind(record.total_points as i64)
+            .bind(record.production_multiplier)
+            .bind(record.performed_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        
+        if updated.rows_affected() == 0 {
+            warn!("[PERSISTENCE] Prestige state out of sync: player={}, prestige_number={}", player_id, record.prestige_number);
+            return Err(GameError::conflict("Prestige state is out of sync"));
+        }
+        
+        sqlx::query(r#"
+            INSERT INTO prestige_history (
+                player_id, prestige_number, points_awarded, total_points,
+                production_multiplier, lifetime_totals, resources_at_reset,
+                bodies_at_reset, server_tick, performed_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        "#)
+            .bind(player_id)
+            .bind(record.prestige_number as i32)
+            .bind(record.points_awarded as i64)
+            .bind(record.total_points as i64)
+            .bind(record.production_multiplier)
+            .bind(serde_json::to_value(&record.lifetime_totals)?)
+            .bind(serde_json::to_value(&record.resources_at_reset)?)
+            .bind(record.bodies_at_reset as i32)
+            .bind(snapshot.tick as i64)
+            .bind(record.performed_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        
+        // 周回データの削除
+        sqlx::query("DELETE FROM celestial_bodies WHERE player_id = $1")
+            .bind(player_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        
+        sqlx::query("DELETE FROM player_upgrades WHERE player_id = $1")
+            .bind(player_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        
+        Self::insert_snapshot(&mut *tx, &snapshot, compressed).await?;
+        
+        tx.commit().await.map_err(db_error)?;
+        
+        info!("[PERSISTENCE] Prestige recorded: player={}, prestige_number={}", player_id, record.prestige_number);
+        
+        // キャッシュの更新
+        self.last_snapshots.insert(player_id, (snapshot.tick, snapshot));
+        
+        Ok(())
+    }
+    
+    /// 永続プレステージ状態の読み込み（サーバー側の正本）
+    pub async fn load_prestige_state(&self, player_id: Uuid) -> Result<Option<PrestigeState>> {
+        let row = sqlx::query(r#"
+            SELECT prestige_count, total_points, production_multiplier, last_prestige_at
+            FROM player_prestige
+            WHERE player_id = $1
+        "#)
+            .bind(player_id)
+            .fetch_optional(&self.db_pool)
             .await
             .map_err(|e| {
                 error!("[PERSISTENCE] Database error: {}", e);
                 GameError::Database(e)
             })?;
         
+        Ok(row.map(|row| PrestigeState {
+            prestige_count: row.get::<i32, _>("prestige_count") as u32,
+            total_points: row.get::<i64, _>("total_points") as u64,
+            production_multiplier: row.get("production_multiplier"),
+            last_prestige_at: row.get("last_prestige_at"),
+        }))
+    }
+    
+    /// 実績解除の記録
+    ///
+    /// `player_achievements`に解除時刻を保存し、統計表示用に`game_statistics.achievements`にも追記する。
+    /// 既に記録済みの実績は無視される。
+    pub async fn record_achievements(&self, player_id: Uuid, unlocks: &[AchievementUnlock]) -> Result<()> {
+        if unlocks.is_empty() {
+            return Ok(());
+        }
+        
+        let db_error = |e: sqlx::Error| {
+            error!("[PERSISTENCE] Database error: {}", e);
+            GameError::Database(e)
+        };
+        
+        let mut tx = self.db_pool.begin().await.map_err(db_error)?;
+        let mut newly_recorded = Vec::new();
+        
+        for unlock in unlocks {
+            let inserted = sqlx::query(r#"
+                INSERT INTO player_achievements (player_id, achievement_id, unlocked_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (player_id, achievement_id) DO NOTHING
+            "#)
+                .bind(player_id)
+                .bind(&unlock.achievement_id)
+                .bind(unlock.unlocked_at)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            
+            if inserted.rows_affected() > 0 {
+                newly_recorded.push(serde_json::json!({
+                    "id": unlock.achievement_id,
+                    "name": unlock.name,
+                    "description": unlock.description,
+                    "unlocked_at": unlock.unlocked_at,
+                    "progress": 1.0,
+                }));
+            }
+        }
+        
+        if !newly_recorded.is_empty() {
+            sqlx::query(r#"
+                INSERT INTO game_statistics (user_id, achievements)
+                VALUES ($1, $2)
+                ON CONFLICT (user_id) DO UPDATE SET
+                    achievements = game_statistics.achievements || EXCLUDED.achievements,
+                    updated_at = NOW()
+            "#)
+                .bind(player_id)
+                .bind(serde_json::Value::Array(newly_recorded))
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        }
+        
+        tx.commit().await.map_err(db_error)?;
+        
+        debug!("[PERSISTENCE] Achievements recorded: player={}, count={}", player_id, unlocks.len());
+        
         Ok(())
     }
     
+    /// 解除済み実績の読み込み
+    pub async fn load_achievements(&self, player_id: Uuid) -> Result<Vec<(AchievementId, DateTime<Utc>)>> {
+        let rows = sqlx::query(r#"
+            SELECT achievement_id, unlocked_at
+            FROM player_achievements
+            WHERE player_id = $1
+            ORDER BY unlocked_at
+        "#)
+            .bind(player_id)
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|e| {
+                error!("[PERSISTENCE] Database error: {}", e);
+                GameError::Database(e)
+            })?;
+        
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("achievement_id"), row.get("unlocked_at")))
+            .collect())
+    }
+    
     /// スナップショットの読み込み
     pub async fn load_snapshot(&mut self, player_id: Uuid, tick: Option<u64>) -> Result<Option<GameStateSnapshot>> {
         // キャッシュから確認
@@ -1,11 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use rayon::prelude::*;
-use rstar::{RTree, RTreeObject, AABB, Envelope};
+use rstar::AABB;
 use serde::{Deserialize, Serialize};
 
-use crate::errors::{GameError, Result};
+use crate::errors::Result;
 use crate::game::celestial_bodies::{CelestialBody, BodyId, Vec3Fixed, Point3Fixed};
+use crate::game::accretion::{self, AccretionSettings};
+use crate::game::collision::{self, CollisionOutcome, CollisionSettings};
+use crate::game::determinism::sorted_body_ids;
+use crate::game::integrator::{IntegratorKind, SlicePhaseSpace};
+use crate::game::octree::{point_mass_acceleration, GravitySettings, Octree};
+use crate::game::spatial_index::SpatialIndex;
+use crate::game::timestep::{self, BodySlices, TimestepSettings};
 use crate::game::resources::{Fixed, fixed};
+use crate::models::websocket::{DestructionReason, StateDelta};
+
+pub use crate::game::spatial_index::PhysicsAABB;
 
 /// 物理演算の定数
 pub const GRAVITATIONAL_CONSTANT: f64 = 6.67430e-11;
@@ -14,25 +24,6 @@ pub const SOFTENING_FACTOR: f64 = 1e-3;
 pub const MAX_VELOCITY: f64 = 0.1 * SPEED_OF_LIGHT;
 pub const TICK_DURATION: f64 = 0.05; // 20Hz
 
-/// 物理演算用のバウンディングボックス
-#[derive(Debug, Clone)]
-pub struct PhysicsAABB {
-    pub body_id: BodyId,
-    pub min: Point3Fixed,
-    pub max: Point3Fixed,
-}
-
-impl RTreeObject for PhysicsAABB {
-    type Envelope = AABB<[f64; 3]>;
-    
-    fn envelope(&self) -> Self::Envelope {
-        AABB::from_corners(
-            [self.min.x, self.min.y, self.min.z],
-            [self.max.x, self.max.y, self.max.z],
-        )
-    }
-}
-
 /// 空間グリッドのセル
 #[derive(Debug, Clone)]
 pub struct SpatialCell {
@@ -72,108 +63,6 @@ impl SpatialCell {
     }
 }
 
-/// Barnes-Hut木のノード
-#[derive(Debug, Clone)]
-pub struct BHNode {
-    pub bounds: AABB<[f64; 3]>,
-    pub center_of_mass: Point3Fixed,
-    pub total_mass: Fixed,
-    pub body_id: Option<BodyId>,
-    pub children: Option<Box<[BHNode; 8]>>,
-}
-
-impl BHNode {
-    pub fn new(bounds: AABB<[f64; 3]>) -> Self {
-        Self {
-            bounds,
-            center_of_mass: Point3Fixed::origin(),
-            total_mass: 0,
-            body_id: None,
-            children: None,
-        }
-    }
-    
-    pub fn is_leaf(&self) -> bool {
-        self.children.is_none()
-    }
-    
-    pub fn insert(&mut self, body_id: BodyId, position: Point3Fixed, mass: Fixed) {
-        // 質量中心の更新
-        let total_mass = self.total_mass + mass;
-        if total_mass > 0 {
-            let weight = fixed::to_f64(mass) / fixed::to_f64(total_mass);
-            self.center_of_mass = Point3Fixed::from(
-                self.center_of_mass.coords * (1.0 - weight) + position.coords * weight
-            );
-        }
-        self.total_mass = total_mass;
-        
-        if self.is_leaf() {
-            if self.body_id.is_none() {
-                // 空のリーフノードに追加
-                self.body_id = Some(body_id);
-            } else {
-                // 既存のボディがあるリーフノードを分割
-                self.subdivide();
-                if let Some(_existing_id) = self.body_id.take() {
-                    // 既存のボディを再挿入（位置が必要）
-                    // 実際の実装では既存ボディの位置を保持する必要がある
-                }
-                self.insert_into_children(body_id, position, mass);
-            }
-        } else {
-            // 内部ノードに追加
-            self.insert_into_children(body_id, position, mass);
-        }
-    }
-    
-    fn subdivide(&mut self) {
-        let center = self.bounds.center();
-        let lower = self.bounds.lower();
-        let upper = self.bounds.upper();
-        let size = [upper[0] - lower[0], upper[1] - lower[1], upper[2] - lower[2]];
-        let half_size = [size[0] / 2.0, size[1] / 2.0, size[2] / 2.0];
-        
-        let mut children = Vec::with_capacity(8);
-        for i in 0..8 {
-            let offset = [
-                if i & 1 == 0 { -half_size[0] } else { half_size[0] } / 2.0,
-                if i & 2 == 0 { -half_size[1] } else { half_size[1] } / 2.0,
-                if i & 4 == 0 { -half_size[2] } else { half_size[2] } / 2.0,
-            ];
-            
-            let child_center = [
-                center[0] + offset[0],
-                center[1] + offset[1],
-                center[2] + offset[2],
-            ];
-            
-            let child_bounds = AABB::from_corners(
-                [child_center[0] - half_size[0]/2.0, child_center[1] - half_size[1]/2.0, child_center[2] - half_size[2]/2.0],
-                [child_center[0] + half_size[0]/2.0, child_center[1] + half_size[1]/2.0, child_center[2] + half_size[2]/2.0],
-            );
-            
-            children.push(BHNode::new(child_bounds));
-        }
-        
-        self.children = Some(Box::new([
-            children[0].clone(), children[1].clone(), children[2].clone(), children[3].clone(),
-            children[4].clone(), children[5].clone(), children[6].clone(), children[7].clone(),
-        ]));
-    }
-    
-    fn insert_into_children(&mut self, body_id: BodyId, position: Point3Fixed, mass: Fixed) {
-        if let Some(ref mut children) = self.children {
-            for child in children.iter_mut() {
-                if child.bounds.contains_point(&[position.x, position.y, position.z]) {
-                    child.insert(body_id, position, mass);
-                    break;
-                }
-            }
-        }
-    }
-}
-
 /// 物理演算状態
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhysicsState {
@@ -181,6 +70,9 @@ pub struct PhysicsState {
     pub total_energy: Fixed,
     pub total_momentum: Vec3Fixed,
     pub bodies_updated: Vec<BodyId>,
+    /// 直前のティックで接近している組の天体を進めた分割数
+    #[serde(default)]
+    pub substeps: u32,
 }
 
 impl PhysicsState {
@@ -190,274 +82,293 @@ impl PhysicsState {
             total_energy: 0,
             total_momentum: Vec3Fixed::zeros(),
             bodies_updated: Vec::new(),
+            substeps: 0,
         }
     }
 }
 
+/// 物理演算中に発生した出来事
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PhysicsEvent {
+    /// 2つの天体が衝突した
+    Collision {
+        body1_id: BodyId,
+        body2_id: BodyId,
+        outcome: CollisionOutcome,
+    },
+    /// 衝突や吸収で天体が取り除かれた（`absorbed_by`は吸収した天体）
+    BodyDestroyed {
+        body_id: BodyId,
+        reason: DestructionReason,
+        absorbed_by: Option<BodyId>,
+    },
+    /// 衝突で破片が生成された
+    DebrisCreated {
+        body_id: BodyId,
+        source_id: BodyId,
+    },
+    /// 天体が境界の外へ出た
+    LeftBounds {
+        body_id: BodyId,
+    },
+}
+
+impl PhysicsEvent {
+    /// 天体の削除をクライアントへ伝える差分（削除以外は`None`）
+    pub fn destruction_delta(&self) -> Option<StateDelta> {
+        match self {
+            PhysicsEvent::BodyDestroyed { body_id, reason, .. } => Some(StateDelta::CelestialBodyDestroyed {
+                id: *body_id,
+                reason: *reason,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// 直接的な重力加速度の計算（O(n²)、並列）
+pub fn direct_accelerations(positions: &[Vec3Fixed], masses: &[f64], settings: &GravitySettings) -> Vec<Vec3Fixed> {
+    positions.par_iter().enumerate().map(|(i, position)| {
+        positions.iter().zip(masses).enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(Vec3Fixed::zeros(), |acc, (_, (other, mass))| {
+                acc + point_mass_acceleration(position, other, *mass, settings)
+            })
+    }).collect()
+}
+
 /// 物理演算エンジン
+#[derive(Debug)]
 pub struct PhysicsEngine {
-    pub spatial_tree: RTree<PhysicsAABB>,
-    pub bh_tree: Option<BHNode>,
+    /// 天体マネージャーの索引を渡さずに更新する場合に使う空間インデックス
+    pub spatial_index: SpatialIndex,
+    pub bh_tree: Option<Octree>,
     pub theta: f64, // Barnes-Hut近似パラメータ
+    pub integrator: IntegratorKind,
+    pub timestep: TimestepSettings,
     pub max_bodies_direct: usize,
     pub gravity_enabled: bool,
     pub collision_enabled: bool,
+    pub collision_settings: CollisionSettings,
+    pub accretion_settings: AccretionSettings,
+    /// 境界の半径（原点からの距離、`None`の場合は無制限）
+    pub world_radius: Option<f64>,
     pub state: PhysicsState,
+    /// 境界外にいる天体（境界を出た時点でのみ報告するため）
+    out_of_bounds: HashSet<BodyId>,
+    /// 前回の積分の最後に計算した加速度（次のティックの最初のkickで使い回す）
+    force_cache: Option<ForceCache>,
+}
+
+/// 加速度と、それを計算した時の天体の並び・位置・質量・近似パラメータ
+#[derive(Debug, Clone)]
+struct ForceCache {
+    ids: Vec<BodyId>,
+    positions: Vec<Vec3Fixed>,
+    masses: Vec<f64>,
+    theta: f64,
+    accelerations: Vec<Vec3Fixed>,
+}
+
+impl ForceCache {
+    /// 積分を始める状態がキャッシュした時と同じなら、その加速度を返す
+    fn take_if_valid(self, ids: &[BodyId], positions: &[Vec3Fixed], masses: &[f64], theta: f64) -> Option<Vec<Vec3Fixed>> {
+        (self.ids == ids && self.positions == positions && self.masses == masses && self.theta == theta)
+            .then_some(self.accelerations)
+    }
 }
 
 impl PhysicsEngine {
     pub fn new() -> Self {
         Self {
-            spatial_tree: RTree::new(),
+            spatial_index: SpatialIndex::new(),
             bh_tree: None,
             theta: 0.5, // 精度パラメータ
+            integrator: IntegratorKind::default(),
+            timestep: TimestepSettings::default(),
             max_bodies_direct: 1000,
             gravity_enabled: true,
             collision_enabled: true,
+            collision_settings: CollisionSettings::default(),
+            accretion_settings: AccretionSettings::default(),
+            world_radius: None,
             state: PhysicsState::new(),
+            out_of_bounds: HashSet::new(),
+            force_cache: None,
         }
     }
     
     /// 物理演算の更新
-    pub fn update(&mut self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<()> {
+    ///
+    /// 衝突や境界外への移動など、この更新で発生した出来事を返す。
+    pub fn update(&mut self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<Vec<PhysicsEvent>> {
+        let mut spatial_index = std::mem::take(&mut self.spatial_index);
+        let result = self.update_with_index(bodies, &mut spatial_index, delta_time);
+        self.spatial_index = spatial_index;
+        result
+    }
+    
+    /// 共有の空間インデックスを使った物理演算の更新
+    ///
+    /// インデックスは積分後の位置に同期され、衝突や吸収で増減した天体も反映した状態で返る。
+    pub fn update_with_index(
+        &mut self,
+        bodies: &mut HashMap<BodyId, CelestialBody>,
+        spatial_index: &mut SpatialIndex,
+        delta_time: f64,
+    ) -> Result<Vec<PhysicsEvent>> {
+        let mut events = Vec::new();
         if bodies.is_empty() {
-            return Ok(());
+            spatial_index.clear();
+            return Ok(events);
         }
         
-        // 空間インデックスの更新
-        self.update_spatial_index(bodies);
+        spatial_index.sync(bodies);
+        let start_positions: HashMap<BodyId, Vec3Fixed> = bodies.iter()
+            .map(|(id, body)| (*id, body.physics.position))
+            .collect();
         
-        // 重力計算
-        if self.gravity_enabled {
-            self.calculate_gravity(bodies, delta_time)?;
-        }
+        // 重力と位置の時間積分（接近している組の天体だけティックを分割する）
+        let substeps = self.integrate(bodies, spatial_index, delta_time);
         
-        // 位置の更新
-        self.update_positions(bodies, delta_time);
+        // 空間インデックスの更新（余白からはみ出した天体だけを登録し直す）
+        spatial_index.sync(bodies);
         
-        // 衝突検出
+        // 衝突検出（ティック内の移動を掃引して、すり抜けも検出する）
         if self.collision_enabled {
-            self.detect_collisions(bodies)?;
+            events.extend(self.detect_collisions(bodies, spatial_index, &start_positions)?);
         }
         
+        // ブラックホールの降着
+        events.extend(accretion::process_accretion(bodies, delta_time, &self.accretion_settings).into_iter().map(|absorption| {
+            PhysicsEvent::BodyDestroyed {
+                body_id: absorption.body_id,
+                reason: DestructionReason::BlackHoleAbsorption,
+                absorbed_by: Some(absorption.black_hole_id),
+            }
+        }));
+        
+        // 衝突と吸収で変化した天体をインデックスに反映
+        Self::apply_events_to_index(&events, bodies, spatial_index);
+        
+        // 境界チェック
+        events.extend(self.detect_out_of_bounds(bodies));
+        
         // 物理状態の更新
         self.update_physics_state(bodies);
         
+        self.state.substeps = substeps;
         self.state.tick += 1;
         
-        Ok(())
+        Ok(events)
     }
     
-    /// 空間インデックスの更新
-    fn update_spatial_index(&mut self, bodies: &HashMap<BodyId, CelestialBody>) {
-        let mut spatial_objects = Vec::new();
-        
-        for (id, body) in bodies.iter() {
-            let radius = body.physics.radius;
-            let pos = body.physics.position;
-            
-            let aabb = PhysicsAABB {
-                body_id: *id,
-                min: Point3Fixed::new(pos.x - fixed::to_f64(radius), pos.y - fixed::to_f64(radius), pos.z - fixed::to_f64(radius)),
-                max: Point3Fixed::new(pos.x + fixed::to_f64(radius), pos.y + fixed::to_f64(radius), pos.z + fixed::to_f64(radius)),
-            };
-            
-            spatial_objects.push(aabb);
-        }
-        
-        self.spatial_tree = RTree::bulk_load(spatial_objects);
-    }
-    
-    /// 重力計算
-    fn calculate_gravity(&mut self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<()> {
-        if bodies.len() <= self.max_bodies_direct {
-            // 直接計算
-            self.calculate_gravity_direct(bodies, delta_time)
-        } else {
-            // Barnes-Hut近似
-            self.calculate_gravity_barnes_hut(bodies, delta_time)
-        }
-    }
-    
-    /// 直接的な重力計算（O(n²)）
-    fn calculate_gravity_direct(&self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<()> {
-        let body_data: Vec<_> = bodies.iter().map(|(id, body)| {
-            (*id, body.physics.position, body.physics.mass)
-        }).collect();
-        
-        // 並列処理で力を計算
-        let forces: Vec<_> = body_data.par_iter().map(|(id, pos, mass)| {
-            let mut force = Vec3Fixed::zeros();
-            
-            for (other_id, other_pos, other_mass) in body_data.iter() {
-                if id == other_id {
-                    continue;
+    /// 衝突と吸収の結果をインデックスに反映（消えた天体を外し、合体・破片・吸収した天体を登録し直す）
+    fn apply_events_to_index(events: &[PhysicsEvent], bodies: &HashMap<BodyId, CelestialBody>, spatial_index: &mut SpatialIndex) {
+        for event in events {
+            match event {
+                PhysicsEvent::Collision { body1_id, body2_id, .. } => {
+                    spatial_index.refresh(*body1_id, bodies);
+                    spatial_index.refresh(*body2_id, bodies);
                 }
-                
-                let r = other_pos - pos;
-                let distance_sq = r.magnitude_squared();
-                
-                if distance_sq < SOFTENING_FACTOR * SOFTENING_FACTOR {
-                    continue; // 軟化係数以下は無視
-                }
-                
-                let distance = distance_sq.sqrt();
-                let force_magnitude = GRAVITATIONAL_CONSTANT * 
-                    fixed::to_f64(*mass) * fixed::to_f64(*other_mass) / 
-                    (distance * distance + SOFTENING_FACTOR * SOFTENING_FACTOR);
-                
-                let force_direction = r.normalize();
-                force += force_direction * force_magnitude;
-            }
-            
-            (*id, force)
-        }).collect();
-        
-        // 速度の更新
-        for (id, force) in forces.iter() {
-            if let Some(body) = bodies.get_mut(id) {
-                let acceleration = *force / fixed::to_f64(body.physics.mass);
-                
-                body.physics.velocity += acceleration * delta_time;
-                
-                // 速度制限
-                let velocity_magnitude = body.physics.velocity.magnitude();
-                if velocity_magnitude > MAX_VELOCITY {
-                    body.physics.velocity = body.physics.velocity.normalize() * MAX_VELOCITY;
+                PhysicsEvent::BodyDestroyed { body_id, absorbed_by, .. } => {
+                    spatial_index.refresh(*body_id, bodies);
+                    if let Some(survivor_id) = absorbed_by {
+                        spatial_index.refresh(*survivor_id, bodies);
+                    }
                 }
+                PhysicsEvent::DebrisCreated { body_id, .. } => spatial_index.refresh(*body_id, bodies),
+                PhysicsEvent::LeftBounds { .. } => {}
             }
         }
-        
-        Ok(())
-    }
-    
-    /// Barnes-Hut近似による重力計算
-    fn calculate_gravity_barnes_hut(&mut self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<()> {
-        // Barnes-Hut木の構築
-        self.build_bh_tree(bodies);
-        
-        // 各ボディに対して力を計算
-        for (id, body) in bodies.iter_mut() {
-            let force = self.calculate_force_from_tree(*id, body.physics.position.into(), body.physics.mass);
-            
-            let acceleration = force / fixed::to_f64(body.physics.mass);
-            
-            body.physics.velocity += acceleration * delta_time;
-            
-            // 速度制限
-            let velocity_magnitude = body.physics.velocity.magnitude();
-            if velocity_magnitude > MAX_VELOCITY {
-                body.physics.velocity = body.physics.velocity.normalize() * MAX_VELOCITY;
-            }
-        }
-        
-        Ok(())
     }
     
-    /// Barnes-Hut木の構築
-    fn build_bh_tree(&mut self, bodies: &HashMap<BodyId, CelestialBody>) {
-        if bodies.is_empty() {
-            return;
-        }
-        
-        // 全ボディを含む境界を計算
-        let mut min_pos = Point3Fixed::new(f64::MAX, f64::MAX, f64::MAX);
-        let mut max_pos = Point3Fixed::new(f64::MIN, f64::MIN, f64::MIN);
-        
-        for body in bodies.values() {
-            let pos = body.physics.position;
-            min_pos.x = min_pos.x.min(pos.x);
-            min_pos.y = min_pos.y.min(pos.y);
-            min_pos.z = min_pos.z.min(pos.z);
-            max_pos.x = max_pos.x.max(pos.x);
-            max_pos.y = max_pos.y.max(pos.y);
-            max_pos.z = max_pos.z.max(pos.z);
+    /// 重力と位置の時間積分
+    ///
+    /// `spatial_index`で近傍を探して分割の計画を立て、接近している組の天体だけを細かく進める。
+    /// 分割した天体の分割数を返す。
+    fn integrate(&mut self, bodies: &mut HashMap<BodyId, CelestialBody>, spatial_index: &SpatialIndex, delta_time: f64) -> u32 {
+        if !self.gravity_enabled {
+            // 重力がなければ等速直線運動なので分割しても結果は変わらない
+            self.update_positions(bodies, delta_time);
+            return 1;
         }
         
-        let bounds = AABB::from_corners(
-            [min_pos.x, min_pos.y, min_pos.z],
-            [max_pos.x, max_pos.y, max_pos.z],
+        // 天体ID順に並べて、実行ごとに同じ順序で計算する
+        let ids = sorted_body_ids(bodies);
+        let masses: Vec<f64> = ids.iter().map(|id| fixed::to_f64(bodies[id].physics.mass)).collect();
+        let mut positions: Vec<Vec3Fixed> = ids.iter().map(|id| bodies[id].physics.position).collect();
+        let mut velocities: Vec<Vec3Fixed> = ids.iter().map(|id| bodies[id].physics.velocity).collect();
+        let radii: Vec<f64> = ids.iter().map(|id| fixed::to_f64(bodies[id].physics.radius)).collect();
+        let plan = timestep::plan_substeps(
+            &BodySlices { ids: &ids, positions: &positions, velocities: &velocities, masses: &masses, radii: &radii },
+            spatial_index,
+            delta_time,
+            GRAVITATIONAL_CONSTANT,
+            &self.timestep,
         );
         
-        let mut root = BHNode::new(bounds);
-        
-        // 全ボディを木に挿入
-        for (id, body) in bodies.iter() {
-            root.insert(*id, body.physics.position.into(), body.physics.mass);
-        }
-        
-        self.bh_tree = Some(root);
-    }
-    
-    /// 木から力を計算
-    fn calculate_force_from_tree(&self, body_id: BodyId, position: Point3Fixed, mass: Fixed) -> Vec3Fixed {
-        if let Some(ref tree) = self.bh_tree {
-            self.calculate_force_recursive(tree, body_id, position, mass)
-        } else {
-            Vec3Fixed::zeros()
+        let settings = self.gravity_settings();
+        let use_tree = ids.len() > self.max_bodies_direct;
+        // 前のティックから衝突やコマンドで位置が動いていなければ、最後の加速度をそのまま使える
+        let cached = self.force_cache.take()
+            .and_then(|cache| cache.take_if_valid(&ids, &positions, &masses, settings.theta));
+        let bh_tree = &mut self.bh_tree;
+        
+        let mut accelerations = |positions: &[Vec3Fixed]| {
+            if use_tree {
+                // Barnes-Hut近似
+                let tree = Octree::from_points(&ids, positions, &masses);
+                let accelerations = tree.accelerations_for(&ids, positions, &settings);
+                *bh_tree = Some(tree);
+                accelerations
+            } else {
+                // 直接計算
+                direct_accelerations(positions, &masses, &settings)
+            }
+        };
+        let mut state = SlicePhaseSpace::new(&mut positions, &mut velocities, &mut accelerations);
+        if let Some(cached) = cached {
+            state = state.with_accelerations(cached);
         }
-    }
-    
-    /// 再帰的に力を計算
-    fn calculate_force_recursive(&self, node: &BHNode, body_id: BodyId, position: Point3Fixed, mass: Fixed) -> Vec3Fixed {
-        if node.total_mass == 0 {
-            return Vec3Fixed::zeros();
+        plan.advance(self.integrator.integrator(), &mut state, delta_time);
+        
+        if let Some(accelerations) = state.into_accelerations() {
+            self.force_cache = Some(ForceCache {
+                ids: ids.clone(),
+                positions: positions.clone(),
+                masses,
+                theta: settings.theta,
+                accelerations,
+            });
         }
         
-        let r = node.center_of_mass - position;
-        let distance = r.magnitude();
-        
-        if node.is_leaf() {
-            // リーフノードの場合、直接計算
-            if let Some(node_body_id) = node.body_id {
-                if node_body_id == body_id {
-                    return Vec3Fixed::zeros(); // 自分自身への力は無視
-                }
-            }
-            
-            if distance < SOFTENING_FACTOR {
-                return Vec3Fixed::zeros();
-            }
-            
-            let force_magnitude = GRAVITATIONAL_CONSTANT * 
-                fixed::to_f64(mass) * fixed::to_f64(node.total_mass) / 
-                (distance * distance + SOFTENING_FACTOR * SOFTENING_FACTOR);
-            
-            let force_direction = r.normalize();
-            return force_direction * force_magnitude;
-        } else {
-            // 内部ノードの場合、theta基準で判定
-            let lower = node.bounds.lower();
-            let upper = node.bounds.upper();
-            let size = [upper[0] - lower[0], upper[1] - lower[1], upper[2] - lower[2]];
-            let node_size = (size[0] + size[1] + size[2]) / 3.0;
-            
-            if node_size / distance < self.theta {
-                // 近似を使用
-                if distance < SOFTENING_FACTOR {
-                    return Vec3Fixed::zeros();
-                }
-                
-                let force_magnitude = GRAVITATIONAL_CONSTANT * 
-                    fixed::to_f64(mass) * fixed::to_f64(node.total_mass) / 
-                    (distance * distance + SOFTENING_FACTOR * SOFTENING_FACTOR);
+        for ((id, position), velocity) in ids.iter().zip(positions).zip(velocities) {
+            if let Some(body) = bodies.get_mut(id) {
+                body.physics.position = position;
                 
-                let force_direction = r.normalize();
-                return force_direction * force_magnitude;
-            } else {
-                // 子ノードを再帰的に計算
-                let mut total_force = Vec3Fixed::zeros();
-                if let Some(ref children) = node.children {
-                    for child in children.iter() {
-                        total_force += self.calculate_force_recursive(child, body_id, position, mass);
-                    }
-                }
-                return total_force;
+                // 速度制限
+                body.physics.velocity = if velocity.magnitude() > MAX_VELOCITY {
+                    velocity.normalize() * MAX_VELOCITY
+                } else {
+                    velocity
+                };
             }
         }
+        
+        plan.substeps
     }
     
-    /// 位置の更新
+    /// 木の走査と直接計算に使う重力パラメータ
+    fn gravity_settings(&self) -> GravitySettings {
+        GravitySettings {
+            gravitational_constant: GRAVITATIONAL_CONSTANT,
+            softening_factor: SOFTENING_FACTOR,
+            theta: self.theta,
+        }
+    }
+    
+    /// 位置の更新（重力が無効な場合の等速運動）
     fn update_positions(&mut self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) {
         for body in bodies.values_mut() {
             body.physics.position += body.physics.velocity * delta_time;
@@ -465,82 +376,99 @@ impl PhysicsEngine {
     }
     
     /// 衝突検出
-    fn detect_collisions(&mut self, bodies: &mut HashMap<BodyId, CelestialBody>) -> Result<()> {
+    ///
+    /// 各天体のティック開始時から終了時までの移動を掃引した球で判定する。候補は掃引範囲を
+    /// 自身の移動量だけ広げて探すので、組のうち速い方の天体から必ず見つかる。
+    fn detect_collisions(
+        &mut self,
+        bodies: &mut HashMap<BodyId, CelestialBody>,
+        spatial_index: &SpatialIndex,
+        start_positions: &HashMap<BodyId, Vec3Fixed>,
+    ) -> Result<Vec<PhysicsEvent>> {
         let mut collision_pairs = Vec::new();
+        let mut seen_pairs = HashSet::new();
         
-        for (id, body) in bodies.iter() {
-            let radius = body.physics.radius;
-            let pos = body.physics.position;
-            
-            let query_aabb = AABB::from_corners(
-                [pos.x - fixed::to_f64(radius), pos.y - fixed::to_f64(radius), pos.z - fixed::to_f64(radius)],
-                [pos.x + fixed::to_f64(radius), pos.y + fixed::to_f64(radius), pos.z + fixed::to_f64(radius)],
-            );
+        for (id, body) in sorted_body_ids(bodies).iter().map(|id| (id, &bodies[id])) {
+            let radius = fixed::to_f64(body.physics.radius);
+            let end = body.physics.position;
+            let start = start_positions.get(id).copied().unwrap_or(end);
+            let reach = radius + (end - start).magnitude();
             
-            let nearby_bodies: Vec<_> = self.spatial_tree.locate_in_envelope(&query_aabb).collect();
+            // 境界が交差する天体を候補とする（内包判定では接触を見逃す）
+            let nearby_bodies = spatial_index.intersecting(start.inf(&end).add_scalar(-reach), start.sup(&end).add_scalar(reach));
             
-            for nearby in nearby_bodies {
-                if nearby.body_id != *id {
-                    if let Some(other_body) = bodies.get(&nearby.body_id) {
-                        let distance = (body.physics.position - other_body.physics.position).magnitude();
+            for nearby_id in nearby_bodies {
+                if nearby_id != *id {
+                    if let Some(other_body) = bodies.get(&nearby_id) {
+                        let other_end = other_body.physics.position;
+                        let other_start = start_positions.get(&nearby_id).copied().unwrap_or(other_end);
                         let collision_distance = fixed::to_f64(body.physics.radius + other_body.physics.radius);
+                        let impact = timestep::time_of_impact(start, end, other_start, other_end, collision_distance);
                         
-                        if distance < collision_distance {
-                            collision_pairs.push((*id, nearby.body_id));
+                        // 同じ組を両方向から検出しないよう正規化する
+                        let pair = if *id < nearby_id { (*id, nearby_id) } else { (nearby_id, *id) };
+                        if impact.is_some() && seen_pairs.insert(pair) {
+                            collision_pairs.push(pair);
                         }
                     }
                 }
             }
         }
         
-        // 衝突処理
+        // 衝突処理（同じティックで既に吸収された天体は除外、組の順に処理）
+        collision_pairs.sort_unstable();
+        let mut events = Vec::new();
         for (id1, id2) in collision_pairs {
-            self.handle_collision(bodies, id1, id2)?;
+            if !bodies.contains_key(&id1) || !bodies.contains_key(&id2) {
+                continue;
+            }
+            events.extend(self.handle_collision(bodies, id1, id2)?);
         }
         
-        Ok(())
+        Ok(events)
     }
     
-    /// 衝突処理
-    fn handle_collision(&self, bodies: &mut HashMap<BodyId, CelestialBody>, id1: BodyId, id2: BodyId) -> Result<()> {
-        // 非弾性衝突を実装
-        let (body1, body2) = {
-            let body1 = bodies.get(&id1).ok_or(GameError::BodyNotFound)?.clone();
-            let body2 = bodies.get(&id2).ok_or(GameError::BodyNotFound)?.clone();
-            (body1, body2)
-        };
-        
-        let total_mass = body1.physics.mass + body2.physics.mass;
-        let m1 = fixed::to_f64(body1.physics.mass);
-        let m2 = fixed::to_f64(body2.physics.mass);
-        
-        // 運動量保存
-        let final_velocity = (body1.physics.velocity * m1 + body2.physics.velocity * m2) / (m1 + m2);
-        
-        // 新しい位置（質量中心）
-        let final_position = Point3Fixed::from((body1.physics.position.to_homogeneous().xyz() * m1 + body2.physics.position.to_homogeneous().xyz() * m2) / (m1 + m2));
-        
-        // より大きな天体を残す
-        let (survivor_id, removed_id) = if body1.physics.mass > body2.physics.mass {
-            (id1, id2)
-        } else {
-            (id2, id1)
+    /// 境界外への移動の検出
+    fn detect_out_of_bounds(&mut self, bodies: &HashMap<BodyId, CelestialBody>) -> Vec<PhysicsEvent> {
+        let Some(world_radius) = self.world_radius else {
+            return Vec::new();
         };
         
-        // 生存者を更新
-        if let Some(survivor) = bodies.get_mut(&survivor_id) {
-            survivor.physics.mass = total_mass;
-            survivor.physics.velocity = final_velocity;
-            survivor.physics.position = final_position.to_homogeneous().xyz();
-            // 新しい半径を計算（体積保存）
-            let new_radius = fixed::from_f64(fixed::to_f64(total_mass).cbrt());
-            survivor.physics.radius = new_radius;
+        let mut events = Vec::new();
+        for (id, body) in sorted_body_ids(bodies).iter().map(|id| (id, &bodies[id])) {
+            if body.physics.position.magnitude() > world_radius {
+                if self.out_of_bounds.insert(*id) {
+                    events.push(PhysicsEvent::LeftBounds { body_id: *id });
+                }
+            } else {
+                self.out_of_bounds.remove(id);
+            }
         }
         
-        // 削除されるボディを除去
-        bodies.remove(&removed_id);
-        
-        Ok(())
+        self.out_of_bounds.retain(|id| bodies.contains_key(id));
+        events
+    }
+    
+    /// 衝突処理（結果の判定と天体の更新は`collision`モジュールに委ねる）
+    fn handle_collision(&self, bodies: &mut HashMap<BodyId, CelestialBody>, id1: BodyId, id2: BodyId) -> Result<Vec<PhysicsEvent>> {
+        let resolution = collision::resolve(bodies, id1, id2, &self.collision_settings)?;
+        
+        let mut events = vec![PhysicsEvent::Collision {
+            body1_id: id1,
+            body2_id: id2,
+            outcome: resolution.outcome,
+        }];
+        events.extend(resolution.removed.into_iter().map(|removed| PhysicsEvent::BodyDestroyed {
+            body_id: removed.body_id,
+            reason: removed.reason,
+            absorbed_by: removed.absorbed_by,
+        }));
+        events.extend(resolution.debris.into_iter().map(|(body_id, source_id)| PhysicsEvent::DebrisCreated {
+            body_id,
+            source_id,
+        }));
+        
+        Ok(events)
     }
     
     /// 物理状態の更新
@@ -548,7 +476,9 @@ impl PhysicsEngine {
         let mut total_energy = fixed::from_f64(0.0);
         let mut total_momentum = Vec3Fixed::zeros();
         
-        for body in bodies.values() {
+        // 浮動小数点の加算順を固定するため天体ID順に集計する
+        let body_ids = sorted_body_ids(bodies);
+        for body in body_ids.iter().map(|id| &bodies[id]) {
             // 運動エネルギー
             let velocity_sq = body.physics.velocity.magnitude_squared();
             let kinetic_energy = fixed::from_f64(0.5 * fixed::to_f64(body.physics.mass) * velocity_sq);
@@ -560,7 +490,7 @@ impl PhysicsEngine {
         
         self.state.total_energy = total_energy;
         self.state.total_momentum = total_momentum;
-        self.state.bodies_updated = bodies.keys().copied().collect();
+        self.state.bodies_updated = body_ids;
     }
     
     /// 物理状態の取得
@@ -573,6 +503,14 @@ impl PhysicsEngine {
         self.theta = theta.clamp(0.1, 2.0);
     }
     
+    pub fn set_integrator(&mut self, integrator: IntegratorKind) {
+        self.integrator = integrator;
+    }
+    
+    pub fn set_timestep_settings(&mut self, settings: TimestepSettings) {
+        self.timestep = settings;
+    }
+    
     pub fn set_gravity_enabled(&mut self, enabled: bool) {
         self.gravity_enabled = enabled;
     }
@@ -580,21 +518,70 @@ impl PhysicsEngine {
     pub fn set_collision_enabled(&mut self, enabled: bool) {
         self.collision_enabled = enabled;
     }
+    
+    pub fn set_collision_settings(&mut self, settings: CollisionSettings) {
+        self.collision_settings = settings;
+    }
+    
+    pub fn set_accretion_settings(&mut self, settings: AccretionSettings) {
+        self.accretion_settings = settings;
+    }
+    
+    pub fn set_world_radius(&mut self, world_radius: Option<f64>) {
+        self.world_radius = world_radius;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use uuid::Uuid;
     use crate::game::celestial_bodies::CelestialType;
     
+    /// 格子状に並べたテスト用の天体
+    fn lattice_bodies(per_axis: usize) -> HashMap<BodyId, CelestialBody> {
+        let mut bodies = HashMap::new();
+        for i in 0..per_axis * per_axis * per_axis {
+            let id = Uuid::new_v4();
+            let position = Vec3Fixed::new(
+                (i % per_axis) as f64 * 10.0,
+                (i / per_axis % per_axis) as f64 * 10.0,
+                (i / (per_axis * per_axis)) as f64 * 10.0 + (i % 3) as f64,
+            );
+            bodies.insert(id, CelestialBody::new(
+                id,
+                CelestialType::Asteroid,
+                position,
+                fixed::from_f64(1.0e6 + i as f64 * 1.0e3),
+                fixed::from_f64(0.1),
+            ));
+        }
+        bodies
+    }
+    
     #[test]
-    fn test_bh_node_creation() {
-        let bounds = AABB::from_corners([0.0, 0.0, 0.0], [100.0; 3]);
-        let node = BHNode::new(bounds);
-        
-        assert!(node.is_leaf());
-        assert_eq!(node.total_mass, 0);
-        assert!(node.body_id.is_none());
+    fn test_barnes_hut_matches_direct_summation() {
+        let mut direct_bodies = lattice_bodies(8);
+        let mut tree_bodies = direct_bodies.clone();
+        
+        let mut direct = PhysicsEngine::new();
+        direct.max_bodies_direct = usize::MAX;
+        direct.update(&mut direct_bodies, TICK_DURATION).unwrap();
+        
+        let mut barnes_hut = PhysicsEngine::new();
+        barnes_hut.max_bodies_direct = 0;
+        barnes_hut.set_theta(0.3);
+        barnes_hut.update(&mut tree_bodies, TICK_DURATION).unwrap();
+        
+        // 分割時に天体が失われていないこと
+        assert_eq!(barnes_hut.bh_tree.as_ref().unwrap().len(), direct_bodies.len());
+        
+        // 格子の中心付近は合力が打ち消し合うため、最大の速度変化を基準に比較する
+        let scale = direct_bodies.values().map(|b| b.physics.velocity.magnitude()).fold(0.0, f64::max);
+        for (id, body) in direct_bodies.iter() {
+            let error = (tree_bodies[id].physics.velocity - body.physics.velocity).magnitude() / scale;
+            assert!(error < 1e-2, "relative error {} too large", error);
+        }
     }
     
     #[test]
@@ -653,4 +640,119 @@ mod tests {
         assert_ne!(body1_after.physics.velocity.magnitude(), 0);
         assert_ne!(body2_after.physics.velocity.magnitude(), 0);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_collision_and_bounds_events() {
+        let mut engine = PhysicsEngine::new();
+        engine.set_gravity_enabled(false);
+        engine.set_world_radius(Some(100.0));
+        let mut bodies = HashMap::new();
+        
+        let large_id = Uuid::new_v4();
+        let small_id = Uuid::new_v4();
+        let runaway_id = Uuid::new_v4();
+        
+        bodies.insert(large_id, CelestialBody::new(
+            large_id,
+            CelestialType::Asteroid,
+            Vec3Fixed::new(0.0, 0.0, 0.0),
+            fixed::from_f64(2000.0),
+            fixed::from_f64(1.0),
+        ));
+        bodies.insert(small_id, CelestialBody::new(
+            small_id,
+            CelestialType::Asteroid,
+            Vec3Fixed::new(1.5, 0.0, 0.0),
+            fixed::from_f64(1000.0),
+            fixed::from_f64(1.0),
+        ));
+        let mut runaway = CelestialBody::new(
+            runaway_id,
+            CelestialType::Comet,
+            Vec3Fixed::new(99.0, 0.0, 0.0),
+            fixed::from_f64(10.0),
+            fixed::from_f64(0.1),
+        );
+        runaway.physics.velocity = Vec3Fixed::new(100.0, 0.0, 0.0);
+        bodies.insert(runaway_id, runaway);
+        
+        let events = engine.update(&mut bodies, TICK_DURATION).unwrap();
+        
+        // 衝突は1組につき1回だけ報告される
+        assert_eq!(events.len(), 3);
+        let (body1_id, body2_id) = if large_id < small_id { (large_id, small_id) } else { (small_id, large_id) };
+        assert!(events.contains(&PhysicsEvent::Collision { body1_id, body2_id, outcome: CollisionOutcome::Merge }));
+        let destroyed = PhysicsEvent::BodyDestroyed {
+            body_id: small_id,
+            reason: DestructionReason::Collision,
+            absorbed_by: Some(large_id),
+        };
+        assert!(destroyed.destruction_delta().is_some());
+        assert!(events.contains(&destroyed));
+        assert!(events.contains(&PhysicsEvent::LeftBounds { body_id: runaway_id }));
+        assert!(!bodies.contains_key(&small_id));
+        
+        // 境界外に留まっている間は再度報告しない
+        let events = engine.update(&mut bodies, TICK_DURATION).unwrap();
+        assert!(events.is_empty());
+    }
+    
+    #[test]
+    fn test_fast_bodies_do_not_tunnel() {
+        let mut engine = PhysicsEngine::new();
+        engine.set_gravity_enabled(false);
+        let mut bodies = HashMap::new();
+        
+        let target_id = Uuid::new_v4();
+        let bullet_id = Uuid::new_v4();
+        bodies.insert(target_id, CelestialBody::new(
+            target_id,
+            CelestialType::Asteroid,
+            Vec3Fixed::new(0.0, 0.0, 0.0),
+            fixed::from_f64(1000.0),
+            fixed::from_f64(1.0),
+        ));
+        // 1ティックで標的の反対側まで通り抜ける速さ
+        let mut bullet = CelestialBody::new(
+            bullet_id,
+            CelestialType::Asteroid,
+            Vec3Fixed::new(-10.0, 0.0, 0.0),
+            fixed::from_f64(10.0),
+            fixed::from_f64(0.5),
+        );
+        bullet.physics.velocity = Vec3Fixed::new(400.0, 0.0, 0.0);
+        bodies.insert(bullet_id, bullet);
+        
+        let events = engine.update(&mut bodies, TICK_DURATION).unwrap();
+        assert!(events.iter().any(|event| matches!(event, PhysicsEvent::Collision { .. })));
+    }
+    
+    #[test]
+    fn test_close_approach_is_substepped() {
+        let mut engine = PhysicsEngine::new();
+        let mut bodies = HashMap::new();
+        
+        let star_id = Uuid::new_v4();
+        let comet_id = Uuid::new_v4();
+        bodies.insert(star_id, CelestialBody::new(
+            star_id,
+            CelestialType::Asteroid,
+            Vec3Fixed::new(0.0, 0.0, 0.0),
+            fixed::from_f64(1000.0),
+            fixed::from_f64(1.0),
+        ));
+        let mut comet = CelestialBody::new(
+            comet_id,
+            CelestialType::Comet,
+            Vec3Fixed::new(0.0, 5.0, 0.0),
+            fixed::from_f64(1.0),
+            fixed::from_f64(0.1),
+        );
+        comet.physics.velocity = Vec3Fixed::new(200.0, 0.0, 0.0);
+        bodies.insert(comet_id, comet);
+        
+        engine.update(&mut bodies, TICK_DURATION).unwrap();
+        assert!(engine.state.substeps > 1);
+        assert!(engine.state.substeps <= engine.timestep.max_substeps);
+    }
+}
@@ -1,15 +1,24 @@
 //! SIMD最適化された物理演算エンジン
+//!
+//! 重力の直接計算は`PhysicsStore`の成分ごとの配列から4天体ずつ`f64x4`に読み込み、レーン並列で計算する。
+//! `f64x4`が使う命令はビルド時の命令セットで決まるため、x86ではレーン並列版をAVX・AVX2+FMAを有効にして
+//! コンパイルした版も用意し、実行時にCPUの対応命令を調べて最も速いものを選ぶ。
+//! レーン並列版は加算の順序がスカラー版と異なるため、結果は丸め誤差の範囲で一致する。
 
-use wide::{f64x4, i64x4};
-use simdeez::*;
+use wide::{f64x4, CmpGe, CmpNe};
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{instrument, debug, info};
 
 use crate::errors::{GameError, Result};
-use crate::game::celestial_bodies::{CelestialBody, BodyId};
+use crate::game::celestial_bodies::{CelestialBody, BodyId, Vec3Fixed};
+use crate::game::integrator::{IntegratorKind, PhaseSpace};
+use crate::game::octree::{GravitySettings, Octree};
+use crate::game::physics_store::{MassPoints, PhysicsStore};
 use crate::game::resources::{Fixed, fixed};
+use crate::game::spatial_index::SpatialIndex;
+use crate::game::timestep::{self, TimestepSettings};
 use crate::services::metrics::MetricsService;
 use crate::middleware::metrics::PhysicsMetricsRecorder;
 
@@ -50,6 +59,18 @@ impl SimdVector3 {
     }
 }
 
+impl From<Vec3Fixed> for SimdVector3 {
+    fn from(v: Vec3Fixed) -> Self {
+        Self::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<SimdVector3> for Vec3Fixed {
+    fn from(v: SimdVector3) -> Self {
+        Vec3Fixed::new(v.x, v.y, v.z)
+    }
+}
+
 impl std::ops::Add for SimdVector3 {
     type Output = Self;
 
@@ -112,6 +133,99 @@ impl From<&CelestialBody> for SimdCelestialBody {
     }
 }
 
+/// 力の計算カーネルの選び方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KernelSelection {
+    /// 実行時にCPUの対応命令を調べて選ぶ
+    #[default]
+    Auto,
+    /// 常にスカラー版（命令セットに依存しない結果が必要な場合）
+    Scalar,
+    /// 常にレーン並列版
+    Simd,
+}
+
+impl KernelSelection {
+    /// 実際に使うカーネル
+    pub fn resolve(self) -> ForceKernel {
+        match self {
+            KernelSelection::Auto => ForceKernel::detect(),
+            KernelSelection::Scalar => ForceKernel::Scalar,
+            KernelSelection::Simd => ForceKernel::best_lanes().unwrap_or(ForceKernel::Simd),
+        }
+    }
+}
+
+/// 力の計算カーネル
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForceKernel {
+    /// 1天体ずつのスカラー計算
+    Scalar,
+    /// `f64x4`による4天体ずつのレーン並列計算（ビルド時の命令セット、x86_64ではSSE2・aarch64ではNEON）
+    Simd,
+    /// レーン並列版をAVXを有効にしてコンパイルしたもの
+    SimdAvx,
+    /// レーン並列版をAVX2とFMAを有効にしてコンパイルしたもの
+    SimdAvx2Fma,
+}
+
+impl ForceKernel {
+    /// 実行中のCPUで使える最も速いカーネル
+    pub fn detect() -> Self {
+        Self::best_lanes().unwrap_or(ForceKernel::Scalar)
+    }
+
+    /// 実行中のCPUで使える最も速いレーン並列版（ベクトル命令がなければ`None`）
+    fn best_lanes() -> Option<Self> {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if avx2_fma_available() {
+                Some(ForceKernel::SimdAvx2Fma)
+            } else if avx_available() {
+                Some(ForceKernel::SimdAvx)
+            } else if cfg!(target_feature = "sse2") {
+                Some(ForceKernel::Simd)
+            } else {
+                None
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            // NEONはaarch64の基本命令セットに含まれる
+            Some(ForceKernel::Simd)
+        }
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            None
+        }
+    }
+
+    /// 実行中のCPUで使えるか（使えないカーネルが設定された場合はビルド時の命令セットの版で計算する）
+    pub fn is_supported(self) -> bool {
+        match self {
+            ForceKernel::Scalar | ForceKernel::Simd => true,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            ForceKernel::SimdAvx => avx_available(),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            ForceKernel::SimdAvx2Fma => avx2_fma_available(),
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            ForceKernel::SimdAvx | ForceKernel::SimdAvx2Fma => false,
+        }
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn avx_available() -> bool {
+    is_x86_feature_detected!("avx")
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn avx2_fma_available() -> bool {
+    is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+}
+
 /// SIMD最適化物理演算エンジン
 pub struct SimdPhysicsEngine {
     pub gravitational_constant: f64,
@@ -120,6 +234,17 @@ pub struct SimdPhysicsEngine {
     pub max_velocity: f64,
     pub simd_threshold: usize, // SIMD使用開始の閾値
     pub direct_threshold: usize, // 直接計算の閾値
+    pub theta: f64, // Barnes-Hut近似パラメータ
+    pub integrator: IntegratorKind,
+    pub timestep: TimestepSettings,
+    /// 力の計算カーネル
+    pub force_kernel: ForceKernel,
+    /// `update_optimized`で使う作業用の配列（天体数が変わらなければ使い回す）
+    store: PhysicsStore,
+    /// 分割数の判定に使う近傍検索用のインデックス
+    spatial_index: SpatialIndex,
+    /// 直前の更新で接近している組の天体を進めた分割数
+    last_substeps: u32,
     metrics_recorder: PhysicsMetricsRecorder,
 }
 
@@ -133,273 +258,211 @@ impl SimdPhysicsEngine {
             max_velocity: 0.1 * 299792458.0,
             simd_threshold: 16, // 16体以上でSIMD使用
             direct_threshold: 1000, // 1000体以下で直接計算
+            theta: 0.5,
+            integrator: IntegratorKind::default(),
+            timestep: TimestepSettings::default(),
+            force_kernel: ForceKernel::detect(),
+            store: PhysicsStore::new(),
+            spatial_index: SpatialIndex::new(),
+            last_substeps: 0,
             metrics_recorder: PhysicsMetricsRecorder::new(metrics_service),
         }
     }
 
     /// 物理演算の更新（最適化版）
+    ///
+    /// 天体側の位置と速度を正として作業用の配列に読み込み、進めたあと書き戻す。
+    /// 天体を所有するシミュレーションは`update_store`で配列を直接進める。
     #[instrument(skip(self, bodies), fields(body_count = bodies.len()))]
     pub fn update_optimized(&mut self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<()> {
         if bodies.is_empty() {
             return Ok(());
         }
 
+        let mut store = std::mem::take(&mut self.store);
+        let mut spatial_index = std::mem::take(&mut self.spatial_index);
+        store.load(bodies);
+        spatial_index.sync(bodies);
+
+        let result = self.update_store(&mut store, &spatial_index, delta_time);
+        store.publish(bodies);
+
+        self.store = store;
+        self.spatial_index = spatial_index;
+        result
+    }
+
+    /// 配列上で物理演算を進める（位置と速度はその場で更新し、天体の複製やティックごとの確保はしない）
+    ///
+    /// `spatial_index`は分割数の判定で近傍を探すのに使う。
+    #[instrument(skip(self, store, spatial_index), fields(body_count = store.len()))]
+    pub fn update_store(&mut self, store: &mut PhysicsStore, spatial_index: &SpatialIndex, delta_time: f64) -> Result<()> {
+        if store.is_empty() {
+            return Ok(());
+        }
+
         let timer_id = self.metrics_recorder.start_timer();
         
-        let body_count = bodies.len();
+        let body_count = store.len();
         debug!("[PHYSICS_SIMD] Starting SIMD physics update for {} bodies", body_count);
 
-        // アルゴリズム選択
-        let result = if body_count <= self.direct_threshold {
-            if body_count >= self.simd_threshold {
-                self.calculate_gravity_simd_parallel(bodies, delta_time)
-            } else {
-                self.calculate_gravity_direct_simd(bodies, delta_time)
-            }
-        } else {
-            // 大量の天体の場合はBarnes-Hutを使用（既存実装）
-            self.calculate_gravity_barnes_hut_simd(bodies, delta_time)
-        };
+        // 接近している組の天体だけティックを分割する
+        let plan = timestep::plan_substeps(&*store, spatial_index, delta_time, self.gravitational_constant, &self.timestep);
+        self.last_substeps = plan.substeps;
 
-        // 位置更新
-        self.update_positions_simd(bodies, delta_time);
+        // 選択された積分法で重力と位置を時間積分
+        let mut state = StorePhaseSpace { engine: self, store, active: None };
+        plan.advance(self.integrator.integrator(), &mut state, delta_time);
 
-        let collision_checks = self.apply_velocity_limits_simd(bodies);
+        let collision_checks = store.limit_speed(self.max_velocity);
         let duration = self.metrics_recorder.end_timer(&timer_id, body_count, collision_checks);
 
         debug!("[PHYSICS_SIMD] SIMD physics update completed in {:.3}ms", duration * 1000.0);
-        result
+        Ok(())
     }
 
-    /// SIMD並列重力計算
-    fn calculate_gravity_simd_parallel(&self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<()> {
-        // 天体データをSIMD形式に変換
-        let simd_bodies: Vec<SimdCelestialBody> = bodies.values().map(|b| b.into()).collect();
-        
-        // 並列でSIMD計算
-        let forces: Vec<_> = simd_bodies.par_chunks(4)
-            .flat_map(|chunk| {
-                self.calculate_forces_simd_chunk(chunk, &simd_bodies)
-            })
-            .collect();
-
-        // 力を元のボディに適用
-        for (force, (id, body)) in forces.iter().zip(bodies.iter_mut()) {
-            let acceleration = *force * (1.0 / fixed::to_f64(body.physics.mass));
-            
-            body.physics.velocity.x += acceleration.x * delta_time;
-            body.physics.velocity.y += acceleration.y * delta_time;
-            body.physics.velocity.z += acceleration.z * delta_time;
+    /// 天体数に応じたアルゴリズムで重力加速度を計算し、配列の加速度欄に書き込む
+    fn calculate_accelerations(&self, store: &mut PhysicsStore) {
+        if store.len() <= self.direct_threshold {
+            let parallel = store.len() >= self.simd_threshold;
+            let (points, ax, ay, az) = store.split_accelerations();
+            if parallel {
+                self.calculate_accelerations_direct_parallel(points, ax, ay, az);
+            } else {
+                self.calculate_accelerations_direct(points, ax, ay, az);
+            }
+        } else {
+            // 大量の天体の場合はBarnes-Hutを使用
+            self.calculate_accelerations_barnes_hut(store);
         }
-
-        Ok(())
     }
 
-    /// SIMD直接重力計算
-    fn calculate_gravity_direct_simd(&self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<()> {
-        let body_ids: Vec<BodyId> = bodies.keys().copied().collect();
-        let mut forces: HashMap<BodyId, SimdVector3> = HashMap::new();
-
-        // 各ボディに対して力を計算
-        for &id1 in &body_ids {
-            let body1 = &bodies[&id1];
-            let pos1 = SimdVector3::new(
-                body1.physics.position.x,
-                body1.physics.position.y,
-                body1.physics.position.z,
-            );
-            let mass1 = fixed::to_f64(body1.physics.mass);
-
-            let mut total_force = SimdVector3::zero();
-
-            // 他の全ボディとの相互作用を計算
-            for &id2 in &body_ids {
-                if id1 == id2 { continue; }
-
-                let body2 = &bodies[&id2];
-                let pos2 = SimdVector3::new(
-                    body2.physics.position.x,
-                    body2.physics.position.y,
-                    body2.physics.position.z,
-                );
-                let mass2 = fixed::to_f64(body2.physics.mass);
-
-                let force = self.calculate_gravitational_force_simd(pos1, mass1, pos2, mass2);
-                total_force += force;
-            }
+    /// 直接計算（天体ごとに並列）
+    fn calculate_accelerations_direct_parallel(&self, points: MassPoints<'_>, ax: &mut [f64], ay: &mut [f64], az: &mut [f64]) {
+        ax.par_iter_mut()
+            .zip(ay.par_iter_mut())
+            .zip(az.par_iter_mut())
+            .enumerate()
+            .for_each(|(index, ((ax, ay), az))| {
+                (*ax, *ay, *az) = self.acceleration_at(points, index);
+            });
+    }
 
-            forces.insert(id1, total_force);
+    /// 直接計算
+    fn calculate_accelerations_direct(&self, points: MassPoints<'_>, ax: &mut [f64], ay: &mut [f64], az: &mut [f64]) {
+        for (index, ((ax, ay), az)) in ax.iter_mut().zip(ay.iter_mut()).zip(az.iter_mut()).enumerate() {
+            (*ax, *ay, *az) = self.acceleration_at(points, index);
         }
+    }
 
-        // 力を速度に反映
-        for (id, force) in forces {
-            if let Some(body) = bodies.get_mut(&id) {
-                let acceleration = force * (1.0 / fixed::to_f64(body.physics.mass));
-                
-                body.physics.velocity.x += acceleration.x * delta_time;
-                body.physics.velocity.y += acceleration.y * delta_time;
-                body.physics.velocity.z += acceleration.z * delta_time;
-            }
+    /// 1つの天体が他の全天体から受ける重力加速度
+    fn acceleration_at(&self, points: MassPoints<'_>, index: usize) -> (f64, f64, f64) {
+        match self.force_kernel {
+            ForceKernel::Scalar => self.acceleration_at_scalar(points, index, 0),
+            // SAFETY: 実行中のCPUがAVX2とFMAに対応していることを確かめてから呼ぶ
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            ForceKernel::SimdAvx2Fma if avx2_fma_available() => unsafe { self.acceleration_at_avx2_fma(points, index) },
+            // SAFETY: 実行中のCPUがAVXに対応していることを確かめてから呼ぶ
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            ForceKernel::SimdAvx if avx_available() => unsafe { self.acceleration_at_avx(points, index) },
+            // 対応していない命令の版が設定された場合もビルド時の命令セットの版で計算する
+            _ => self.acceleration_at_simd(points, index),
         }
-
-        Ok(())
     }
 
-    /// Barnes-Hut + SIMD最適化
-    fn calculate_gravity_barnes_hut_simd(&self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) -> Result<()> {
-        // Barnes-Hut木を構築（既存の実装を使用）
-        // ここではリーフノードでの力計算にSIMDを適用
-        
-        info!("[PHYSICS_SIMD] Using Barnes-Hut with SIMD optimization for {} bodies", bodies.len());
-        
-        // 簡略化：既存のBarnes-Hut実装を呼び出し
-        // 実際の実装では、木の各ノードでの計算にSIMDを適用
-        self.calculate_gravity_direct_simd(bodies, delta_time)
+    /// レーン並列版をAVX2とFMAを有効にしてコンパイルしたもの
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn acceleration_at_avx2_fma(&self, points: MassPoints<'_>, index: usize) -> (f64, f64, f64) {
+        self.acceleration_at_simd(points, index)
     }
 
-    /// SIMDチャンクの力計算
-    fn calculate_forces_simd_chunk(&self, chunk: &[SimdCelestialBody], all_bodies: &[SimdCelestialBody]) -> Vec<SimdVector3> {
-        let mut forces = Vec::with_capacity(chunk.len());
-
-        for body in chunk {
-            let mut total_force = SimdVector3::zero();
-
-            // 4体ずつまとめて計算
-            for other_chunk in all_bodies.chunks(4) {
-                let force = self.calculate_force_simd_vectorized(body, other_chunk);
-                total_force += force;
-            }
+    /// レーン並列版をAVXを有効にしてコンパイルしたもの
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx")]
+    unsafe fn acceleration_at_avx(&self, points: MassPoints<'_>, index: usize) -> (f64, f64, f64) {
+        self.acceleration_at_simd(points, index)
+    }
 
-            forces.push(total_force);
+    /// 4天体ずつレーン並列で計算（端数はスカラー版で足す）
+    ///
+    /// 呼び出し元の命令セットでコンパイルされるよう常にインライン展開する。
+    #[inline(always)]
+    fn acceleration_at_simd(&self, points: MassPoints<'_>, index: usize) -> (f64, f64, f64) {
+        let softening_sq = f64x4::splat(self.softening_factor * self.softening_factor);
+        let gravitational_constant = f64x4::splat(self.gravitational_constant);
+        let (px, py, pz) = (f64x4::splat(points.x[index]), f64x4::splat(points.y[index]), f64x4::splat(points.z[index]));
+        let own_index = f64x4::splat(index as f64);
+        let zero = f64x4::splat(0.0);
+        let (mut ax, mut ay, mut az) = (zero, zero, zero);
+
+        let lanes = points.x.len() / 4 * 4;
+        for base in (0..lanes).step_by(4) {
+            let dx = lane(points.x, base) - px;
+            let dy = lane(points.y, base) - py;
+            let dz = lane(points.z, base) - pz;
+            let distance_sq = dx * dx + dy * dy + dz * dz;
+
+            // 自分自身と近すぎる天体のレーンは0にする
+            let lane_index = f64x4::new([0.0, 1.0, 2.0, 3.0]) + base as f64;
+            let active = lane_index.cmp_ne(own_index) & distance_sq.cmp_ge(softening_sq);
+            let scale = gravitational_constant * lane(points.mass, base) / ((distance_sq + softening_sq) * distance_sq.sqrt());
+            let scale = active.blend(scale, zero);
+
+            ax += dx * scale;
+            ay += dy * scale;
+            az += dz * scale;
         }
 
-        forces
+        let (rx, ry, rz) = self.acceleration_at_scalar(points, index, lanes);
+        (ax.reduce_add() + rx, ay.reduce_add() + ry, az.reduce_add() + rz)
     }
 
-    /// SIMDベクトル化された力計算
-    fn calculate_force_simd_vectorized(&self, body: &SimdCelestialBody, others: &[SimdCelestialBody]) -> SimdVector3 {
-        let mut total_force = SimdVector3::zero();
+    /// `start`番目以降の天体からの寄与を1天体ずつ計算
+    fn acceleration_at_scalar(&self, points: MassPoints<'_>, index: usize, start: usize) -> (f64, f64, f64) {
+        let softening_sq = self.softening_factor * self.softening_factor;
+        let (px, py, pz) = (points.x[index], points.y[index], points.z[index]);
+        let mut acceleration = (0.0, 0.0, 0.0);
 
-        // SIMDレジスタに位置とマスを読み込み
-        let body_pos = f64x4::new([body.position.x, body.position.y, body.position.z, 0.0]);
-        let body_mass = f64x4::splat(body.mass);
+        for other in start..points.x.len() {
+            if other == index { continue; }
 
-        for other in others {
-            if body.id == other.id { continue; }
-
-            // 相対位置ベクトル
-            let other_pos = f64x4::new([other.position.x, other.position.y, other.position.z, 0.0]);
-            let r_vec = other_pos - body_pos;
-
-            // 距離の二乗を計算
-            let r_squared = r_vec * r_vec;
-            let distance_sq = r_squared.extract(0) + r_squared.extract(1) + r_squared.extract(2);
-
-            if distance_sq < self.softening_factor * self.softening_factor {
+            let (dx, dy, dz) = (points.x[other] - px, points.y[other] - py, points.z[other] - pz);
+            let distance_sq = dx * dx + dy * dy + dz * dz;
+            if distance_sq < softening_sq {
                 continue;
             }
 
-            let distance = distance_sq.sqrt();
-            let force_magnitude = self.gravitational_constant * body.mass * other.mass / 
-                                 (distance_sq + self.softening_factor * self.softening_factor);
-
-            // 力の方向
-            let force_direction = SimdVector3::new(
-                r_vec.extract(0) / distance,
-                r_vec.extract(1) / distance,
-                r_vec.extract(2) / distance,
-            );
-
-            total_force += force_direction * force_magnitude;
+            // 質量で割った力（方向ベクトルの正規化も含める）
+            let scale = self.gravitational_constant * points.mass[other] / ((distance_sq + softening_sq) * distance_sq.sqrt());
+            acceleration.0 += dx * scale;
+            acceleration.1 += dy * scale;
+            acceleration.2 += dz * scale;
         }
 
-        total_force
+        acceleration
     }
 
-    /// SIMD重力計算（2体間）
-    fn calculate_gravitational_force_simd(&self, pos1: SimdVector3, mass1: f64, pos2: SimdVector3, mass2: f64) -> SimdVector3 {
-        let r = pos2 - pos1;
-        let distance_sq = r.magnitude_squared();
-
-        if distance_sq < self.softening_factor * self.softening_factor {
-            return SimdVector3::zero();
-        }
-
-        let distance = distance_sq.sqrt();
-        let force_magnitude = self.gravitational_constant * mass1 * mass2 / 
-                             (distance_sq + self.softening_factor * self.softening_factor);
+    /// Barnes-Hut近似
+    fn calculate_accelerations_barnes_hut(&self, store: &mut PhysicsStore) {
+        info!("[PHYSICS_SIMD] Using Barnes-Hut with SIMD optimization for {} bodies", store.len());
 
-        r.normalize() * force_magnitude
-    }
+        // スカラー版と共通のオクツリーを並列構築
+        store.gather_tree_positions();
+        let tree = Octree::from_points(store.ids(), &store.tree_positions, &store.mass);
 
-    /// SIMD位置更新
-    fn update_positions_simd(&self, bodies: &mut HashMap<BodyId, CelestialBody>, delta_time: f64) {
-        // 4体ずつまとめて位置を更新
-        let body_ids: Vec<BodyId> = bodies.keys().copied().collect();
-        
-        for chunk in body_ids.chunks(4) {
-            // SIMDレジスタに速度とデルタタイムを読み込み
-            let dt = f64x4::splat(delta_time);
-            
-            for &id in chunk {
-                if let Some(body) = bodies.get_mut(&id) {
-                    // SIMD演算で位置更新
-                    let velocity = f64x4::new([
-                        body.physics.velocity.x,
-                        body.physics.velocity.y,
-                        body.physics.velocity.z,
-                        0.0
-                    ]);
-                    
-                    let position = f64x4::new([
-                        body.physics.position.x,
-                        body.physics.position.y,
-                        body.physics.position.z,
-                        0.0
-                    ]);
-                    
-                    let new_position = position + velocity * dt;
-                    
-                    body.physics.position.x = new_position.extract(0);
-                    body.physics.position.y = new_position.extract(1);
-                    body.physics.position.z = new_position.extract(2);
-                }
-            }
-        }
-    }
-
-    /// SIMD速度制限適用
-    fn apply_velocity_limits_simd(&self, bodies: &mut HashMap<BodyId, CelestialBody>) -> usize {
-        let max_vel = f64x4::splat(self.max_velocity);
-        let mut collision_checks = 0;
+        let settings = GravitySettings {
+            gravitational_constant: self.gravitational_constant,
+            softening_factor: self.softening_factor,
+            theta: self.theta,
+        };
 
-        for body in bodies.values_mut() {
-            let velocity = f64x4::new([
-                body.physics.velocity.x,
-                body.physics.velocity.y,
-                body.physics.velocity.z,
-                0.0
-            ]);
-
-            // 速度の大きさを計算
-            let vel_squared = velocity * velocity;
-            let vel_magnitude_sq = vel_squared.extract(0) + vel_squared.extract(1) + vel_squared.extract(2);
-            let vel_magnitude = vel_magnitude_sq.sqrt();
-
-            if vel_magnitude > self.max_velocity {
-                // 正規化して制限速度を適用
-                let normalized = velocity * f64x4::splat(1.0 / vel_magnitude);
-                let limited_velocity = normalized * max_vel;
-
-                body.physics.velocity.x = limited_velocity.extract(0);
-                body.physics.velocity.y = limited_velocity.extract(1);
-                body.physics.velocity.z = limited_velocity.extract(2);
-                
-                collision_checks += 1;
-            }
+        let accelerations = tree.accelerations_for(store.ids(), &store.tree_positions, &settings);
+        for (slot, acceleration) in accelerations.iter().enumerate() {
+            store.ax[slot] = acceleration.x;
+            store.ay[slot] = acceleration.y;
+            store.az[slot] = acceleration.z;
         }
-
-        collision_checks
     }
 
     /// パフォーマンス統計を取得
@@ -409,16 +472,70 @@ impl SimdPhysicsEngine {
             direct_threshold: self.direct_threshold,
             gravitational_constant: self.gravitational_constant,
             softening_factor: self.softening_factor,
+            theta: self.theta,
+            integrator: self.integrator,
+            force_kernel: self.force_kernel,
+            substeps: self.last_substeps,
+            max_substeps: self.timestep.max_substeps,
+        }
+    }
+
+    /// Barnes-Hut近似の開き角を設定
+    pub fn set_theta(&mut self, theta: f64) {
+        let theta = theta.clamp(0.1, 2.0);
+        if theta != self.theta {
+            self.theta = theta;
+            self.store.invalidate_forces();
         }
     }
 
     /// 設定を更新
     pub fn configure(&mut self, config: SimdPhysicsConfig) {
+        self.store.invalidate_forces();
         self.simd_threshold = config.simd_threshold;
         self.direct_threshold = config.direct_threshold;
         self.gravitational_constant = config.gravitational_constant;
         self.softening_factor = config.softening_factor;
         self.max_velocity = config.max_velocity;
+        self.set_theta(config.theta);
+        self.integrator = config.integrator;
+        self.timestep = config.timestep;
+        self.force_kernel = config.kernel.resolve();
+    }
+}
+
+/// 配列の`base`番目から4要素を読み込む
+#[inline(always)]
+fn lane(values: &[f64], base: usize) -> f64x4 {
+    f64x4::new([values[base], values[base + 1], values[base + 2], values[base + 3]])
+}
+
+/// 配列上の天体の位相空間（加速度はエンジンの設定で計算する）
+///
+/// 配列の加速度欄は位置が変わるまで有効なので、ベルレ法の最後のkickで計算した加速度を
+/// 次のステップ（次のティックを含む）の最初のkickでそのまま使う。
+struct StorePhaseSpace<'a> {
+    engine: &'a SimdPhysicsEngine,
+    store: &'a mut PhysicsStore,
+    /// kickとdriftで進める天体（`None`なら全天体）
+    active: Option<Vec<bool>>,
+}
+
+impl PhaseSpace for StorePhaseSpace<'_> {
+    fn kick(&mut self, dt: f64) {
+        if self.store.forces_stale() {
+            self.engine.calculate_accelerations(self.store);
+            self.store.mark_forces_current();
+        }
+        self.store.kick(dt, self.active.as_deref());
+    }
+
+    fn drift(&mut self, dt: f64) {
+        self.store.drift(dt, self.active.as_deref());
+    }
+
+    fn restrict(&mut self, active: Option<Vec<bool>>) {
+        self.active = active;
     }
 }
 
@@ -430,6 +547,22 @@ pub struct SimdPhysicsConfig {
     pub gravitational_constant: f64,
     pub softening_factor: f64,
     pub max_velocity: f64,
+    /// Barnes-Hut近似の開き角
+    #[serde(default = "default_theta")]
+    pub theta: f64,
+    /// 時間積分法
+    #[serde(default)]
+    pub integrator: IntegratorKind,
+    /// 接近時のティック分割
+    #[serde(default)]
+    pub timestep: TimestepSettings,
+    /// 力の計算カーネル
+    #[serde(default)]
+    pub kernel: KernelSelection,
+}
+
+fn default_theta() -> f64 {
+    0.5
 }
 
 impl Default for SimdPhysicsConfig {
@@ -440,6 +573,10 @@ impl Default for SimdPhysicsConfig {
             gravitational_constant: 6.67430e-11,
             softening_factor: 1e-3,
             max_velocity: 0.1 * 299792458.0,
+            theta: default_theta(),
+            integrator: IntegratorKind::default(),
+            timestep: TimestepSettings::default(),
+            kernel: KernelSelection::default(),
         }
     }
 }
@@ -451,6 +588,13 @@ pub struct PhysicsPerformanceStats {
     pub direct_threshold: usize,
     pub gravitational_constant: f64,
     pub softening_factor: f64,
+    pub theta: f64,
+    pub integrator: IntegratorKind,
+    /// 使用中の力の計算カーネル
+    pub force_kernel: ForceKernel,
+    /// 直前の更新でティックを分割した数
+    pub substeps: u32,
+    pub max_substeps: u32,
 }
 
 #[cfg(test)]
@@ -516,4 +660,166 @@ mod tests {
         assert_eq!(engine.simd_threshold, 16);
         assert_eq!(engine.direct_threshold, 1000);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_barnes_hut_simd_matches_direct() {
+        let metrics_config = crate::services::metrics::MetricsConfig::default();
+        let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
+        let mut engine = SimdPhysicsEngine::new(metrics_service);
+        engine.set_theta(0.3);
+
+        let bodies: HashMap<BodyId, CelestialBody> = (0..400).map(|i| {
+            let position = nalgebra::Vector3::new((i % 7) as f64 * 13.0, (i % 11) as f64 * 7.0, (i % 13) as f64 * 5.0 + i as f64 * 0.1);
+            let body = CelestialBody::new(
+                Uuid::new_v4(),
+                CelestialType::Asteroid,
+                position,
+                fixed::from_f64(1.0e6),
+                fixed::from_f64(0.1),
+            );
+            (body.id, body)
+        }).collect();
+
+        let mut direct = PhysicsStore::from_bodies(&bodies);
+        let (points, ax, ay, az) = direct.split_accelerations();
+        engine.calculate_accelerations_direct(points, ax, ay, az);
+        let mut barnes_hut = PhysicsStore::from_bodies(&bodies);
+        engine.calculate_accelerations_barnes_hut(&mut barnes_hut);
+
+        let acceleration = |store: &PhysicsStore, slot: usize| Vec3Fixed::new(store.ax[slot], store.ay[slot], store.az[slot]);
+        let scale = (0..direct.len()).map(|slot| acceleration(&direct, slot).magnitude()).fold(0.0, f64::max);
+        for slot in 0..direct.len() {
+            let error = (acceleration(&barnes_hut, slot) - acceleration(&direct, slot)).magnitude() / scale;
+            assert!(error < 1e-2, "relative error {} too large", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_update_matches_body_update() {
+        let metrics_config = crate::services::metrics::MetricsConfig::default();
+        let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
+        let mut engine = SimdPhysicsEngine::new(metrics_service);
+        engine.gravitational_constant = 1.0;
+
+        let mut bodies: HashMap<BodyId, CelestialBody> = (0..20).map(|i| {
+            let angle = i as f64 * 0.3;
+            let mut body = CelestialBody::new(
+                Uuid::new_v4(),
+                CelestialType::Asteroid,
+                nalgebra::Vector3::new(100.0 * angle.cos(), 100.0 * angle.sin(), i as f64),
+                fixed::from_f64(1000.0),
+                fixed::from_f64(1.0),
+            );
+            body.physics.velocity = nalgebra::Vector3::new(-angle.sin(), angle.cos(), 0.0);
+            (body.id, body)
+        }).collect();
+
+        // シミュレーションが所有する配列を直接進めても、天体経由で進めた結果と一致する
+        let mut store = PhysicsStore::from_bodies(&bodies);
+        let mut spatial_index = SpatialIndex::new();
+        spatial_index.sync(&bodies);
+        for _ in 0..10 {
+            engine.update_store(&mut store, &spatial_index, 0.05).unwrap();
+        }
+        for _ in 0..10 {
+            engine.update_optimized(&mut bodies, 0.05).unwrap();
+        }
+
+        for (slot, body_id) in store.ids().iter().enumerate() {
+            let body = &bodies[body_id];
+            assert!((store.position(slot) - body.physics.position).magnitude() < 1e-9);
+            assert!((store.velocity(slot) - body.physics.velocity).magnitude() < 1e-9);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_update_reuses_closing_accelerations() {
+        let metrics_config = crate::services::metrics::MetricsConfig::default();
+        let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
+        let mut engine = SimdPhysicsEngine::new(metrics_service);
+        engine.gravitational_constant = 1.0;
+
+        let bodies: HashMap<BodyId, CelestialBody> = (0..12).map(|i| {
+            let angle = i as f64 * 0.5;
+            let mut body = CelestialBody::new(
+                Uuid::new_v4(),
+                CelestialType::Asteroid,
+                nalgebra::Vector3::new(80.0 * angle.cos(), 80.0 * angle.sin(), 0.0),
+                fixed::from_f64(500.0),
+                fixed::from_f64(1.0),
+            );
+            body.physics.velocity = nalgebra::Vector3::new(-angle.sin(), angle.cos(), 0.0);
+            (body.id, body)
+        }).collect();
+        let mut spatial_index = SpatialIndex::new();
+        spatial_index.sync(&bodies);
+
+        // ティックの終わりに計算した加速度は、次のティックの最初のkickまで有効なまま残る
+        let mut reused = PhysicsStore::from_bodies(&bodies);
+        let mut recomputed = PhysicsStore::from_bodies(&bodies);
+        for _ in 0..10 {
+            engine.update_store(&mut reused, &spatial_index, 0.05).unwrap();
+            assert!(!reused.forces_stale());
+
+            recomputed.invalidate_forces();
+            engine.update_store(&mut recomputed, &spatial_index, 0.05).unwrap();
+        }
+
+        for slot in 0..reused.len() {
+            assert_eq!(reused.position(slot), recomputed.position(slot));
+            assert_eq!(reused.velocity(slot), recomputed.velocity(slot));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_simd_kernel_matches_scalar() {
+        let metrics_config = crate::services::metrics::MetricsConfig::default();
+        let metrics_service = Arc::new(crate::services::metrics::MetricsService::new(metrics_config).unwrap());
+        let mut engine = SimdPhysicsEngine::new(metrics_service);
+        engine.gravitational_constant = 1.0;
+
+        // 4で割り切れない天体数で端数の処理も確かめる。重なった2天体は軟化長より近いので寄与しない
+        let mut bodies: HashMap<BodyId, CelestialBody> = (0..103).map(|i| {
+            let angle = i as f64 * 0.7;
+            let body = CelestialBody::new(
+                Uuid::new_v4(),
+                CelestialType::Asteroid,
+                nalgebra::Vector3::new(50.0 * angle.cos() + i as f64, 50.0 * angle.sin(), (i % 9) as f64 * 3.0),
+                fixed::from_f64(100.0 + i as f64),
+                fixed::from_f64(0.5),
+            );
+            (body.id, body)
+        }).collect();
+        let twin = CelestialBody::new(Uuid::new_v4(), CelestialType::Asteroid, nalgebra::Vector3::new(50.0, 0.0, 0.0), fixed::from_f64(100.0), fixed::from_f64(0.5));
+        bodies.insert(twin.id, twin);
+
+        let accelerations = |engine: &mut SimdPhysicsEngine, kernel: ForceKernel| {
+            engine.force_kernel = kernel;
+            let mut store = PhysicsStore::from_bodies(&bodies);
+            let (points, ax, ay, az) = store.split_accelerations();
+            engine.calculate_accelerations_direct(points, ax, ay, az);
+            (0..store.len()).map(|slot| Vec3Fixed::new(store.ax[slot], store.ay[slot], store.az[slot])).collect::<Vec<_>>()
+        };
+        let scalar = accelerations(&mut engine, ForceKernel::Scalar);
+        let scale = scalar.iter().map(|a| a.magnitude()).fold(0.0, f64::max);
+
+        // 実行中のCPUで使えるレーン並列版をすべて同じ入力で比べる
+        let kernels = [ForceKernel::Simd, ForceKernel::SimdAvx, ForceKernel::SimdAvx2Fma];
+        for kernel in kernels.into_iter().filter(|kernel| kernel.is_supported()) {
+            let lanes = accelerations(&mut engine, kernel);
+
+            // 加算の順序が違うだけなので、最大の加速度に対する丸め誤差の範囲で一致する
+            for (expected, actual) in scalar.iter().zip(&lanes) {
+                assert!(actual.iter().all(|component| component.is_finite()));
+                let error = (actual - expected).magnitude() / scale;
+                assert!(error < 1e-12, "{:?}: relative error {} too large", kernel, error);
+            }
+        }
+
+        // レーン並列版を指定すると、使える版のうち最も速いものが選ばれる
+        let forced = KernelSelection::Simd.resolve();
+        assert_ne!(forced, ForceKernel::Scalar);
+        assert!(forced.is_supported());
+        assert_eq!(KernelSelection::Scalar.resolve(), ForceKernel::Scalar);
+    }
+}
@@ -0,0 +1,373 @@
+//! 物理状態のStructure of Arrays
+//!
+//! 位置・速度・質量・半径を成分ごとの連続した`Vec<f64>`で持ち、力の計算と時間積分をその場で行う。
+//! 天体のメタデータ（種類・生命・資源など）は`CelestialBody`側に残し、位置と速度はこちらが正とする。
+//! 毎ティック天体を複製して配列を組み立てる代わりに、天体の増減だけを反映し、積分後に位置と速度を
+//! `CelestialBody`へ書き戻す。並びは天体ID順で、実行ごとに同じ順序で計算する。
+
+use std::collections::HashMap;
+
+use crate::game::celestial_bodies::{BodyId, CelestialBody, Vec3Fixed};
+use crate::game::resources::fixed;
+use crate::game::integrator::is_active;
+use crate::game::timestep::{BodySlots, PairState};
+
+/// 力の計算で読み取る質点の成分
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MassPoints<'a> {
+    pub x: &'a [f64],
+    pub y: &'a [f64],
+    pub z: &'a [f64],
+    pub mass: &'a [f64],
+}
+
+/// 天体の物理状態（成分ごとの配列）
+#[derive(Debug, Clone, Default)]
+pub struct PhysicsStore {
+    ids: Vec<BodyId>,
+    slots: HashMap<BodyId, usize>,
+    pub(crate) x: Vec<f64>,
+    pub(crate) y: Vec<f64>,
+    pub(crate) z: Vec<f64>,
+    pub(crate) vx: Vec<f64>,
+    pub(crate) vy: Vec<f64>,
+    pub(crate) vz: Vec<f64>,
+    pub(crate) mass: Vec<f64>,
+    pub(crate) radius: Vec<f64>,
+    /// 加速度の計算結果（位置と質量が変わるまで次のkickで使い回す）
+    pub(crate) ax: Vec<f64>,
+    pub(crate) ay: Vec<f64>,
+    pub(crate) az: Vec<f64>,
+    /// オクツリー構築用の位置（天体数が多いときだけ使う）
+    pub(crate) tree_positions: Vec<Vec3Fixed>,
+    /// 最後に加速度を計算してから位置・質量・顔ぶれが変わったか
+    positions_changed: bool,
+}
+
+impl PhysicsStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 天体群から作成
+    pub fn from_bodies(bodies: &HashMap<BodyId, CelestialBody>) -> Self {
+        let mut store = Self::new();
+        store.load(bodies);
+        store
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// 天体ID（格納順）
+    pub fn ids(&self) -> &[BodyId] {
+        &self.ids
+    }
+
+    /// 天体の格納位置
+    pub fn slot(&self, body_id: BodyId) -> Option<usize> {
+        self.slots.get(&body_id).copied()
+    }
+
+    pub fn position(&self, slot: usize) -> Vec3Fixed {
+        Vec3Fixed::new(self.x[slot], self.y[slot], self.z[slot])
+    }
+
+    pub fn velocity(&self, slot: usize) -> Vec3Fixed {
+        Vec3Fixed::new(self.vx[slot], self.vy[slot], self.vz[slot])
+    }
+
+    /// 加速度欄が現在の位置と質量に対して古いか
+    pub(crate) fn forces_stale(&self) -> bool {
+        self.positions_changed
+    }
+
+    /// 加速度欄を現在の位置と質量で計算し直したことを記録
+    pub(crate) fn mark_forces_current(&mut self) {
+        self.positions_changed = false;
+    }
+
+    /// 次のkickで加速度を計算し直させる（重力の設定を変えた場合など）
+    pub fn invalidate_forces(&mut self) {
+        self.positions_changed = true;
+    }
+
+    /// 天体の追加（登録済みなら物理状態を上書き）
+    pub fn insert(&mut self, body: &CelestialBody) {
+        if let Some(slot) = self.slot(body.id) {
+            self.write(slot, body);
+            return;
+        }
+
+        let slot = self.ids.partition_point(|id| *id < body.id);
+        self.ids.insert(slot, body.id);
+        for column in self.columns_mut() {
+            column.insert(slot, 0.0);
+        }
+        self.write(slot, body);
+        self.reindex_from(slot);
+        self.positions_changed = true;
+    }
+
+    /// 天体の削除
+    pub fn remove(&mut self, body_id: BodyId) -> bool {
+        let Some(slot) = self.slots.remove(&body_id) else {
+            return false;
+        };
+        self.ids.remove(slot);
+        for column in self.columns_mut() {
+            column.remove(slot);
+        }
+        self.reindex_from(slot);
+        self.positions_changed = true;
+        true
+    }
+
+    /// 天体の増減と質量・半径の反映（位置と速度はこちらの値を保つ）
+    ///
+    /// 追加・削除した天体の数を返す。天体の顔ぶれが変わらなければメモリを確保しない。
+    pub fn sync(&mut self, bodies: &HashMap<BodyId, CelestialBody>) -> usize {
+        let removed: Vec<BodyId> = self.ids.iter().filter(|id| !bodies.contains_key(id)).copied().collect();
+        for body_id in &removed {
+            self.remove(*body_id);
+        }
+
+        let mut added = 0;
+        for body in bodies.values() {
+            match self.slot(body.id) {
+                Some(slot) => {
+                    let mass = fixed::to_f64(body.physics.mass);
+                    self.positions_changed |= self.mass[slot] != mass;
+                    self.mass[slot] = mass;
+                    self.radius[slot] = fixed::to_f64(body.physics.radius);
+                }
+                None => {
+                    self.insert(body);
+                    added += 1;
+                }
+            }
+        }
+        removed.len() + added
+    }
+
+    /// 天体群の物理状態をすべて読み込む（天体側の位置と速度を正とする）
+    pub fn load(&mut self, bodies: &HashMap<BodyId, CelestialBody>) {
+        self.sync(bodies);
+        for slot in 0..self.len() {
+            let body_id = self.ids[slot];
+            self.write(slot, &bodies[&body_id]);
+        }
+    }
+
+    /// 位置と速度を天体へ書き戻す
+    pub fn publish(&self, bodies: &mut HashMap<BodyId, CelestialBody>) {
+        for (slot, body_id) in self.ids.iter().enumerate() {
+            if let Some(body) = bodies.get_mut(body_id) {
+                body.physics.position = self.position(slot);
+                body.physics.velocity = self.velocity(slot);
+            }
+        }
+    }
+
+    /// 読み取る質点と書き込む加速度に分けて借用
+    pub(crate) fn split_accelerations(&mut self) -> (MassPoints<'_>, &mut [f64], &mut [f64], &mut [f64]) {
+        let points = MassPoints { x: &self.x, y: &self.y, z: &self.z, mass: &self.mass };
+        (points, &mut self.ax, &mut self.ay, &mut self.az)
+    }
+
+    /// オクツリー構築用に位置を`Vec3Fixed`の配列へ並べ直す（確保済みの領域を使い回す）
+    pub(crate) fn gather_tree_positions(&mut self) {
+        self.tree_positions.clear();
+        self.tree_positions.extend(self.x.iter().zip(&self.y).zip(&self.z).map(|((x, y), z)| Vec3Fixed::new(*x, *y, *z)));
+    }
+
+    /// 現在の速度で位置を進める（`active`が偽の天体は据え置く）
+    pub(crate) fn drift(&mut self, dt: f64, active: Option<&[bool]>) {
+        for (position, velocity) in [(&mut self.x, &self.vx), (&mut self.y, &self.vy), (&mut self.z, &self.vz)] {
+            for (slot, (p, v)) in position.iter_mut().zip(velocity).enumerate() {
+                if is_active(active, slot) {
+                    *p += v * dt;
+                }
+            }
+        }
+        self.positions_changed = true;
+    }
+
+    /// 計算済みの加速度で速度を進める（`active`が偽の天体は据え置く）
+    pub(crate) fn kick(&mut self, dt: f64, active: Option<&[bool]>) {
+        for (velocity, acceleration) in [(&mut self.vx, &self.ax), (&mut self.vy, &self.ay), (&mut self.vz, &self.az)] {
+            for (slot, (v, a)) in velocity.iter_mut().zip(acceleration).enumerate() {
+                if is_active(active, slot) {
+                    *v += a * dt;
+                }
+            }
+        }
+    }
+
+    /// 速さを`max_speed`以下に抑え、抑えた天体の数を返す
+    pub(crate) fn limit_speed(&mut self, max_speed: f64) -> usize {
+        let mut limited = 0;
+        for slot in 0..self.len() {
+            let speed = self.velocity(slot).magnitude();
+            if speed > max_speed {
+                let scale = max_speed / speed;
+                self.vx[slot] *= scale;
+                self.vy[slot] *= scale;
+                self.vz[slot] *= scale;
+                limited += 1;
+            }
+        }
+        limited
+    }
+
+    fn write(&mut self, slot: usize, body: &CelestialBody) {
+        let physics = &body.physics;
+        let mass = fixed::to_f64(physics.mass);
+        self.positions_changed |= self.position(slot) != physics.position || self.mass[slot] != mass;
+        self.x[slot] = physics.position.x;
+        self.y[slot] = physics.position.y;
+        self.z[slot] = physics.position.z;
+        self.vx[slot] = physics.velocity.x;
+        self.vy[slot] = physics.velocity.y;
+        self.vz[slot] = physics.velocity.z;
+        self.mass[slot] = mass;
+        self.radius[slot] = fixed::to_f64(physics.radius);
+    }
+
+    fn reindex_from(&mut self, start: usize) {
+        for (slot, body_id) in self.ids.iter().enumerate().skip(start) {
+            self.slots.insert(*body_id, slot);
+        }
+    }
+
+    fn columns_mut(&mut self) -> [&mut Vec<f64>; 11] {
+        [
+            &mut self.x, &mut self.y, &mut self.z,
+            &mut self.vx, &mut self.vy, &mut self.vz,
+            &mut self.mass, &mut self.radius,
+            &mut self.ax, &mut self.ay, &mut self.az,
+        ]
+    }
+}
+
+impl BodySlots for PhysicsStore {
+    fn body_count(&self) -> usize {
+        self.len()
+    }
+
+    fn slot_of(&self, body_id: BodyId) -> Option<usize> {
+        self.slot(body_id)
+    }
+
+    fn position_of(&self, slot: usize) -> Vec3Fixed {
+        self.position(slot)
+    }
+
+    fn pair(&self, slot: usize, other: usize) -> PairState {
+        PairState {
+            offset: self.position(other) - self.position(slot),
+            relative_velocity: self.velocity(other) - self.velocity(slot),
+            radius_sum: self.radius[slot] + self.radius[other],
+            total_mass: self.mass[slot] + self.mass[other],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::game::celestial_bodies::CelestialType;
+
+    fn body(position: Vec3Fixed) -> CelestialBody {
+        CelestialBody::new(Uuid::new_v4(), CelestialType::Asteroid, position, fixed::from_f64(10.0), fixed::from_f64(1.0))
+    }
+
+    #[test]
+    fn test_store_tracks_membership_in_id_order() {
+        let mut bodies: HashMap<BodyId, CelestialBody> = (0..5)
+            .map(|i| body(Vec3Fixed::new(i as f64 * 10.0, 0.0, 0.0)))
+            .map(|body| (body.id, body))
+            .collect();
+        let mut store = PhysicsStore::from_bodies(&bodies);
+
+        let mut expected: Vec<BodyId> = bodies.keys().copied().collect();
+        expected.sort_unstable();
+        assert_eq!(store.ids(), expected.as_slice());
+        for (slot, body_id) in expected.iter().enumerate() {
+            assert_eq!(store.slot(*body_id), Some(slot));
+            assert_eq!(store.position(slot), bodies[body_id].physics.position);
+        }
+
+        // 顔ぶれが変わらなければ何もしない
+        assert_eq!(store.sync(&bodies), 0);
+
+        let removed = expected[2];
+        bodies.remove(&removed);
+        let added = body(Vec3Fixed::new(99.0, 0.0, 0.0));
+        let added_id = added.id;
+        bodies.insert(added_id, added);
+        assert_eq!(store.sync(&bodies), 2);
+        assert_eq!(store.len(), 5);
+        assert!(store.slot(removed).is_none());
+        assert!(store.ids().windows(2).all(|pair| pair[0] < pair[1]));
+        let slot = store.slot(added_id).unwrap();
+        assert_eq!(store.position(slot), Vec3Fixed::new(99.0, 0.0, 0.0));
+        assert_eq!(store.ids()[slot], added_id);
+    }
+
+    #[test]
+    fn test_store_owns_kinematics_until_published() {
+        let mut bodies: HashMap<BodyId, CelestialBody> = [body(Vec3Fixed::zeros())].into_iter().map(|body| (body.id, body)).collect();
+        let body_id = *bodies.keys().next().unwrap();
+        let mut store = PhysicsStore::from_bodies(&bodies);
+
+        store.vx[0] = 2.0;
+        store.drift(0.5, None);
+        assert_eq!(bodies[&body_id].physics.position, Vec3Fixed::zeros());
+
+        // 同期しても配列側の位置と速度は保たれる
+        store.sync(&bodies);
+        store.publish(&mut bodies);
+        assert_eq!(bodies[&body_id].physics.position, Vec3Fixed::new(1.0, 0.0, 0.0));
+        assert_eq!(bodies[&body_id].physics.velocity, Vec3Fixed::new(2.0, 0.0, 0.0));
+
+        assert_eq!(store.limit_speed(1.0), 1);
+        assert!((store.velocity(0).magnitude() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_forces_stay_current_until_positions_change() {
+        let mut bodies: HashMap<BodyId, CelestialBody> = (0..3)
+            .map(|i| body(Vec3Fixed::new(i as f64 * 10.0, 0.0, 0.0)))
+            .map(|body| (body.id, body))
+            .collect();
+        let mut store = PhysicsStore::from_bodies(&bodies);
+        assert!(store.forces_stale());
+        store.mark_forces_current();
+
+        // 速度だけの変化や同じ状態の読み込みでは計算し直さない
+        store.kick(0.5, None);
+        store.sync(&bodies);
+        store.load(&bodies);
+        assert!(!store.forces_stale());
+
+        store.drift(0.5, None);
+        assert!(store.forces_stale());
+        store.mark_forces_current();
+
+        let body_id = store.ids()[1];
+        bodies.get_mut(&body_id).unwrap().physics.mass = fixed::from_f64(20.0);
+        store.sync(&bodies);
+        assert!(store.forces_stale());
+        store.mark_forces_current();
+
+        store.remove(body_id);
+        assert!(store.forces_stale());
+    }
+}
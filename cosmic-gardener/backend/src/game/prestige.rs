@@ -0,0 +1,305 @@
+//! プレステージ（転生）システム
+//!
+//! 累計獲得リソースからプレステージポイントを算出し、リソース・天体・アップグレードを
+//! 一括でリセットする。獲得ポイントは永続的な生産ボーナスとして`GameState`に保持される。
+
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::errors::{GameError, Result};
+use crate::game::celestial_bodies::CelestialBodyManager;
+use crate::game::resources::{ResourceManager, ResourceType, Resources};
+
+/// プレステージの設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrestigeConfig {
+    /// 累計スコア計算時のリソース別重み
+    pub score_weights: HashMap<ResourceType, f64>,
+    /// ポイント1に必要なスコア
+    pub score_divisor: f64,
+    /// ポイント算出の指数（逓減）
+    pub exponent: f64,
+    /// プレステージに必要な最小獲得ポイント
+    pub min_points: u64,
+    /// ポイントあたりの生産ボーナス
+    pub bonus_per_point: f64,
+}
+
+impl Default for PrestigeConfig {
+    fn default() -> Self {
+        // リーダーボードのスコア計算と同じ重み
+        let score_weights = HashMap::from([
+            (ResourceType::CosmicDust, 1.0),
+            (ResourceType::Energy, 2.0),
+            (ResourceType::OrganicMatter, 5.0),
+            (ResourceType::Biomass, 10.0),
+            (ResourceType::DarkMatter, 50.0),
+            (ResourceType::ThoughtPoints, 100.0),
+        ]);
+
+        Self {
+            score_weights,
+            score_divisor: 1_000_000.0,
+            exponent: 0.5,
+            min_points: 1,
+            bonus_per_point: 0.02, // 1ポイントあたり+2%
+        }
+    }
+}
+
+impl PrestigeConfig {
+    /// 設定の検証
+    pub fn validate(&self) -> Result<()> {
+        if !(self.score_divisor.is_finite() && self.score_divisor > 0.0) {
+            return Err(GameError::validation("score_divisor must be positive"));
+        }
+
+        if !(self.exponent.is_finite() && self.exponent > 0.0) {
+            return Err(GameError::validation("exponent must be positive"));
+        }
+
+        if !(self.bonus_per_point.is_finite() && self.bonus_per_point >= 0.0) {
+            return Err(GameError::validation("bonus_per_point must not be negative"));
+        }
+
+        if self.score_weights.values().any(|weight| !weight.is_finite() || *weight < 0.0) {
+            return Err(GameError::validation("score_weights must not be negative"));
+        }
+
+        Ok(())
+    }
+}
+
+/// 永続的なプレステージ状態
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrestigeState {
+    pub prestige_count: u32,
+    /// これまでに獲得したプレステージポイントの合計
+    pub total_points: u64,
+    /// 永続的な生産倍率
+    pub production_multiplier: f64,
+    pub last_prestige_at: Option<DateTime<Utc>>,
+}
+
+impl Default for PrestigeState {
+    fn default() -> Self {
+        Self {
+            prestige_count: 0,
+            total_points: 0,
+            production_multiplier: 1.0,
+            last_prestige_at: None,
+        }
+    }
+}
+
+/// プレステージ実行前のプレビュー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrestigePreview {
+    pub lifetime_score: f64,
+    pub points_available: u64,
+    pub can_prestige: bool,
+    pub production_multiplier_after: f64,
+}
+
+/// プレステージの記録（履歴として永続化される）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrestigeRecord {
+    pub prestige_number: u32,
+    pub points_awarded: u64,
+    pub total_points: u64,
+    pub production_multiplier: f64,
+    pub lifetime_totals: Resources,
+    pub resources_at_reset: Resources,
+    pub bodies_at_reset: usize,
+    pub performed_at: DateTime<Utc>,
+}
+
+impl PrestigeRecord {
+    /// 記録適用後のプレステージ状態
+    pub fn resulting_state(&self) -> PrestigeState {
+        PrestigeState {
+            prestige_count: self.prestige_number,
+            total_points: self.total_points,
+            production_multiplier: self.production_multiplier,
+            last_prestige_at: Some(self.performed_at),
+        }
+    }
+}
+
+/// プレステージ計算器
+#[derive(Debug, Clone)]
+pub struct PrestigeCalculator {
+    config: PrestigeConfig,
+}
+
+impl PrestigeCalculator {
+    pub fn new(config: PrestigeConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &PrestigeConfig {
+        &self.config
+    }
+
+    /// 累計リソースからスコアを計算
+    pub fn lifetime_score(&self, lifetime_totals: &Resources) -> f64 {
+        ResourceType::all()
+            .into_iter()
+            .map(|resource_type| {
+                let weight = self.config.score_weights.get(&resource_type).copied().unwrap_or(0.0);
+                lifetime_totals.get(resource_type) as f64 * weight
+            })
+            .sum()
+    }
+
+    /// 累計リソースに対応する総ポイント
+    pub fn total_points_for(&self, lifetime_totals: &Resources) -> u64 {
+        let score = self.lifetime_score(lifetime_totals);
+        (score / self.config.score_divisor).powf(self.config.exponent).floor() as u64
+    }
+
+    /// 総ポイントに対応する生産倍率
+    pub fn multiplier_for(&self, total_points: u64) -> f64 {
+        1.0 + total_points as f64 * self.config.bonus_per_point
+    }
+
+    /// プレステージのプレビュー
+    pub fn preview(&self, resource_manager: &ResourceManager) -> PrestigePreview {
+        let game_state = resource_manager.get_game_state();
+        let total_points = self.total_points_for(&game_state.lifetime_totals);
+        let points_available = total_points.saturating_sub(game_state.prestige.total_points);
+
+        PrestigePreview {
+            lifetime_score: self.lifetime_score(&game_state.lifetime_totals),
+            points_available,
+            can_prestige: points_available >= self.config.min_points,
+            production_multiplier_after: self.multiplier_for(game_state.prestige.total_points + points_available),
+        }
+    }
+
+    /// プレステージ記録の作成（状態は変更しない）
+    ///
+    /// 永続化に成功してから`apply`することで、リセットを不可分に行う。
+    pub fn prepare(
+        &self,
+        resource_manager: &ResourceManager,
+        celestial_manager: &CelestialBodyManager,
+        now: DateTime<Utc>,
+    ) -> Result<PrestigeRecord> {
+        let preview = self.preview(resource_manager);
+        if !preview.can_prestige {
+            warn!("[PRESTIGE] Not enough progress to prestige: available={}, required={}",
+                preview.points_available, self.config.min_points);
+            return Err(GameError::business_logic(format!(
+                "Prestige requires at least {} points ({} available)",
+                self.config.min_points, preview.points_available
+            )));
+        }
+
+        let game_state = resource_manager.get_game_state();
+        let total_points = game_state.prestige.total_points + preview.points_available;
+
+        Ok(PrestigeRecord {
+            prestige_number: game_state.prestige.prestige_count + 1,
+            points_awarded: preview.points_available,
+            total_points,
+            production_multiplier: self.multiplier_for(total_points),
+            lifetime_totals: game_state.lifetime_totals.clone(),
+            resources_at_reset: game_state.resources.clone(),
+            bodies_at_reset: celestial_manager.get_body_count(),
+            performed_at: now,
+        })
+    }
+
+    /// プレステージ記録の適用（リソース・天体・アップグレードのリセット）
+    pub fn apply(
+        &self,
+        record: &PrestigeRecord,
+        resource_manager: &mut ResourceManager,
+        celestial_manager: &mut CelestialBodyManager,
+    ) {
+        resource_manager.reset_for_prestige(record.resulting_state());
+        let removed = celestial_manager.clear_bodies();
+
+        info!("[PRESTIGE] Prestige #{} applied: awarded={}, total={}, multiplier={:.2}, bodies_removed={}",
+            record.prestige_number, record.points_awarded, record.total_points,
+            record.production_multiplier, removed);
+    }
+
+    /// プレステージの実行（永続化を伴わない場合）
+    pub fn perform(
+        &self,
+        resource_manager: &mut ResourceManager,
+        celestial_manager: &mut CelestialBodyManager,
+        now: DateTime<Utc>,
+    ) -> Result<PrestigeRecord> {
+        let record = self.prepare(resource_manager, celestial_manager, now)?;
+        self.apply(&record, resource_manager, celestial_manager);
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::celestial_bodies::{CelestialType, Vec3Fixed};
+    use crate::game::resources::{fixed, UpgradeType};
+
+    fn manager_with_lifetime_dust(dust: u64) -> ResourceManager {
+        let mut manager = ResourceManager::new(50);
+        manager.get_game_state_mut().lifetime_totals.cosmic_dust = dust;
+        manager
+    }
+
+    #[test]
+    fn test_points_from_lifetime_totals() {
+        let calculator = PrestigeCalculator::new(PrestigeConfig::default());
+        let totals = Resources { cosmic_dust: 4_000_000, ..Default::default() };
+
+        // sqrt(4_000_000 / 1_000_000) = 2
+        assert_eq!(calculator.total_points_for(&totals), 2);
+    }
+
+    #[test]
+    fn test_prestige_requires_progress() {
+        let calculator = PrestigeCalculator::new(PrestigeConfig::default());
+        let mut resource_manager = manager_with_lifetime_dust(1000);
+        let mut celestial_manager = CelestialBodyManager::new(50);
+
+        let result = calculator.perform(&mut resource_manager, &mut celestial_manager, Utc::now());
+        assert!(matches!(result, Err(GameError::BusinessLogic(_))));
+    }
+
+    #[test]
+    fn test_prestige_resets_run_and_keeps_bonus() {
+        let calculator = PrestigeCalculator::new(PrestigeConfig::default());
+        let mut resource_manager = manager_with_lifetime_dust(9_000_000);
+        let mut celestial_manager = CelestialBodyManager::new(50);
+
+        resource_manager.get_resources_mut().cosmic_dust = 10_000;
+        resource_manager.get_game_state_mut().upgrade_levels.set_level(UpgradeType::DustProduction, 3);
+        let mut resources = resource_manager.get_resources().clone();
+        celestial_manager
+            .create_body(CelestialType::Asteroid, Vec3Fixed::new(0.0, 0.0, 0.0), &mut resources)
+            .unwrap();
+
+        let record = calculator.perform(&mut resource_manager, &mut celestial_manager, Utc::now()).unwrap();
+        assert_eq!(record.points_awarded, 3);
+        assert_eq!(record.bodies_at_reset, 1);
+
+        let game_state = resource_manager.get_game_state();
+        assert_eq!(game_state.resources.cosmic_dust, 0);
+        assert_eq!(game_state.upgrade_levels.get_level(UpgradeType::DustProduction), 0);
+        assert_eq!(game_state.lifetime_totals.cosmic_dust, 9_000_000);
+        assert_eq!(game_state.prestige.prestige_count, 1);
+        assert_eq!(celestial_manager.get_body_count(), 0);
+
+        // 永続ボーナスが生産レートに反映される（1.0 * 1.06）
+        assert!((fixed::to_f64(game_state.production_rates.dust_per_tick) - 1.06).abs() < 1e-6);
+
+        // 同じ累計では再度プレステージできない
+        assert!(calculator.perform(&mut resource_manager, &mut celestial_manager, Utc::now()).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! 天体同士の位置関係による生産補正
+//!
+//! 天体の基本の生産レートは種類と生命段階で決まり、周囲の天体との関係で増減する。
+//! 衛星の潮汐は惑星の有機物生成を促し、ハビタブルゾーンの惑星は安定した日射でエネルギーを生み、
+//! 巨大ガス惑星の近くの小惑星・彗星は重力で攪拌されてダストを多く生む。
+//! 補正は天体の配置から毎回計算し直し、天体ごとに内訳を参照できる。
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::game::celestial_bodies::{BodyId, CelestialBody, CelestialType, PlanetType};
+use crate::game::determinism::sorted_body_ids;
+use crate::game::habitability::{self, LightSource};
+use crate::game::resources::{fixed, ProductionRates, ResourceType};
+use crate::game::spatial_index::SpatialIndex;
+
+/// 月の半径（潮汐の強さの基準）
+const LUNAR_RADIUS: f64 = 1737.4;
+
+/// 生産補正のパラメータ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProductionModifierSettings {
+    /// 1天文単位に相当するゲーム内の距離
+    pub astronomical_unit: f64,
+    /// 月と同じ大きさの衛星が`tidal_organic_bonus`の補正を与える中心間距離
+    pub tidal_reference_distance: f64,
+    /// 潮汐の影響が及ぶ距離（天体表面間）
+    pub tidal_range: f64,
+    /// 衛星1つあたりの有機物生成の補正の上限
+    pub tidal_organic_bonus: f64,
+    /// ハビタブルゾーンとみなす放射の範囲（地球 = 1.0）
+    pub habitable_zone_flux: (f64, f64),
+    /// ハビタブルゾーンの惑星のエネルギー生成（1ティックあたり、放射1.0のとき）
+    pub habitable_zone_energy: f64,
+    /// 巨大ガス惑星が小天体を攪拌する距離（天体表面間）
+    pub gas_giant_range: f64,
+    /// 巨大ガス惑星に接しているときのダスト生成の補正（距離に応じて0まで減る）
+    pub gas_giant_dust_bonus: f64,
+}
+
+impl Default for ProductionModifierSettings {
+    fn default() -> Self {
+        Self {
+            astronomical_unit: 50_000.0,
+            tidal_reference_distance: 20_000.0,
+            tidal_range: 60_000.0,
+            tidal_organic_bonus: 0.25,
+            habitable_zone_flux: (0.5, 2.0),
+            habitable_zone_energy: 0.2,
+            gas_giant_range: 30_000.0,
+            gas_giant_dust_bonus: 1.0,
+        }
+    }
+}
+
+/// 補正の要因
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ModifierSource {
+    /// 衛星の潮汐
+    Tidal { moon_id: BodyId },
+    /// ハビタブルゾーンでの日射（放射、地球 = 1.0）
+    HabitableZone { stellar_flux: f64 },
+    /// 巨大ガス惑星による攪拌
+    GasGiant { planet_id: BodyId },
+}
+
+/// 補正の効果
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ModifierEffect {
+    /// 基本レートに対する割合（0.25で+25%）
+    Percent(f64),
+    /// 1ティックあたりの固定量
+    Flat(f64),
+}
+
+/// 生産補正
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProductionModifier {
+    pub source: ModifierSource,
+    pub resource_type: ResourceType,
+    pub effect: ModifierEffect,
+}
+
+/// 生産の内訳
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductionBreakdown {
+    /// 種類と生命段階による基本レート
+    pub base_rates: ProductionRates,
+    pub modifiers: Vec<ProductionModifier>,
+    /// 補正と倍率・効率を適用したレート
+    pub effective_rates: ProductionRates,
+}
+
+/// 補正を適用したレート（固定量を加えてから割合の補正を掛ける）
+pub fn apply(rates: &ProductionRates, modifiers: &[ProductionModifier]) -> ProductionRates {
+    let mut result = rates.clone();
+    for resource_type in ResourceType::all() {
+        let mut flat = 0.0;
+        let mut percent = 0.0;
+        for modifier in modifiers.iter().filter(|modifier| modifier.resource_type == resource_type) {
+            match modifier.effect {
+                ModifierEffect::Flat(amount) => flat += amount,
+                ModifierEffect::Percent(bonus) => percent += bonus,
+            }
+        }
+        if flat != 0.0 || percent != 0.0 {
+            let rate = (fixed::to_f64(rates.get(resource_type)) + flat) * (1.0 + percent);
+            result.set(resource_type, fixed::from_f64(rate.max(0.0)));
+        }
+    }
+    result
+}
+
+/// 天体の生産補正を評価する
+pub fn evaluate(
+    body: &CelestialBody,
+    bodies: &HashMap<BodyId, CelestialBody>,
+    spatial_index: &SpatialIndex,
+    lights: &[LightSource],
+    settings: &ProductionModifierSettings,
+) -> Vec<ProductionModifier> {
+    match &body.body_type {
+        CelestialType::Planet(_) => {
+            let mut modifiers = tidal_modifiers(body, bodies, spatial_index, settings);
+            modifiers.extend(habitable_zone_modifier(body, lights, settings));
+            modifiers
+        },
+        CelestialType::Asteroid | CelestialType::Comet => gas_giant_modifiers(body, bodies, spatial_index, settings),
+        _ => Vec::new(),
+    }
+}
+
+/// 全天体の生産補正を評価する（補正のない天体は含まない）
+pub fn evaluate_all(
+    bodies: &HashMap<BodyId, CelestialBody>,
+    spatial_index: &SpatialIndex,
+    settings: &ProductionModifierSettings,
+) -> HashMap<BodyId, Vec<ProductionModifier>> {
+    let lights = habitability::light_sources(bodies);
+    sorted_body_ids(bodies)
+        .into_iter()
+        .filter_map(|id| {
+            let modifiers = evaluate(&bodies[&id], bodies, spatial_index, &lights, settings);
+            (!modifiers.is_empty()).then_some((id, modifiers))
+        })
+        .collect()
+}
+
+/// 周囲の天体のうち条件に合うもの（ID順）
+fn neighbors<'a>(
+    body: &CelestialBody,
+    bodies: &'a HashMap<BodyId, CelestialBody>,
+    spatial_index: &SpatialIndex,
+    range: f64,
+    predicate: impl Fn(&CelestialType) -> bool,
+) -> impl Iterator<Item = &'a CelestialBody> {
+    // 検索は中心からの距離なので、自身の半径の分だけ広げて表面間の距離にする
+    let reach = range + fixed::to_f64(body.physics.radius);
+    let id = body.id;
+    spatial_index
+        .within_distance(body.physics.position, reach)
+        .into_iter()
+        .filter(move |other_id| *other_id != id)
+        .filter_map(move |other_id| bodies.get(&other_id))
+        .filter(move |other| predicate(&other.body_type))
+}
+
+/// 衛星の潮汐による有機物生成の補正
+///
+/// 潮汐力は衛星の質量に比例し距離の3乗に反比例する。質量は固定小数点の範囲を超えるため、
+/// 密度が一定として半径の3乗で見積もる。
+fn tidal_modifiers(
+    planet: &CelestialBody,
+    bodies: &HashMap<BodyId, CelestialBody>,
+    spatial_index: &SpatialIndex,
+    settings: &ProductionModifierSettings,
+) -> Vec<ProductionModifier> {
+    neighbors(planet, bodies, spatial_index, settings.tidal_range, |body_type| matches!(body_type, CelestialType::Moon))
+        .filter_map(|moon| {
+            let distance = (moon.physics.position - planet.physics.position).magnitude().max(f64::EPSILON);
+            let size = fixed::to_f64(moon.physics.radius) / LUNAR_RADIUS;
+            let strength = (size * settings.tidal_reference_distance / distance).powi(3);
+            let bonus = settings.tidal_organic_bonus * strength.min(1.0);
+            (bonus > 0.0).then_some(ProductionModifier {
+                source: ModifierSource::Tidal { moon_id: moon.id },
+                resource_type: ResourceType::OrganicMatter,
+                effect: ModifierEffect::Percent(bonus),
+            })
+        })
+        .collect()
+}
+
+/// ハビタブルゾーンの惑星の日射によるエネルギー生成
+fn habitable_zone_modifier(
+    planet: &CelestialBody,
+    lights: &[LightSource],
+    settings: &ProductionModifierSettings,
+) -> Option<ProductionModifier> {
+    let stellar_flux = habitability::stellar_flux(planet.physics.position, lights, settings.astronomical_unit);
+    let (min_flux, max_flux) = settings.habitable_zone_flux;
+    (min_flux..=max_flux).contains(&stellar_flux).then_some(ProductionModifier {
+        source: ModifierSource::HabitableZone { stellar_flux },
+        resource_type: ResourceType::Energy,
+        effect: ModifierEffect::Flat(settings.habitable_zone_energy * stellar_flux),
+    })
+}
+
+/// 巨大ガス惑星の近くの小天体のダスト生成の補正（近いほど大きい）
+fn gas_giant_modifiers(
+    body: &CelestialBody,
+    bodies: &HashMap<BodyId, CelestialBody>,
+    spatial_index: &SpatialIndex,
+    settings: &ProductionModifierSettings,
+) -> Vec<ProductionModifier> {
+    let is_gas_giant = |body_type: &CelestialType| {
+        matches!(body_type, CelestialType::Planet(planet) if planet.planet_type == PlanetType::GasGiant)
+    };
+    neighbors(body, bodies, spatial_index, settings.gas_giant_range, is_gas_giant)
+        .filter_map(|giant| {
+            let distance = (giant.physics.position - body.physics.position).magnitude();
+            let gap = (distance - fixed::to_f64(body.physics.radius + giant.physics.radius)).max(0.0);
+            let bonus = settings.gas_giant_dust_bonus * (1.0 - gap / settings.gas_giant_range.max(f64::MIN_POSITIVE));
+            (bonus > 0.0).then_some(ProductionModifier {
+                source: ModifierSource::GasGiant { planet_id: giant.id },
+                resource_type: ResourceType::CosmicDust,
+                effect: ModifierEffect::Percent(bonus),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::game::celestial_bodies::{AtmosphereType, PlanetData, Vec3Fixed};
+
+    fn body(body_type: CelestialType, position: Vec3Fixed, radius: f64) -> CelestialBody {
+        CelestialBody::new(Uuid::new_v4(), body_type, position, fixed::from_f64(1.0), fixed::from_f64(radius))
+    }
+
+    fn planet(planet_type: PlanetType, position: Vec3Fixed) -> CelestialBody {
+        body(CelestialType::Planet(PlanetData {
+            planet_type,
+            atmosphere: AtmosphereType::Oxygen,
+            water_coverage: 70,
+            temperature_range: (15, 25),
+            habitability: 0,
+        }), position, 6371.0)
+    }
+
+    fn world(bodies: Vec<CelestialBody>) -> (HashMap<BodyId, CelestialBody>, SpatialIndex) {
+        let bodies: HashMap<BodyId, CelestialBody> = bodies.into_iter().map(|body| (body.id, body)).collect();
+        let mut index = SpatialIndex::new();
+        index.sync(&bodies);
+        (bodies, index)
+    }
+
+    #[test]
+    fn test_moons_and_habitable_zone_modify_planet_production() {
+        let settings = ProductionModifierSettings::default();
+        let au = settings.astronomical_unit;
+        let earth = planet(PlanetType::Rocky, Vec3Fixed::new(au, 0.0, 0.0));
+        let earth_id = earth.id;
+        let near_moon = body(CelestialType::Moon, Vec3Fixed::new(au, settings.tidal_reference_distance, 0.0), LUNAR_RADIUS);
+        let far_moon = body(CelestialType::Moon, Vec3Fixed::new(au, -2.0 * settings.tidal_reference_distance, 0.0), LUNAR_RADIUS);
+        let (near_id, far_id) = (near_moon.id, far_moon.id);
+        let (bodies, index) = world(vec![earth, near_moon, far_moon]);
+        let sun = LightSource { position: Vec3Fixed::zeros(), luminosity: 1.0 };
+
+        let modifiers = evaluate(&bodies[&earth_id], &bodies, &index, &[sun], &settings);
+
+        let tidal = |moon_id| match modifiers.iter().find(|m| m.source == ModifierSource::Tidal { moon_id }).map(|m| m.effect) {
+            Some(ModifierEffect::Percent(bonus)) => bonus,
+            other => panic!("unexpected tidal modifier: {:?}", other),
+        };
+        assert!((tidal(near_id) - 0.25).abs() < 1e-6);
+        // 2倍の距離では潮汐は1/8になる
+        assert!((tidal(far_id) - 0.25 / 8.0).abs() < 1e-6);
+
+        let mut base = ProductionRates::new();
+        base.organic_per_tick = fixed::from_f64(1.0);
+        let rates = apply(&base, &modifiers);
+        assert!((fixed::to_f64(rates.organic_per_tick) - (1.0 + 0.25 + 0.25 / 8.0)).abs() < 1e-6);
+        assert!((fixed::to_f64(rates.energy_per_tick) - settings.habitable_zone_energy).abs() < 1e-6);
+
+        // ハビタブルゾーンの外ではエネルギーを得られない
+        let outer = planet(PlanetType::Rocky, Vec3Fixed::new(au * 3.0, 0.0, 0.0));
+        let outer_modifiers = evaluate(&outer, &bodies, &index, &[sun], &settings);
+        assert!(outer_modifiers.is_empty());
+    }
+
+    #[test]
+    fn test_gas_giants_stir_nearby_small_bodies() {
+        let settings = ProductionModifierSettings::default();
+        let giant = planet(PlanetType::GasGiant, Vec3Fixed::zeros());
+        let giant_id = giant.id;
+        let touching = body(CelestialType::Asteroid, Vec3Fixed::new(6371.5, 0.0, 0.0), 0.5);
+        let halfway = body(CelestialType::Comet, Vec3Fixed::new(0.0, 6371.5 + settings.gas_giant_range / 2.0, 0.0), 0.5);
+        let distant = body(CelestialType::Asteroid, Vec3Fixed::new(0.0, 0.0, -6372.0 - settings.gas_giant_range * 2.0), 0.5);
+        let rocky = planet(PlanetType::Rocky, Vec3Fixed::new(-20_000.0, 0.0, 0.0));
+        let ids = [touching.id, halfway.id, distant.id];
+        let (bodies, index) = world(vec![giant, touching, halfway, distant, rocky]);
+
+        let all = evaluate_all(&bodies, &index, &settings);
+
+        let bonus = |id: BodyId| match all.get(&id).map(|modifiers| modifiers.as_slice()) {
+            Some([ProductionModifier { source, effect: ModifierEffect::Percent(bonus), .. }]) => {
+                assert_eq!(*source, ModifierSource::GasGiant { planet_id: giant_id });
+                *bonus
+            },
+            None => 0.0,
+            other => panic!("unexpected modifiers: {:?}", other),
+        };
+        assert!((bonus(ids[0]) - 1.0).abs() < 1e-9);
+        assert!((bonus(ids[1]) - 0.5).abs() < 1e-9);
+        assert_eq!(bonus(ids[2]), 0.0);
+        // 岩石惑星は小天体を攪拌しない（惑星自身の補正も光源がなければない）
+        assert!(!all.contains_key(&giant_id));
+    }
+}
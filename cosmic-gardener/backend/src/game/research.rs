@@ -0,0 +1,474 @@
+//! 研究ツリー
+//!
+//! 思考ポイントを消費して技術を研究する。技術は前提条件を持つ有向非巡回グラフとして
+//! `config/research.toml`で定義され、研究キューはティックごとに進行する。
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::errors::{GameError, Result};
+use crate::game::celestial_bodies::{BodyCategory, CelestialBodyManager};
+use crate::game::resources::{ResourceManager, ResourceType, Resources};
+
+/// 組み込みの研究ツリー
+const BUILTIN_TREE: &str = include_str!("../../config/research.toml");
+
+/// 技術ID
+pub type TechnologyId = String;
+
+/// 研究完了時の効果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResearchEffect {
+    /// リソース生産倍率
+    ProductionMultiplier { resource: ResourceType, multiplier: f64 },
+    /// 天体数上限の増加
+    MaxBodies { bonus: usize },
+    /// 種類別の天体数上限の増加
+    BodyTypeLimit { category: BodyCategory, bonus: usize },
+    /// 天体の作成コストの倍率
+    CreationCost { multiplier: f64 },
+    /// 生命進化速度の倍率
+    LifeEvolutionSpeed { multiplier: f64 },
+}
+
+/// 技術定義
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Technology {
+    pub id: TechnologyId,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 思考ポイントのコスト
+    pub cost: u64,
+    pub research_time_ms: u64,
+    #[serde(default)]
+    pub prerequisites: Vec<TechnologyId>,
+    #[serde(default)]
+    pub effects: Vec<ResearchEffect>,
+}
+
+/// 研究ツリー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResearchTree {
+    pub technologies: Vec<Technology>,
+}
+
+impl Default for ResearchTree {
+    fn default() -> Self {
+        Self::from_toml(BUILTIN_TREE).expect("Built-in research tree must be valid")
+    }
+}
+
+impl ResearchTree {
+    /// TOML文字列から読み込み（検証込み）
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let tree: Self = toml::from_str(content)
+            .map_err(|e| GameError::validation(format!("Failed to parse research tree: {}", e)))?;
+        tree.validate()?;
+        Ok(tree)
+    }
+
+    /// ファイルから読み込み（検証込み）
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            GameError::validation(format!("Failed to read research tree {}: {}", path.display(), e))
+        })?;
+        Self::from_toml(&content)
+    }
+
+    /// ツリーの検証（重複・未定義の前提条件・循環）
+    pub fn validate(&self) -> Result<()> {
+        let mut ids = HashSet::new();
+        for tech in &self.technologies {
+            if !ids.insert(tech.id.as_str()) {
+                return Err(GameError::validation(format!("Duplicate technology id: {}", tech.id)));
+            }
+            if tech.research_time_ms == 0 {
+                return Err(GameError::validation(format!("{}: research_time_ms must be positive", tech.id)));
+            }
+            for effect in &tech.effects {
+                let valid = match effect {
+                    ResearchEffect::ProductionMultiplier { multiplier, .. }
+                    | ResearchEffect::LifeEvolutionSpeed { multiplier }
+                    | ResearchEffect::CreationCost { multiplier } => multiplier.is_finite() && *multiplier > 0.0,
+                    ResearchEffect::MaxBodies { .. } | ResearchEffect::BodyTypeLimit { .. } => true,
+                };
+                if !valid {
+                    return Err(GameError::validation(format!("{}: invalid effect {:?}", tech.id, effect)));
+                }
+            }
+        }
+
+        for tech in &self.technologies {
+            for prerequisite in &tech.prerequisites {
+                if !ids.contains(prerequisite.as_str()) {
+                    return Err(GameError::validation(format!(
+                        "{}: unknown prerequisite {}", tech.id, prerequisite
+                    )));
+                }
+            }
+        }
+
+        // トポロジカルソートで循環を検出
+        let mut in_degree: HashMap<&str, usize> = self.technologies.iter()
+            .map(|tech| (tech.id.as_str(), tech.prerequisites.len()))
+            .collect();
+        let mut ready: Vec<&str> = in_degree.iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| *id)
+            .collect();
+        let mut visited = 0;
+
+        while let Some(id) = ready.pop() {
+            visited += 1;
+            for tech in &self.technologies {
+                if tech.prerequisites.iter().any(|prerequisite| prerequisite == id) {
+                    let degree = in_degree.get_mut(tech.id.as_str()).expect("technology exists");
+                    *degree -= 1;
+                    if *degree == 0 {
+                        ready.push(tech.id.as_str());
+                    }
+                }
+            }
+        }
+
+        if visited != self.technologies.len() {
+            return Err(GameError::validation("Research tree contains a prerequisite cycle"));
+        }
+
+        Ok(())
+    }
+
+    /// 技術の取得
+    pub fn get(&self, id: &str) -> Option<&Technology> {
+        self.technologies.iter().find(|tech| tech.id == id)
+    }
+}
+
+/// 研究中の技術
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveResearch {
+    pub technology_id: TechnologyId,
+    pub progress_ms: u64,
+    pub required_ms: u64,
+}
+
+/// プレイヤーの研究進捗（永続化対象）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResearchProgress {
+    pub unlocked: BTreeSet<TechnologyId>,
+    pub queue: VecDeque<ActiveResearch>,
+}
+
+/// 研究済み技術の効果の集計
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResearchEffects {
+    pub production_multipliers: HashMap<ResourceType, f64>,
+    pub max_bodies_bonus: usize,
+    pub body_type_limit_bonuses: HashMap<BodyCategory, usize>,
+    pub creation_cost_multiplier: f64,
+    pub life_evolution_speed: f64,
+}
+
+impl Default for ResearchEffects {
+    fn default() -> Self {
+        Self {
+            production_multipliers: HashMap::new(),
+            max_bodies_bonus: 0,
+            body_type_limit_bonuses: HashMap::new(),
+            creation_cost_multiplier: 1.0,
+            life_evolution_speed: 1.0,
+        }
+    }
+}
+
+impl ResearchEffects {
+    /// 各マネージャーへの効果の反映
+    pub fn apply(&self, resource_manager: &mut ResourceManager, celestial_manager: &mut CelestialBodyManager) {
+        resource_manager.set_production_modifiers(self.production_multipliers.clone());
+        celestial_manager.set_max_bodies_bonus(self.max_bodies_bonus);
+        celestial_manager.set_type_limit_bonuses(self.body_type_limit_bonuses.clone());
+        celestial_manager.set_creation_cost_multiplier(self.creation_cost_multiplier);
+        celestial_manager.set_evolution_speed(self.life_evolution_speed);
+    }
+}
+
+/// 研究管理システム
+#[derive(Debug, Clone)]
+pub struct ResearchManager {
+    tree: Arc<ResearchTree>,
+    progress: ResearchProgress,
+    max_queue_length: usize,
+}
+
+impl ResearchManager {
+    pub fn new(tree: Arc<ResearchTree>, max_queue_length: usize) -> Self {
+        Self {
+            tree,
+            progress: ResearchProgress::default(),
+            max_queue_length,
+        }
+    }
+
+    pub fn tree(&self) -> &ResearchTree {
+        &self.tree
+    }
+
+    pub fn progress(&self) -> &ResearchProgress {
+        &self.progress
+    }
+
+    /// 進捗の復元
+    pub fn set_progress(&mut self, progress: ResearchProgress) {
+        self.progress = progress;
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.progress.unlocked.contains(id)
+    }
+
+    fn is_queued(&self, id: &str) -> bool {
+        self.progress.queue.iter().any(|active| active.technology_id == id)
+    }
+
+    /// 研究の開始（キューへの追加、コストは即時消費）
+    pub fn enqueue(&mut self, id: &str, resources: &mut Resources) -> Result<()> {
+        let tech = self.tree.get(id)
+            .ok_or_else(|| GameError::not_found(format!("Technology {} not found", id)))?;
+
+        if self.is_unlocked(id) || self.is_queued(id) {
+            return Err(GameError::conflict(format!("Technology {} is already researched or queued", id)));
+        }
+
+        if self.progress.queue.len() >= self.max_queue_length {
+            return Err(GameError::business_logic(format!(
+                "Research queue is full ({} entries)", self.max_queue_length
+            )));
+        }
+
+        // 前提条件は研究済みか、キュー内で先に研究される必要がある
+        if let Some(missing) = tech.prerequisites.iter()
+            .find(|prerequisite| !self.is_unlocked(prerequisite) && !self.is_queued(prerequisite))
+        {
+            warn!("[RESEARCH] Missing prerequisite: technology={}, prerequisite={}", id, missing);
+            return Err(GameError::business_logic(format!(
+                "Technology {} requires {}", id, missing
+            )));
+        }
+
+        resources.subtract(ResourceType::ThoughtPoints, tech.cost)?;
+
+        self.progress.queue.push_back(ActiveResearch {
+            technology_id: tech.id.clone(),
+            progress_ms: 0,
+            required_ms: tech.research_time_ms,
+        });
+
+        info!("[RESEARCH] Research queued: technology={}, cost={}", id, tech.cost);
+        Ok(())
+    }
+
+    /// 研究の取り消し（コストは返還、依存する後続の研究も取り消す）
+    pub fn cancel(&mut self, id: &str, resources: &mut Resources) -> Result<()> {
+        let position = self.progress.queue.iter()
+            .position(|active| active.technology_id == id)
+            .ok_or_else(|| GameError::not_found(format!("Technology {} is not queued", id)))?;
+
+        let mut cancelled: HashSet<TechnologyId> = HashSet::new();
+        let mut remaining = VecDeque::new();
+
+        for (index, active) in self.progress.queue.drain(..).enumerate() {
+            let depends_on_cancelled = self.tree.get(&active.technology_id)
+                .map(|tech| tech.prerequisites.iter().any(|prerequisite| cancelled.contains(prerequisite)))
+                .unwrap_or(false);
+
+            if index == position || depends_on_cancelled {
+                cancelled.insert(active.technology_id);
+            } else {
+                remaining.push_back(active);
+            }
+        }
+        self.progress.queue = remaining;
+
+        for tech_id in &cancelled {
+            if let Some(tech) = self.tree.get(tech_id) {
+                resources.add(ResourceType::ThoughtPoints, tech.cost);
+            }
+        }
+
+        info!("[RESEARCH] Research cancelled: {:?}", cancelled);
+        Ok(())
+    }
+
+    /// 研究の進行（完了した技術のIDを返す）
+    pub fn update(&mut self, delta_time_ms: u64) -> Vec<TechnologyId> {
+        let mut remaining_ms = delta_time_ms;
+        let mut completed = Vec::new();
+
+        while remaining_ms > 0 {
+            let Some(active) = self.progress.queue.front_mut() else {
+                break;
+            };
+
+            let needed = active.required_ms.saturating_sub(active.progress_ms);
+            if remaining_ms < needed {
+                active.progress_ms += remaining_ms;
+                break;
+            }
+
+            remaining_ms -= needed;
+            let finished = self.progress.queue.pop_front().expect("queue front exists");
+            info!("[RESEARCH] Research completed: technology={}", finished.technology_id);
+            self.progress.unlocked.insert(finished.technology_id.clone());
+            completed.push(finished.technology_id);
+        }
+
+        completed
+    }
+
+    /// 研究済み技術の効果の集計
+    pub fn effects(&self) -> ResearchEffects {
+        let mut effects = ResearchEffects::default();
+
+        for tech in self.progress.unlocked.iter().filter_map(|id| self.tree.get(id)) {
+            for effect in &tech.effects {
+                match effect {
+                    ResearchEffect::ProductionMultiplier { resource, multiplier } => {
+                        *effects.production_multipliers.entry(*resource).or_insert(1.0) *= multiplier;
+                    }
+                    ResearchEffect::MaxBodies { bonus } => {
+                        effects.max_bodies_bonus += bonus;
+                    }
+                    ResearchEffect::BodyTypeLimit { category, bonus } => {
+                        *effects.body_type_limit_bonuses.entry(*category).or_insert(0) += bonus;
+                    }
+                    ResearchEffect::CreationCost { multiplier } => {
+                        effects.creation_cost_multiplier *= multiplier;
+                    }
+                    ResearchEffect::LifeEvolutionSpeed { multiplier } => {
+                        effects.life_evolution_speed *= multiplier;
+                    }
+                }
+            }
+        }
+
+        effects
+    }
+}
+
+/// 研究設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResearchConfig {
+    pub tree_path: String,
+    pub max_queue_length: usize,
+}
+
+impl Default for ResearchConfig {
+    fn default() -> Self {
+        Self {
+            tree_path: "config/research.toml".to_string(),
+            max_queue_length: 5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::resources::fixed;
+
+    fn manager() -> ResearchManager {
+        ResearchManager::new(Arc::new(ResearchTree::default()), 5)
+    }
+
+    fn thought_points(amount: u64) -> Resources {
+        Resources { thought_points: amount, ..Default::default() }
+    }
+
+    #[test]
+    fn test_builtin_tree_is_valid() {
+        let tree = ResearchTree::default();
+        assert!(tree.get("basic_astronomy").is_some());
+    }
+
+    #[test]
+    fn test_cycle_rejected() {
+        let mut tree = ResearchTree::default();
+        tree.technologies.iter_mut()
+            .find(|tech| tech.id == "basic_astronomy")
+            .unwrap()
+            .prerequisites
+            .push("stellar_engineering".to_string());
+
+        assert!(tree.validate().is_err());
+    }
+
+    #[test]
+    fn test_prerequisites_required() {
+        let mut research = manager();
+        let mut resources = thought_points(1000);
+
+        let result = research.enqueue("stellar_engineering", &mut resources);
+        assert!(matches!(result, Err(GameError::BusinessLogic(_))));
+
+        // 前提条件がキューにあれば追加できる
+        research.enqueue("basic_astronomy", &mut resources).unwrap();
+        research.enqueue("stellar_engineering", &mut resources).unwrap();
+        assert_eq!(resources.thought_points, 1000 - 10 - 50);
+    }
+
+    #[test]
+    fn test_queue_progresses_per_tick() {
+        let mut research = manager();
+        let mut resources = thought_points(1000);
+        research.enqueue("basic_astronomy", &mut resources).unwrap();
+        research.enqueue("stellar_engineering", &mut resources).unwrap();
+
+        assert!(research.update(30_000).is_empty());
+
+        // 残り時間を超えた分は次の研究に繰り越される
+        let completed = research.update(40_000);
+        assert_eq!(completed, vec!["basic_astronomy".to_string()]);
+        assert_eq!(research.progress().queue.front().unwrap().progress_ms, 10_000);
+        assert!(research.is_unlocked("basic_astronomy"));
+    }
+
+    #[test]
+    fn test_cancel_refunds_dependents() {
+        let mut research = manager();
+        let mut resources = thought_points(100);
+        research.enqueue("basic_astronomy", &mut resources).unwrap();
+        research.enqueue("stellar_engineering", &mut resources).unwrap();
+
+        research.cancel("basic_astronomy", &mut resources).unwrap();
+        assert!(research.progress().queue.is_empty());
+        assert_eq!(resources.thought_points, 100);
+    }
+
+    #[test]
+    fn test_effects_modify_managers() {
+        let mut research = manager();
+        let mut resources = thought_points(100);
+        research.enqueue("basic_astronomy", &mut resources).unwrap();
+        research.enqueue("stellar_engineering", &mut resources).unwrap();
+        research.update(360_000);
+
+        let effects = research.effects();
+        assert_eq!(effects.max_bodies_bonus, 1000);
+        assert_eq!(effects.production_multipliers[&ResourceType::Energy], 1.1);
+
+        let mut resource_manager = ResourceManager::new(50);
+        let mut celestial_manager = CelestialBodyManager::new(50);
+        effects.apply(&mut resource_manager, &mut celestial_manager);
+
+        let energy = fixed::to_f64(resource_manager.get_game_state().production_rates.energy_per_tick);
+        assert!((energy - 0.55).abs() < 1e-6);
+        assert_eq!(celestial_manager.max_bodies(), 11_000);
+        assert_eq!(celestial_manager.max_bodies_of(BodyCategory::Star), Some(75));
+    }
+}
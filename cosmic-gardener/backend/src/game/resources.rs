@@ -3,11 +3,17 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::errors::{GameError, Result};
+use crate::game::celestial_bodies::{BodyCategory, BodyId};
+use crate::game::upgrade_catalog::{UpgradeCatalog, UpgradeCatalogHandle};
+use crate::game::prestige::PrestigeState;
 
 /// 固定小数点数（32.32フォーマット）
 pub type Fixed = i64;
 pub const FIXED_SCALE: i64 = 1 << 32;
 
+/// 節目として通知する累計獲得量の下限
+pub const MIN_RESOURCE_MILESTONE: u64 = 1000;
+
 /// 固定小数点数のヘルパー関数
 pub mod fixed {
     use super::Fixed;
@@ -21,6 +27,32 @@ pub mod fixed {
     }
 }
 
+/// 累計獲得量の節目（10の累乗）への到達
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceMilestone {
+    pub resource_type: ResourceType,
+    pub amount: u64,
+}
+
+impl ResourceMilestone {
+    /// `before`から`after`への増加で到達した最大の節目
+    pub fn crossed(before: u64, after: u64) -> Option<u64> {
+        if after < MIN_RESOURCE_MILESTONE {
+            return None;
+        }
+        
+        let mut milestone = MIN_RESOURCE_MILESTONE;
+        while let Some(next) = milestone.checked_mul(10) {
+            if next > after {
+                break;
+            }
+            milestone = next;
+        }
+        
+        (before < milestone).then_some(milestone)
+    }
+}
+
 /// リソースの種類
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ResourceType {
@@ -265,44 +297,15 @@ pub enum UpgradeType {
 }
 
 impl UpgradeType {
-    pub fn base_cost(&self) -> Resources {
-        match self {
-            Self::DustProduction => Resources {
-                cosmic_dust: 100,
-                ..Default::default()
-            },
-            Self::EnergyEfficiency => Resources {
-                energy: 50,
-                ..Default::default()
-            },
-            Self::OrganicGrowth => Resources {
-                organic_matter: 25,
-                ..Default::default()
-            },
-            Self::BiomassConversion => Resources {
-                biomass: 10,
-                ..Default::default()
-            },
-            Self::DarkMatterCollection => Resources {
-                dark_matter: 5,
-                ..Default::default()
-            },
-            Self::ThoughtAcceleration => Resources {
-                thought_points: 1,
-                ..Default::default()
-            },
-        }
-    }
-    
-    pub fn cost_multiplier(&self) -> f64 {
-        match self {
-            Self::DustProduction => 1.5,
-            Self::EnergyEfficiency => 1.8,
-            Self::OrganicGrowth => 2.0,
-            Self::BiomassConversion => 2.2,
-            Self::DarkMatterCollection => 2.5,
-            Self::ThoughtAcceleration => 3.0,
-        }
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::DustProduction,
+            Self::EnergyEfficiency,
+            Self::OrganicGrowth,
+            Self::BiomassConversion,
+            Self::DarkMatterCollection,
+            Self::ThoughtAcceleration,
+        ]
     }
 }
 
@@ -340,19 +343,8 @@ impl UpgradeLevels {
         new_level
     }
     
-    pub fn calculate_cost(&self, upgrade_type: UpgradeType) -> Resources {
-        let base_cost = upgrade_type.base_cost();
-        let current_level = self.get_level(upgrade_type);
-        let multiplier = upgrade_type.cost_multiplier().powi(current_level as i32);
-        
-        Resources {
-            cosmic_dust: (base_cost.cosmic_dust as f64 * multiplier) as u64,
-            energy: (base_cost.energy as f64 * multiplier) as u64,
-            organic_matter: (base_cost.organic_matter as f64 * multiplier) as u64,
-            biomass: (base_cost.biomass as f64 * multiplier) as u64,
-            dark_matter: (base_cost.dark_matter as f64 * multiplier) as u64,
-            thought_points: (base_cost.thought_points as f64 * multiplier) as u64,
-        }
+    pub fn calculate_cost(&self, catalog: &UpgradeCatalog, upgrade_type: UpgradeType) -> Result<Resources> {
+        catalog.upgrade_cost(upgrade_type, self.get_level(upgrade_type))
     }
 }
 
@@ -362,67 +354,150 @@ pub struct GameState {
     pub production_rates: ProductionRates,
     pub accumulators: ResourceAccumulators,
     pub upgrade_levels: UpgradeLevels,
+    /// 累計獲得リソース（プレステージでリセットされない）
+    pub lifetime_totals: Resources,
+    pub prestige: PrestigeState,
     pub last_update: DateTime<Utc>,
 }
 
+/// 天体ごとの生産への寄与
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyContribution {
+    pub body_id: BodyId,
+    pub category: BodyCategory,
+    /// 周囲の天体による補正と倍率・効率を適用したレート
+    pub rates: ProductionRates,
+}
+
+/// プレイヤーの生産の内訳
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductionSummary {
+    /// アップグレードによる生産
+    pub upgrade_rates: ProductionRates,
+    /// 生産している天体ごとの寄与
+    pub bodies: Vec<BodyContribution>,
+    /// プレステージと研究による倍率
+    pub multipliers: HashMap<ResourceType, f64>,
+    /// 倍率を適用した合計（毎ティック蓄積されるレート）
+    pub total_rates: ProductionRates,
+}
+
 /// リソース管理システム
 pub struct ResourceManager {
     game_state: GameState,
     tick_duration_ms: u64,
+    upgrade_catalog: UpgradeCatalogHandle,
+    /// 研究などによる生産倍率（永続化せず、研究状態から再計算される）
+    production_modifiers: HashMap<ResourceType, f64>,
+    /// 天体からの生産の合計（永続化せず、毎ティック天体から集計される）
+    body_production: ProductionRates,
 }
 
 impl ResourceManager {
     pub fn new(tick_duration_ms: u64) -> Self {
+        Self::with_catalog(tick_duration_ms, UpgradeCatalogHandle::default())
+    }
+    
+    pub fn with_catalog(tick_duration_ms: u64, upgrade_catalog: UpgradeCatalogHandle) -> Self {
         Self {
             game_state: GameState {
                 resources: Resources::new(),
                 production_rates: ProductionRates::new(),
                 accumulators: ResourceAccumulators::new(),
                 upgrade_levels: UpgradeLevels::new(),
+                lifetime_totals: Resources::new(),
+                prestige: PrestigeState::default(),
                 last_update: Utc::now(),
             },
             tick_duration_ms,
+            upgrade_catalog,
+            production_modifiers: HashMap::new(),
+            body_production: ProductionRates::new(),
         }
     }
     
-    /// 生産レートの計算
+    /// 生産レートの計算（アップグレードと天体からの生産の合計）
     pub fn calculate_production_rates(&self, game_state: &GameState) -> ProductionRates {
-        let mut rates = ProductionRates::new();
-        
-        // ベース生産率
-        rates.dust_per_tick = fixed::from_f64(1.0);
-        
-        // アップグレードボーナス
-        let dust_upgrade = game_state.upgrade_levels.get_level(UpgradeType::DustProduction);
-        let dust_multiplier = 1.0 + (dust_upgrade as f64 * 0.5);
-        rates.dust_per_tick = fixed::from_f64(fixed::to_f64(rates.dust_per_tick) * dust_multiplier);
+        let mut rates = self.upgrade_catalog.current().production_rates(&game_state.upgrade_levels);
         
-        let energy_upgrade = game_state.upgrade_levels.get_level(UpgradeType::EnergyEfficiency);
-        let energy_multiplier = 1.0 + (energy_upgrade as f64 * 0.3);
-        rates.energy_per_tick = fixed::from_f64(0.5 * energy_multiplier);
-        
-        let organic_upgrade = game_state.upgrade_levels.get_level(UpgradeType::OrganicGrowth);
-        let organic_multiplier = 1.0 + (organic_upgrade as f64 * 0.2);
-        rates.organic_per_tick = fixed::from_f64(0.1 * organic_multiplier);
-        
-        let biomass_upgrade = game_state.upgrade_levels.get_level(UpgradeType::BiomassConversion);
-        let biomass_multiplier = 1.0 + (biomass_upgrade as f64 * 0.15);
-        rates.biomass_per_tick = fixed::from_f64(0.05 * biomass_multiplier);
-        
-        let dark_upgrade = game_state.upgrade_levels.get_level(UpgradeType::DarkMatterCollection);
-        let dark_multiplier = 1.0 + (dark_upgrade as f64 * 0.1);
-        rates.dark_per_tick = fixed::from_f64(0.01 * dark_multiplier);
-        
-        let thought_upgrade = game_state.upgrade_levels.get_level(UpgradeType::ThoughtAcceleration);
-        let thought_multiplier = 1.0 + (thought_upgrade as f64 * 0.05);
-        rates.thought_per_tick = fixed::from_f64(0.001 * thought_multiplier);
+        // プレステージの永続ボーナスと研究による倍率
+        for resource_type in ResourceType::all() {
+            let base = rates.get(resource_type) + self.body_production.get(resource_type);
+            let rate = fixed::to_f64(base) * self.production_multiplier(game_state, resource_type);
+            rates.set(resource_type, fixed::from_f64(rate));
+        }
         
         rates
     }
     
+    /// プレステージと研究による生産倍率
+    fn production_multiplier(&self, game_state: &GameState, resource_type: ResourceType) -> f64 {
+        let modifier = self.production_modifiers.get(&resource_type).copied().unwrap_or(1.0);
+        game_state.prestige.production_multiplier * modifier
+    }
+    
+    /// 天体からの生産の設定（生産レートを再計算する）
+    pub fn set_body_production(&mut self, rates: ProductionRates) {
+        self.body_production = rates;
+        self.game_state.production_rates = self.calculate_production_rates(&self.game_state);
+    }
+    
+    /// 生産の内訳（`bodies`は天体マネージャーが集計した天体ごとの寄与）
+    pub fn production_summary(&self, bodies: Vec<BodyContribution>) -> ProductionSummary {
+        ProductionSummary {
+            upgrade_rates: self.upgrade_catalog.current().production_rates(&self.game_state.upgrade_levels),
+            bodies,
+            multipliers: ResourceType::all()
+                .into_iter()
+                .map(|resource_type| (resource_type, self.production_multiplier(&self.game_state, resource_type)))
+                .collect(),
+            total_rates: self.game_state.production_rates.clone(),
+        }
+    }
+    
+    /// 生産倍率の設定（生産レートを再計算する）
+    pub fn set_production_modifiers(&mut self, modifiers: HashMap<ResourceType, f64>) {
+        self.production_modifiers = modifiers;
+        self.game_state.production_rates = self.calculate_production_rates(&self.game_state);
+    }
+    
+    /// プレステージによるリセット（累計とプレステージ状態のみ引き継ぐ）
+    pub fn reset_for_prestige(&mut self, prestige: PrestigeState) {
+        let lifetime_totals = std::mem::take(&mut self.game_state.lifetime_totals);
+        self.body_production = ProductionRates::new();
+        
+        self.game_state = GameState {
+            resources: Resources::new(),
+            production_rates: ProductionRates::new(),
+            accumulators: ResourceAccumulators::new(),
+            upgrade_levels: UpgradeLevels::new(),
+            lifetime_totals,
+            prestige,
+            last_update: Utc::now(),
+        };
+        self.game_state.production_rates = self.calculate_production_rates(&self.game_state);
+    }
+    
+    /// 次のレベルへのアップグレードコスト
+    pub fn upgrade_cost(&self, upgrade_type: UpgradeType) -> Result<Resources> {
+        self.game_state.upgrade_levels.calculate_cost(&self.upgrade_catalog.current(), upgrade_type)
+    }
+    
     /// アップグレードの適用
     pub fn apply_upgrade(&mut self, upgrade_type: UpgradeType) -> Result<()> {
-        let cost = self.game_state.upgrade_levels.calculate_cost(upgrade_type);
+        let catalog = self.upgrade_catalog.current();
+        let current_level = self.game_state.upgrade_levels.get_level(upgrade_type);
+        
+        // 最大レベルの検証
+        let max_level = catalog.max_level(upgrade_type)?;
+        if current_level >= max_level {
+            log::warn!("[RESOURCES] Upgrade already at max level: type={:?}, level={}", upgrade_type, current_level);
+            return Err(GameError::business_logic(format!(
+                "Upgrade {:?} is already at max level {}", upgrade_type, max_level
+            )));
+        }
+        
+        let cost = self.game_state.upgrade_levels.calculate_cost(&catalog, upgrade_type)?;
         
         // コストの検証
         if !self.game_state.resources.can_afford(&cost) {
@@ -443,12 +518,40 @@ impl ResourceManager {
         Ok(())
     }
     
+    /// アップグレードカタログの取得
+    pub fn upgrade_catalog(&self) -> &UpgradeCatalogHandle {
+        &self.upgrade_catalog
+    }
+    
     /// リソースの蓄積処理
-    pub fn accumulate_resources(&mut self, delta_time_ms: u64) {
+    ///
+    /// 累計獲得量が節目に到達したリソースを返す。
+    pub fn accumulate_resources(&mut self, delta_time_ms: u64) -> Vec<ResourceMilestone> {
+        let rates = self.game_state.production_rates.clone();
+        let lifetime_before = self.game_state.lifetime_totals.clone();
+        self.accumulate_with_rates(&rates, delta_time_ms);
+        
+        self.game_state.last_update = Utc::now();
+        
+        ResourceType::all()
+            .into_iter()
+            .filter_map(|resource_type| {
+                let amount = ResourceMilestone::crossed(
+                    lifetime_before.get(resource_type),
+                    self.game_state.lifetime_totals.get(resource_type),
+                )?;
+                Some(ResourceMilestone { resource_type, amount })
+            })
+            .collect()
+    }
+    
+    /// 指定した生産レートでリソースを蓄積し、獲得した整数量を返す
+    pub fn accumulate_with_rates(&mut self, rates: &ProductionRates, delta_time_ms: u64) -> Resources {
         let time_factor = delta_time_ms as f64 / self.tick_duration_ms as f64;
+        let mut gained = Resources::new();
         
         for resource_type in ResourceType::all() {
-            let rate = self.game_state.production_rates.get(resource_type);
+            let rate = rates.get(resource_type);
             let production = fixed::from_f64(fixed::to_f64(rate) * time_factor);
             
             // 累積値に加算
@@ -462,10 +565,18 @@ impl ResourceManager {
             if whole_amount > 0 {
                 self.game_state.resources.add(resource_type, whole_amount as u64);
                 self.game_state.accumulators.set(resource_type, remainder);
+                self.game_state.lifetime_totals.add(resource_type, whole_amount as u64);
+                gained.add(resource_type, whole_amount as u64);
             }
         }
         
-        self.game_state.last_update = Utc::now();
+        gained
+    }
+    
+    /// 生産以外で得たリソースの付与（累計獲得量にも加算する）
+    pub fn grant(&mut self, resource_type: ResourceType, amount: u64) {
+        self.game_state.resources.add(resource_type, amount);
+        self.game_state.lifetime_totals.add(resource_type, amount);
     }
     
     /// ゲーム状態の取得
@@ -473,11 +584,21 @@ impl ResourceManager {
         &self.game_state
     }
     
+    /// ゲーム状態の可変参照取得
+    pub fn get_game_state_mut(&mut self) -> &mut GameState {
+        &mut self.game_state
+    }
+    
     /// ゲーム状態の設定
     pub fn set_game_state(&mut self, state: GameState) {
         self.game_state = state;
     }
     
+    /// ティック長（ミリ秒）の取得
+    pub fn tick_duration_ms(&self) -> u64 {
+        self.tick_duration_ms
+    }
+    
     /// リソースの取得
     pub fn get_resources(&self) -> &Resources {
         &self.game_state.resources
@@ -542,15 +663,16 @@ mod tests {
     
     #[test]
     fn test_upgrade_cost_calculation() {
+        let catalog = UpgradeCatalog::default();
         let mut upgrades = UpgradeLevels::new();
         
         // レベル0の場合
-        let cost_0 = upgrades.calculate_cost(UpgradeType::DustProduction);
+        let cost_0 = upgrades.calculate_cost(&catalog, UpgradeType::DustProduction).unwrap();
         assert_eq!(cost_0.cosmic_dust, 100);
         
         // レベル1の場合
         upgrades.upgrade(UpgradeType::DustProduction);
-        let cost_1 = upgrades.calculate_cost(UpgradeType::DustProduction);
+        let cost_1 = upgrades.calculate_cost(&catalog, UpgradeType::DustProduction).unwrap();
         assert_eq!(cost_1.cosmic_dust, 150); // 100 * 1.5
     }
     
@@ -567,4 +689,56 @@ mod tests {
         // 2つのダストが生成されるはず
         assert_eq!(manager.game_state.resources.cosmic_dust, 2);
     }
+    
+    #[test]
+    fn test_resource_milestones() {
+        assert_eq!(ResourceMilestone::crossed(999, 1000), Some(1000));
+        assert_eq!(ResourceMilestone::crossed(1000, 9999), None);
+        assert_eq!(ResourceMilestone::crossed(9_999, 150_000), Some(100_000));
+        assert_eq!(ResourceMilestone::crossed(0, 999), None);
+        
+        let mut manager = ResourceManager::new(50);
+        manager.game_state.production_rates.energy_per_tick = fixed::from_f64(1.0);
+        manager.game_state.lifetime_totals.energy = 998;
+        
+        let milestones = manager.accumulate_resources(100);
+        assert_eq!(milestones, vec![ResourceMilestone { resource_type: ResourceType::Energy, amount: 1000 }]);
+        assert!(manager.accumulate_resources(100).is_empty());
+    }
+    
+    #[test]
+    fn test_body_production_joins_player_rates() {
+        let mut manager = ResourceManager::new(50);
+        let upgrade_dust = manager.calculate_production_rates(&manager.game_state).dust_per_tick;
+        manager.set_production_modifiers(HashMap::from([(ResourceType::Energy, 2.0)]));
+        
+        let mut body_rates = ProductionRates::new();
+        body_rates.dust_per_tick = fixed::from_f64(0.5);
+        body_rates.energy_per_tick = fixed::from_f64(1.5);
+        manager.set_body_production(body_rates.clone());
+        
+        // 天体の生産にも研究の倍率が掛かる（基本のエネルギー生産0.5と合わせて2倍）
+        let rates = &manager.game_state.production_rates;
+        assert_eq!(rates.dust_per_tick, upgrade_dust + fixed::from_f64(0.5));
+        assert!((fixed::to_f64(rates.energy_per_tick) - 4.0).abs() < 1e-6);
+        
+        manager.accumulate_resources(1000); // 20ティック
+        assert_eq!(manager.game_state.resources.energy, 80);
+        
+        let summary = manager.production_summary(Vec::new());
+        assert_eq!(summary.multipliers[&ResourceType::Energy], 2.0);
+        assert_eq!(summary.upgrade_rates.dust_per_tick, upgrade_dust);
+        assert_eq!(summary.total_rates.energy_per_tick, manager.game_state.production_rates.energy_per_tick);
+    }
+    
+    #[test]
+    fn test_upgrade_max_level() {
+        let mut manager = ResourceManager::new(50);
+        let max_level = manager.upgrade_catalog().current().max_level(UpgradeType::ThoughtAcceleration).unwrap();
+        manager.game_state.upgrade_levels.set_level(UpgradeType::ThoughtAcceleration, max_level);
+        manager.game_state.resources.thought_points = u64::MAX / 2;
+        
+        let result = manager.apply_upgrade(UpgradeType::ThoughtAcceleration);
+        assert!(matches!(result, Err(GameError::BusinessLogic(_))));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,343 @@
+//! 天体の空間インデックス
+//!
+//! 物理演算（衝突検出）とゲームプレイ（作成時の分離距離チェック、近傍検索）で共有するR木。
+//! 各天体は移動量を見込んだ余白付きの境界ボックスで登録し、天体がその内側に留まっている間は
+//! 木を更新しない。毎ティック作り直す代わりに、はみ出した天体だけを差し替える。
+
+use std::collections::HashMap;
+use rstar::{Envelope, PointDistance, RTree, RTreeObject, AABB};
+
+use crate::game::celestial_bodies::{BodyId, CelestialBody, Point3Fixed, Vec3Fixed};
+use crate::game::resources::fixed;
+
+/// 空間インデックスに登録するバウンディングボックス
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicsAABB {
+    pub body_id: BodyId,
+    pub min: Point3Fixed,
+    pub max: Point3Fixed,
+}
+
+impl PhysicsAABB {
+    /// 中心と半径から作成
+    pub fn around(body_id: BodyId, center: Vec3Fixed, half_extent: f64) -> Self {
+        Self {
+            body_id,
+            min: Point3Fixed::from(center.add_scalar(-half_extent)),
+            max: Point3Fixed::from(center.add_scalar(half_extent)),
+        }
+    }
+
+    /// `other`を完全に含むか
+    pub fn contains(&self, other: &PhysicsAABB) -> bool {
+        self.envelope().contains_envelope(&other.envelope())
+    }
+}
+
+impl RTreeObject for PhysicsAABB {
+    type Envelope = AABB<[f64; 3]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(
+            [self.min.x, self.min.y, self.min.z],
+            [self.max.x, self.max.y, self.max.z],
+        )
+    }
+}
+
+impl PointDistance for PhysicsAABB {
+    fn distance_2(&self, point: &[f64; 3]) -> f64 {
+        self.envelope().distance_2(point)
+    }
+}
+
+/// 空間インデックスの設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialIndexSettings {
+    /// 境界ボックスに加える最小の余白
+    pub min_margin: f64,
+    /// 速度に掛けて余白に加える時間（秒）。速い天体ほど余白を大きく取る
+    pub velocity_lookahead: f64,
+    /// 登録し直す天体の割合がこれを超えたら木全体を作り直す
+    pub rebuild_ratio: f64,
+}
+
+impl Default for SpatialIndexSettings {
+    fn default() -> Self {
+        Self {
+            min_margin: 1.0,
+            velocity_lookahead: 1.0,
+            rebuild_ratio: 0.25,
+        }
+    }
+}
+
+/// 登録中の天体（木には余白付きの境界を登録し、判定には最新の位置と半径を使う）
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    bounds: PhysicsAABB,
+    position: Vec3Fixed,
+    radius: f64,
+}
+
+impl IndexEntry {
+    /// 余白なしの境界
+    fn tight_bounds(&self, body_id: BodyId) -> PhysicsAABB {
+        PhysicsAABB::around(body_id, self.position, self.radius)
+    }
+}
+
+/// 天体の空間インデックス
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+    tree: RTree<PhysicsAABB>,
+    entries: HashMap<BodyId, IndexEntry>,
+    settings: SpatialIndexSettings,
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_settings(settings: SpatialIndexSettings) -> Self {
+        Self { settings, ..Self::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, body_id: BodyId) -> bool {
+        self.entries.contains_key(&body_id)
+    }
+
+    /// 天体の登録・更新（余白の内側に留まっていれば木は変更しない）
+    ///
+    /// 木を登録し直した場合は`true`を返す。
+    pub fn insert(&mut self, body: &CelestialBody) -> bool {
+        let entry = self.entry_for(body);
+        let reinserted = match self.entries.get(&body.id) {
+            Some(current) if current.bounds.contains(&entry.tight_bounds(body.id)) => {
+                let bounds = current.bounds.clone();
+                self.entries.insert(body.id, IndexEntry { bounds, ..entry });
+                return false;
+            }
+            Some(current) => {
+                self.tree.remove(&current.bounds);
+                true
+            }
+            None => true,
+        };
+        self.tree.insert(entry.bounds.clone());
+        self.entries.insert(body.id, entry);
+        reinserted
+    }
+
+    /// 天体の登録解除
+    pub fn remove(&mut self, body_id: BodyId) -> bool {
+        match self.entries.remove(&body_id) {
+            Some(entry) => {
+                self.tree.remove(&entry.bounds);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 一つの天体の反映（`bodies`にあれば登録・更新し、なければ登録を外す）
+    pub fn refresh(&mut self, body_id: BodyId, bodies: &HashMap<BodyId, CelestialBody>) {
+        match bodies.get(&body_id) {
+            Some(body) => {
+                self.insert(body);
+            }
+            None => {
+                self.remove(body_id);
+            }
+        }
+    }
+
+    /// 全天体との同期（消えた天体の削除と、余白からはみ出した天体の登録し直し）
+    ///
+    /// 登録し直した天体の数を返す。多くの天体がはみ出した場合は木全体を作り直す。
+    pub fn sync(&mut self, bodies: &HashMap<BodyId, CelestialBody>) -> usize {
+        let removed: Vec<BodyId> = self.entries.keys()
+            .filter(|id| !bodies.contains_key(id))
+            .copied()
+            .collect();
+        for id in removed {
+            self.remove(id);
+        }
+
+        let mut stale = Vec::new();
+        for (id, body) in bodies {
+            let entry = self.entry_for(body);
+            match self.entries.get_mut(id) {
+                Some(current) if current.bounds.contains(&entry.tight_bounds(*id)) => {
+                    current.position = entry.position;
+                    current.radius = entry.radius;
+                }
+                _ => stale.push(body),
+            }
+        }
+
+        if stale.len() as f64 > bodies.len() as f64 * self.settings.rebuild_ratio {
+            self.rebuild(bodies);
+        } else {
+            for body in &stale {
+                self.insert(body);
+            }
+        }
+        stale.len()
+    }
+
+    /// 木全体の作り直し
+    pub fn rebuild(&mut self, bodies: &HashMap<BodyId, CelestialBody>) {
+        self.entries = bodies.values().map(|body| (body.id, self.entry_for(body))).collect();
+        self.tree = RTree::bulk_load(self.entries.values().map(|entry| entry.bounds.clone()).collect());
+    }
+
+    /// 全登録の削除
+    pub fn clear(&mut self) {
+        self.tree = RTree::new();
+        self.entries.clear();
+    }
+
+    /// 中心が`point`に近い順に最大`k`個の天体
+    pub fn nearest(&self, point: Vec3Fixed, k: usize) -> Vec<BodyId> {
+        let mut best: Vec<(f64, BodyId)> = Vec::with_capacity(k + 1);
+        if k == 0 {
+            return Vec::new();
+        }
+
+        // 余白付きの境界までの距離は中心までの距離以下なので、k番目より遠くなったら打ち切れる
+        for (bounds, lower_bound) in self.tree.nearest_neighbor_iter_with_distance_2(&[point.x, point.y, point.z]) {
+            if best.len() == k && lower_bound > best[k - 1].0 {
+                break;
+            }
+            let Some(entry) = self.entries.get(&bounds.body_id) else {
+                continue;
+            };
+            let candidate = ((entry.position - point).magnitude_squared(), bounds.body_id);
+            let index = best.partition_point(|other| *other < candidate);
+            best.insert(index, candidate);
+            best.truncate(k);
+        }
+
+        best.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// 表面が`point`から`distance`未満にある天体（ID順）
+    pub fn within_distance(&self, point: Vec3Fixed, distance: f64) -> Vec<BodyId> {
+        let query = PhysicsAABB::around(BodyId::nil(), point, distance).envelope();
+        let mut ids: Vec<BodyId> = self.tree.locate_in_envelope_intersecting(&query)
+            .filter(|bounds| {
+                self.entries.get(&bounds.body_id).is_some_and(|entry| {
+                    (entry.position - point).magnitude() < entry.radius + distance
+                })
+            })
+            .map(|bounds| bounds.body_id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// 境界ボックスが`min`〜`max`と交差する天体（ID順）
+    pub fn intersecting(&self, min: Vec3Fixed, max: Vec3Fixed) -> Vec<BodyId> {
+        let query = AABB::from_corners([min.x, min.y, min.z], [max.x, max.y, max.z]);
+        let mut ids: Vec<BodyId> = self.tree.locate_in_envelope_intersecting(&query)
+            .filter(|bounds| {
+                self.entries.get(&bounds.body_id).is_some_and(|entry| {
+                    entry.tight_bounds(bounds.body_id).envelope().intersects(&query)
+                })
+            })
+            .map(|bounds| bounds.body_id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn entry_for(&self, body: &CelestialBody) -> IndexEntry {
+        let radius = fixed::to_f64(body.physics.radius);
+        let margin = self.settings.min_margin
+            + body.physics.velocity.magnitude() * self.settings.velocity_lookahead;
+        IndexEntry {
+            bounds: PhysicsAABB::around(body.id, body.physics.position, radius + margin),
+            position: body.physics.position,
+            radius,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::game::celestial_bodies::CelestialType;
+
+    fn asteroid(position: Vec3Fixed, radius: f64) -> CelestialBody {
+        CelestialBody::new(Uuid::new_v4(), CelestialType::Asteroid, position, fixed::from_f64(1.0), fixed::from_f64(radius))
+    }
+
+    fn line_of_bodies(count: usize) -> HashMap<BodyId, CelestialBody> {
+        (0..count)
+            .map(|i| asteroid(Vec3Fixed::new(i as f64 * 10.0, 0.0, 0.0), 1.0))
+            .map(|body| (body.id, body))
+            .collect()
+    }
+
+    #[test]
+    fn test_queries_match_brute_force() {
+        let bodies = line_of_bodies(50);
+        let mut index = SpatialIndex::new();
+        index.sync(&bodies);
+        assert_eq!(index.len(), 50);
+
+        let point = Vec3Fixed::new(123.0, 4.0, 0.0);
+        let mut by_distance: Vec<(f64, BodyId)> = bodies.values()
+            .map(|body| ((body.physics.position - point).magnitude_squared(), body.id))
+            .collect();
+        by_distance.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let expected: Vec<BodyId> = by_distance.iter().take(5).map(|(_, id)| *id).collect();
+        assert_eq!(index.nearest(point, 5), expected);
+
+        // 中心ではなく表面までの距離で判定する（中心まで約8.06の天体も半径1なので含まれる）
+        let within = index.within_distance(point, 7.5);
+        assert_eq!(within.len(), 2);
+        assert!(within.iter().all(|id| (bodies[id].physics.position - point).magnitude() < 8.5));
+
+        let boxed = index.intersecting(Vec3Fixed::new(-1.0, -1.0, -1.0), Vec3Fixed::new(28.5, 1.0, 1.0));
+        assert_eq!(boxed.len(), 3);
+    }
+
+    #[test]
+    fn test_small_moves_do_not_touch_the_tree() {
+        let mut bodies = line_of_bodies(20);
+        let mut index = SpatialIndex::new();
+        index.sync(&bodies);
+
+        // 余白の内側の移動では登録し直さないが、位置は最新になる
+        let moved_id = *bodies.keys().next().unwrap();
+        bodies.get_mut(&moved_id).unwrap().physics.position.y += 0.5;
+        assert_eq!(index.sync(&bodies), 0);
+        let position = bodies[&moved_id].physics.position;
+        assert_eq!(index.nearest(position, 1), vec![moved_id]);
+
+        // 余白を越えた天体だけを登録し直す
+        bodies.get_mut(&moved_id).unwrap().physics.position.y += 50.0;
+        assert_eq!(index.sync(&bodies), 1);
+        let position = bodies[&moved_id].physics.position;
+        assert_eq!(index.within_distance(position, 0.5), vec![moved_id]);
+
+        // 消えた天体は登録から外れる
+        bodies.remove(&moved_id);
+        index.sync(&bodies);
+        assert!(!index.contains(moved_id));
+        assert!(index.within_distance(position, 0.5).is_empty());
+        assert_eq!(index.len(), 19);
+    }
+}
@@ -0,0 +1,408 @@
+//! 恒星進化
+//!
+//! 恒星を主系列星 → 巨星 → 残骸（白色矮星・中性子星・ブラックホール）の順に進化させる。
+//! 進化の速さと行き先は質量（太陽質量単位）で決まり、重い恒星は超新星爆発を起こして
+//! 宇宙の塵を撒き散らし、周囲の惑星の大気と海を剥ぎ取る。
+
+use std::collections::HashMap;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::game::celestial_bodies::{AtmosphereType, BlackHoleData, BodyId, CelestialBody, CelestialType, StarData, SOLAR_RADIUS};
+use crate::game::determinism::sorted_body_ids;
+use crate::game::life::{self, ExtinctionCause, LifeTransition};
+use crate::game::resources::fixed;
+
+/// 太陽質量の恒星の主系列寿命（ティック）
+pub const SOLAR_LIFESPAN_TICKS: u64 = 10_000_000;
+/// 主系列寿命の下限（20Hzで1時間）
+pub const MIN_LIFESPAN_TICKS: u64 = 72_000;
+/// 巨星期の長さ（主系列寿命に対する割合）
+pub const GIANT_PHASE_FRACTION: f64 = 0.1;
+/// 中性子星になる最小質量
+pub const NEUTRON_STAR_MIN_MASS: f64 = 8.0;
+/// ブラックホールになる最小質量
+pub const BLACK_HOLE_MIN_MASS: f64 = 25.0;
+/// 白色矮星の質量の上限（チャンドラセカール限界）
+pub const CHANDRASEKHAR_LIMIT: f64 = 1.4;
+/// 超新星で放出された1太陽質量あたりの宇宙の塵
+pub const DUST_PER_EJECTED_MASS: f64 = 1000.0;
+/// 超新星が惑星に影響する半径
+pub const SUPERNOVA_BLAST_RADIUS: f64 = 150_000.0;
+/// 大気が剥ぎ取られる被害の大きさ（爆心で1.0）
+const ATMOSPHERE_STRIP_SEVERITY: f64 = 0.5;
+
+/// 巨星になった時の半径・光度の倍率と表面温度の倍率
+const GIANT_RADIUS_FACTOR: f64 = 10.0;
+const GIANT_LUMINOSITY_FACTOR: f64 = 100.0;
+const GIANT_TEMPERATURE_FACTOR: f64 = 0.6;
+/// 残骸の半径（恒星の半径と同じ単位）
+const WHITE_DWARF_RADIUS: f64 = SOLAR_RADIUS * 0.01;
+const NEUTRON_STAR_RADIUS: f64 = 12.0;
+/// 1太陽質量あたりのシュヴァルツシルト半径（km）
+const SCHWARZSCHILD_RADIUS_PER_MASS: f64 = 2.95;
+
+/// 恒星の進化段階
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StellarPhase {
+    #[default]
+    MainSequence,
+    Giant,
+    WhiteDwarf,
+    NeutronStar,
+    /// 恒星ではなくなり`CelestialType::BlackHole`に置き換わる
+    BlackHole,
+}
+
+impl StellarPhase {
+    /// 段階名（実績の対象名に使う）
+    pub fn name(&self) -> &'static str {
+        match self {
+            StellarPhase::MainSequence => "main_sequence",
+            StellarPhase::Giant => "giant",
+            StellarPhase::WhiteDwarf => "white_dwarf",
+            StellarPhase::NeutronStar => "neutron_star",
+            StellarPhase::BlackHole => "black_hole",
+        }
+    }
+
+    /// 進化を終えた残骸か
+    pub fn is_remnant(&self) -> bool {
+        matches!(self, StellarPhase::WhiteDwarf | StellarPhase::NeutronStar | StellarPhase::BlackHole)
+    }
+}
+
+/// 超新星爆発
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Supernova {
+    /// 放出された宇宙の塵
+    pub dust_released: u64,
+    /// 大気や海を失った惑星
+    pub affected_planets: Vec<BodyId>,
+    /// 被害を受けた惑星の生命の後退・絶滅
+    pub life_transitions: Vec<LifeTransition>,
+}
+
+/// 恒星の進化段階の変化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StellarTransition {
+    pub body_id: BodyId,
+    pub from_phase: StellarPhase,
+    pub to_phase: StellarPhase,
+    pub supernova: Option<Supernova>,
+}
+
+/// 主系列寿命（質量の-2.5乗に比例）
+pub fn main_sequence_lifespan(mass: f64) -> u64 {
+    if mass <= 0.0 {
+        return u64::MAX;
+    }
+    ((SOLAR_LIFESPAN_TICKS as f64) * mass.powf(-2.5)).max(MIN_LIFESPAN_TICKS as f64) as u64
+}
+
+/// 巨星期を含めた寿命
+pub fn total_lifespan(main_sequence: u64) -> u64 {
+    main_sequence.saturating_add((main_sequence as f64 * GIANT_PHASE_FRACTION) as u64)
+}
+
+/// 主系列星の光度（質量光度関係、太陽光度単位）
+pub fn main_sequence_luminosity(mass: f64) -> f64 {
+    mass.max(0.0).powf(3.5)
+}
+
+/// 質量から決まる最期の姿
+pub fn final_phase(mass: f64) -> StellarPhase {
+    if mass >= BLACK_HOLE_MIN_MASS {
+        StellarPhase::BlackHole
+    } else if mass >= NEUTRON_STAR_MIN_MASS {
+        StellarPhase::NeutronStar
+    } else {
+        StellarPhase::WhiteDwarf
+    }
+}
+
+/// 作成直後の恒星に寿命と光度を設定
+pub fn initialize_star(body: &mut CelestialBody) {
+    let mass = fixed::to_f64(body.physics.mass);
+    if let CelestialType::Star(star) = &mut body.body_type {
+        star.lifespan = main_sequence_lifespan(mass);
+        star.luminosity = fixed::from_f64(main_sequence_luminosity(mass));
+        star.phase = StellarPhase::MainSequence;
+        body.lifecycle.lifespan = Some(total_lifespan(star.lifespan));
+    }
+}
+
+/// 全恒星を`elapsed_ticks`だけ進化させ、段階が変わった恒星を返す
+pub fn evolve_stars(bodies: &mut HashMap<BodyId, CelestialBody>, elapsed_ticks: u64) -> Vec<StellarTransition> {
+    let mut transitions = Vec::new();
+
+    for body_id in sorted_body_ids(bodies) {
+        let Some(CelestialType::Star(star)) = bodies.get_mut(&body_id).map(|body| &mut body.body_type) else {
+            continue;
+        };
+        star.age = star.age.saturating_add(elapsed_ticks);
+
+        // 長い経過時間では複数の段階を一度に進む
+        while let Some(transition) = advance_phase(bodies, body_id) {
+            transitions.push(transition);
+        }
+    }
+
+    transitions
+}
+
+/// 寿命に達していれば次の段階へ進める
+fn advance_phase(bodies: &mut HashMap<BodyId, CelestialBody>, body_id: BodyId) -> Option<StellarTransition> {
+    let body = bodies.get_mut(&body_id)?;
+    let mass = fixed::to_f64(body.physics.mass);
+    let CelestialType::Star(star) = &mut body.body_type else {
+        return None;
+    };
+    let from_phase = star.phase;
+
+    match from_phase {
+        StellarPhase::MainSequence if star.age >= star.lifespan => {
+            star.phase = StellarPhase::Giant;
+            star.luminosity = fixed::from_f64(fixed::to_f64(star.luminosity) * GIANT_LUMINOSITY_FACTOR);
+            star.temperature = (star.temperature as f64 * GIANT_TEMPERATURE_FACTOR) as u32;
+            body.physics.radius = fixed::from_f64(fixed::to_f64(body.physics.radius) * GIANT_RADIUS_FACTOR);
+            Some(StellarTransition { body_id, from_phase, to_phase: StellarPhase::Giant, supernova: None })
+        }
+        StellarPhase::Giant if star.age >= total_lifespan(star.lifespan) => {
+            let to_phase = final_phase(mass);
+            let supernova = match to_phase {
+                StellarPhase::WhiteDwarf => {
+                    become_white_dwarf(body, mass);
+                    None
+                }
+                _ => Some(explode(bodies, body_id, mass, to_phase)),
+            };
+            Some(StellarTransition { body_id, from_phase, to_phase, supernova })
+        }
+        _ => None,
+    }
+}
+
+/// 外層を穏やかに放出して白色矮星になる
+fn become_white_dwarf(body: &mut CelestialBody, mass: f64) {
+    body.physics.mass = fixed::from_f64((0.5 + 0.1 * mass).min(CHANDRASEKHAR_LIMIT));
+    body.physics.radius = fixed::from_f64(WHITE_DWARF_RADIUS);
+    body.lifecycle.lifespan = None;
+    if let CelestialType::Star(star) = &mut body.body_type {
+        set_remnant(star, StellarPhase::WhiteDwarf, 0.001, 20_000);
+    }
+}
+
+/// 超新星爆発（残骸を残し、周囲の惑星に被害を与える）
+fn explode(bodies: &mut HashMap<BodyId, CelestialBody>, body_id: BodyId, mass: f64, remnant: StellarPhase) -> Supernova {
+    let remnant_mass = match remnant {
+        StellarPhase::BlackHole => mass * 0.3,
+        _ => CHANDRASEKHAR_LIMIT,
+    };
+    let dust_released = ((mass - remnant_mass).max(0.0) * DUST_PER_EJECTED_MASS) as u64;
+
+    let center = match bodies.get_mut(&body_id) {
+        Some(body) => {
+            body.physics.mass = fixed::from_f64(remnant_mass);
+            body.lifecycle.lifespan = None;
+            if remnant == StellarPhase::BlackHole {
+                let schwarzschild_radius = fixed::from_f64(remnant_mass * SCHWARZSCHILD_RADIUS_PER_MASS);
+                body.physics.radius = schwarzschild_radius;
+                body.body_type = CelestialType::BlackHole(BlackHoleData {
+                    schwarzschild_radius,
+                    accretion_rate: 0,
+                    formation_time: Utc::now(),
+                });
+            } else {
+                body.physics.radius = fixed::from_f64(NEUTRON_STAR_RADIUS);
+                if let CelestialType::Star(star) = &mut body.body_type {
+                    set_remnant(star, StellarPhase::NeutronStar, 0.0, 600_000);
+                }
+            }
+            body.physics.position
+        }
+        None => return Supernova { dust_released, affected_planets: Vec::new(), life_transitions: Vec::new() },
+    };
+
+    // 爆心に近いほど海が蒸発し、人口が大きく失われる。近くの惑星は大気も失う
+    let mut affected_planets = Vec::new();
+    let mut life_transitions = Vec::new();
+    for planet_id in sorted_body_ids(bodies) {
+        let Some(planet) = bodies.get_mut(&planet_id) else {
+            continue;
+        };
+        let distance = (planet.physics.position - center).magnitude();
+        let CelestialType::Planet(planet_data) = &mut planet.body_type else {
+            continue;
+        };
+        if distance >= SUPERNOVA_BLAST_RADIUS {
+            continue;
+        }
+
+        let severity = 1.0 - distance / SUPERNOVA_BLAST_RADIUS;
+        planet_data.water_coverage = (planet_data.water_coverage as f64 * (1.0 - severity)) as u8;
+        if severity >= ATMOSPHERE_STRIP_SEVERITY {
+            planet_data.atmosphere = AtmosphereType::None;
+        }
+        life_transitions.extend(life::apply_catastrophe(planet, severity, ExtinctionCause::Supernova));
+        affected_planets.push(planet_id);
+    }
+
+    Supernova { dust_released, affected_planets, life_transitions }
+}
+
+fn set_remnant(star: &mut StarData, phase: StellarPhase, luminosity: f64, temperature: u32) {
+    star.phase = phase;
+    star.luminosity = fixed::from_f64(luminosity);
+    star.temperature = temperature;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::game::celestial_bodies::{LifeStage, PlanetData, PlanetType, SpectralType, Vec3Fixed};
+    use crate::game::life::LifeEventKind;
+
+    fn star(spectral_type: SpectralType, position: Vec3Fixed) -> CelestialBody {
+        let (min_mass, max_mass) = spectral_type.mass_range();
+        let mass = (min_mass + max_mass) * 0.5;
+        let mut body = CelestialBody::new(
+            Uuid::new_v4(),
+            CelestialType::Star(StarData {
+                temperature: spectral_type.temperature_range().0,
+                spectral_type,
+                luminosity: 0,
+                age: 0,
+                lifespan: 0,
+                phase: StellarPhase::MainSequence,
+            }),
+            position,
+            fixed::from_f64(mass),
+            fixed::from_f64(mass.sqrt() * SOLAR_RADIUS),
+        );
+        initialize_star(&mut body);
+        body
+    }
+
+    fn planet(position: Vec3Fixed, habitability: u8) -> CelestialBody {
+        let mut body = CelestialBody::new(
+            Uuid::new_v4(),
+            CelestialType::Planet(PlanetData {
+                planet_type: PlanetType::Ocean,
+                atmosphere: AtmosphereType::Oxygen,
+                water_coverage: 70,
+                temperature_range: (0, 30),
+                habitability,
+            }),
+            position,
+            fixed::from_f64(1.0),
+            fixed::from_f64(1.0),
+        );
+        body.lifecycle.life_stage = LifeStage::Animal { species_count: 10, food_chain_complexity: 1 };
+        body.lifecycle.population = 1_000_000;
+        body
+    }
+
+    fn phase(body: &CelestialBody) -> StellarPhase {
+        match &body.body_type {
+            CelestialType::Star(star) => star.phase,
+            CelestialType::BlackHole(_) => StellarPhase::BlackHole,
+            _ => panic!("not a star"),
+        }
+    }
+
+    #[test]
+    fn test_lifespan_depends_on_mass() {
+        assert_eq!(main_sequence_lifespan(1.0), SOLAR_LIFESPAN_TICKS);
+        assert!(main_sequence_lifespan(0.3) > main_sequence_lifespan(1.0));
+        assert_eq!(main_sequence_lifespan(50.0), MIN_LIFESPAN_TICKS);
+
+        assert_eq!(final_phase(SpectralType::G.mass_range().1), StellarPhase::WhiteDwarf);
+        assert_eq!(final_phase(9.0), StellarPhase::NeutronStar);
+        assert_eq!(final_phase(SpectralType::O.mass_range().0 + 10.0), StellarPhase::BlackHole);
+    }
+
+    #[test]
+    fn test_sun_like_star_becomes_white_dwarf() {
+        let mut bodies = HashMap::new();
+        let sun = star(SpectralType::G, Vec3Fixed::zeros());
+        let sun_id = sun.id;
+        let (lifespan, radius) = match &sun.body_type {
+            CelestialType::Star(data) => (data.lifespan, sun.physics.radius),
+            _ => unreachable!(),
+        };
+        assert_eq!(sun.lifecycle.lifespan, Some(total_lifespan(lifespan)));
+        bodies.insert(sun_id, sun);
+
+        assert!(evolve_stars(&mut bodies, lifespan - 1).is_empty());
+
+        let transitions = evolve_stars(&mut bodies, 1);
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].to_phase, StellarPhase::Giant);
+        assert!(bodies[&sun_id].physics.radius > radius);
+
+        let transitions = evolve_stars(&mut bodies, lifespan);
+        assert_eq!(transitions[0].to_phase, StellarPhase::WhiteDwarf);
+        assert!(transitions[0].supernova.is_none());
+        assert!(fixed::to_f64(bodies[&sun_id].physics.mass) <= CHANDRASEKHAR_LIMIT);
+        assert_eq!(bodies[&sun_id].lifecycle.lifespan, None);
+
+        // 残骸はそれ以上進化しない
+        assert!(evolve_stars(&mut bodies, u64::MAX / 2).is_empty());
+    }
+
+    #[test]
+    fn test_massive_star_collapses_into_black_hole_with_supernova() {
+        let mut bodies = HashMap::new();
+        let giant = star(SpectralType::O, Vec3Fixed::zeros());
+        let giant_id = giant.id;
+        bodies.insert(giant_id, giant);
+        let near = planet(Vec3Fixed::new(1000.0, 0.0, 0.0), 90);
+        let near_id = near.id;
+        bodies.insert(near_id, near);
+        let far = planet(Vec3Fixed::new(SUPERNOVA_BLAST_RADIUS * 2.0, 0.0, 0.0), 90);
+        let far_id = far.id;
+        bodies.insert(far_id, far);
+
+        // 一度に寿命を越えると巨星期を経て崩壊まで進む
+        let transitions = evolve_stars(&mut bodies, 10 * SOLAR_LIFESPAN_TICKS);
+        let phases: Vec<StellarPhase> = transitions.iter().map(|t| t.to_phase).collect();
+        assert_eq!(phases, vec![StellarPhase::Giant, StellarPhase::BlackHole]);
+        assert_eq!(phase(&bodies[&giant_id]), StellarPhase::BlackHole);
+
+        let supernova = transitions[1].supernova.as_ref().unwrap();
+        assert!(supernova.dust_released > 0);
+        assert_eq!(supernova.affected_planets, vec![near_id]);
+
+        let planet_data = |id: &BodyId| match &bodies[id].body_type {
+            CelestialType::Planet(data) => data.clone(),
+            _ => unreachable!(),
+        };
+        assert!(planet_data(&near_id).water_coverage < 70);
+        assert_eq!(planet_data(&near_id).atmosphere, AtmosphereType::None);
+        assert_eq!(planet_data(&far_id).water_coverage, 70);
+        assert_eq!(planet_data(&far_id).atmosphere, AtmosphereType::Oxygen);
+        // 爆心近くの生命は絶滅する
+        assert_eq!(bodies[&near_id].lifecycle.life_stage, LifeStage::None);
+        assert_eq!(supernova.life_transitions.last().unwrap().kind, LifeEventKind::Extinction);
+        assert!(supernova.life_transitions.iter().all(|t| t.body_id == near_id));
+        assert_eq!(bodies[&far_id].lifecycle.population, 1_000_000);
+    }
+
+    #[test]
+    fn test_b_type_star_becomes_neutron_star() {
+        let mut bodies = HashMap::new();
+        let body = star(SpectralType::B, Vec3Fixed::zeros());
+        let id = body.id;
+        bodies.insert(id, body);
+
+        let transitions = evolve_stars(&mut bodies, 10 * SOLAR_LIFESPAN_TICKS);
+
+        assert_eq!(transitions.last().unwrap().to_phase, StellarPhase::NeutronStar);
+        assert!(transitions.last().unwrap().supernova.is_some());
+        assert_eq!(phase(&bodies[&id]), StellarPhase::NeutronStar);
+        assert!((fixed::to_f64(bodies[&id].physics.mass) - CHANDRASEKHAR_LIMIT).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,379 @@
+//! ティック予算による負荷制御
+//!
+//! ゲームループは1ティックを`1 / target_tps`秒の予算で回し、サブシステムごとの処理時間を計る。
+//! 予算の超過が続いたら決められた順に処理を間引き（Barnes-Hut近似の精度 → 放置中の世界の生命の更新
+//! → ブロードキャストの頻度）、余裕のあるティックが続いたら逆の順に戻す。
+//! 遅れたティックは上限付きの複数ステップで追いつき、上限を超えた遅れは捨てる。
+
+use std::time::{Duration, Instant};
+
+/// 処理時間を計るサブシステム
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    Commands,
+    Physics,
+    Resources,
+    Broadcast,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 4] = [
+        Subsystem::Commands,
+        Subsystem::Physics,
+        Subsystem::Resources,
+        Subsystem::Broadcast,
+    ];
+
+    /// メトリクスのラベル
+    pub fn name(&self) -> &'static str {
+        match self {
+            Subsystem::Commands => "commands",
+            Subsystem::Physics => "physics",
+            Subsystem::Resources => "resources",
+            Subsystem::Broadcast => "broadcast",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 縮退の段階（上の段階は下の段階の間引きも含む）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DegradationLevel {
+    #[default]
+    Normal,
+    /// Barnes-Hut近似の開き角を広げる
+    ReducedAccuracy,
+    /// 放置中の世界の生命を更新しない
+    SkipIdleLife,
+    /// ブロードキャストの頻度を下げる
+    ThrottledBroadcast,
+}
+
+impl DegradationLevel {
+    pub fn reduces_accuracy(self) -> bool {
+        self >= DegradationLevel::ReducedAccuracy
+    }
+
+    pub fn skips_idle_life(self) -> bool {
+        self >= DegradationLevel::SkipIdleLife
+    }
+
+    pub fn throttles_broadcast(self) -> bool {
+        self >= DegradationLevel::ThrottledBroadcast
+    }
+
+    /// 1段階下げる（最も重い段階ではそのまま）
+    fn degrade(self) -> Self {
+        match self {
+            DegradationLevel::Normal => DegradationLevel::ReducedAccuracy,
+            DegradationLevel::ReducedAccuracy => DegradationLevel::SkipIdleLife,
+            DegradationLevel::SkipIdleLife | DegradationLevel::ThrottledBroadcast => DegradationLevel::ThrottledBroadcast,
+        }
+    }
+
+    /// 1段階戻す
+    fn recover(self) -> Self {
+        match self {
+            DegradationLevel::Normal | DegradationLevel::ReducedAccuracy => DegradationLevel::Normal,
+            DegradationLevel::SkipIdleLife => DegradationLevel::ReducedAccuracy,
+            DegradationLevel::ThrottledBroadcast => DegradationLevel::SkipIdleLife,
+        }
+    }
+
+    /// アトミック変数に保存する値
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => DegradationLevel::Normal,
+            1 => DegradationLevel::ReducedAccuracy,
+            2 => DegradationLevel::SkipIdleLife,
+            _ => DegradationLevel::ThrottledBroadcast,
+        }
+    }
+}
+
+/// ティック予算の設定
+#[derive(Debug, Clone)]
+pub struct TickBudgetConfig {
+    /// 予算の超過がこのティック数続いたら1段階下げる
+    pub degrade_after: u32,
+    /// 余裕のあるティックがこのティック数続いたら1段階戻す
+    pub recover_after: u32,
+    /// 予算に対する処理時間の割合がこれ以下のティックを余裕があるとみなす
+    pub recover_ratio: f64,
+    /// 1回のティックで追いつく最大ステップ数
+    pub max_catch_up_steps: u32,
+    /// 通常時のBarnes-Hut近似の開き角
+    pub theta: f64,
+    /// 縮退時のBarnes-Hut近似の開き角
+    pub degraded_theta: f64,
+    /// 縮退時のブロードキャスト間隔の倍率
+    pub broadcast_throttle: u32,
+    /// 参加者の最後の操作からこの時間が経った世界を放置中とみなす
+    pub idle_after: Duration,
+}
+
+impl Default for TickBudgetConfig {
+    fn default() -> Self {
+        Self {
+            degrade_after: 3,
+            recover_after: 100,
+            recover_ratio: 0.6,
+            max_catch_up_steps: 4,
+            theta: 0.5,
+            degraded_theta: 1.0,
+            broadcast_throttle: 4,
+            idle_after: Duration::from_secs(60),
+        }
+    }
+}
+
+/// このティックで進めるステップ数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepPlan {
+    pub steps: u32,
+    /// 上限を超えたため捨てたステップ数
+    pub dropped: u32,
+}
+
+/// 締めたティックの計測結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickReport {
+    /// サブシステムごとの処理時間
+    pub costs: Vec<(Subsystem, Duration)>,
+    pub total: Duration,
+    pub overrun: bool,
+    /// 次のティックから使う縮退の段階
+    pub level: DegradationLevel,
+}
+
+/// ティック予算のスケジューラ
+#[derive(Debug)]
+pub struct TickScheduler {
+    config: TickBudgetConfig,
+    /// 1ステップで進めるシミュレーション時間
+    step: Duration,
+    /// 1ティックの予算
+    budget: Duration,
+    /// まだ進めていない経過時間
+    lag: Duration,
+    costs: [Duration; Subsystem::ALL.len()],
+    level: DegradationLevel,
+    overrun_streak: u32,
+    headroom_streak: u32,
+    overruns: u64,
+    dropped_steps: u64,
+}
+
+impl TickScheduler {
+    pub fn new(step: Duration, budget: Duration, config: TickBudgetConfig) -> Self {
+        Self {
+            config,
+            step: step.max(Duration::from_micros(1)),
+            budget,
+            lag: Duration::ZERO,
+            costs: [Duration::ZERO; Subsystem::ALL.len()],
+            level: DegradationLevel::Normal,
+            overrun_streak: 0,
+            headroom_streak: 0,
+            overruns: 0,
+            dropped_steps: 0,
+        }
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    pub fn level(&self) -> DegradationLevel {
+        self.level
+    }
+
+    /// 予算を超過したティック数
+    pub fn overruns(&self) -> u64 {
+        self.overruns
+    }
+
+    /// 追いつけずに捨てたステップ数
+    pub fn dropped_steps(&self) -> u64 {
+        self.dropped_steps
+    }
+
+    /// 前回のティックからの経過時間に対して進めるステップ数を決める
+    ///
+    /// 遅れは固定長のステップでまとめて取り戻すが、1ティックで`max_catch_up_steps`を超える分は捨てる
+    /// （取り戻すための処理でさらに遅れる悪循環を防ぐ）。
+    pub fn plan_steps(&mut self, elapsed: Duration) -> StepPlan {
+        self.lag += elapsed;
+        let due = (self.lag.as_nanos() / self.step.as_nanos()) as u64;
+        self.lag -= self.step * due as u32;
+
+        let steps = due.min(self.config.max_catch_up_steps.max(1) as u64) as u32;
+        let dropped = (due - steps as u64) as u32;
+        self.dropped_steps += dropped as u64;
+        StepPlan { steps, dropped }
+    }
+
+    /// サブシステムの処理時間を記録
+    pub fn record(&mut self, subsystem: Subsystem, cost: Duration) {
+        self.costs[subsystem.index()] += cost;
+    }
+
+    /// 処理時間を計りながら実行
+    pub fn measure<R>(&mut self, subsystem: Subsystem, f: impl FnOnce() -> R) -> R {
+        let started = Instant::now();
+        let result = f();
+        self.record(subsystem, started.elapsed());
+        result
+    }
+
+    /// ティックを締め、処理時間から次のティックの縮退の段階を決める
+    pub fn finish_tick(&mut self) -> TickReport {
+        let costs: Vec<(Subsystem, Duration)> = Subsystem::ALL
+            .into_iter()
+            .map(|subsystem| (subsystem, self.costs[subsystem.index()]))
+            .collect();
+        let total: Duration = self.costs.iter().sum();
+        self.costs = [Duration::ZERO; Subsystem::ALL.len()];
+
+        let overrun = total > self.budget;
+        if overrun {
+            self.overruns += 1;
+            self.overrun_streak += 1;
+            self.headroom_streak = 0;
+            if self.overrun_streak >= self.config.degrade_after {
+                self.level = self.level.degrade();
+                self.overrun_streak = 0;
+            }
+        } else if total.as_secs_f64() <= self.budget.as_secs_f64() * self.config.recover_ratio {
+            self.headroom_streak += 1;
+            self.overrun_streak = 0;
+            if self.headroom_streak >= self.config.recover_after {
+                self.level = self.level.recover();
+                self.headroom_streak = 0;
+            }
+        } else {
+            // 予算内だが余裕もない間は今の段階を保つ
+            self.overrun_streak = 0;
+            self.headroom_streak = 0;
+        }
+
+        TickReport { costs, total, overrun, level: self.level }
+    }
+
+    /// 現在の段階でのBarnes-Hut近似の開き角
+    pub fn theta(&self) -> f64 {
+        if self.level.reduces_accuracy() {
+            self.config.degraded_theta
+        } else {
+            self.config.theta
+        }
+    }
+
+    /// 現在の段階でのブロードキャスト間隔（ティック数）
+    pub fn broadcast_interval(&self, base_ticks: u64) -> u64 {
+        let base_ticks = base_ticks.max(1);
+        if self.level.throttles_broadcast() {
+            base_ticks * self.config.broadcast_throttle.max(1) as u64
+        } else {
+            base_ticks
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler() -> TickScheduler {
+        let config = TickBudgetConfig { degrade_after: 2, recover_after: 3, ..TickBudgetConfig::default() };
+        TickScheduler::new(Duration::from_millis(10), Duration::from_millis(10), config)
+    }
+
+    fn run_tick(scheduler: &mut TickScheduler, physics_ms: u64) -> TickReport {
+        scheduler.record(Subsystem::Physics, Duration::from_millis(physics_ms));
+        scheduler.finish_tick()
+    }
+
+    #[test]
+    fn test_overruns_degrade_in_order_and_recover() {
+        let mut scheduler = scheduler();
+        assert_eq!(scheduler.theta(), 0.5);
+
+        // 超過が続くたびに1段階ずつ下がり、最も重い段階で止まる
+        let mut levels = Vec::new();
+        for _ in 0..8 {
+            levels.push(run_tick(&mut scheduler, 15).level);
+        }
+        assert_eq!(levels, vec![
+            DegradationLevel::Normal,
+            DegradationLevel::ReducedAccuracy,
+            DegradationLevel::ReducedAccuracy,
+            DegradationLevel::SkipIdleLife,
+            DegradationLevel::SkipIdleLife,
+            DegradationLevel::ThrottledBroadcast,
+            DegradationLevel::ThrottledBroadcast,
+            DegradationLevel::ThrottledBroadcast,
+        ]);
+        assert_eq!(scheduler.overruns(), 8);
+        assert_eq!(scheduler.theta(), 1.0);
+        assert_eq!(scheduler.broadcast_interval(2), 8);
+
+        // 予算内でも余裕がなければ戻さない
+        for _ in 0..5 {
+            assert!(!run_tick(&mut scheduler, 8).overrun);
+        }
+        assert_eq!(scheduler.level(), DegradationLevel::ThrottledBroadcast);
+
+        // 余裕のあるティックが続くと逆の順に戻る
+        for _ in 0..3 {
+            run_tick(&mut scheduler, 1);
+        }
+        assert_eq!(scheduler.level(), DegradationLevel::SkipIdleLife);
+        assert_eq!(scheduler.broadcast_interval(2), 2);
+        for _ in 0..6 {
+            run_tick(&mut scheduler, 1);
+        }
+        assert_eq!(scheduler.level(), DegradationLevel::Normal);
+        assert_eq!(scheduler.overruns(), 8);
+    }
+
+    #[test]
+    fn test_catch_up_steps_are_bounded() {
+        let mut scheduler = scheduler();
+
+        // 端数は次のティックに持ち越す
+        assert_eq!(scheduler.plan_steps(Duration::from_millis(6)), StepPlan { steps: 0, dropped: 0 });
+        assert_eq!(scheduler.plan_steps(Duration::from_millis(6)), StepPlan { steps: 1, dropped: 0 });
+        assert_eq!(scheduler.plan_steps(Duration::from_millis(28)), StepPlan { steps: 3, dropped: 0 });
+
+        // 上限を超えた遅れは捨てる
+        assert_eq!(scheduler.plan_steps(Duration::from_millis(70)), StepPlan { steps: 4, dropped: 3 });
+        assert_eq!(scheduler.dropped_steps(), 3);
+        assert_eq!(scheduler.plan_steps(Duration::from_millis(10)), StepPlan { steps: 1, dropped: 0 });
+    }
+
+    #[test]
+    fn test_report_sums_subsystem_costs() {
+        let mut scheduler = scheduler();
+        scheduler.record(Subsystem::Commands, Duration::from_millis(1));
+        scheduler.record(Subsystem::Physics, Duration::from_millis(2));
+        scheduler.record(Subsystem::Physics, Duration::from_millis(3));
+        let value = scheduler.measure(Subsystem::Broadcast, || 42);
+        assert_eq!(value, 42);
+
+        let report = scheduler.finish_tick();
+        assert_eq!(report.costs.len(), Subsystem::ALL.len());
+        assert_eq!(report.costs[1], (Subsystem::Physics, Duration::from_millis(5)));
+        assert!(report.total >= Duration::from_millis(6));
+
+        // 締めたら次のティックは0から数える
+        assert_eq!(scheduler.finish_tick().total, Duration::ZERO);
+    }
+}
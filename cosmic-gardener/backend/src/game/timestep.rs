@@ -0,0 +1,354 @@
+//! 適応的な時間刻みと連続衝突判定
+//!
+//! 1ティックを固定の刻み幅で進めると、高速で接近する天体同士が1ステップの間にすり抜けてしまう。
+//! 最も近い天体との隙間・相対速度・相互の加速度から、接近している組の天体だけをティック内で分割して進め
+//! （ブロック時間刻み）、それ以外の天体はティック刻みのまま進める。衝突はティック内の移動を線分とみなした
+//! 球の掃引で判定する。
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::celestial_bodies::{BodyId, Vec3Fixed};
+use crate::game::integrator::{Integrator, PhaseSpace};
+use crate::game::spatial_index::SpatialIndex;
+
+/// 時間刻みの設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimestepSettings {
+    /// 接近に応じてティックを分割するか
+    pub adaptive: bool,
+    /// 1ティックあたりの最大分割数
+    pub max_substeps: u32,
+    /// 1サブステップで詰めてよい隙間の割合
+    pub courant_factor: f64,
+    /// 加速度による刻み幅の係数（隙間/加速度の平方根に掛ける）
+    pub accuracy: f64,
+    /// 隙間の下限（接触している天体で刻み幅が0にならないようにする）
+    pub min_gap: f64,
+}
+
+impl Default for TimestepSettings {
+    fn default() -> Self {
+        Self {
+            adaptive: true,
+            max_substeps: 64,
+            courant_factor: 0.25,
+            accuracy: 0.1,
+            min_gap: 0.01,
+        }
+    }
+}
+
+/// 分割の判定で読み取る天体の並び（格納順の番号で参照する）
+pub trait BodySlots {
+    /// 天体の数
+    fn body_count(&self) -> usize;
+
+    /// 天体の格納位置
+    fn slot_of(&self, body_id: BodyId) -> Option<usize>;
+
+    /// 天体の位置
+    fn position_of(&self, slot: usize) -> Vec3Fixed;
+
+    /// 2天体の相対的な状態
+    fn pair(&self, slot: usize, other: usize) -> PairState;
+}
+
+/// ID順に並べた天体の配列
+pub struct BodySlices<'a> {
+    pub ids: &'a [BodyId],
+    pub positions: &'a [Vec3Fixed],
+    pub velocities: &'a [Vec3Fixed],
+    pub masses: &'a [f64],
+    pub radii: &'a [f64],
+}
+
+impl BodySlots for BodySlices<'_> {
+    fn body_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn slot_of(&self, body_id: BodyId) -> Option<usize> {
+        self.ids.binary_search(&body_id).ok()
+    }
+
+    fn position_of(&self, slot: usize) -> Vec3Fixed {
+        self.positions[slot]
+    }
+
+    fn pair(&self, slot: usize, other: usize) -> PairState {
+        PairState {
+            offset: self.positions[other] - self.positions[slot],
+            relative_velocity: self.velocities[other] - self.velocities[slot],
+            radius_sum: self.radii[slot] + self.radii[other],
+            total_mass: self.masses[slot] + self.masses[other],
+        }
+    }
+}
+
+/// ティックの分割計画
+///
+/// 接近している組の天体だけを`substeps`回に分けて進め、それ以外の天体はティック刻みのまま1回で進める。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubstepPlan {
+    /// 分割して進める天体の分割数（分割する天体がなければ1）
+    pub substeps: u32,
+    /// 格納順の天体ごとに、分割して進めるか
+    pub subdivided: Vec<bool>,
+}
+
+impl SubstepPlan {
+    /// どの天体も分割しない計画
+    pub fn single(body_count: usize) -> Self {
+        Self { substeps: 1, subdivided: vec![false; body_count] }
+    }
+
+    /// 分割して進める天体の数
+    pub fn subdivided_count(&self) -> usize {
+        self.subdivided.iter().filter(|subdivided| **subdivided).count()
+    }
+
+    /// 計画に沿って位相空間を`dt`だけ進める
+    ///
+    /// 先にティック刻みの天体を進め、分割する天体はその終了位置からの重力を受けながら細かく進める。
+    /// 分割しない場合は全天体を1ステップで進める。
+    pub fn advance(&self, integrator: &dyn Integrator, state: &mut dyn PhaseSpace, dt: f64) {
+        if self.substeps <= 1 {
+            integrator.advance(state, dt);
+            return;
+        }
+
+        state.restrict(Some(self.subdivided.iter().map(|subdivided| !subdivided).collect()));
+        integrator.advance(state, dt);
+
+        state.restrict(Some(self.subdivided.clone()));
+        let substep_dt = dt / self.substeps as f64;
+        for _ in 0..self.substeps {
+            integrator.advance(state, substep_dt);
+        }
+        state.restrict(None);
+    }
+}
+
+/// ティックの分割計画を立てる
+///
+/// 各天体と最も近い天体の組について、隙間を相対速度で詰める時間と、相互の重力加速度で詰める時間の
+/// 短い方を許容刻み幅とする。ティックより短い刻み幅が必要な組の天体だけを分割の対象とし、
+/// 対象の中で最も細かい分割数に揃える。
+pub fn plan_substeps(
+    bodies: &impl BodySlots,
+    spatial_index: &SpatialIndex,
+    dt: f64,
+    gravitational_constant: f64,
+    settings: &TimestepSettings,
+) -> SubstepPlan {
+    let mut plan = SubstepPlan::single(bodies.body_count());
+    if !settings.adaptive || dt <= 0.0 || bodies.body_count() < 2 {
+        return plan;
+    }
+
+    for slot in 0..bodies.body_count() {
+        for neighbor_id in spatial_index.nearest(bodies.position_of(slot), 2) {
+            let Some(neighbor) = bodies.slot_of(neighbor_id).filter(|neighbor| *neighbor != slot) else {
+                continue;
+            };
+
+            let allowed_dt = bodies.pair(slot, neighbor).allowed_dt(gravitational_constant, settings);
+            let substeps = substep_count(dt, allowed_dt, settings);
+            if substeps > 1 {
+                plan.subdivided[slot] = true;
+                plan.subdivided[neighbor] = true;
+                plan.substeps = plan.substeps.max(substeps);
+            }
+        }
+    }
+
+    plan
+}
+
+/// 隣り合う2天体の相対的な状態
+#[derive(Debug, Clone, Copy)]
+pub struct PairState {
+    pub offset: Vec3Fixed,
+    pub relative_velocity: Vec3Fixed,
+    pub radius_sum: f64,
+    pub total_mass: f64,
+}
+
+impl PairState {
+    /// この組が許容する刻み幅（制約がなければ無限大）
+    pub fn allowed_dt(&self, gravitational_constant: f64, settings: &TimestepSettings) -> f64 {
+        let distance = self.offset.magnitude();
+        let gap = (distance - self.radius_sum).max(settings.min_gap);
+        let mut allowed = f64::INFINITY;
+
+        let relative_speed = self.relative_velocity.magnitude();
+        if relative_speed > 0.0 {
+            allowed = allowed.min(settings.courant_factor * gap / relative_speed);
+        }
+
+        let acceleration = gravitational_constant * self.total_mass / distance.max(settings.min_gap).powi(2);
+        if acceleration > 0.0 {
+            allowed = allowed.min(settings.accuracy * (gap / acceleration).sqrt());
+        }
+        allowed
+    }
+}
+
+/// 許容刻み幅`min_dt`でティック`dt`を進めるのに必要な分割数（上限で切り詰める）
+pub fn substep_count(dt: f64, min_dt: f64, settings: &TimestepSettings) -> u32 {
+    ((dt / min_dt).ceil() as u32).clamp(1, settings.max_substeps.max(1))
+}
+
+/// 2つの球が移動中に最初に接触する時刻（0.0〜1.0、接触しなければ`None`）
+///
+/// それぞれ`start`から`end`へ等速直線運動するものとして、中心間の距離が`radius_sum`になる時刻を求める。
+/// 最初から重なっている場合は0.0を返す。
+pub fn time_of_impact(
+    start_a: Vec3Fixed,
+    end_a: Vec3Fixed,
+    start_b: Vec3Fixed,
+    end_b: Vec3Fixed,
+    radius_sum: f64,
+) -> Option<f64> {
+    let offset = start_b - start_a;
+    let motion = (end_b - start_b) - (end_a - start_a);
+    let c = offset.magnitude_squared() - radius_sum * radius_sum;
+    if c < 0.0 {
+        return Some(0.0);
+    }
+
+    let a = motion.magnitude_squared();
+    if a <= f64::EPSILON {
+        return None;
+    }
+    let b = 2.0 * offset.dot(&motion);
+    let discriminant = b * b - 4.0 * a * c;
+    if b >= 0.0 || discriminant < 0.0 {
+        // 離れていく、または最接近しても届かない
+        return None;
+    }
+
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    (t <= 1.0).then_some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+    use crate::game::celestial_bodies::{CelestialBody, CelestialType};
+    use crate::game::determinism::sorted_body_ids;
+    use crate::game::resources::fixed;
+
+    fn body(position: Vec3Fixed, velocity: Vec3Fixed) -> CelestialBody {
+        let mut body = CelestialBody::new(Uuid::new_v4(), CelestialType::Asteroid, position, fixed::from_f64(1.0), fixed::from_f64(1.0));
+        body.physics.velocity = velocity;
+        body
+    }
+
+    /// 計画と、入力した天体の順に並べた分割の有無
+    fn plan_for(bodies: Vec<CelestialBody>) -> (SubstepPlan, Vec<bool>) {
+        let order: Vec<BodyId> = bodies.iter().map(|body| body.id).collect();
+        let bodies: HashMap<BodyId, CelestialBody> = bodies.into_iter().map(|body| (body.id, body)).collect();
+        let mut index = SpatialIndex::new();
+        index.sync(&bodies);
+
+        let ids = sorted_body_ids(&bodies);
+        let positions: Vec<Vec3Fixed> = ids.iter().map(|id| bodies[id].physics.position).collect();
+        let velocities: Vec<Vec3Fixed> = ids.iter().map(|id| bodies[id].physics.velocity).collect();
+        let masses: Vec<f64> = ids.iter().map(|id| fixed::to_f64(bodies[id].physics.mass)).collect();
+        let radii: Vec<f64> = ids.iter().map(|id| fixed::to_f64(bodies[id].physics.radius)).collect();
+        let slices = BodySlices { ids: &ids, positions: &positions, velocities: &velocities, masses: &masses, radii: &radii };
+
+        let plan = plan_substeps(&slices, &index, 0.05, 6.67430e-11, &TimestepSettings::default());
+        let subdivided = order.iter().map(|id| plan.subdivided[slices.slot_of(*id).unwrap()]).collect();
+        (plan, subdivided)
+    }
+
+    fn substeps_for(bodies: Vec<CelestialBody>) -> u32 {
+        plan_for(bodies).0.substeps
+    }
+
+    #[test]
+    fn test_fast_close_approach_is_subdivided() {
+        let calm = substeps_for(vec![
+            body(Vec3Fixed::zeros(), Vec3Fixed::zeros()),
+            body(Vec3Fixed::new(100.0, 0.0, 0.0), Vec3Fixed::new(1.0, 0.0, 0.0)),
+        ]);
+        assert_eq!(calm, 1);
+
+        // 隙間8を秒速900で詰める組は、1ステップで隙間の1/4以上詰めないよう分割する
+        let approaching = substeps_for(vec![
+            body(Vec3Fixed::zeros(), Vec3Fixed::zeros()),
+            body(Vec3Fixed::new(10.0, 0.0, 0.0), Vec3Fixed::new(-900.0, 0.0, 0.0)),
+        ]);
+        assert_eq!(approaching, 23);
+
+        // 上限を超えては分割しない
+        let grazing = substeps_for(vec![
+            body(Vec3Fixed::zeros(), Vec3Fixed::zeros()),
+            body(Vec3Fixed::new(2.5, 0.0, 0.0), Vec3Fixed::new(0.0, 1.0e6, 0.0)),
+        ]);
+        assert_eq!(grazing, TimestepSettings::default().max_substeps);
+    }
+
+    #[test]
+    fn test_only_close_pairs_are_subdivided() {
+        let (plan, subdivided) = plan_for(vec![
+            body(Vec3Fixed::zeros(), Vec3Fixed::zeros()),
+            body(Vec3Fixed::new(10.0, 0.0, 0.0), Vec3Fixed::new(-900.0, 0.0, 0.0)),
+            body(Vec3Fixed::new(5000.0, 0.0, 0.0), Vec3Fixed::new(0.0, 1.0, 0.0)),
+            body(Vec3Fixed::new(5100.0, 0.0, 0.0), Vec3Fixed::zeros()),
+        ]);
+
+        // 離れた天体はティック刻みのまま進める
+        assert_eq!(plan.substeps, 23);
+        assert_eq!(subdivided, vec![true, true, false, false]);
+        assert_eq!(plan.subdivided_count(), 2);
+
+        let (calm, _) = plan_for(vec![
+            body(Vec3Fixed::zeros(), Vec3Fixed::zeros()),
+            body(Vec3Fixed::new(100.0, 0.0, 0.0), Vec3Fixed::new(1.0, 0.0, 0.0)),
+        ]);
+        assert_eq!(calm, SubstepPlan::single(2));
+    }
+
+    #[test]
+    fn test_swept_spheres_catch_tunnelling() {
+        // 1ティックで互いの位置を通り越す組は、終了時点では離れていても接触を検出する
+        let t = time_of_impact(
+            Vec3Fixed::new(-10.0, 0.0, 0.0),
+            Vec3Fixed::new(10.0, 0.0, 0.0),
+            Vec3Fixed::new(10.0, 0.0, 0.0),
+            Vec3Fixed::new(-10.0, 0.0, 0.0),
+            2.0,
+        ).unwrap();
+        assert!((t - 0.45).abs() < 1e-9);
+
+        // 直交する経路で同時に交点を通過する
+        let crossing = time_of_impact(
+            Vec3Fixed::new(-10.0, 0.0, 0.0),
+            Vec3Fixed::new(10.0, 0.0, 0.0),
+            Vec3Fixed::new(0.0, -10.0, 0.0),
+            Vec3Fixed::new(0.0, 10.0, 0.0),
+            1.0,
+        );
+        assert!(crossing.is_some());
+
+        // 届かない・離れていく・最初から重なっている
+        let miss = time_of_impact(
+            Vec3Fixed::new(-10.0, 5.0, 0.0),
+            Vec3Fixed::new(10.0, 5.0, 0.0),
+            Vec3Fixed::zeros(),
+            Vec3Fixed::zeros(),
+            2.0,
+        );
+        assert!(miss.is_none());
+        let receding = time_of_impact(Vec3Fixed::zeros(), Vec3Fixed::new(-5.0, 0.0, 0.0), Vec3Fixed::new(3.0, 0.0, 0.0), Vec3Fixed::new(3.0, 0.0, 0.0), 2.0);
+        assert!(receding.is_none());
+        let overlapping = time_of_impact(Vec3Fixed::zeros(), Vec3Fixed::zeros(), Vec3Fixed::new(1.0, 0.0, 0.0), Vec3Fixed::new(1.0, 0.0, 0.0), 2.0);
+        assert_eq!(overlapping, Some(0.0));
+    }
+}
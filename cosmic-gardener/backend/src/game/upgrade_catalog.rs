@@ -0,0 +1,377 @@
+//! アップグレードカタログ
+//!
+//! アップグレードのコスト・成長曲線・効果・最大レベルを`config/upgrades.toml`から読み込む。
+//! バランス調整をコード変更なしで行えるよう、実行中の再読み込みにも対応する。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn, error};
+
+use crate::errors::{GameError, Result};
+use crate::game::resources::{fixed, ProductionRates, ResourceType, Resources, UpgradeLevels, UpgradeType};
+
+/// 組み込みのカタログ（設定ファイルが無い場合に使用）
+const BUILTIN_CATALOG: &str = include_str!("../../config/upgrades.toml");
+
+/// コストの成長曲線
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GrowthCurve {
+    /// base * multiplier^level
+    Exponential { multiplier: f64 },
+    /// base * (1 + increment * level)
+    Linear { increment: f64 },
+    /// base * (level + 1)^exponent
+    Polynomial { exponent: f64 },
+}
+
+impl GrowthCurve {
+    /// 指定レベルでのコスト倍率
+    pub fn factor(&self, level: u32) -> f64 {
+        match self {
+            Self::Exponential { multiplier } => multiplier.powi(level as i32),
+            Self::Linear { increment } => 1.0 + increment * level as f64,
+            Self::Polynomial { exponent } => (level as f64 + 1.0).powf(*exponent),
+        }
+    }
+
+    fn validate(&self) -> std::result::Result<(), String> {
+        let valid = match self {
+            Self::Exponential { multiplier } => multiplier.is_finite() && *multiplier >= 1.0,
+            Self::Linear { increment } => increment.is_finite() && *increment >= 0.0,
+            Self::Polynomial { exponent } => exponent.is_finite() && *exponent >= 0.0,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("invalid growth curve {:?}", self))
+        }
+    }
+}
+
+/// アップグレードの効果（対象リソースの生産倍率）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeEffect {
+    pub target: ResourceType,
+    /// レベルごとの生産ボーナス（倍率 = 1 + level * bonus_per_level）
+    pub bonus_per_level: f64,
+}
+
+/// アップグレード定義
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeDefinition {
+    pub id: UpgradeType,
+    pub name: String,
+    pub base_cost: HashMap<ResourceType, u64>,
+    pub growth: GrowthCurve,
+    pub effect: UpgradeEffect,
+    pub max_level: u32,
+}
+
+impl UpgradeDefinition {
+    /// 指定レベルから次のレベルに上げるためのコスト
+    pub fn cost_at_level(&self, level: u32) -> Resources {
+        let factor = self.growth.factor(level);
+        let mut cost = Resources::new();
+
+        for (resource_type, base) in &self.base_cost {
+            cost.set(*resource_type, (*base as f64 * factor) as u64);
+        }
+
+        cost
+    }
+
+    /// 指定レベルでの生産倍率
+    pub fn production_multiplier(&self, level: u32) -> f64 {
+        1.0 + level as f64 * self.effect.bonus_per_level
+    }
+}
+
+/// アップグレードカタログ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeCatalog {
+    /// アップグレードなしの基本生産量（ティックあたり）
+    pub base_production: HashMap<ResourceType, f64>,
+    pub upgrades: Vec<UpgradeDefinition>,
+}
+
+impl Default for UpgradeCatalog {
+    fn default() -> Self {
+        Self::from_toml(BUILTIN_CATALOG).expect("Built-in upgrade catalog must be valid")
+    }
+}
+
+impl UpgradeCatalog {
+    /// TOML文字列から読み込み（検証込み）
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let catalog: Self = toml::from_str(content)
+            .map_err(|e| GameError::validation(format!("Failed to parse upgrade catalog: {}", e)))?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    /// ファイルから読み込み（検証込み）
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            GameError::validation(format!("Failed to read upgrade catalog {}: {}", path.display(), e))
+        })?;
+        Self::from_toml(&content)
+    }
+
+    /// カタログの検証
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        for resource_type in ResourceType::all() {
+            match self.base_production.get(&resource_type) {
+                Some(rate) if rate.is_finite() && *rate >= 0.0 => {}
+                Some(rate) => errors.push(format!("base_production.{:?} is invalid: {}", resource_type, rate)),
+                None => errors.push(format!("base_production.{:?} is missing", resource_type)),
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for upgrade in &self.upgrades {
+            if !seen.insert(upgrade.id) {
+                errors.push(format!("{:?}: duplicate upgrade id", upgrade.id));
+            }
+            if upgrade.base_cost.is_empty() || upgrade.base_cost.values().all(|cost| *cost == 0) {
+                errors.push(format!("{:?}: base_cost must contain a positive cost", upgrade.id));
+            }
+            if let Err(message) = upgrade.growth.validate() {
+                errors.push(format!("{:?}: {}", upgrade.id, message));
+            }
+            if !upgrade.effect.bonus_per_level.is_finite() || upgrade.effect.bonus_per_level < 0.0 {
+                errors.push(format!("{:?}: bonus_per_level must be a non-negative number", upgrade.id));
+            }
+            if upgrade.max_level == 0 {
+                errors.push(format!("{:?}: max_level must be at least 1", upgrade.id));
+            }
+        }
+
+        for upgrade_type in UpgradeType::all() {
+            if !seen.contains(&upgrade_type) {
+                errors.push(format!("{:?}: missing from catalog", upgrade_type));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(GameError::validation(format!("Invalid upgrade catalog: {}", errors.join("; "))))
+        }
+    }
+
+    /// アップグレード定義の取得
+    pub fn get(&self, upgrade_type: UpgradeType) -> Option<&UpgradeDefinition> {
+        self.upgrades.iter().find(|upgrade| upgrade.id == upgrade_type)
+    }
+
+    /// 現在のレベルから1段階上げるためのコスト
+    pub fn upgrade_cost(&self, upgrade_type: UpgradeType, current_level: u32) -> Result<Resources> {
+        let definition = self.definition(upgrade_type)?;
+        Ok(definition.cost_at_level(current_level))
+    }
+
+    /// 最大レベルの取得
+    pub fn max_level(&self, upgrade_type: UpgradeType) -> Result<u32> {
+        Ok(self.definition(upgrade_type)?.max_level)
+    }
+
+    /// アップグレードレベルから生産レートを計算
+    pub fn production_rates(&self, upgrade_levels: &UpgradeLevels) -> ProductionRates {
+        let mut multipliers: HashMap<ResourceType, f64> = HashMap::new();
+
+        for upgrade in &self.upgrades {
+            let level = upgrade_levels.get_level(upgrade.id);
+            *multipliers.entry(upgrade.effect.target).or_insert(1.0) *= upgrade.production_multiplier(level);
+        }
+
+        let mut rates = ProductionRates::new();
+        for resource_type in ResourceType::all() {
+            let base = self.base_production.get(&resource_type).copied().unwrap_or(0.0);
+            let multiplier = multipliers.get(&resource_type).copied().unwrap_or(1.0);
+            rates.set(resource_type, fixed::from_f64(base * multiplier));
+        }
+
+        rates
+    }
+
+    fn definition(&self, upgrade_type: UpgradeType) -> Result<&UpgradeDefinition> {
+        self.get(upgrade_type).ok_or_else(|| {
+            GameError::not_found(format!("Upgrade {:?} is not defined in the catalog", upgrade_type))
+        })
+    }
+}
+
+/// 共有カタログハンドル（再読み込み時に全参照先へ反映される）
+#[derive(Debug, Clone)]
+pub struct UpgradeCatalogHandle {
+    current: Arc<RwLock<Arc<UpgradeCatalog>>>,
+    source: Option<PathBuf>,
+}
+
+impl Default for UpgradeCatalogHandle {
+    fn default() -> Self {
+        Self::new(UpgradeCatalog::default())
+    }
+}
+
+impl UpgradeCatalogHandle {
+    pub fn new(catalog: UpgradeCatalog) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(catalog))),
+            source: None,
+        }
+    }
+
+    /// ファイルからハンドルを作成（起動時検証）
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let catalog = UpgradeCatalog::from_file(path)?;
+        info!("[UPGRADE_CATALOG] Loaded {} upgrades from {}", catalog.upgrades.len(), path.display());
+
+        Ok(Self {
+            current: Arc::new(RwLock::new(Arc::new(catalog))),
+            source: Some(path.to_path_buf()),
+        })
+    }
+
+    /// 現在のカタログ
+    pub fn current(&self) -> Arc<UpgradeCatalog> {
+        self.current.read().expect("upgrade catalog lock poisoned").clone()
+    }
+
+    /// カタログの差し替え（検証に失敗した場合は現在のカタログを維持）
+    pub fn replace(&self, catalog: UpgradeCatalog) -> Result<()> {
+        catalog.validate()?;
+        *self.current.write().expect("upgrade catalog lock poisoned") = Arc::new(catalog);
+        Ok(())
+    }
+
+    /// 読み込み元ファイルからの再読み込み
+    pub fn reload(&self) -> Result<()> {
+        let path = self.source.as_ref()
+            .ok_or_else(|| GameError::bad_request("Upgrade catalog has no source file"))?;
+        let catalog = UpgradeCatalog::from_file(path)?;
+        self.replace(catalog)?;
+        info!("[UPGRADE_CATALOG] Reloaded upgrade catalog from {}", path.display());
+        Ok(())
+    }
+
+    /// ファイルの更新を監視して自動再読み込みする
+    pub fn spawn_watcher(&self, poll_interval: Duration) -> Option<tokio::task::JoinHandle<()>> {
+        let path = self.source.clone()?;
+        let handle = self.clone();
+
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+            let mut last_modified = modified_time(&path);
+
+            loop {
+                interval.tick().await;
+
+                let modified = modified_time(&path);
+                if modified.is_none() || modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                if let Err(e) = handle.reload() {
+                    // 不正な変更は無視して現在のカタログを維持
+                    error!("[UPGRADE_CATALOG] Failed to reload {}: {}", path.display(), e);
+                }
+            }
+        }))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    match std::fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => Some(modified),
+        Err(e) => {
+            warn!("[UPGRADE_CATALOG] Cannot stat {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// カタログ読み込み設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeCatalogConfig {
+    pub path: String,
+    pub hot_reload: bool,
+    pub reload_interval_secs: u64,
+}
+
+impl Default for UpgradeCatalogConfig {
+    fn default() -> Self {
+        Self {
+            path: "config/upgrades.toml".to_string(),
+            hot_reload: true,
+            reload_interval_secs: 5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_catalog_is_valid() {
+        let catalog = UpgradeCatalog::default();
+        assert_eq!(catalog.upgrades.len(), UpgradeType::all().len());
+        assert!(catalog.validate().is_ok());
+    }
+
+    #[test]
+    fn test_production_rates_from_catalog() {
+        let catalog = UpgradeCatalog::default();
+        let mut levels = UpgradeLevels::new();
+
+        let rates = catalog.production_rates(&levels);
+        assert!((fixed::to_f64(rates.dust_per_tick) - 1.0).abs() < 1e-6);
+        assert!((fixed::to_f64(rates.energy_per_tick) - 0.5).abs() < 1e-6);
+
+        levels.set_level(UpgradeType::DustProduction, 2);
+        let rates = catalog.production_rates(&levels);
+        assert!((fixed::to_f64(rates.dust_per_tick) - 2.0).abs() < 1e-6); // 1.0 * (1 + 2 * 0.5)
+    }
+
+    #[test]
+    fn test_growth_curves() {
+        assert_eq!(GrowthCurve::Exponential { multiplier: 2.0 }.factor(3), 8.0);
+        assert_eq!(GrowthCurve::Linear { increment: 0.5 }.factor(4), 3.0);
+        assert_eq!(GrowthCurve::Polynomial { exponent: 2.0 }.factor(2), 9.0);
+    }
+
+    #[test]
+    fn test_missing_upgrade_rejected() {
+        let mut catalog = UpgradeCatalog::default();
+        catalog.upgrades.retain(|upgrade| upgrade.id != UpgradeType::ThoughtAcceleration);
+
+        let result = catalog.validate();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invalid_replace_keeps_current() {
+        let handle = UpgradeCatalogHandle::default();
+
+        let mut invalid = UpgradeCatalog::default();
+        invalid.upgrades[0].max_level = 0;
+        assert!(handle.replace(invalid).is_err());
+        assert!(handle.current().upgrades.iter().all(|upgrade| upgrade.max_level > 0));
+
+        let mut rebalanced = UpgradeCatalog::default();
+        rebalanced.base_production.insert(ResourceType::CosmicDust, 3.0);
+        handle.replace(rebalanced).unwrap();
+        assert_eq!(handle.current().base_production[&ResourceType::CosmicDust], 3.0);
+    }
+}
@@ -6,7 +6,8 @@ use uuid::Uuid;
 
 use crate::errors::{GameError, Result};
 use crate::game::resources::{Resources, ResourceType, Fixed, fixed};
-use crate::game::celestial_bodies::{CelestialBody, CelestialType, BodyId, Vec3Fixed};
+use crate::game::celestial_bodies::{CelestialType, Vec3Fixed};
+use crate::game::spatial_index::SpatialIndex;
 
 /// プレイヤーID
 pub type PlayerId = Uuid;
@@ -222,13 +223,13 @@ impl ValidationEngine {
         player_id: PlayerId,
         body_type: &CelestialType,
         position: &Vec3Fixed,
-        existing_bodies: &HashMap<BodyId, CelestialBody>,
+        spatial_index: &SpatialIndex,
     ) -> Result<()> {
         // レート制限チェック
         self.check_creation_rate_limit(player_id)?;
         
         // 位置の検証
-        self.validate_position(position, existing_bodies)?;
+        self.validate_position(position, spatial_index)?;
         
         // 行動パターンの記録
         self.record_action(player_id, ActionType::CreateBody, Some(*position), None);
@@ -237,7 +238,7 @@ impl ValidationEngine {
     }
     
     /// 位置の検証
-    fn validate_position(&self, position: &Vec3Fixed, existing_bodies: &HashMap<BodyId, CelestialBody>) -> Result<()> {
+    fn validate_position(&self, position: &Vec3Fixed, spatial_index: &SpatialIndex) -> Result<()> {
         // 境界チェック
         if position.magnitude() > self.max_position {
             return Err(GameError::OutOfBounds);
@@ -245,12 +246,8 @@ impl ValidationEngine {
         
         // 最小分離距離チェック
         const MIN_SEPARATION: f64 = 10.0;
-        for body in existing_bodies.values() {
-            let distance = (position - body.physics.position).magnitude();
-            let min_dist = body.physics.radius + fixed::from_f64(MIN_SEPARATION);
-            if distance < min_dist {
-                return Err(GameError::TooClose);
-            }
+        if !spatial_index.within_distance(*position, MIN_SEPARATION).is_empty() {
+            return Err(GameError::TooClose);
         }
         
         Ok(())
@@ -572,15 +569,15 @@ mod tests {
     #[test]
     fn test_position_validation() {
         let validator = ValidationEngine::new();
-        let bodies = HashMap::new();
+        let spatial_index = SpatialIndex::new();
         
         // 境界内の位置
         let valid_pos = Vec3Fixed::new(fixed::from_f64(100.0), fixed::from_f64(200.0), fixed::from_f64(300.0));
-        assert!(validator.validate_position(&valid_pos, &bodies).is_ok());
+        assert!(validator.validate_position(&valid_pos, &spatial_index).is_ok());
         
         // 境界外の位置
         let invalid_pos = Vec3Fixed::new(fixed::from_f64(200000.0), fixed::from_f64(0.0), fixed::from_f64(0.0));
-        assert!(validator.validate_position(&invalid_pos, &bodies).is_err());
+        assert!(validator.validate_position(&invalid_pos, &spatial_index).is_err());
     }
     
     #[test]
@@ -1,6 +1,7 @@
 use actix_web::{web, App, HttpServer, middleware::Logger};
 use actix_cors::Cors;
 use std::sync::Arc;
+use std::time::Duration;
 use cosmic_gardener_backend::*;
 use cosmic_gardener_backend::config::Config;
 use cosmic_gardener_backend::services::logging::{LoggingService, LoggingConfig};
@@ -11,6 +12,9 @@ use cosmic_gardener_backend::services::database_pool::EnhancedDatabasePool;
 use cosmic_gardener_backend::services::websocket::compression::CompressionService;
 use cosmic_gardener_backend::game::physics_simd::SimdPhysicsEngine;
 use cosmic_gardener_backend::game::concurrent_game_loop::ConcurrentGameLoop;
+use cosmic_gardener_backend::game::upgrade_catalog::UpgradeCatalogHandle;
+use cosmic_gardener_backend::game::research::ResearchTree;
+use cosmic_gardener_backend::game::achievements::AchievementCatalog;
 use cosmic_gardener_backend::middleware::LoggingMiddleware;
 use cosmic_gardener_backend::handlers::health::{init_health_system, configure_health_routes};
 
@@ -81,21 +85,48 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let physics_engine = Arc::new(SimdPhysicsEngine::new(metrics_service.clone()));
     tracing::info!("SIMD physics engine initialized");
     
+    // Load upgrade catalog (fails startup on invalid catalog)
+    let upgrade_catalog = UpgradeCatalogHandle::load(&config.upgrades.path)?;
+    if config.upgrades.hot_reload {
+        upgrade_catalog.spawn_watcher(Duration::from_secs(config.upgrades.reload_interval_secs));
+    }
+    tracing::info!("Upgrade catalog loaded from {}", config.upgrades.path);
+    
+    // Load research tree (fails startup on invalid tree)
+    let research_tree = Arc::new(ResearchTree::from_file(&config.research.tree_path)?);
+    tracing::info!("Research tree loaded: {} technologies", research_tree.technologies.len());
+    
+    // Load achievement catalog (fails startup on invalid catalog)
+    let achievement_catalog = Arc::new(AchievementCatalog::from_file(&config.achievements.catalog_path)?);
+    tracing::info!("Achievement catalog loaded: {} achievements", achievement_catalog.achievements.len());
+    
     // Initialize concurrent game loop
     let concurrent_game_loop = Arc::new(ConcurrentGameLoop::new(
-        config.game_loop.clone(),
-        physics_engine.clone(),
-        database_service.clone(),
         metrics_service.clone(),
+        config.game_loop.clone(),
     ));
     tracing::info!("Concurrent game loop initialized");
     
-    // Start concurrent game loop
-    concurrent_game_loop.start().await?;
+    // Start concurrent game loop (runs until shutdown, so it gets its own task)
+    {
+        let concurrent_game_loop = concurrent_game_loop.clone();
+        tokio::spawn(async move {
+            if let Err(e) = concurrent_game_loop.start().await {
+                tracing::error!("Concurrent game loop stopped with error: {}", e);
+            }
+        });
+    }
     tracing::info!("Game loop started");
     
     // Initialize legacy game state for backwards compatibility
-    let game_state = websocket_handler::GameState::new();
+    let game_state = websocket_handler::GameState::with_config(
+        config.offline_progress.clone(),
+        config.prestige.clone(),
+        upgrade_catalog.clone(),
+        research_tree.clone(),
+        config.research.max_queue_length,
+        achievement_catalog.clone(),
+    );
     
     // Add initial resources for testing
     {
@@ -105,6 +136,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         resources.energy = 500;
     }
     
+    // Legacy game loop applies queued socket commands at tick start, so it must run for sessions to get acks
+    tokio::spawn(websocket_handler::run_game_loop(game_state.clone()));
+    
     tracing::info!("Game systems initialized successfully!");
     tracing::info!("Starting HTTP server on http://{}:{}", config.server_host, config.server_port);
     
@@ -235,6 +235,11 @@ impl GameMetricsRecorder {
     pub fn update_celestial_bodies(&self, count: usize) {
         self.metrics_service.metrics().celestial_bodies_total.set(count as i64);
     }
+
+    /// ティックの処理時間と予算の超過を記録
+    pub fn record_tick_budget(&self, subsystem_costs: &[(&str, f64)], overrun: bool, dropped_steps: u32, degradation_level: u8) {
+        self.metrics_service.record_tick_budget(subsystem_costs, overrun, dropped_steps, degradation_level);
+    }
 }
 
 /// システムメトリクス記録ヘルパー
@@ -295,7 +295,7 @@ pub enum ResourceType {
 }
 
 /// 破壊理由
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DestructionReason {
     /// 衝突
     Collision,
@@ -321,7 +321,7 @@ pub struct CelestialEventResponse {
 }
 
 /// 天体イベントタイプ
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CelestialEventType {
     /// 衝突
     Collision,
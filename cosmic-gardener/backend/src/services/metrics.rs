@@ -1,7 +1,7 @@
 //! Prometheusメトリクス統合サービス
 
 use prometheus::{
-    Counter, Gauge, Histogram, IntCounter, IntGauge, 
+    Counter, Gauge, Histogram, HistogramVec, IntCounter, IntGauge, 
     Opts, Registry, TextEncoder, Encoder,
     register_counter, register_int_counter, register_gauge, 
     register_int_gauge, register_histogram,
@@ -70,6 +70,12 @@ pub struct PerformanceMetrics {
     pub resource_generation_rate: Gauge,
     pub game_ticks_total: IntCounter,
     
+    // ティック予算
+    pub tick_overruns_total: IntCounter,
+    pub tick_dropped_steps_total: IntCounter,
+    pub tick_degradation_level: IntGauge,
+    pub tick_subsystem_duration: HistogramVec,
+    
     // システム
     pub memory_usage_bytes: IntGauge,
     pub cpu_usage_percent: Gauge,
@@ -244,6 +250,33 @@ impl MetricsService {
         )?;
         registry.register(Box::new(game_ticks_total.clone()))?;
         
+        // ティック予算メトリクス
+        let tick_overruns_total = IntCounter::with_opts(
+            Opts::new("tick_overruns_total", "Total number of ticks that exceeded the tick budget")
+                .namespace(namespace)
+        )?;
+        registry.register(Box::new(tick_overruns_total.clone()))?;
+        
+        let tick_dropped_steps_total = IntCounter::with_opts(
+            Opts::new("tick_dropped_steps_total", "Total number of simulation steps dropped while catching up")
+                .namespace(namespace)
+        )?;
+        registry.register(Box::new(tick_dropped_steps_total.clone()))?;
+        
+        let tick_degradation_level = IntGauge::with_opts(
+            Opts::new("tick_degradation_level", "Current load degradation level of the game loop")
+                .namespace(namespace)
+        )?;
+        registry.register(Box::new(tick_degradation_level.clone()))?;
+        
+        let tick_subsystem_duration = HistogramVec::new(
+            prometheus::HistogramOpts::new("tick_subsystem_duration_seconds", "Time spent in each subsystem per tick")
+                .namespace(namespace)
+                .buckets(vec![0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1]),
+            &["subsystem"],
+        )?;
+        registry.register(Box::new(tick_subsystem_duration.clone()))?;
+        
         // システムメトリクス
         let memory_usage_bytes = IntGauge::with_opts(
             Opts::new("memory_usage_bytes", "Current memory usage in bytes")
@@ -287,6 +320,10 @@ impl MetricsService {
             celestial_bodies_total,
             resource_generation_rate,
             game_ticks_total,
+            tick_overruns_total,
+            tick_dropped_steps_total,
+            tick_degradation_level,
+            tick_subsystem_duration,
             memory_usage_bytes,
             cpu_usage_percent,
             goroutines_count,
@@ -397,6 +434,18 @@ impl MetricsService {
         self.metrics.game_ticks_total.inc();
     }
     
+    /// ティックの処理時間と予算の超過を記録
+    pub fn record_tick_budget(&self, subsystem_costs: &[(&str, f64)], overrun: bool, dropped_steps: u32, degradation_level: u8) {
+        for (subsystem, duration_secs) in subsystem_costs {
+            self.metrics.tick_subsystem_duration.with_label_values(&[subsystem]).observe(*duration_secs);
+        }
+        if overrun {
+            self.metrics.tick_overruns_total.inc();
+        }
+        self.metrics.tick_dropped_steps_total.inc_by(dropped_steps as u64);
+        self.metrics.tick_degradation_level.set(degradation_level as i64);
+    }
+    
     /// システムリソースを記録
     pub fn record_system_resources(&self, memory_bytes: usize, cpu_percent: f64) {
         self.metrics.memory_usage_bytes.set(memory_bytes as i64);
@@ -422,6 +471,9 @@ impl MetricsService {
             cache_hit_rate: self.calculate_cache_hit_rate(),
             active_players: self.metrics.active_players.get(),
             celestial_bodies: self.metrics.celestial_bodies_total.get(),
+            tick_overruns_total: self.metrics.tick_overruns_total.get(),
+            tick_dropped_steps_total: self.metrics.tick_dropped_steps_total.get(),
+            tick_degradation_level: self.metrics.tick_degradation_level.get(),
             memory_usage_mb: self.metrics.memory_usage_bytes.get() as f64 / 1024.0 / 1024.0,
             cpu_usage_percent: self.metrics.cpu_usage_percent.get(),
         }
@@ -461,6 +513,9 @@ pub struct MetricsSummary {
     pub cache_hit_rate: f64,
     pub active_players: i64,
     pub celestial_bodies: i64,
+    pub tick_overruns_total: u64,
+    pub tick_dropped_steps_total: u64,
+    pub tick_degradation_level: i64,
     pub memory_usage_mb: f64,
     pub cpu_usage_percent: f64,
 }
@@ -515,4 +570,21 @@ mod tests {
         let summary = service.get_metrics_summary();
         assert!((summary.cache_hit_rate - 0.6666666666666666).abs() < f64::EPSILON);
     }
+    
+    #[test]
+    fn test_tick_budget_recording() {
+        let config = MetricsConfig::default();
+        let service = MetricsService::new(config).unwrap();
+        
+        service.record_tick_budget(&[("physics", 0.02), ("resources", 0.001)], true, 2, 1);
+        service.record_tick_budget(&[("physics", 0.005)], false, 0, 0);
+        
+        let summary = service.get_metrics_summary();
+        assert_eq!(summary.tick_overruns_total, 1);
+        assert_eq!(summary.tick_dropped_steps_total, 2);
+        assert_eq!(summary.tick_degradation_level, 0);
+        
+        let metrics_output = service.export_metrics().unwrap();
+        assert!(metrics_output.contains("tick_subsystem_duration_seconds"));
+    }
 }
\ No newline at end of file
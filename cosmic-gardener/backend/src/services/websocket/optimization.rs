@@ -8,6 +8,7 @@ use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::domain::value_objects::{Position3D, Velocity3D};
+use crate::game::orbital::OrbitalElements;
 use crate::models::websocket::CelestialBodyData;
 
 /// 帯域幅最適化マネージャー
@@ -164,38 +165,19 @@ impl OrbitPredictor {
         }
     }
 
-    /// ケプラー軌道要素から位置を予測
+    /// ケプラー軌道要素から親天体に対する相対位置を予測
     pub fn predict_position(
         &self,
         orbital_elements: &OrbitalElements,
         time_delta: f64,
     ) -> Position3D {
-        // 簡易版：円軌道を仮定
-        let mean_motion = (self.g * orbital_elements.central_mass / 
-                          orbital_elements.semi_major_axis.powi(3)).sqrt();
-        let angle_delta = mean_motion * time_delta;
-        
-        let new_angle = orbital_elements.true_anomaly + angle_delta;
-        let r = orbital_elements.semi_major_axis;
-        
-        Position3D::new(
-            r * new_angle.cos(),
-            r * new_angle.sin(),
-            0.0,  // 2D平面上の軌道を仮定
-        )
+        match orbital_elements.propagate(time_delta, self.g).to_state_vector(self.g) {
+            Ok((position, _)) => Position3D::new(position.x, position.y, position.z),
+            Err(_) => Position3D::zero(),
+        }
     }
 }
 
-/// ケプラー軌道要素
-#[derive(Debug, Clone)]
-pub struct OrbitalElements {
-    pub semi_major_axis: f64,
-    pub eccentricity: f64,
-    pub inclination: f64,
-    pub true_anomaly: f64,
-    pub central_mass: f64,
-}
-
 impl DeltaEncoder {
     pub fn new() -> Self {
         let mut compressible_fields = HashSet::new();
@@ -1,15 +1,67 @@
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 use tokio::time::interval;
 use actix_web::{web, HttpRequest, HttpResponse, Error};
 use actix_ws::{Message, Session, MessageStream};
 use futures::StreamExt;
 use serde_json;
 
-use crate::game::{ResourceManager, CelestialBodyManager, PhysicsEngine};
+use crate::game::{ResourceManager, CelestialBodyManager, PhysicsEngine, OfflineProgressCalculator, OfflineProgressConfig, UpgradeCatalogHandle, PrestigeCalculator, PrestigeConfig, ResearchManager, ResearchTree, AchievementCatalog, AchievementTracker};
+use crate::game::achievements::{AchievementTrigger, PlayerMetrics, TriggerKind};
+use crate::game::celestial_bodies::{BodyId, CelestialType, Vec3Fixed};
+use crate::game::command_queue::{command_queue, CommandAck, CommandQueue, CommandSender, DEFAULT_COMMAND_QUEUE_CAPACITY};
+use crate::game::offline::OfflineReport;
+use crate::game::prestige::PrestigeRecord;
+use crate::errors::GameError;
+use crate::game::physics::PhysicsEvent;
+use crate::game::life::{self, ExtinctionCause, LifeTransition};
+use crate::game::resources::ResourceType;
 use crate::websocket_messages::{ClientMessage, ServerMessage, CelestialBodyInfo};
 
+/// ソケットから受け付けた状態変更（ゲームループが各ティックの開始時に適用する）
+#[derive(Debug, Clone)]
+pub enum ClientCommand {
+    CreateBody {
+        body_type: CelestialType,
+        position: Vec3Fixed,
+    },
+    RemoveBody {
+        body_id: BodyId,
+    },
+    SpendResources {
+        cosmic_dust: u64,
+        energy: u64,
+    },
+    SetGameRunning {
+        running: bool,
+    },
+    StartResearch {
+        technology_id: String,
+    },
+    CancelResearch {
+        technology_id: String,
+    },
+    Prestige,
+    /// 不在期間の進行を適用（接続時に積む）
+    ApplyOfflineProgress,
+}
+
+/// コマンドを適用した結果
+#[derive(Debug)]
+pub enum ClientCommandOutcome {
+    BodyCreated(BodyId),
+    BodyRemoved,
+    ResourcesSpent,
+    RunningChanged,
+    ResearchUpdated,
+    Prestiged(PrestigeRecord),
+    /// 不在期間が短いなどで進行がなければ`None`
+    OfflineProgressApplied(Option<OfflineReport>),
+}
+
+pub type ClientCommandResult = crate::errors::Result<ClientCommandOutcome>;
+
 /// ゲーム状態を管理する構造体
 #[derive(Clone)]
 pub struct GameState {
@@ -18,16 +70,54 @@ pub struct GameState {
     pub physics_engine: Arc<Mutex<PhysicsEngine>>,
     pub is_running: Arc<Mutex<bool>>,
     pub tick: Arc<Mutex<u64>>,
+    pub offline_calculator: Arc<OfflineProgressCalculator>,
+    pub prestige_calculator: Arc<PrestigeCalculator>,
+    pub research_manager: Arc<Mutex<ResearchManager>>,
+    pub achievements: Arc<Mutex<AchievementTracker>>,
+    /// ゲームループから全セッションへ配信するメッセージ
+    pub broadcaster: broadcast::Sender<ServerMessage>,
+    /// 状態変更コマンドの送信側（セッションのタスクから使う）
+    pub commands: CommandSender<ClientCommand, ClientCommandResult>,
+    /// ゲームループが各ティックの開始時に取り出すコマンドキュー
+    command_queue: Arc<Mutex<CommandQueue<ClientCommand, ClientCommandResult>>>,
 }
 
 impl GameState {
     pub fn new() -> Self {
+        Self::with_config(
+            OfflineProgressConfig::default(),
+            PrestigeConfig::default(),
+            UpgradeCatalogHandle::default(),
+            Arc::new(ResearchTree::default()),
+            5,
+            Arc::new(AchievementCatalog::default()),
+        )
+    }
+    
+    pub fn with_config(
+        offline_config: OfflineProgressConfig,
+        prestige_config: PrestigeConfig,
+        upgrade_catalog: UpgradeCatalogHandle,
+        research_tree: Arc<ResearchTree>,
+        research_queue_length: usize,
+        achievement_catalog: Arc<AchievementCatalog>,
+    ) -> Self {
+        let (broadcaster, _) = broadcast::channel(64);
+        let (commands, command_queue) = command_queue(DEFAULT_COMMAND_QUEUE_CAPACITY);
+        
         Self {
-            resource_manager: Arc::new(Mutex::new(ResourceManager::new(50))),
+            resource_manager: Arc::new(Mutex::new(ResourceManager::with_catalog(50, upgrade_catalog))),
             celestial_manager: Arc::new(Mutex::new(CelestialBodyManager::new(50))),
             physics_engine: Arc::new(Mutex::new(PhysicsEngine::new())),
             is_running: Arc::new(Mutex::new(false)),
             tick: Arc::new(Mutex::new(0)),
+            offline_calculator: Arc::new(OfflineProgressCalculator::new(offline_config)),
+            prestige_calculator: Arc::new(PrestigeCalculator::new(prestige_config)),
+            research_manager: Arc::new(Mutex::new(ResearchManager::new(research_tree, research_queue_length))),
+            achievements: Arc::new(Mutex::new(AchievementTracker::new(achievement_catalog))),
+            broadcaster,
+            commands,
+            command_queue: Arc::new(Mutex::new(command_queue)),
         }
     }
 }
@@ -58,19 +148,38 @@ async fn handle_websocket_session(
         let _ = session.text(msg).await;
     }
     
+    // 不在期間の進行を適用
+    apply_offline_progress(&mut session, &game_state).await;
+    
     // 初期ゲーム状態を送信
     send_game_state(&mut session, &game_state).await;
     
     // メッセージループ
-    while let Some(msg) = stream.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
-                    handle_client_message(&mut session, client_msg, &game_state).await;
+    let mut broadcasts = game_state.broadcaster.subscribe();
+    loop {
+        tokio::select! {
+            msg = stream.next() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
+                            handle_client_message(&mut session, client_msg, &game_state).await;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    _ => {}
+                }
+            }
+            broadcast = broadcasts.recv() => {
+                match broadcast {
+                    Ok(server_msg) => {
+                        if let Ok(msg) = serde_json::to_string(&server_msg) {
+                            let _ = session.text(msg).await;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
                 }
             }
-            Ok(Message::Close(_)) => break,
-            _ => {}
         }
     }
 }
@@ -86,33 +195,60 @@ async fn handle_client_message(
         }
         
         ClientMessage::CreateBody { body_type, position } => {
-            let mut resource_manager = game_state.resource_manager.lock().await;
-            let mut celestial_manager = game_state.celestial_manager.lock().await;
+            let position = nalgebra::Vector3::new(position[0], position[1], position[2]);
+            let Some(ack) = submit_command(session, game_state, ClientCommand::CreateBody { body_type, position }).await else {
+                return;
+            };
             
-            let resources = resource_manager.get_resources_mut();
-            let position_vec = nalgebra::Vector3::new(position[0], position[1], position[2]);
-            
-            match celestial_manager.create_body(body_type, position_vec, resources) {
-                Ok(body_id) => {
-                    let response = ServerMessage::BodyCreated {
-                        body_id,
-                        success: true,
-                        error: None,
-                    };
-                    if let Ok(msg) = serde_json::to_string(&response) {
-                        let _ = session.text(msg).await;
-                    }
-                    
-                    // 更新されたゲーム状態を送信
-                    drop(resource_manager);
-                    drop(celestial_manager);
-                    send_game_state(session, game_state).await;
-                }
-                Err(e) => {
-                    let response = ServerMessage::BodyCreated {
-                        body_id: uuid::Uuid::new_v4(),
-                        success: false,
-                        error: Some(format!("{:?}", e)),
+            let response = match ack.result {
+                Ok(ClientCommandOutcome::BodyCreated(body_id)) => ServerMessage::BodyCreated {
+                    body_id,
+                    success: true,
+                    error: None,
+                },
+                result => ServerMessage::BodyCreated {
+                    body_id: uuid::Uuid::new_v4(),
+                    success: false,
+                    error: result.err().map(|e| format!("{:?}", e)),
+                },
+            };
+            let success = matches!(response, ServerMessage::BodyCreated { success: true, .. });
+            if let Ok(msg) = serde_json::to_string(&response) {
+                let _ = session.text(msg).await;
+            }
+            
+            // 更新されたゲーム状態を送信
+            if success {
+                send_game_state(session, game_state).await;
+            }
+        }
+        
+        ClientMessage::RemoveBody { body_id } => {
+            let Some(ack) = submit_command(session, game_state, ClientCommand::RemoveBody { body_id }).await else {
+                return;
+            };
+            let success = ack.result.is_ok();
+            
+            let response = ServerMessage::BodyRemoved { body_id, success };
+            if let Ok(msg) = serde_json::to_string(&response) {
+                let _ = session.text(msg).await;
+            }
+            
+            if success {
+                send_game_state(session, game_state).await;
+            }
+        }
+        
+        ClientMessage::SpendResources { cosmic_dust, energy } => {
+            let Some(ack) = submit_command(session, game_state, ClientCommand::SpendResources { cosmic_dust, energy }).await else {
+                return;
+            };
+            
+            match ack.result {
+                Ok(_) => send_game_state(session, game_state).await,
+                Err(_) => {
+                    let response = ServerMessage::Error {
+                        message: "Insufficient resources".to_string(),
                     };
                     if let Ok(msg) = serde_json::to_string(&response) {
                         let _ = session.text(msg).await;
@@ -121,58 +257,258 @@ async fn handle_client_message(
             }
         }
         
-        ClientMessage::RemoveBody { body_id } => {
-            let mut celestial_manager = game_state.celestial_manager.lock().await;
-            let success = celestial_manager.remove_body(body_id).is_ok();
+        ClientMessage::SetGameRunning { running } => {
+            submit_command(session, game_state, ClientCommand::SetGameRunning { running }).await;
+        }
+        
+        ClientMessage::GetAchievements => {
+            let metrics = collect_player_metrics(game_state).await;
+            let achievements = game_state.achievements.lock().await.statuses(&metrics);
+            
+            let response = ServerMessage::Achievements { achievements };
+            if let Ok(msg) = serde_json::to_string(&response) {
+                let _ = session.text(msg).await;
+            }
+        }
+        
+        ClientMessage::GetResearchState => {
+            send_research_state(session, game_state).await;
+        }
+        
+        ClientMessage::StartResearch { technology_id } => {
+            let Some(ack) = submit_command(session, game_state, ClientCommand::StartResearch { technology_id }).await else {
+                return;
+            };
+            send_research_result(session, game_state, ack.result).await;
+        }
+        
+        ClientMessage::CancelResearch { technology_id } => {
+            let Some(ack) = submit_command(session, game_state, ClientCommand::CancelResearch { technology_id }).await else {
+                return;
+            };
+            send_research_result(session, game_state, ack.result).await;
+        }
+        
+        ClientMessage::GetProductionBreakdown => {
+            let resource_manager = game_state.resource_manager.lock().await;
+            let bodies = game_state.celestial_manager.lock().await.body_contributions();
+            let summary = resource_manager.production_summary(bodies);
+            
+            let response = ServerMessage::ProductionBreakdown { summary };
+            if let Ok(msg) = serde_json::to_string(&response) {
+                let _ = session.text(msg).await;
+            }
+        }
+        
+        ClientMessage::GetPrestigePreview => {
+            let resource_manager = game_state.resource_manager.lock().await;
+            let preview = game_state.prestige_calculator.preview(&resource_manager);
+            
+            let response = ServerMessage::PrestigePreview { preview };
+            if let Ok(msg) = serde_json::to_string(&response) {
+                let _ = session.text(msg).await;
+            }
+        }
+        
+        ClientMessage::Prestige => {
+            let Some(ack) = submit_command(session, game_state, ClientCommand::Prestige).await else {
+                return;
+            };
+            
+            let response = match ack.result {
+                Ok(ClientCommandOutcome::Prestiged(record)) => ServerMessage::PrestigeCompleted { record },
+                Ok(outcome) => ServerMessage::Error {
+                    message: format!("Unexpected outcome: {:?}", outcome),
+                },
+                Err(e) => ServerMessage::Error {
+                    message: format!("{:?}", e),
+                },
+            };
+            let success = matches!(response, ServerMessage::PrestigeCompleted { .. });
             
-            let response = ServerMessage::BodyRemoved { body_id, success };
             if let Ok(msg) = serde_json::to_string(&response) {
                 let _ = session.text(msg).await;
             }
             
             if success {
-                drop(celestial_manager);
                 send_game_state(session, game_state).await;
             }
         }
+    }
+}
+
+/// コマンドをキューに積み、ゲームループが適用するまで待つ
+///
+/// キューが一杯などで受け付けられなかった場合はエラーを送信して`None`を返す。
+async fn submit_command(
+    session: &mut Session,
+    game_state: &GameState,
+    command: ClientCommand,
+) -> Option<CommandAck<ClientCommandResult>> {
+    match game_state.commands.submit(command).await {
+        Ok(ack) => Some(ack),
+        Err(e) => {
+            let response = ServerMessage::Error {
+                message: format!("{:?}", e),
+            };
+            if let Ok(msg) = serde_json::to_string(&response) {
+                let _ = session.text(msg).await;
+            }
+            None
+        }
+    }
+}
+
+/// ティック開始時に積まれていたコマンドを受け付け順に適用
+async fn apply_queued_commands(game_state: &GameState) {
+    let tick = *game_state.tick.lock().await;
+    let pending = game_state.command_queue.lock().await.drain();
+    
+    for command in pending {
+        let result = apply_client_command(game_state, &command.command).await;
+        if let Err(e) = &result {
+            tracing::warn!(tick, sequence = command.sequence(), "Command rejected: {:?}", e);
+        }
+        // 応答を待たずに切断したセッションへの送信エラーは無視
+        command.acknowledge(tick, result);
+    }
+}
+
+/// 1つのコマンドを適用（ゲームループのタスクからのみ呼ぶ）
+async fn apply_client_command(game_state: &GameState, command: &ClientCommand) -> ClientCommandResult {
+    match command {
+        ClientCommand::CreateBody { body_type, position } => {
+            let body_id = {
+                let mut resource_manager = game_state.resource_manager.lock().await;
+                let mut celestial_manager = game_state.celestial_manager.lock().await;
+                celestial_manager.create_body(body_type.clone(), *position, resource_manager.get_resources_mut())?
+            };
+            
+            let trigger = AchievementTrigger::with_subject(TriggerKind::BodyCreated, body_type.name());
+            record_achievement_trigger(game_state, &trigger).await;
+            Ok(ClientCommandOutcome::BodyCreated(body_id))
+        }
         
-        ClientMessage::SpendResources { cosmic_dust, energy } => {
+        ClientCommand::RemoveBody { body_id } => {
+            game_state.celestial_manager.lock().await.remove_body(*body_id)?;
+            Ok(ClientCommandOutcome::BodyRemoved)
+        }
+        
+        ClientCommand::SpendResources { cosmic_dust, energy } => {
             let mut resource_manager = game_state.resource_manager.lock().await;
             let resources = resource_manager.get_resources_mut();
-            
-            if resources.cosmic_dust >= cosmic_dust && resources.energy >= energy {
-                resources.cosmic_dust -= cosmic_dust;
-                resources.energy -= energy;
-                
-                drop(resource_manager);
-                send_game_state(session, game_state).await;
-            } else {
-                let response = ServerMessage::Error {
-                    message: "Insufficient resources".to_string(),
-                };
-                if let Ok(msg) = serde_json::to_string(&response) {
-                    let _ = session.text(msg).await;
-                }
+            if resources.cosmic_dust < *cosmic_dust || resources.energy < *energy {
+                return Err(GameError::InsufficientResources);
             }
+            resources.cosmic_dust -= cosmic_dust;
+            resources.energy -= energy;
+            Ok(ClientCommandOutcome::ResourcesSpent)
         }
         
-        ClientMessage::SetGameRunning { running } => {
-            let mut is_running = game_state.is_running.lock().await;
-            *is_running = running;
+        ClientCommand::SetGameRunning { running } => {
+            *game_state.is_running.lock().await = *running;
+            Ok(ClientCommandOutcome::RunningChanged)
+        }
+        
+        ClientCommand::StartResearch { technology_id } => {
+            let mut resource_manager = game_state.resource_manager.lock().await;
+            let mut research_manager = game_state.research_manager.lock().await;
+            research_manager.enqueue(technology_id, resource_manager.get_resources_mut())?;
+            Ok(ClientCommandOutcome::ResearchUpdated)
+        }
+        
+        ClientCommand::CancelResearch { technology_id } => {
+            let mut resource_manager = game_state.resource_manager.lock().await;
+            let mut research_manager = game_state.research_manager.lock().await;
+            research_manager.cancel(technology_id, resource_manager.get_resources_mut())?;
+            Ok(ClientCommandOutcome::ResearchUpdated)
+        }
+        
+        ClientCommand::Prestige => {
+            let mut resource_manager = game_state.resource_manager.lock().await;
+            let mut celestial_manager = game_state.celestial_manager.lock().await;
+            let record = game_state.prestige_calculator.perform(
+                &mut resource_manager,
+                &mut celestial_manager,
+                chrono::Utc::now(),
+            )?;
+            Ok(ClientCommandOutcome::Prestiged(record))
+        }
+        
+        ClientCommand::ApplyOfflineProgress => {
+            let mut resource_manager = game_state.resource_manager.lock().await;
+            let mut celestial_manager = game_state.celestial_manager.lock().await;
+            let report = game_state.offline_calculator.apply(
+                &mut resource_manager,
+                &mut celestial_manager,
+                chrono::Utc::now(),
+            );
+            Ok(ClientCommandOutcome::OfflineProgressApplied(report))
+        }
+    }
+}
+
+/// 不在期間の進行をキューに積み、ゲームループが適用したらレポートを送信
+async fn apply_offline_progress(session: &mut Session, game_state: &GameState) {
+    let Some(ack) = submit_command(session, game_state, ClientCommand::ApplyOfflineProgress).await else {
+        return;
+    };
+    
+    if let Ok(ClientCommandOutcome::OfflineProgressApplied(Some(report))) = ack.result {
+        let message = ServerMessage::OfflineReport { report };
+        if let Ok(msg) = serde_json::to_string(&message) {
+            let _ = session.text(msg).await;
+        }
+    }
+}
+
+/// 研究の開始・取り消しの結果を送信
+async fn send_research_result(session: &mut Session, game_state: &GameState, result: ClientCommandResult) {
+    match result {
+        Ok(_) => send_research_state(session, game_state).await,
+        Err(e) => {
+            let response = ServerMessage::Error {
+                message: format!("{:?}", e),
+            };
+            if let Ok(msg) = serde_json::to_string(&response) {
+                let _ = session.text(msg).await;
+            }
         }
     }
 }
 
+async fn send_research_state(session: &mut Session, game_state: &GameState) {
+    let thought_points = game_state.resource_manager.lock().await.get_resources().thought_points;
+    let research_manager = game_state.research_manager.lock().await;
+    let progress = research_manager.progress();
+    
+    let message = ServerMessage::ResearchState {
+        unlocked_technologies: progress.unlocked.iter().cloned().collect(),
+        queue: progress.queue.iter().cloned().collect(),
+        thought_points,
+    };
+    
+    if let Ok(msg) = serde_json::to_string(&message) {
+        let _ = session.text(msg).await;
+    }
+}
+
 async fn send_game_state(session: &mut Session, game_state: &GameState) {
     let resource_manager = game_state.resource_manager.lock().await;
     let celestial_manager = game_state.celestial_manager.lock().await;
     let tick = game_state.tick.lock().await;
     
     let resources = resource_manager.get_resources().clone();
+    let habitability = celestial_manager.evaluate_all_habitability();
+    let mut production = celestial_manager.production_breakdowns();
     let bodies: Vec<CelestialBodyInfo> = celestial_manager
         .get_all_bodies()
         .values()
-        .map(|body| body.into())
+        .map(|body| {
+            CelestialBodyInfo::from(body)
+                .with_habitability(habitability.get(&body.id).copied())
+                .with_production(production.remove(&body.id))
+        })
         .collect();
     
     let message = ServerMessage::GameState {
@@ -186,6 +522,36 @@ async fn send_game_state(session: &mut Session, game_state: &GameState) {
     }
 }
 
+/// 実績判定用の指標を収集
+async fn collect_player_metrics(game_state: &GameState) -> PlayerMetrics {
+    let resource_manager = game_state.resource_manager.lock().await;
+    let celestial_manager = game_state.celestial_manager.lock().await;
+    let research_unlocked = game_state.research_manager.lock().await.progress().unlocked.len();
+    
+    PlayerMetrics::collect(&resource_manager, &celestial_manager, research_unlocked)
+}
+
+/// イベントを実績トラッカーに記録し、解除された実績を配信
+async fn record_achievement_trigger(game_state: &GameState, trigger: &AchievementTrigger) {
+    let unlocks = game_state.achievements.lock().await.record(trigger, chrono::Utc::now());
+    
+    for achievement in unlocks {
+        let _ = game_state.broadcaster.send(ServerMessage::AchievementUnlocked { achievement });
+    }
+}
+
+/// 生命の変化をクライアントへ配信し、実績トラッカーに記録
+async fn publish_life_transitions(game_state: &GameState, transitions: Vec<LifeTransition>) {
+    for transition in transitions {
+        let event = transition.to_celestial_event(chrono::Utc::now());
+        let _ = game_state.broadcaster.send(ServerMessage::CelestialEvent { event });
+        
+        if let Some(trigger) = transition.achievement_trigger() {
+            record_achievement_trigger(game_state, &trigger).await;
+        }
+    }
+}
+
 /// ゲームループを実行する関数
 pub async fn run_game_loop(game_state: GameState) {
     let mut interval = interval(Duration::from_millis(50)); // 20Hz
@@ -193,37 +559,137 @@ pub async fn run_game_loop(game_state: GameState) {
     loop {
         interval.tick().await;
         
+        // 状態の変更はティックの開始時にまとめて適用する（停止中も受け付ける）
+        apply_queued_commands(&game_state).await;
+        
         let is_running = *game_state.is_running.lock().await;
         if !is_running {
             continue;
         }
         
         // 物理演算の更新
-        {
+        let physics_events = {
             let mut physics_engine = game_state.physics_engine.lock().await;
             let mut celestial_manager = game_state.celestial_manager.lock().await;
             
-            if let Err(e) = physics_engine.update(celestial_manager.get_all_bodies_mut(), 0.05) {
-                eprintln!("Physics update error: {:?}", e);
+            let (bodies, spatial_index) = celestial_manager.bodies_and_index_mut();
+            match physics_engine.update_with_index(bodies, spatial_index, 0.05) {
+                Ok(events) => events,
+                Err(e) => {
+                    eprintln!("Physics update error: {:?}", e);
+                    Vec::new()
+                }
             }
+        };
+        
+        for physics_event in physics_events {
+            let trigger = match physics_event {
+                PhysicsEvent::Collision { body1_id, body2_id, outcome } => {
+                    // 衝突を生き延びた天体の生命も大きな被害を受ける
+                    let life_transitions: Vec<LifeTransition> = {
+                        let mut celestial_manager = game_state.celestial_manager.lock().await;
+                        let severity = life::collision_severity(outcome);
+                        [body1_id, body2_id].into_iter()
+                            .flat_map(|body_id| celestial_manager.apply_life_catastrophe(body_id, severity, ExtinctionCause::Collision))
+                            .collect()
+                    };
+                    publish_life_transitions(&game_state, life_transitions).await;
+                    AchievementTrigger::with_subject(TriggerKind::Collision, outcome.name())
+                }
+                PhysicsEvent::BodyDestroyed { absorbed_by: Some(_), .. } => AchievementTrigger::new(TriggerKind::BodyAbsorbed),
+                PhysicsEvent::LeftBounds { .. } => AchievementTrigger::new(TriggerKind::BodyLeftBounds),
+                PhysicsEvent::BodyDestroyed { .. } | PhysicsEvent::DebrisCreated { .. } => continue,
+            };
+            record_achievement_trigger(&game_state, &trigger).await;
         }
         
-        // 生命システムの更新
+        // 研究の進行
         {
-            let mut celestial_manager = game_state.celestial_manager.lock().await;
-            celestial_manager.update_life_systems(50);
+            let unlocked = game_state.research_manager.lock().await.update(50);
+            
+            if !unlocked.is_empty() {
+                // ロック順序はハンドラと同じ（リソース → 研究）
+                let mut resource_manager = game_state.resource_manager.lock().await;
+                let mut celestial_manager = game_state.celestial_manager.lock().await;
+                let effects = game_state.research_manager.lock().await.effects();
+                effects.apply(&mut resource_manager, &mut celestial_manager);
+                
+                drop(celestial_manager);
+                drop(resource_manager);
+                
+                for research_id in unlocked {
+                    let trigger = AchievementTrigger::with_subject(TriggerKind::ResearchUnlocked, research_id.clone());
+                    
+                    // 接続中のセッションが無い場合の送信エラーは無視
+                    let _ = game_state.broadcaster.send(ServerMessage::ResearchUnlocked { research_id });
+                    record_achievement_trigger(&game_state, &trigger).await;
+                }
+            }
+        }
+        
+        // 生命システムの更新
+        let transitions = game_state.celestial_manager.lock().await.update_life_systems(50);
+        publish_life_transitions(&game_state, transitions).await;
+        
+        // 恒星進化の更新
+        let stellar_transitions = game_state.celestial_manager.lock().await.update_stellar_evolution(50);
+        for transition in stellar_transitions {
+            let trigger = AchievementTrigger::with_subject(TriggerKind::StellarEvolved, transition.to_phase.name());
+            record_achievement_trigger(&game_state, &trigger).await;
+            
+            if let Some(supernova) = transition.supernova {
+                game_state.resource_manager.lock().await.grant(ResourceType::CosmicDust, supernova.dust_released);
+                publish_life_transitions(&game_state, supernova.life_transitions).await;
+                record_achievement_trigger(&game_state, &AchievementTrigger::new(TriggerKind::Supernova)).await;
+            }
         }
         
-        // リソースの蓄積
+        // リソースの蓄積（天体からの生産を合算してから）
         {
             let mut resource_manager = game_state.resource_manager.lock().await;
+            let body_production = game_state.celestial_manager.lock().await.total_production();
+            resource_manager.set_body_production(body_production);
             resource_manager.accumulate_resources(50);
         }
         
+        // しきい値実績の判定
+        {
+            let metrics = collect_player_metrics(&game_state).await;
+            let unlocks = game_state.achievements.lock().await.evaluate(&metrics, chrono::Utc::now());
+            
+            for achievement in unlocks {
+                let _ = game_state.broadcaster.send(ServerMessage::AchievementUnlocked { achievement });
+            }
+        }
+        
         // ティック数の更新
         {
             let mut tick = game_state.tick.lock().await;
             *tick += 1;
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_submitted_command_is_acknowledged_by_game_loop() {
+        let game_state = GameState::new();
+        let game_loop = tokio::spawn(run_game_loop(game_state.clone()));
+
+        // 停止中でもティックの開始時にコマンドは適用される
+        let ack = tokio::time::timeout(
+            Duration::from_secs(5),
+            game_state.commands.submit(ClientCommand::SetGameRunning { running: false }),
+        )
+        .await
+        .expect("game loop did not acknowledge the command")
+        .unwrap();
+        assert!(matches!(ack.result, Ok(ClientCommandOutcome::RunningChanged)));
+        assert!(!*game_state.is_running.lock().await);
+
+        game_loop.abort();
+    }
+}
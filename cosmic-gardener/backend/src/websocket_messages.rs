@@ -1,7 +1,14 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::game::celestial_bodies::{CelestialType, CelestialBody};
-use crate::game::resources::Resources;
+use crate::game::resources::{ProductionSummary, Resources};
+use crate::game::offline::OfflineReport;
+use crate::game::prestige::{PrestigePreview, PrestigeRecord};
+use crate::game::research::ActiveResearch;
+use crate::game::achievements::{AchievementStatus, AchievementUnlock};
+use crate::game::habitability::HabitabilityBreakdown;
+use crate::game::production_modifiers::ProductionBreakdown;
+use crate::models::websocket::CelestialEventResponse;
 
 /// クライアントからサーバーへのメッセージ
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +38,31 @@ pub enum ClientMessage {
     SetGameRunning {
         running: bool,
     },
+    
+    /// プレステージ獲得量の確認
+    GetPrestigePreview,
+    
+    /// プレステージの実行
+    Prestige,
+    
+    /// 研究状態の要求
+    GetResearchState,
+    
+    /// 研究の開始
+    StartResearch {
+        technology_id: String,
+    },
+    
+    /// 研究の取り消し
+    CancelResearch {
+        technology_id: String,
+    },
+    
+    /// 実績一覧の要求
+    GetAchievements,
+    
+    /// 生産の内訳の要求
+    GetProductionBreakdown,
 }
 
 /// サーバーからクライアントへのメッセージ
@@ -62,6 +94,53 @@ pub enum ServerMessage {
         message: String,
     },
     
+    /// プレステージ獲得量
+    PrestigePreview {
+        preview: PrestigePreview,
+    },
+    
+    /// プレステージの結果
+    PrestigeCompleted {
+        record: PrestigeRecord,
+    },
+    
+    /// 研究状態
+    ResearchState {
+        unlocked_technologies: Vec<String>,
+        queue: Vec<ActiveResearch>,
+        thought_points: u64,
+    },
+    
+    /// 研究完了（StateDelta::ResearchUnlockedに対応）
+    ResearchUnlocked {
+        research_id: String,
+    },
+    
+    /// 実績一覧
+    Achievements {
+        achievements: Vec<AchievementStatus>,
+    },
+    
+    /// 生産の内訳（アップグレードと天体ごとの寄与）
+    ProductionBreakdown {
+        summary: ProductionSummary,
+    },
+    
+    /// 実績解除の通知
+    AchievementUnlocked {
+        achievement: AchievementUnlock,
+    },
+    
+    /// オフライン進行の結果
+    OfflineReport {
+        report: OfflineReport,
+    },
+    
+    /// 天体イベント（生命の誕生・進化など）
+    CelestialEvent {
+        event: CelestialEventResponse,
+    },
+    
     /// 接続確認
     Ping,
 }
@@ -77,6 +156,26 @@ pub struct CelestialBodyInfo {
     pub radius: f64,
     pub age: u64,
     pub population: u64,
+    /// 惑星の居住可能性の内訳
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub habitability: Option<HabitabilityBreakdown>,
+    /// 生産レートと周囲の天体による補正の内訳
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub production: Option<ProductionBreakdown>,
+}
+
+impl CelestialBodyInfo {
+    /// 居住可能性の内訳を付ける
+    pub fn with_habitability(mut self, habitability: Option<HabitabilityBreakdown>) -> Self {
+        self.habitability = habitability;
+        self
+    }
+    
+    /// 生産の内訳を付ける
+    pub fn with_production(mut self, production: Option<ProductionBreakdown>) -> Self {
+        self.production = production;
+        self
+    }
 }
 
 impl From<&CelestialBody> for CelestialBodyInfo {
@@ -90,6 +189,8 @@ impl From<&CelestialBody> for CelestialBodyInfo {
             radius: crate::game::resources::fixed::to_f64(body.physics.radius),
             age: body.lifecycle.age,
             population: body.lifecycle.population,
+            habitability: None,
+            production: None,
         }
     }
 }
\ No newline at end of file